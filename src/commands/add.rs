@@ -1,30 +1,35 @@
 //! Staging commands for populating the index from the working directory.
 
+use crate::utils::IndexEntry;
 use crate::{commands, utils};
 use anyhow::{Result, anyhow, bail};
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
 fn add_all(root_path: &Path) -> Result<()> {
     let ignore_rules = utils::parse_ignore_file(root_path)?;
 
-    let mut work_dir_map = HashMap::new();
-    utils::get_work_dir_map(root_path, Path::new(""), &mut work_dir_map)?;
+    let mut work_dir_entries = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_dir_entries)?;
 
-    let mut index_map = utils::read_index_map(root_path)?;
+    utils::update_index_entries(root_path, |index_entries| {
+        for (path, work_entry) in work_dir_entries {
+            let full_path = root_path.join(&path);
 
-    for (path, _) in work_dir_map {
-        let full_path = root_path.join(&path);
+            if utils::is_ignored(&full_path, root_path, &ignore_rules) {
+                continue;
+            }
 
-        if utils::is_ignored(&full_path, root_path, &ignore_rules) {
-            continue;
+            let blob_hash = commands::write_blob(root_path, &full_path)?;
+            index_entries.insert(
+                path,
+                IndexEntry {
+                    mode: work_entry.mode,
+                    hash: blob_hash,
+                },
+            );
         }
-
-        let blob_hash = commands::write_blob(root_path, &full_path)?;
-        index_map.insert(path, blob_hash);
-    }
-
-    utils::write_index_map(root_path, &index_map)?;
-    Ok(())
+        Ok(())
+    })
 }
 
 /// Stages a path into the index.
@@ -44,7 +49,8 @@ pub fn add(root_path: &Path, file: &Path) -> Result<()> {
         root_path.join(file)
     };
 
-    if !file_path.exists() || !file_path.is_file() {
+    let is_file_or_link = fs::symlink_metadata(&file_path).is_ok_and(|metadata| !metadata.is_dir());
+    if !is_file_or_link {
         bail!("Could not find file: {}", file.display());
     }
 
@@ -53,7 +59,8 @@ pub fn add(root_path: &Path, file: &Path) -> Result<()> {
         .map_err(|_| anyhow!("File must be inside the repository root."))?;
 
     let blob_hash = commands::write_blob(root_path, &file_path)?;
-    utils::stage_index_entry(root_path, relative_path, &blob_hash)?;
+    let mode = utils::work_file_mode(&file_path)?;
+    utils::stage_index_entry(root_path, relative_path, &blob_hash, mode)?;
 
     Ok(())
 }
@@ -0,0 +1,92 @@
+//! Recreating commits from mailbox patches written by `format-patch`.
+
+use crate::commands::{
+    self,
+    apply::{self, ApplyTarget},
+};
+use crate::utils::{self, MailPatch};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Refuses to run while the index has staged or unmerged changes.
+fn ensure_clean_index(root_path: &Path) -> Result<()> {
+    if !utils::read_index_conflicts(root_path)?.is_empty() {
+        bail!("Cannot apply patches: you have unmerged paths. Resolve them first.");
+    }
+
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let head_entries = utils::get_commit_entries_map(root_path, head_commit.as_deref())?;
+    if utils::read_effective_index(root_path)? != head_entries {
+        bail!("Cannot apply patches: you have staged changes. Commit or stash them first.");
+    }
+    Ok(())
+}
+
+/// Applies one mailed patch to the index and working tree and commits it.
+fn apply_mail(root_path: &Path, mail: &MailPatch) -> Result<String> {
+    let patches = utils::parse_patch(mail.diff.as_bytes())?;
+    if patches.is_empty() {
+        bail!("Patch is empty.");
+    }
+    apply::apply_patches(root_path, &patches, ApplyTarget::IndexAndWorkTree, false)?;
+
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let entries = utils::read_effective_index(root_path)?;
+    let tree_hash = commands::write_tree_from_entries(root_path, &entries)?;
+    let parents: Vec<String> = head_commit.iter().cloned().collect();
+    let commit_hash = commands::commit_tree_with_author(
+        root_path,
+        &tree_hash,
+        &parents,
+        mail.message(),
+        mail.author.clone(),
+    )?;
+
+    let old_commit = head_commit.as_deref().unwrap_or(utils::NULL_HASH);
+    utils::update_head(
+        root_path,
+        &commit_hash,
+        Some(old_commit),
+        &format!("am: {}", mail.subject),
+    )?;
+    Ok(commit_hash)
+}
+
+/// Applies the patches mailed in `files` (mbox files as written by
+/// `format-patch`), committing each on top of HEAD with its original
+/// author, author date and message. Returns the new commits in order.
+///
+/// Each patch must apply to both the index and the working tree. Every
+/// file is parsed before anything is applied; if a patch does not apply,
+/// the commits made for the patches before it are kept and the rest are
+/// left unapplied.
+pub fn am(root_path: &Path, files: &[PathBuf]) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+    ensure_clean_index(root_path)?;
+
+    let mut mails = Vec::new();
+    for file in files {
+        let text = fs::read(file)
+            .map_err(|err| anyhow!("Could not read patch '{}': {}", file.display(), err))?;
+        mails.extend(utils::parse_mailbox(&String::from_utf8_lossy(&text))?);
+    }
+
+    let mut commits = Vec::new();
+    for (index, mail) in mails.iter().enumerate() {
+        let commit_hash = apply_mail(root_path, mail).map_err(|err| {
+            anyhow!(
+                "Patch failed at {:04} {}: {}\n{} patch(es) before it were committed.",
+                index + 1,
+                mail.subject,
+                err,
+                commits.len()
+            )
+        })?;
+        commits.push(commit_hash);
+    }
+
+    Ok(commits)
+}
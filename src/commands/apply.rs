@@ -0,0 +1,234 @@
+//! Applies unified diffs to the working tree or the index.
+
+use crate::commands;
+use crate::utils::{self, FilePatch, IndexEntry};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// How `apply` treats a patch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Apply to the index instead of the working tree.
+    pub cached: bool,
+    /// Only check that the patch applies; change nothing.
+    pub check: bool,
+    /// Apply the patch backwards.
+    pub reverse: bool,
+}
+
+/// Where a patch is applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ApplyTarget {
+    WorkTree,
+    Index,
+    /// The index, also updating the working tree, whose touched files must
+    /// match the index.
+    IndexAndWorkTree,
+}
+
+impl ApplyTarget {
+    fn name(self) -> &'static str {
+        match self {
+            ApplyTarget::WorkTree => "working tree",
+            ApplyTarget::Index | ApplyTarget::IndexAndWorkTree => "index",
+        }
+    }
+}
+
+/// A file's mode and content, or `None` when it does not exist.
+type FileState = Option<(&'static str, Vec<u8>)>;
+
+/// Reads `path` from `target`.
+fn read_target(
+    root_path: &Path,
+    target: ApplyTarget,
+    index: &HashMap<PathBuf, IndexEntry>,
+    path: &Path,
+) -> Result<FileState> {
+    match target {
+        ApplyTarget::WorkTree => {
+            let full_path = root_path.join(path);
+            match fs::symlink_metadata(&full_path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    bail!("{}: is a directory in the working tree", path.display())
+                }
+                Ok(_) => Ok(Some((
+                    utils::work_file_mode(&full_path)?,
+                    utils::read_work_file(&full_path)?,
+                ))),
+                Err(_) => Ok(None),
+            }
+        }
+        ApplyTarget::Index | ApplyTarget::IndexAndWorkTree => match index.get(path) {
+            Some(entry) => Ok(Some((
+                entry.mode,
+                utils::read_blob(root_path, &entry.hash)?,
+            ))),
+            None => Ok(None),
+        },
+    }
+}
+
+/// Applies `patches` in order to `target` and returns the paths they touch.
+///
+/// Every result is computed before anything is written, so a patch that
+/// does not apply leaves `target` untouched; with `check_only` nothing is
+/// written at all.
+pub(crate) fn apply_patches(
+    root_path: &Path,
+    patches: &[FilePatch],
+    target: ApplyTarget,
+    check_only: bool,
+) -> Result<Vec<PathBuf>> {
+    let mut index = utils::read_effective_index(root_path)?;
+    let conflicts = utils::read_index_conflicts(root_path)?;
+    let mut results: BTreeMap<PathBuf, FileState> = BTreeMap::new();
+
+    let current = |path: &Path, results: &BTreeMap<PathBuf, FileState>| -> Result<FileState> {
+        if target != ApplyTarget::WorkTree && conflicts.contains_key(path) {
+            bail!("{}: has unresolved conflicts in the index", path.display());
+        }
+        match results.get(path) {
+            Some(state) => Ok(state.clone()),
+            None => read_target(root_path, target, &index, path),
+        }
+    };
+
+    for patch in patches {
+        if patch.binary {
+            bail!(
+                "{}: binary patches are not supported",
+                patch.path().display()
+            );
+        }
+
+        let (mode, content) = match &patch.old_path {
+            Some(old_path) => current(old_path, &results)?.ok_or_else(|| {
+                anyhow!(
+                    "{}: does not exist in {}",
+                    old_path.display(),
+                    target.name()
+                )
+            })?,
+            None => (utils::MODE_FILE, Vec::new()),
+        };
+
+        if let Some(new_path) = &patch.new_path
+            && patch.old_path.as_ref() != Some(new_path)
+            && current(new_path, &results)?.is_some()
+        {
+            bail!(
+                "{}: already exists in {}",
+                new_path.display(),
+                target.name()
+            );
+        }
+
+        let content = utils::apply_hunks(&content, &patch.hunks)
+            .map_err(|err| anyhow!("patch failed: {}: {}", patch.path().display(), err))?;
+
+        if let Some(old_path) = &patch.old_path
+            && !patch.copy
+        {
+            results.insert(old_path.clone(), None);
+        }
+        match &patch.new_path {
+            Some(new_path) => {
+                let mode = patch.new_mode.unwrap_or(mode);
+                results.insert(new_path.clone(), Some((mode, content)));
+            }
+            None if !content.is_empty() => {
+                bail!(
+                    "{}: deleted file still has contents",
+                    patch.path().display()
+                )
+            }
+            None => {}
+        }
+    }
+
+    if target == ApplyTarget::IndexAndWorkTree {
+        for path in results.keys() {
+            let work = read_target(root_path, ApplyTarget::WorkTree, &index, path)?;
+            if work != read_target(root_path, ApplyTarget::Index, &index, path)? {
+                bail!("{}: does not match index", path.display());
+            }
+        }
+    }
+
+    if check_only {
+        return Ok(results.into_keys().collect());
+    }
+
+    if target != ApplyTarget::Index {
+        for (path, _) in results.iter().filter(|(_, state)| state.is_none()) {
+            utils::remove_work_file(root_path, path)?;
+        }
+        for (path, state) in &results {
+            if let Some((mode, content)) = state {
+                utils::write_work_file(&root_path.join(path), mode, content)?;
+            }
+        }
+    }
+
+    if target != ApplyTarget::WorkTree {
+        for (path, state) in &results {
+            match state {
+                Some((mode, content)) => {
+                    let object = commands::format_object(content);
+                    let hash = utils::hash_bytes(&object);
+                    commands::write_object(root_path, &hash, &object)?;
+                    index.insert(path.clone(), IndexEntry { mode, hash });
+                }
+                None => {
+                    index.remove(path);
+                }
+            }
+        }
+        utils::write_index_entries(root_path, &index)?;
+    }
+
+    Ok(results.into_keys().collect())
+}
+
+/// Applies the patch in `patch_file` (`-` for standard input) and returns
+/// the paths it touches.
+///
+/// The patch may hold plain (`diff -u`) or Git-style unified diffs; see
+/// [`utils::parse_patch`]. Hunks must match exactly, though they may have
+/// moved up or down. Nothing is changed unless every file applies.
+pub fn apply(root_path: &Path, patch_file: &Path, options: ApplyOptions) -> Result<Vec<PathBuf>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let text = if patch_file == Path::new("-") {
+        let mut text = Vec::new();
+        std::io::stdin().read_to_end(&mut text)?;
+        text
+    } else {
+        fs::read(patch_file)
+            .map_err(|err| anyhow!("Could not read patch '{}': {}", patch_file.display(), err))?
+    };
+
+    let mut patches = utils::parse_patch(&text)?;
+    if patches.is_empty() {
+        bail!("No valid patches in input");
+    }
+    if options.reverse {
+        if let Some(copy) = patches.iter().find(|patch| patch.copy) {
+            bail!("{}: cannot reverse a copy", copy.path().display());
+        }
+        patches = patches.iter().map(FilePatch::reversed).collect();
+    }
+
+    let target = if options.cached {
+        ApplyTarget::Index
+    } else {
+        ApplyTarget::WorkTree
+    };
+    apply_patches(root_path, &patches, target, options.check)
+}
@@ -0,0 +1,424 @@
+//! Binary search through history for the commit that introduced a bug.
+//!
+//! The commits still suspect are those reachable from the bad commit but
+//! from none of the good ones. Each step checks out the suspect that splits
+//! them most evenly, using the checkout machinery, until a single bad
+//! commit is left. The session lives in `.rustygit/BISECT_*` files and every
+//! mark is logged to `BISECT_LOG`, which `bisect replay` can re-run.
+
+use crate::commands::checkout;
+use crate::utils::{self, BisectState};
+use anyhow::{Result, anyhow, bail};
+use std::{collections::HashSet, fs, path::Path, process::Command};
+
+/// Where a bisect session stands after a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BisectOutcome {
+    /// A bad commit (`bad`) and/or good commits (`good` known so far) are
+    /// still needed before the search can start.
+    Waiting { bad: bool, good: usize },
+    /// `commit` was checked out for testing; `remaining` commits will be
+    /// left to test after it, taking roughly `steps` more steps.
+    Testing {
+        commit: String,
+        remaining: usize,
+        steps: usize,
+    },
+    /// The search is over: this is the first bad commit.
+    FirstBad(String),
+    /// Only skipped commits are left to test; the first bad commit is one
+    /// of these.
+    OnlySkipped(Vec<String>),
+}
+
+/// How a commit was judged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mark {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl Mark {
+    fn name(self) -> &'static str {
+        match self {
+            Mark::Good => "good",
+            Mark::Bad => "bad",
+            Mark::Skip => "skip",
+        }
+    }
+}
+
+/// Formats a commit as `[<hash>] <subject>` for the log.
+fn describe(root_path: &Path, commit_hash: &str) -> Result<String> {
+    let message = utils::get_commit_message(root_path, commit_hash)?;
+    Ok(format!(
+        "[{}] {}",
+        commit_hash,
+        message.lines().next().unwrap_or_default()
+    ))
+}
+
+fn read_state(root_path: &Path) -> Result<BisectState> {
+    utils::ensure_repo_exists(root_path)?;
+
+    utils::read_bisect_state(root_path)?
+        .ok_or_else(|| anyhow!("You need to start by running 'bisect start'."))
+}
+
+/// Resolves `revisions` to commits, defaulting to HEAD when there are none.
+fn resolve_commits(root_path: &Path, revisions: &[String]) -> Result<Vec<String>> {
+    if revisions.is_empty() {
+        return Ok(vec![utils::resolve_commit(root_path, "HEAD")?]);
+    }
+
+    revisions
+        .iter()
+        .map(|revision| utils::resolve_commit(root_path, revision))
+        .collect()
+}
+
+/// Records `commits` as good, bad or skipped in the state and the log.
+fn mark_commits(
+    root_path: &Path,
+    state: &mut BisectState,
+    mark: Mark,
+    commits: &[String],
+) -> Result<()> {
+    for commit_hash in commits {
+        match mark {
+            Mark::Bad => state.bad = Some(commit_hash.clone()),
+            Mark::Good if !state.good.contains(commit_hash) => state.good.push(commit_hash.clone()),
+            Mark::Skip if !state.skip.contains(commit_hash) => state.skip.push(commit_hash.clone()),
+            _ => {}
+        }
+
+        utils::append_bisect_log(
+            root_path,
+            &format!("# {}: {}", mark.name(), describe(root_path, commit_hash)?),
+        )?;
+        utils::append_bisect_log(
+            root_path,
+            &format!("rustygit bisect {} {}", mark.name(), commit_hash),
+        )?;
+    }
+
+    utils::write_bisect_state(root_path, state)
+}
+
+/// Reads the session, marks `revisions` and returns the updated state.
+fn record(root_path: &Path, mark: Mark, revisions: &[String]) -> Result<BisectState> {
+    let mut state = read_state(root_path)?;
+    let commits = resolve_commits(root_path, revisions)?;
+
+    mark_commits(root_path, &mut state, mark, &commits)?;
+    Ok(state)
+}
+
+/// Starts a session at HEAD, optionally marking a bad and good commits.
+fn start_session(root_path: &Path, bad: Option<&str>, good: &[String]) -> Result<BisectState> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if utils::read_bisect_state(root_path)?.is_some() {
+        bail!("A bisect is already in progress.\nRun 'bisect reset' first.");
+    }
+    if utils::read_merge_head(root_path)?.is_some()
+        || utils::read_sequencer(root_path)?.is_some()
+        || utils::read_rebase_state(root_path)?.is_some()
+        || !utils::read_index_conflicts(root_path)?.is_empty()
+    {
+        bail!("Cannot bisect: a merge, cherry-pick, revert or rebase is in progress.");
+    }
+
+    let head_commit = utils::get_current_commit_hash(root_path)?
+        .ok_or_else(|| anyhow!("Cannot bisect: HEAD does not point to a commit yet."))?;
+    let bad = bad
+        .map(|revision| utils::resolve_commit(root_path, revision))
+        .transpose()?;
+    let good = good
+        .iter()
+        .map(|revision| utils::resolve_commit(root_path, revision))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut state = BisectState {
+        start: utils::current_branch(root_path)?.unwrap_or(head_commit),
+        ..BisectState::default()
+    };
+    utils::write_bisect_state(root_path, &state)?;
+    utils::append_bisect_log(root_path, "rustygit bisect start")?;
+
+    if let Some(bad) = bad {
+        mark_commits(root_path, &mut state, Mark::Bad, &[bad])?;
+    }
+    mark_commits(root_path, &mut state, Mark::Good, &good)?;
+    Ok(state)
+}
+
+/// Returns the commits that may still be the first bad one, starting with
+/// `bad` itself: those reachable from it but from no good commit.
+fn suspects(root_path: &Path, bad: &str, good: &[String]) -> Result<Vec<String>> {
+    let mut excluded: HashSet<String> = HashSet::new();
+    for commit_hash in good {
+        if !excluded.contains(commit_hash) {
+            excluded.extend(utils::collect_ancestors(root_path, commit_hash)?);
+        }
+    }
+
+    let mut suspects = Vec::new();
+    let mut stack = vec![bad.to_string()];
+    while let Some(commit_hash) = stack.pop() {
+        if excluded.insert(commit_hash.clone()) {
+            stack.extend(utils::get_commit_parents(root_path, &commit_hash)?);
+            suspects.push(commit_hash);
+        }
+    }
+
+    Ok(suspects)
+}
+
+/// Counts the suspects reachable from `start`, including `start` itself.
+fn count_reachable(root_path: &Path, start: &str, suspects: &HashSet<&str>) -> Result<usize> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut stack = vec![start.to_string()];
+
+    while let Some(commit_hash) = stack.pop() {
+        if suspects.contains(commit_hash.as_str()) && seen.insert(commit_hash.clone()) {
+            stack.extend(utils::get_commit_parents(root_path, &commit_hash)?);
+        }
+    }
+
+    Ok(seen.len())
+}
+
+/// Estimates how many more steps a search among `all` suspects takes.
+fn estimate_steps(all: usize) -> usize {
+    if all < 3 {
+        return 0;
+    }
+
+    let n = all.ilog2() as usize;
+    let e = 1 << n;
+    if e < 3 * (all - e) { n } else { n - 1 }
+}
+
+/// Works out the next step of the search and checks out the commit to test.
+fn next_step(root_path: &Path, state: &BisectState) -> Result<BisectOutcome> {
+    let Some(bad) = state.bad.as_deref().filter(|_| !state.good.is_empty()) else {
+        return Ok(BisectOutcome::Waiting {
+            bad: state.bad.is_some(),
+            good: state.good.len(),
+        });
+    };
+
+    let suspects = suspects(root_path, bad, &state.good)?;
+    if suspects.is_empty() {
+        bail!(
+            "The bad commit {} is an ancestor of a good commit.\nCheck the good and bad marks, or run 'bisect reset'.",
+            bad
+        );
+    }
+    if suspects.len() == 1 {
+        utils::append_bisect_log(
+            root_path,
+            &format!("# first bad commit: {}", describe(root_path, bad)?),
+        )?;
+        return Ok(BisectOutcome::FirstBad(bad.to_string()));
+    }
+
+    // Test the suspect whose answer halves the suspects most evenly.
+    let all = suspects.len();
+    let suspect_set: HashSet<&str> = suspects.iter().map(String::as_str).collect();
+    let mut best: Option<(usize, usize, &String)> = None;
+    for commit_hash in &suspects {
+        if commit_hash == bad || state.skip.contains(commit_hash) {
+            continue;
+        }
+        let reaches = count_reachable(root_path, commit_hash, &suspect_set)?;
+        let balance = reaches.min(all - reaches);
+        if best.is_none_or(|(best_balance, _, _)| balance > best_balance) {
+            best = Some((balance, reaches, commit_hash));
+        }
+    }
+
+    let Some((_, reaches, commit_hash)) = best else {
+        utils::append_bisect_log(root_path, "# only skipped commits left to test")?;
+        return Ok(BisectOutcome::OnlySkipped(
+            suspects
+                .into_iter()
+                .filter(|commit_hash| commit_hash == bad || state.skip.contains(commit_hash))
+                .collect(),
+        ));
+    };
+
+    if utils::read_symbolic_head(root_path)?.is_some()
+        || utils::get_current_commit_hash(root_path)?.as_ref() != Some(commit_hash)
+    {
+        checkout::switch_to(root_path, commit_hash)?;
+    }
+
+    Ok(BisectOutcome::Testing {
+        commit: commit_hash.clone(),
+        remaining: all - reaches - 1,
+        steps: estimate_steps(all),
+    })
+}
+
+/// Starts a bisect session at HEAD.
+///
+/// `bad` and `good` optionally mark commits right away, as `bisect bad` and
+/// `bisect good` would. Once both are known the first commit to test is
+/// checked out, detaching HEAD; [`bisect_reset`] returns to the branch.
+pub fn bisect_start(root_path: &Path, bad: Option<&str>, good: &[String]) -> Result<BisectOutcome> {
+    let state = start_session(root_path, bad, good)?;
+    next_step(root_path, &state)
+}
+
+/// Marks commits (HEAD when `revisions` is empty) as good and moves on.
+pub fn bisect_good(root_path: &Path, revisions: &[String]) -> Result<BisectOutcome> {
+    let state = record(root_path, Mark::Good, revisions)?;
+    next_step(root_path, &state)
+}
+
+/// Marks a commit (HEAD when `None`) as bad and moves on.
+pub fn bisect_bad(root_path: &Path, revision: Option<&str>) -> Result<BisectOutcome> {
+    let revisions: Vec<String> = revision.map(String::from).into_iter().collect();
+    let state = record(root_path, Mark::Bad, &revisions)?;
+    next_step(root_path, &state)
+}
+
+/// Marks commits (HEAD when `revisions` is empty) as untestable and moves on.
+pub fn bisect_skip(root_path: &Path, revisions: &[String]) -> Result<BisectOutcome> {
+    let state = record(root_path, Mark::Skip, revisions)?;
+    next_step(root_path, &state)
+}
+
+/// Ends the session, checking out `commit` or else the branch (or detached
+/// commit) that was checked out when it started.
+pub fn bisect_reset(root_path: &Path, commit: Option<&str>) -> Result<()> {
+    let state = read_state(root_path)?;
+    let target = commit.unwrap_or(&state.start);
+
+    if utils::current_branch(root_path)?.as_deref() != Some(target) {
+        checkout::switch_to(root_path, target)?;
+    }
+    utils::clear_bisect_state(root_path)
+}
+
+/// Returns the session's log: one `rustygit bisect <command>` line per
+/// command, with `#` comments describing the commits.
+pub fn bisect_log(root_path: &Path) -> Result<String> {
+    read_state(root_path)?;
+    utils::read_bisect_log(root_path)
+}
+
+/// Re-runs the commands of a log written by [`bisect_log`], ending any
+/// session in progress first, and checks out the next commit to test.
+///
+/// Lines may start with `rustygit bisect` or `git bisect`; blank lines and
+/// `#` comments are ignored.
+pub fn bisect_replay(root_path: &Path, log_file: &Path) -> Result<BisectOutcome> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let text = fs::read_to_string(log_file)
+        .map_err(|err| anyhow!("Cannot read '{}': {}", log_file.display(), err))?;
+
+    let mut commands: Vec<(String, Vec<String>)> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.as_slice() {
+            ["rustygit" | "git", "bisect", command, args @ ..]
+                if matches!(*command, "start" | "good" | "bad" | "skip") =>
+            {
+                (command.to_string(), args)
+            }
+            _ => bail!("Invalid bisect log line: '{}'", line),
+        };
+        commands.push((command, args.iter().map(|arg| arg.to_string()).collect()));
+    }
+    if commands
+        .first()
+        .is_none_or(|(command, _)| command != "start")
+    {
+        bail!("The bisect log does not begin with 'bisect start'.");
+    }
+
+    if utils::read_bisect_state(root_path)?.is_some() {
+        bisect_reset(root_path, None)?;
+    }
+
+    let mut state = BisectState::default();
+    for (command, args) in commands {
+        state = match command.as_str() {
+            "start" => {
+                if utils::read_bisect_state(root_path)?.is_some() {
+                    bail!("The bisect log contains more than one 'bisect start'.");
+                }
+                start_session(
+                    root_path,
+                    args.first().map(String::as_str),
+                    args.get(1..).unwrap_or_default(),
+                )?
+            }
+            "good" => record(root_path, Mark::Good, &args)?,
+            "bad" => record(root_path, Mark::Bad, &args)?,
+            _ => record(root_path, Mark::Skip, &args)?,
+        };
+    }
+
+    next_step(root_path, &state)
+}
+
+/// Runs `command` (a program and its arguments, passed on unchanged) at each
+/// commit to test and marks the commit by its exit status: 0 is good, 125
+/// skip, any other status below 128 bad.
+///
+/// Returns the outcome after each run, ending with the result of the
+/// search. A status of 128 or above (or a signal) stops the run.
+pub fn bisect_run(root_path: &Path, command: &[String]) -> Result<Vec<BisectOutcome>> {
+    let state = read_state(root_path)?;
+    let Some((program, args)) = command.split_first() else {
+        bail!("bisect run needs a command to run.");
+    };
+    let command_line = command.join(" ");
+
+    let mut outcome = next_step(root_path, &state)?;
+    if let BisectOutcome::Waiting { .. } = outcome {
+        bail!("Cannot run bisect: mark a bad and at least one good commit first.");
+    }
+
+    let mut outcomes = Vec::new();
+    while let BisectOutcome::Testing { commit, .. } = &outcome {
+        let status = Command::new(program)
+            .args(args)
+            .current_dir(root_path)
+            .status()
+            .map_err(|err| anyhow!("Unable to run '{}': {}", command_line, err))?;
+
+        let mark = match status.code() {
+            Some(0) => Mark::Good,
+            Some(125) => Mark::Skip,
+            Some(code) if code < 128 => Mark::Bad,
+            code => bail!(
+                "bisect run failed: '{}' exited with {}",
+                command_line,
+                code.map_or_else(
+                    || String::from("a signal"),
+                    |code| format!("status {}", code)
+                )
+            ),
+        };
+
+        let state = record(root_path, mark, std::slice::from_ref(commit))?;
+        outcome = next_step(root_path, &state)?;
+        outcomes.push(outcome.clone());
+    }
+
+    if outcomes.is_empty() {
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
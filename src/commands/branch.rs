@@ -1,62 +1,151 @@
-//! Branch creation and listing.
+//! Branch creation, listing, deletion, renaming and force-moving.
 
 use crate::utils;
-use anyhow::{Result, bail};
-use std::fs;
+use anyhow::{Result, anyhow, bail};
 use std::path::Path;
 
-fn get_current_branch_name(root_path: &Path) -> Result<Option<String>> {
-    let head_path = root_path.join(".rustygit").join("HEAD");
-    let head_content = fs::read_to_string(head_path)?.trim().to_string();
+fn branch_ref(branch_name: &str) -> String {
+    format!("refs/heads/{}", branch_name)
+}
+
+/// Creates a branch reference pointing at the current commit.
+pub fn create_branch(root_path: &Path, branch_name: &str) -> Result<()> {
+    create_branch_at(root_path, branch_name, None, false).map(|_| ())
+}
 
-    if head_content.starts_with("ref: ") {
-        let ref_path = head_content[5..].trim();
-        if ref_path.starts_with("refs/heads/") {
-            let branch_name = &ref_path["refs/heads/".len()..];
-            return Ok(Some(branch_name.to_string()));
+/// Creates `branch_name` at `start_point` (a revision, default HEAD) and
+/// returns the commit it points to.
+///
+/// With `force`, an existing branch is moved instead of rejected, like
+/// `branch -f`; the checked-out branch can never be force-moved.
+pub fn create_branch_at(
+    root_path: &Path,
+    branch_name: &str,
+    start_point: Option<&str>,
+    force: bool,
+) -> Result<String> {
+    utils::ensure_repo_exists(root_path)?;
+    utils::check_ref_name(branch_name)?;
+
+    let ref_name = branch_ref(branch_name);
+
+    let exists = utils::ref_exists(root_path, &ref_name);
+    if exists {
+        if !force {
+            bail!("Branch '{}' already exists.", branch_name);
+        }
+        if utils::current_branch(root_path)?.as_deref() == Some(branch_name) {
+            bail!("Cannot force update the current branch '{}'.", branch_name);
         }
     }
 
-    Ok(None)
+    // Without commits yet, the new branch is unborn just like the current one.
+    let commit_hash = match start_point {
+        Some(start_point) => utils::resolve_commit(root_path, start_point)?,
+        None => utils::get_current_commit_hash(root_path)?.unwrap_or_default(),
+    };
+
+    let reflog_message = format!(
+        "branch: {} {}",
+        if exists { "Reset to" } else { "Created from" },
+        start_point.unwrap_or("HEAD")
+    );
+    // A branch created concurrently by another process must not be clobbered.
+    let expected_old = (!exists).then_some(utils::NULL_HASH);
+    utils::update_ref(
+        root_path,
+        &ref_name,
+        &commit_hash,
+        expected_old,
+        &reflog_message,
+    )?;
+
+    Ok(commit_hash)
 }
 
-/// Creates a branch reference pointing at the current commit.
-pub fn create_branch(root_path: &Path, branch_name: &str) -> Result<()> {
+/// Deletes `branch_name` and returns the commit it pointed to.
+///
+/// Unless `force` is set (`branch -D`), a branch whose commit is not reachable
+/// from HEAD is kept so no work is lost. The checked-out branch is never deleted.
+pub fn delete_branch(root_path: &Path, branch_name: &str, force: bool) -> Result<String> {
     utils::ensure_repo_exists(root_path)?;
 
-    let refs_path = root_path.join(".rustygit").join("refs").join("heads");
+    let ref_name = branch_ref(branch_name);
+    if !utils::ref_exists(root_path, &ref_name) {
+        bail!("Branch '{}' not found.", branch_name);
+    }
 
-    if refs_path.join(branch_name).exists() {
-        bail!("Branch '{}' already exists.", branch_name);
+    if utils::current_branch(root_path)?.as_deref() == Some(branch_name) {
+        bail!("Cannot delete branch '{}': it is checked out.", branch_name);
     }
 
-    let current_commit_hash = utils::get_current_commit_hash(root_path)?.unwrap_or_default();
+    let branch_commit = utils::read_ref(root_path, &ref_name)?.unwrap_or_default();
+
+    if !force && !branch_commit.is_empty() {
+        let merged = match utils::get_current_commit_hash(root_path)? {
+            Some(head_commit) => utils::is_ancestor(root_path, &branch_commit, &head_commit)?,
+            None => false,
+        };
 
-    fs::write(refs_path.join(branch_name), &current_commit_hash)?;
+        if !merged {
+            bail!(
+                "The branch '{}' is not fully merged.\nIf you are sure you want to delete it, run 'rustygit branch -D {}'.",
+                branch_name,
+                branch_name
+            );
+        }
+    }
+
+    let expected_old = (!branch_commit.is_empty()).then_some(branch_commit.as_str());
+    utils::delete_ref(root_path, &ref_name, expected_old)?;
+
+    Ok(branch_commit)
+}
+
+/// Renames `old_name` to `new_name`, keeping HEAD attached if `old_name` is checked out.
+pub fn rename_branch(root_path: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    utils::ensure_repo_exists(root_path)?;
+    utils::check_ref_name(new_name)?;
+
+    let old_ref = branch_ref(old_name);
+    let new_ref = branch_ref(new_name);
+
+    if !utils::ref_exists(root_path, &old_ref) {
+        bail!("Branch '{}' not found.", old_name);
+    }
+    if utils::ref_exists(root_path, &new_ref) {
+        bail!("Branch '{}' already exists.", new_name);
+    }
+
+    utils::rename_ref(
+        root_path,
+        &old_ref,
+        &new_ref,
+        &format!("Branch: renamed {} to {}", old_ref, new_ref),
+    )?;
+
+    if utils::current_branch(root_path)?.as_deref() == Some(old_name) {
+        utils::set_head_to_branch(root_path, new_name, None, None)?;
+    }
 
     Ok(())
 }
 
+/// Renames the checked-out branch to `new_name`.
+pub fn rename_current_branch(root_path: &Path, new_name: &str) -> Result<()> {
+    let current = utils::current_branch(root_path)?
+        .ok_or_else(|| anyhow!("Cannot rename: HEAD is detached."))?;
+    rename_branch(root_path, &current, new_name)
+}
+
 /// Lists all local branches and marks the currently checked-out branch.
 pub fn branch(root_path: &Path) -> Result<()> {
-    let current_branch = get_current_branch_name(root_path)?;
-    let refs_path = root_path.join(".rustygit").join("refs").join("heads");
-    let dir = fs::read_dir(refs_path)?;
-
-    if let Some(current) = current_branch {
-        for entry in dir {
-            let entry = entry?;
-            let branch_name = entry.file_name().into_string().unwrap();
-            if branch_name == current {
-                println!("* {}", branch_name);
-            } else {
-                println!("  {}", branch_name);
-            }
-        }
-    } else {
-        for entry in dir {
-            let entry = entry?;
-            let branch_name = entry.file_name().into_string().unwrap();
+    let current_branch = utils::current_branch(root_path)?;
+
+    for (branch_name, _) in utils::list_refs(root_path, "refs/heads")? {
+        if current_branch.as_deref() == Some(branch_name.as_str()) {
+            println!("* {}", branch_name);
+        } else {
             println!("  {}", branch_name);
         }
     }
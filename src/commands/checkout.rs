@@ -1,176 +1,172 @@
 //! Checkout logic for switching branches or commits safely.
 
-use crate::{commands::TreeEntry, utils};
+use crate::{
+    commands,
+    utils::{self, IndexEntry},
+};
 use anyhow::{Result, anyhow, bail};
-use std::{fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
-fn is_branch(root_path: &Path, branch_name: &str) -> Result<bool> {
-    let heads_dir_path = root_path.join(".rustygit").join("refs").join("heads");
-    let dir = fs::read_dir(heads_dir_path);
-
-    for entry in dir? {
-        let entry = entry?;
-        if entry.file_name() == branch_name {
-            return Ok(true);
-        }
-    }
-    Ok(false)
+fn is_branch(root_path: &Path, branch_name: &str) -> bool {
+    utils::ref_exists(root_path, &format!("refs/heads/{}", branch_name))
 }
 
-fn is_valid_hash(root_path: &Path, target: &str) -> Result<bool> {
-    if target.len() != 40 {
-        return Ok(false);
-    }
-
-    let path = root_path
-        .join(".rustygit")
-        .join("objects")
-        .join(&target[..2])
-        .join(&target[2..]);
-
-    if !path.exists() {
-        return Ok(false);
+/// Describes the current HEAD for the reflog: the branch name, or the commit hash when detached.
+fn describe_head(root_path: &Path) -> Result<String> {
+    match utils::current_branch(root_path)? {
+        Some(branch_name) => Ok(branch_name),
+        None => Ok(utils::get_current_commit_hash(root_path)?.unwrap_or_default()),
     }
-
-    Ok(true)
 }
 
-fn clear_repository(root_path: &Path) -> Result<()> {
-    let dir = fs::read_dir(root_path)?;
-
-    for entry in dir {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() {
-            // skip rustygit.exe on Windows
-            #[cfg(target_os = "windows")]
-            {
-                if let Some(file_name) = path.file_name() {
-                    if file_name == "rustygit.exe" {
-                        continue;
-                    }
-                }
-            }
-            // skip rustygit on Unix-like systems
-            #[cfg(not(target_os = "windows"))]
-            {
-                if let Some(file_name) = path.file_name() {
-                    if file_name == "rustygit.exe" {
-                        continue;
-                    }
-                }
-            }
-            fs::remove_file(path)?;
-        } else if path.is_dir() {
-            if path.ends_with(".rustygit") || path.ends_with(".git") {
-                continue;
-            }
-            fs::remove_dir_all(path)?;
-        }
-    }
-
-    Ok(())
+/// Reflog message for a switch; `@{-N}` is resolved by parsing these back.
+fn checkout_message(from: &str, to: &str) -> String {
+    format!("checkout: moving from {} to {}", from, to)
 }
 
-fn restore_tree(root_path: &Path, path: &Path, tree_hash: &str) -> Result<()> {
-    // Tree restoration is recursive: directories recurse into subtree hashes,
-    // while files materialize blob contents at the target path.
-    let tree_entries: Vec<TreeEntry> = utils::parse_tree(root_path, tree_hash)?;
-
-    for entry in tree_entries {
-        let entry_path = path.join(&entry.name);
-
-        if entry.mode == "40000" {
-            fs::create_dir_all(&entry_path)?;
-            let subtree_hash = utils::bytes_to_hex(&entry.hash);
-            restore_tree(root_path, &entry_path, &subtree_hash)?;
-        } else if entry.mode == "100644" {
-            let blob_hash = utils::bytes_to_hex(&entry.hash);
-            let blob_content = utils::parse_blob(root_path, &blob_hash)?;
-            fs::write(&entry_path, blob_content)?;
+/// Computes the index after moving from `head_entries` to `target_entries`.
+///
+/// Staged changes are carried over when their path is the same in both
+/// trees; a staged change to a path the checkout would replace is refused.
+fn checkout_index(
+    root_path: &Path,
+    head_entries: &HashMap<PathBuf, IndexEntry>,
+    target_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let index_entries = utils::read_effective_index(root_path)?;
+
+    let mut new_index = target_entries.clone();
+    let paths: HashSet<&PathBuf> = head_entries.keys().chain(index_entries.keys()).collect();
+
+    for path in paths {
+        let staged = index_entries.get(path);
+        if staged == head_entries.get(path) {
+            continue;
         }
+        if head_entries.get(path) != target_entries.get(path) {
+            bail!(
+                "Staged changes to '{}' would be overwritten by checkout",
+                path.display()
+            );
+        }
+        match staged {
+            Some(entry) => new_index.insert(path.clone(), entry.clone()),
+            None => new_index.remove(path),
+        };
     }
 
-    Ok(())
+    Ok(new_index)
 }
 
-fn checkout_hash(root_path: &Path, target: &str) -> Result<()> {
-    let commit_content = utils::parse_commit(root_path, target)?;
-
-    let n_idx = commit_content
-        .find('\n')
-        .ok_or_else(|| anyhow!("Malformed commit object: missing newline after tree"))?;
-
-    let tree_hash = &commit_content[5..n_idx];
-
-    utils::checkout_safety_check(root_path, Some(tree_hash.to_string()))?;
-
-    clear_repository(root_path)?;
-
-    restore_tree(root_path, root_path, tree_hash)?;
-
-    let head_path = root_path.join(".rustygit").join("HEAD");
-    fs::write(head_path, target)?;
-
-    Ok(())
+/// Moves the working tree and index from HEAD to `target_commit` (`None`
+/// for an unborn branch), touching only paths that differ between the two.
+///
+/// Untracked and ignored files are left alone; local changes that would be
+/// overwritten make the checkout fail before anything is changed.
+fn switch_tree(root_path: &Path, target_commit: Option<&str>) -> Result<()> {
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let head_entries = utils::get_commit_entries_map(root_path, head_commit.as_deref())?;
+    let target_entries = utils::get_commit_entries_map(root_path, target_commit)?;
+
+    let target_tree_hash = target_commit
+        .map(|commit_hash| utils::get_commit_tree_hash(root_path, commit_hash))
+        .transpose()?;
+    utils::checkout_safety_check(root_path, target_tree_hash)?;
+    let new_index = checkout_index(root_path, &head_entries, &target_entries)?;
+
+    utils::update_work_tree(root_path, &head_entries, &target_entries)?;
+    utils::write_index_entries(root_path, &new_index)
 }
 
-fn checkout_branch(root_path: &Path, branch_name: &str) -> Result<()> {
-    let branch_ref_path = root_path
-        .join(".rustygit")
-        .join("refs")
-        .join("heads")
-        .join(branch_name);
+fn checkout_hash(root_path: &Path, target: &str, reflog_message: &str) -> Result<()> {
+    switch_tree(root_path, Some(target))?;
 
-    let commit_hash = fs::read_to_string(branch_ref_path)?.trim().to_string();
-
-    clear_repository(root_path)?;
+    utils::update_ref(root_path, "HEAD", target, None, reflog_message)
+}
 
-    if !commit_hash.is_empty() {
-        let commit_content = utils::parse_commit(root_path, &commit_hash)?;
+fn checkout_branch(root_path: &Path, branch_name: &str, reflog_message: &str) -> Result<()> {
+    let commit_hash = utils::read_ref(root_path, &format!("refs/heads/{}", branch_name))?;
+    let head_commit = utils::get_current_commit_hash(root_path)?;
 
-        let n_idx = commit_content
-            .find('\n')
-            .ok_or_else(|| anyhow!("Malformed commit object: missing newline after tree"))?;
+    switch_tree(root_path, commit_hash.as_deref())?;
 
-        let tree_hash = &commit_content[5..n_idx];
+    utils::set_head_to_branch(
+        root_path,
+        branch_name,
+        Some(head_commit.as_deref().unwrap_or(utils::NULL_HASH)),
+        Some(reflog_message),
+    )
+}
 
-        utils::checkout_safety_check(root_path, Some(tree_hash.to_string()))?;
+/// Switches to `target` like [`checkout`] without printing anything.
+///
+/// Returns true when a branch was checked out, false when HEAD was detached.
+pub(crate) fn switch_to(root_path: &Path, target: &str) -> Result<bool> {
+    let previous = if target == "-" { "@{-1}" } else { target };
+    let target = utils::resolve_previous_checkout(root_path, previous)?
+        .unwrap_or_else(|| target.to_string());
 
-        clear_repository(root_path)?;
+    let from = describe_head(root_path)?;
 
-        restore_tree(root_path, root_path, tree_hash)?;
-    } else {
-        clear_repository(root_path)?;
+    if is_branch(root_path, &target) {
+        checkout_branch(root_path, &target, &checkout_message(&from, &target))?;
+        return Ok(true);
     }
 
-    let head_path = root_path.join(".rustygit").join("HEAD");
-    fs::write(head_path, format!("ref: refs/heads/{}\n", branch_name))?;
-
-    Ok(())
+    let commit_hash = utils::resolve_commit(root_path, &target).map_err(|err| {
+        anyhow!(
+            "'{}' is neither a valid branch name nor a commit: {}",
+            target,
+            err
+        )
+    })?;
+
+    checkout_hash(
+        root_path,
+        &commit_hash,
+        &checkout_message(&from, &commit_hash),
+    )?;
+    Ok(false)
 }
 
 /// Switches repository state to a branch or commit.
 ///
+/// `target` is a branch name, `-` / `@{-N}` for a previously checked-out
+/// branch or commit, or any revision expression accepted by
+/// [`utils::resolve_commit`], which detaches HEAD.
+///
 /// Includes safety checks to prevent overwriting uncommitted working-directory changes.
 pub fn checkout(root_path: &Path, target: &str) -> Result<()> {
     utils::ensure_repo_exists(root_path)?;
 
-    if is_branch(root_path, target)? {
-        checkout_branch(root_path, target)?;
-        println!("Switched to branch '{}'", target);
-    } else if is_valid_hash(root_path, target)? {
-        checkout_hash(root_path, target)?;
-        println!("Checked out commit '{}'", target);
+    if switch_to(root_path, target)? {
         println!(
-            "WARNING: You are in 'detached HEAD' state. To switch back to a branch, use 'rustygit checkout <branch-name>'."
-        );
-    } else {
-        bail!(
-            "'{}' is neither a valid branch name nor a commit hash.",
-            target
+            "Switched to branch '{}'",
+            utils::current_branch(root_path)?.unwrap_or_default()
         );
+        return Ok(());
     }
+
+    let commit_hash = utils::get_current_commit_hash(root_path)?.unwrap_or_default();
+    println!("Checked out commit '{}'", commit_hash);
+    println!(
+        "WARNING: You are in 'detached HEAD' state. To switch back to a branch, use 'rustygit checkout <branch-name>'."
+    );
     Ok(())
 }
+
+/// Restores the files selected by `pathspecs` without moving HEAD, like
+/// `checkout <source> -- <paths>`.
+///
+/// With a `source` revision both the index and working tree are updated from
+/// it; without one, working files are restored from the index.
+pub fn checkout_paths(
+    root_path: &Path,
+    source: Option<&str>,
+    pathspecs: &[String],
+) -> Result<Vec<PathBuf>> {
+    commands::restore_paths(root_path, pathspecs, source, source.is_some(), true)
+}
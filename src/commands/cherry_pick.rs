@@ -0,0 +1,361 @@
+//! Cherry-picking and reverting individual commits.
+//!
+//! Both apply the change a commit introduced relative to its parent to HEAD
+//! with a three-way merge: a cherry-pick merges the commit using its parent
+//! as the base, a revert merges the parent using the commit as the base.
+//! Commits are applied one after another, each as a new commit; a sequence
+//! that stops on conflicts keeps the remaining commits in the sequencer
+//! state for `--continue` and the starting point for `--abort`.
+
+use crate::commands::{self, ResetMode, merge};
+use crate::utils::{self, IndexEntry, Sequencer, SequencerAction};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// What a cherry-pick or revert did with one commit.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PickOutcome {
+    /// `commit` was created from `original`.
+    Committed { original: String, commit: String },
+    /// Applying `original` changed nothing, so it was skipped.
+    Empty(String),
+    /// Applying `original` left these paths unmerged; the sequence stopped.
+    Conflicted {
+        original: String,
+        paths: Vec<PathBuf>,
+    },
+}
+
+fn subject(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+/// Builds the message of the commit that picks or reverts `commit_hash`.
+fn pick_message(root_path: &Path, action: SequencerAction, commit_hash: &str) -> Result<String> {
+    let message = utils::get_commit_message(root_path, commit_hash)?;
+
+    Ok(match action {
+        SequencerAction::Pick => format!(
+            "{}\n\n(cherry picked from commit {})\n",
+            message.trim_end(),
+            commit_hash
+        ),
+        SequencerAction::Revert => format!(
+            "Revert \"{}\"\n\nThis reverts commit {}.\n",
+            subject(&message),
+            commit_hash
+        ),
+    })
+}
+
+fn head_commit(root_path: &Path, action: SequencerAction) -> Result<String> {
+    utils::get_current_commit_hash(root_path)?
+        .ok_or_else(|| anyhow!("Cannot {}: HEAD has no commits.", action.command()))
+}
+
+/// Commits `entries` on top of `head_commit` and moves HEAD there.
+fn commit_entries(
+    root_path: &Path,
+    action: SequencerAction,
+    head_commit: &str,
+    entries: &HashMap<PathBuf, IndexEntry>,
+    message: String,
+) -> Result<String> {
+    let reflog_message = format!("{}: {}", action.command(), subject(&message));
+    let tree_hash = commands::write_tree_from_entries(root_path, entries)?;
+    let commit_hash =
+        commands::commit_tree(root_path, &tree_hash, &[head_commit.to_string()], message)?;
+
+    utils::update_head(root_path, &commit_hash, Some(head_commit), &reflog_message)?;
+    Ok(commit_hash)
+}
+
+/// The change of one commit applied to the working tree and index.
+pub(crate) enum AppliedChange {
+    /// Applied cleanly; the resulting snapshot, ready to be committed.
+    Clean(HashMap<PathBuf, IndexEntry>),
+    /// The change is already present; nothing was written.
+    Empty,
+    /// These paths were left unmerged, with conflict markers and index stages.
+    Conflicted(Vec<PathBuf>),
+}
+
+/// Applies (or, for a revert, un-applies) the change `commit_hash` made to
+/// the `head_commit` snapshot, writing the result to the working tree and
+/// index without committing it.
+pub(crate) fn apply_change(
+    root_path: &Path,
+    action: SequencerAction,
+    commit_hash: &str,
+    head_commit: &str,
+) -> Result<AppliedChange> {
+    let parents = utils::get_commit_parents(root_path, commit_hash)?;
+    if parents.len() > 1 {
+        bail!(
+            "Commit {} is a merge; {} of merge commits is not supported.",
+            commit_hash,
+            action.command()
+        );
+    }
+
+    let head_entries = utils::get_commit_entries_map(root_path, Some(head_commit))?;
+    let parent_entries =
+        utils::get_commit_entries_map(root_path, parents.first().map(String::as_str))?;
+    let picked_entries = utils::get_commit_entries_map(root_path, Some(commit_hash))?;
+
+    let original_message = utils::get_commit_message(root_path, commit_hash)?;
+    let label = format!("{}... {}", &commit_hash[..7], subject(&original_message));
+    let (base_entries, their_entries, their_label) = match action {
+        SequencerAction::Pick => (&parent_entries, &picked_entries, label),
+        SequencerAction::Revert => (
+            &picked_entries,
+            &parent_entries,
+            format!("parent of {}", label),
+        ),
+    };
+
+    let result = merge::merge_entries(
+        root_path,
+        base_entries,
+        &head_entries,
+        their_entries,
+        "HEAD",
+        &their_label,
+    )?;
+
+    if result.unmerged.is_empty() && result.merged == head_entries {
+        return Ok(AppliedChange::Empty);
+    }
+
+    merge::write_content_merge(root_path, &head_entries, &result)?;
+
+    if !result.unmerged.is_empty() {
+        let mut paths: Vec<PathBuf> = result.unmerged.into_keys().collect();
+        paths.sort();
+        return Ok(AppliedChange::Conflicted(paths));
+    }
+
+    Ok(AppliedChange::Clean(result.merged))
+}
+
+/// Applies `commit_hash` with [`apply_change`] and commits a clean result; a
+/// conflicting one is recorded in `CHERRY_PICK_HEAD`/`REVERT_HEAD` and `MERGE_MSG`.
+fn apply_commit(
+    root_path: &Path,
+    action: SequencerAction,
+    commit_hash: &str,
+) -> Result<PickOutcome> {
+    let head_commit = head_commit(root_path, action)?;
+    let message = pick_message(root_path, action, commit_hash)?;
+
+    match apply_change(root_path, action, commit_hash, &head_commit)? {
+        AppliedChange::Clean(entries) => {
+            let commit = commit_entries(root_path, action, &head_commit, &entries, message)?;
+            Ok(PickOutcome::Committed {
+                original: commit_hash.to_string(),
+                commit,
+            })
+        }
+        AppliedChange::Empty => Ok(PickOutcome::Empty(commit_hash.to_string())),
+        AppliedChange::Conflicted(paths) => {
+            utils::write_pick_state(root_path, action, commit_hash, &message)?;
+            Ok(PickOutcome::Conflicted {
+                original: commit_hash.to_string(),
+                paths,
+            })
+        }
+    }
+}
+
+/// Applies every commit left in `sequencer`, stopping at the first conflict.
+///
+/// The sequencer state is saved when the sequence stops, so that
+/// `--continue` and `--abort` work; it is removed once the todo list is done.
+fn run_sequence(
+    root_path: &Path,
+    mut sequencer: Sequencer,
+    mut outcomes: Vec<PickOutcome>,
+) -> Result<Vec<PickOutcome>> {
+    while !sequencer.todo.is_empty() {
+        let commit_hash = sequencer.todo.remove(0);
+
+        let outcome = match apply_commit(root_path, sequencer.action, &commit_hash) {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                // Keep what was already applied resumable and abortable.
+                if !outcomes.is_empty() || utils::read_sequencer(root_path)?.is_some() {
+                    sequencer.todo.insert(0, commit_hash);
+                    utils::write_sequencer(root_path, &sequencer)?;
+                }
+                return Err(err);
+            }
+        };
+
+        let stopped = matches!(outcome, PickOutcome::Conflicted { .. });
+        outcomes.push(outcome);
+
+        if stopped {
+            utils::write_sequencer(root_path, &sequencer)?;
+            return Ok(outcomes);
+        }
+    }
+
+    utils::clear_sequencer(root_path)?;
+    Ok(outcomes)
+}
+
+/// Checks that nothing is in progress and the index matches HEAD, then
+/// applies each of `revisions` in order.
+fn start_sequence(
+    root_path: &Path,
+    action: SequencerAction,
+    revisions: &[String],
+) -> Result<Vec<PickOutcome>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if let Some(sequencer) = utils::read_sequencer(root_path)? {
+        let command = sequencer.action.command();
+        bail!(
+            "Cannot {}: a {} is already in progress.\nRun '{} --continue' or '{} --abort'.",
+            action.command(),
+            command,
+            command,
+            command
+        );
+    }
+
+    if utils::read_rebase_state(root_path)?.is_some() {
+        bail!("Cannot {}: a rebase is in progress.", action.command());
+    }
+
+    if utils::read_merge_head(root_path)?.is_some()
+        || !utils::read_index_conflicts(root_path)?.is_empty()
+    {
+        bail!(
+            "Cannot {}: there are unmerged paths. Resolve them or run 'merge --abort'.",
+            action.command()
+        );
+    }
+
+    let original_head = head_commit(root_path, action)?;
+    let head_entries = utils::get_commit_entries_map(root_path, Some(&original_head))?;
+    if utils::read_effective_index(root_path)? != head_entries {
+        bail!(
+            "Cannot {}: you have staged changes. Commit them first.",
+            action.command()
+        );
+    }
+
+    let todo = revisions
+        .iter()
+        .map(|revision| utils::resolve_commit(root_path, revision))
+        .collect::<Result<Vec<String>>>()?;
+
+    let sequencer = Sequencer {
+        action,
+        original_head,
+        todo,
+    };
+    run_sequence(root_path, sequencer, Vec::new())
+}
+
+/// Commits the resolution of a stopped cherry-pick or revert, unless the
+/// user already did, and applies the remaining commits.
+fn continue_sequence(root_path: &Path, action: SequencerAction) -> Result<Vec<PickOutcome>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let sequencer = utils::read_sequencer(root_path)?
+        .filter(|sequencer| sequencer.action == action)
+        .ok_or_else(|| anyhow!("There is no {} in progress.", action.command()))?;
+
+    let conflicts = utils::read_index_conflicts(root_path)?;
+    if !conflicts.is_empty() {
+        let mut paths: Vec<String> = conflicts.keys().map(|p| p.display().to_string()).collect();
+        paths.sort();
+        bail!(
+            "Cannot continue: unmerged paths: {}\nResolve the conflicts and 'add' each file first.",
+            paths.join(", ")
+        );
+    }
+
+    let mut outcomes = Vec::new();
+
+    if let Some((_, commit_hash)) = utils::read_pick_head(root_path)? {
+        let head_commit = head_commit(root_path, action)?;
+        let head_entries = utils::get_commit_entries_map(root_path, Some(&head_commit))?;
+        let index_entries = utils::read_effective_index(root_path)?;
+
+        if index_entries == head_entries {
+            outcomes.push(PickOutcome::Empty(commit_hash));
+        } else {
+            let message = utils::read_merge_msg(root_path)?.unwrap_or_default();
+            let commit = commit_entries(root_path, action, &head_commit, &index_entries, message)?;
+            outcomes.push(PickOutcome::Committed {
+                original: commit_hash,
+                commit,
+            });
+        }
+        utils::clear_merge_state(root_path)?;
+    }
+
+    run_sequence(root_path, sequencer, outcomes)
+}
+
+/// Abandons a stopped cherry-pick or revert, moving HEAD back to where the
+/// sequence started. Local changes to paths it did not touch are preserved.
+fn abort_sequence(root_path: &Path, action: SequencerAction) -> Result<()> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let sequencer = utils::read_sequencer(root_path)?
+        .filter(|sequencer| sequencer.action == action)
+        .ok_or_else(|| anyhow!("There is no {} in progress.", action.command()))?;
+
+    merge::discard_content_merge(root_path)?;
+    commands::reset(root_path, &sequencer.original_head, ResetMode::Keep)?;
+    utils::clear_merge_state(root_path)?;
+    utils::clear_sequencer(root_path)
+}
+
+/// Applies the changes introduced by each of `revisions` (any revision
+/// expressions) to HEAD, creating one commit per revision whose message
+/// ends with `(cherry picked from commit <hash>)`.
+///
+/// Commits whose changes are already present are skipped. On conflicts, the
+/// sequence stops with conflict markers and index stages; resolve them and
+/// run [`cherry_pick_continue`], or [`cherry_pick_abort`].
+pub fn cherry_pick(root_path: &Path, revisions: &[String]) -> Result<Vec<PickOutcome>> {
+    start_sequence(root_path, SequencerAction::Pick, revisions)
+}
+
+/// Commits the resolved cherry-pick and picks the remaining commits.
+pub fn cherry_pick_continue(root_path: &Path) -> Result<Vec<PickOutcome>> {
+    continue_sequence(root_path, SequencerAction::Pick)
+}
+
+/// Abandons a stopped cherry-pick and returns HEAD to where it started.
+pub fn cherry_pick_abort(root_path: &Path) -> Result<()> {
+    abort_sequence(root_path, SequencerAction::Pick)
+}
+
+/// Undoes the changes introduced by each of `revisions` with one new commit
+/// per revision, titled `Revert "<subject>"`, that records
+/// `This reverts commit <hash>.`
+///
+/// Conflicts stop the sequence as for [`cherry_pick`]; see
+/// [`revert_continue`] and [`revert_abort`].
+pub fn revert(root_path: &Path, revisions: &[String]) -> Result<Vec<PickOutcome>> {
+    start_sequence(root_path, SequencerAction::Revert, revisions)
+}
+
+/// Commits the resolved revert and reverts the remaining commits.
+pub fn revert_continue(root_path: &Path) -> Result<Vec<PickOutcome>> {
+    continue_sequence(root_path, SequencerAction::Revert)
+}
+
+/// Abandons a stopped revert and returns HEAD to where it started.
+pub fn revert_abort(root_path: &Path) -> Result<()> {
+    abort_sequence(root_path, SequencerAction::Revert)
+}
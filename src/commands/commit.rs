@@ -1,6 +1,6 @@
 //! Commit creation and index-to-history transitions.
 
-use crate::utils::IgnoreRule;
+use crate::utils::{IgnoreRule, IndexEntry, Signature};
 use crate::{commands, utils};
 use anyhow::{Result, bail};
 use std::fs;
@@ -9,31 +9,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
-#[derive(Clone)]
-struct User {
-    name: String,
-    email: String,
-}
-
 struct CommitObject {
     tree_hash: String,
-    parent: Option<String>,
-    author: User,
-    committer: User,
+    parents: Vec<String>,
+    author: Signature,
+    committer: Signature,
     message: String,
-    timestamp: i64,
-    timezone: String,
 }
 
-fn build_commit(path: &Path, message: String) -> Result<CommitObject> {
-    let empty_ignore_rules = Vec::new();
-    let tree_hash = commands::write_tree(path, path, &empty_ignore_rules)?;
-    let mut parent: Option<String> = None;
-    let author = User {
-        name: String::from("Shivam Bhagat"),
-        email: String::from("shivambhagat@rustygit.com"),
-    };
-    let committer = author.clone();
+fn get_head_parent(path: &Path) -> Result<Option<String>> {
     let head_path = path.join(".rustygit").join("HEAD");
     let head_content = fs::read_to_string(&head_path)?;
     let ref_path = head_content[5..].trim();
@@ -43,23 +27,11 @@ fn build_commit(path: &Path, message: String) -> Result<CommitObject> {
     if head_ref_path.exists() {
         let parent_content = fs::read_to_string(&head_ref_path)?;
         if !parent_content.trim().is_empty() {
-            parent = Some(parent_content.trim().to_string());
+            return Ok(Some(parent_content.trim().to_string()));
         }
     }
 
-    let (timestamp, timezone) = utils::get_time();
-
-    let commit_object = CommitObject {
-        tree_hash,
-        parent,
-        author,
-        committer,
-        message,
-        timestamp,
-        timezone,
-    };
-
-    Ok(commit_object)
+    Ok(None)
 }
 
 fn format_commit(commit_object: CommitObject) -> Vec<u8> {
@@ -67,30 +39,13 @@ fn format_commit(commit_object: CommitObject) -> Vec<u8> {
 
     formatted.extend_from_slice(format!("tree {}\n", commit_object.tree_hash).as_bytes());
 
-    if let Some(parent) = &commit_object.parent {
+    for parent in &commit_object.parents {
         formatted.extend_from_slice(format!("parent {}\n", parent).as_bytes());
     }
 
+    formatted.extend_from_slice(format!("author {}\n", commit_object.author.format()).as_bytes());
     formatted.extend_from_slice(
-        format!(
-            "author {} <{}> {} {}\n",
-            commit_object.author.name,
-            commit_object.author.email,
-            commit_object.timestamp,
-            commit_object.timezone
-        )
-        .as_bytes(),
-    );
-
-    formatted.extend_from_slice(
-        format!(
-            "committer {} <{}> {} {}\n\n",
-            commit_object.committer.name,
-            commit_object.committer.email,
-            commit_object.timestamp,
-            commit_object.timezone
-        )
-        .as_bytes(),
+        format!("committer {}\n\n", commit_object.committer.format()).as_bytes(),
     );
 
     formatted.extend_from_slice(commit_object.message.as_bytes());
@@ -98,29 +53,66 @@ fn format_commit(commit_object: CommitObject) -> Vec<u8> {
     formatted
 }
 
-fn update_head(repo_root: &Path, commit_hash: &str) -> Result<()> {
-    let head_path = repo_root.join(".rustygit").join("HEAD");
-    let head_content = fs::read_to_string(&head_path)?;
-    let ref_path = head_content[5..].trim();
+/// Writes a commit object for `tree_hash` with the given parents and returns its hash.
+///
+/// The author and committer come from config and environment overrides.
+/// No references are updated.
+pub fn commit_tree(
+    path: &Path,
+    tree_hash: &str,
+    parents: &[String],
+    message: String,
+) -> Result<String> {
+    commit_tree_with_author(
+        path,
+        tree_hash,
+        parents,
+        message,
+        utils::author_signature(path)?,
+    )
+}
 
-    let head_ref_path = repo_root.join(".rustygit").join(ref_path);
+/// Like [`commit_tree`], but records `author` instead of the configured
+/// identity, e.g. to recreate a commit received as a patch.
+pub fn commit_tree_with_author(
+    path: &Path,
+    tree_hash: &str,
+    parents: &[String],
+    message: String,
+    author: Signature,
+) -> Result<String> {
+    let commit_object = CommitObject {
+        tree_hash: tree_hash.to_string(),
+        parents: parents.to_vec(),
+        author,
+        committer: utils::committer_signature(path)?,
+        message,
+    };
 
-    fs::write(head_ref_path, format!("{}\n", commit_hash))?;
-    Ok(())
+    let data = format_commit(commit_object);
+    let mut content: Vec<u8> = Vec::new();
+
+    content.extend_from_slice(format!("commit {}\0", data.len()).as_bytes());
+    content.extend_from_slice(&data);
+
+    let hash = utils::hash_bytes(&content);
+    commands::write_object(path, &hash, &content)?;
+
+    Ok(hash)
 }
 
-fn get_current_tree_map(root_path: &Path) -> Result<HashMap<PathBuf, String>> {
+fn get_current_tree_map(root_path: &Path) -> Result<HashMap<PathBuf, IndexEntry>> {
     let mut current_tree_map = HashMap::new();
 
     if let Some(tree_hash) = utils::get_current_tree_hash(root_path)? {
-        utils::get_tree_files_map(root_path, Path::new(""), &tree_hash, &mut current_tree_map)?;
+        utils::get_tree_entries_map(root_path, Path::new(""), &tree_hash, &mut current_tree_map)?;
     }
 
     Ok(current_tree_map)
 }
 
 fn ensure_index_has_head_snapshot(root_path: &Path) -> Result<()> {
-    let index_map = utils::read_index_map(root_path)?;
+    let index_map = utils::read_index_entries(root_path)?;
     if !index_map.is_empty() {
         return Ok(());
     }
@@ -130,34 +122,40 @@ fn ensure_index_has_head_snapshot(root_path: &Path) -> Result<()> {
         return Ok(());
     }
 
-    utils::write_index_map(root_path, &current_tree_map)?;
+    utils::write_index_entries(root_path, &current_tree_map)?;
     Ok(())
 }
 
 fn auto_stage_tracked_files(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<()> {
     // `commit -a` stages tracked modifications and deletions by comparing
-    // the current HEAD tree with working-directory hashes.
+    // the current HEAD tree with working-directory hashes and modes.
     // Untracked paths are intentionally ignored.
     let current_tree_map = get_current_tree_map(root_path)?;
     let mut work_dir_map = HashMap::new();
-    utils::get_work_dir_map(root_path, Path::new(""), &mut work_dir_map)?;
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_dir_map)?;
 
-    let mut index_map = utils::read_index_map(root_path)?;
+    let mut index_map = utils::read_index_entries(root_path)?;
 
-    for (path, hash) in &current_tree_map {
-        index_map.entry(path.clone()).or_insert(hash.clone());
+    for (path, entry) in &current_tree_map {
+        index_map.entry(path.clone()).or_insert(entry.clone());
     }
 
-    for (path, current_hash) in &current_tree_map {
+    for (path, current_entry) in &current_tree_map {
         if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
             continue;
         }
 
         match work_dir_map.get(path) {
-            Some(work_hash) => {
-                if work_hash != current_hash {
+            Some(work_entry) => {
+                if work_entry != current_entry {
                     let blob_hash = commands::write_blob(root_path, &root_path.join(path))?;
-                    index_map.insert(path.clone(), blob_hash);
+                    index_map.insert(
+                        path.clone(),
+                        IndexEntry {
+                            mode: work_entry.mode,
+                            hash: blob_hash,
+                        },
+                    );
                 }
             }
             None => {
@@ -166,21 +164,24 @@ fn auto_stage_tracked_files(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) ->
         }
     }
 
-    utils::write_index_map(root_path, &index_map)?;
+    utils::write_index_entries(root_path, &index_map)?;
     Ok(())
 }
 
 /// Creates a commit from the index, optionally auto-staging tracked changes.
 ///
 /// When `all` is true, tracked modifications and deletions are staged first,
-/// matching the behavior of `commit -a`.
+/// matching the behavior of `commit -a`. Refuses to run while the index has
+/// unmerged paths; after a conflicted merge, the commit gets `MERGE_HEAD` as
+/// its second parent and `MERGE_MSG` as the default message (which is also
+/// the default after a conflicted cherry-pick or revert).
 pub fn commit_with_all(
     path: &Path,
     message: String,
     ignore_rules: &Vec<IgnoreRule>,
     all: bool,
 ) -> Result<String> {
-    utils::ensure_repo_exists(&path)?;
+    utils::ensure_repo_exists(path)?;
 
     // ensure head is attached
     let head_path = path.join(".rustygit").join("HEAD");
@@ -190,24 +191,49 @@ pub fn commit_with_all(
         bail!("Cannot commit: HEAD is detached.");
     }
 
+    let conflicts = utils::read_index_conflicts(path)?;
+    if !conflicts.is_empty() {
+        let mut paths: Vec<String> = conflicts.keys().map(|p| p.display().to_string()).collect();
+        paths.sort();
+        bail!(
+            "Cannot commit: unmerged paths: {}\nResolve the conflicts and 'add' each file first.",
+            paths.join(", ")
+        );
+    }
+
     if all {
         auto_stage_tracked_files(path, ignore_rules)?;
     } else {
         ensure_index_has_head_snapshot(path)?;
     }
 
-    let commit_object = build_commit(path, message)?;
-    let data = format_commit(commit_object);
-    let mut content: Vec<u8> = Vec::new();
-
-    content.extend_from_slice(format!("commit {}\0", data.len()).as_bytes());
-    content.extend_from_slice(&data);
+    // A merge, cherry-pick or revert stopped on conflicts is concluded by this commit.
+    let merge_head = utils::read_merge_head(path)?;
+    let stopped = merge_head.is_some() || utils::read_pick_head(path)?.is_some();
+    let message = match utils::read_merge_msg(path)? {
+        Some(merge_msg) if stopped && message.is_empty() => merge_msg,
+        _ => message,
+    };
 
-    let hash = utils::hash_bytes(&content);
-    commands::write_object(path, &hash, &content)?;
+    let empty_ignore_rules = Vec::new();
+    let tree_hash = commands::write_tree(path, path, &empty_ignore_rules)?;
+    let mut parents: Vec<String> = get_head_parent(path)?.into_iter().collect();
+    parents.extend(merge_head);
+
+    let reflog_message = format!(
+        "commit{}: {}",
+        match parents.len() {
+            0 => " (initial)",
+            1 => "",
+            _ => " (merge)",
+        },
+        message.lines().next().unwrap_or_default()
+    );
+    let hash = commit_tree(path, &tree_hash, &parents, message)?;
 
-    update_head(path, &hash)?;
-    utils::clear_index(path)?;
+    let old_commit = parents.first().map_or(utils::NULL_HASH, String::as_str);
+    utils::update_head(path, &hash, Some(old_commit), &reflog_message)?;
+    utils::clear_merge_state(path)?;
 
     Ok(hash)
 }
@@ -0,0 +1,43 @@
+//! Reading and writing repository and global configuration.
+
+use crate::utils::{self, ConfigScope};
+use anyhow::{Result, anyhow, bail};
+use std::path::Path;
+
+fn ensure_scope_available(root_path: &Path, scope: ConfigScope) -> Result<()> {
+    // The global file can be edited from anywhere; the repository file needs a repository.
+    if scope == ConfigScope::Local {
+        utils::ensure_repo_exists(root_path)?;
+    }
+    Ok(())
+}
+
+/// Returns the effective value of `key`, preferring repository over global config.
+pub fn config_get(root_path: &Path, key: &str) -> Result<String> {
+    utils::get_config_value(root_path, key)?
+        .ok_or_else(|| anyhow!("Config key '{}' is not set.", key))
+}
+
+/// Sets `key` to `value` in the repository or global config file.
+pub fn config_set(root_path: &Path, scope: ConfigScope, key: &str, value: &str) -> Result<()> {
+    ensure_scope_available(root_path, scope)?;
+    utils::set_config_value(root_path, scope, key, value)
+}
+
+/// Removes `key` from the repository or global config file.
+pub fn config_unset(root_path: &Path, scope: ConfigScope, key: &str) -> Result<()> {
+    ensure_scope_available(root_path, scope)?;
+
+    if !utils::unset_config_value(root_path, scope, key)? {
+        bail!("Config key '{}' is not set.", key);
+    }
+    Ok(())
+}
+
+/// Lists every configured `key=value` pair, global entries first.
+pub fn config_list(root_path: &Path) -> Result<Vec<String>> {
+    Ok(utils::list_config(root_path)?
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect())
+}
@@ -1,108 +1,321 @@
-//! Working-directory diff rendering against HEAD-tracked content.
+//! Diff rendering between the working tree, the index and commits.
 
-use crate::utils::{self, IgnoreRule};
-use anyhow::Result;
+use crate::utils::{self, IndexEntry, RenameOptions, TreeChange};
+use anyhow::{Result, bail};
 use similar::TextDiff;
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
 };
 
-/// Prints line-level differences between working directory files and HEAD.
-pub fn diff(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<()> {
-    let mut work_dir_map: HashMap<PathBuf, String> = HashMap::new();
-    let cur_tree_hash = utils::get_current_tree_hash(root_path)?;
+/// The two snapshots `diff` compares.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffMode {
+    /// The working tree against the index: `diff`.
+    WorkTree,
+    /// The index against a commit (HEAD when `None`): `diff --cached [<rev>]`.
+    Cached(Option<String>),
+    /// The working tree against a commit: `diff <rev>`.
+    Commit(String),
+    /// One commit against another: `diff <rev1> <rev2>` or `<rev1>..<rev2>`.
+    Commits(String, String),
+}
 
-    utils::get_work_dir_map(root_path, Path::new(""), &mut work_dir_map)?;
+impl DiffMode {
+    /// Picks the mode from command-line revisions.
+    ///
+    /// A single `<rev1>..<rev2>` argument compares two commits, with an
+    /// empty side standing for HEAD.
+    pub fn from_args(cached: bool, revisions: &[String]) -> Result<DiffMode> {
+        let mode = match revisions {
+            [] if cached => DiffMode::Cached(None),
+            [] => DiffMode::WorkTree,
+            [range] if range.contains("..") => {
+                let (from, to) = range.split_once("..").unwrap_or_default();
+                let side = |revision: &str| {
+                    if revision.is_empty() {
+                        String::from("HEAD")
+                    } else {
+                        revision.to_string()
+                    }
+                };
+                DiffMode::Commits(side(from), side(to))
+            }
+            [revision] if cached => DiffMode::Cached(Some(revision.clone())),
+            [revision] => DiffMode::Commit(revision.clone()),
+            [from, to] => DiffMode::Commits(from.clone(), to.clone()),
+            _ => bail!("diff takes at most two revisions."),
+        };
 
-    let mut cur_tree_map: HashMap<PathBuf, String> = HashMap::new();
-    if let Some(hash) = cur_tree_hash {
-        utils::get_tree_files_map(root_path, Path::new(""), &hash, &mut cur_tree_map)?;
+        if cached && matches!(mode, DiffMode::Commits(..)) {
+            bail!("diff --cached takes at most one revision.");
+        }
+        Ok(mode)
     }
 
-    let mut modified_files: Vec<PathBuf> = Vec::new();
-    let mut untracked_files: Vec<PathBuf> = Vec::new();
-    let mut deleted_files: Vec<PathBuf> = Vec::new();
+    /// Returns true when the new side of the comparison is the working tree.
+    fn new_is_work_tree(&self) -> bool {
+        matches!(self, DiffMode::WorkTree | DiffMode::Commit(_))
+    }
+}
 
-    for (path, work_hash) in work_dir_map.iter() {
-        if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
-            continue;
+fn commit_entries(root_path: &Path, revision: &str) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let commit_hash = utils::resolve_commit(root_path, revision)?;
+    utils::get_commit_entries_map(root_path, Some(&commit_hash))
+}
+
+fn head_entries(root_path: &Path) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    utils::get_commit_entries_map(root_path, head_commit.as_deref())
+}
+
+/// Returns the working tree entries of the paths tracked in `index`.
+fn tracked_work_entries(
+    root_path: &Path,
+    index: &HashMap<PathBuf, IndexEntry>,
+) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let mut work_dir_map: HashMap<PathBuf, IndexEntry> = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_dir_map)?;
+
+    work_dir_map.retain(|path, _| index.contains_key(path));
+    Ok(work_dir_map)
+}
+
+/// Returns the changes between the two snapshots `mode` names, limited to
+/// `pathspecs` (everything when empty), sorted by path.
+///
+/// Untracked files are never reported. Unmerged paths are left out of the
+/// comparisons involving the index; [`diff`] lists them separately.
+pub fn diff_changes(
+    root_path: &Path,
+    mode: &DiffMode,
+    pathspecs: &[String],
+) -> Result<Vec<TreeChange>> {
+    utils::ensure_repo_exists(root_path)?;
+    let pathspecs = utils::parse_pathspecs(root_path, pathspecs)?;
+
+    let (mut old, mut new) = match mode {
+        DiffMode::WorkTree => {
+            let index = utils::read_effective_index(root_path)?;
+            let work = tracked_work_entries(root_path, &index)?;
+            (index, work)
+        }
+        DiffMode::Cached(revision) => {
+            let old = match revision {
+                Some(revision) => commit_entries(root_path, revision)?,
+                None => head_entries(root_path)?,
+            };
+            (old, utils::read_effective_index(root_path)?)
         }
-        let in_current = cur_tree_map.get(path);
+        DiffMode::Commit(revision) => {
+            let index = utils::read_effective_index(root_path)?;
+            (
+                commit_entries(root_path, revision)?,
+                tracked_work_entries(root_path, &index)?,
+            )
+        }
+        DiffMode::Commits(from, to) => (
+            commit_entries(root_path, from)?,
+            commit_entries(root_path, to)?,
+        ),
+    };
 
-        match in_current {
-            Some(cur_hash) => {
-                if work_hash != cur_hash {
-                    modified_files.push(path.clone());
-                }
+    if !matches!(mode, DiffMode::Commits(..)) {
+        for path in utils::read_index_conflicts(root_path)?.keys() {
+            old.remove(path);
+            new.remove(path);
+        }
+    }
+
+    Ok(utils::diff_entry_maps(&old, &new, &pathspecs))
+}
+
+/// Number of lines of context `diff` shows around each change by default.
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// Abbreviated all-zero hash standing for a missing side in `index` lines.
+const NULL_ABBREV: &str = "0000000";
+
+fn abbrev(hash: &str) -> &str {
+    &hash[..7.min(hash.len())]
+}
+
+/// Reads one side of a change from the object store, or from the working
+/// tree when `work_tree` is set.
+fn read_side(
+    root_path: &Path,
+    path: &Path,
+    entry: &IndexEntry,
+    work_tree: bool,
+) -> Result<Vec<u8>> {
+    if work_tree {
+        utils::read_work_file(&root_path.join(path))
+    } else {
+        utils::read_blob(root_path, &entry.hash)
+    }
+}
+
+/// Renders `changes` as a Git-style unified diff with `context` lines
+/// around each change.
+///
+/// Each file gets a `diff --git` header, `new file`/`deleted file`/`old
+/// mode`/`new mode` lines as needed, `similarity index` and `rename
+/// from`/`rename to` (or `copy from`/`copy to`) lines for renames and
+/// copies, and an `index <old>..<new>` line unless the content is the same,
+/// then `---`/`+++` headers (`/dev/null` for a missing side) and `@@`
+/// hunks. Binary files get a `Binary files ... differ` line instead. The
+/// new side is read from the working tree when `work_tree` is set.
+pub(crate) fn unified_diff(
+    root_path: &Path,
+    changes: &[TreeChange],
+    work_tree: bool,
+    context: usize,
+) -> Result<String> {
+    let mut text = String::new();
+
+    for change in changes {
+        let path = change.path.to_string_lossy().replace('\\', "/");
+        let old_path = change.old_path().to_string_lossy().replace('\\', "/");
+        text.push_str(&format!("diff --git a/{} b/{}\n", old_path, path));
+
+        match (&change.old, &change.new) {
+            (None, Some(new)) => {
+                text.push_str(&format!("new file mode {}\n", new.mode));
+                text.push_str(&format!("index {}..{}\n", NULL_ABBREV, abbrev(&new.hash)));
             }
-            None => {
-                untracked_files.push(path.clone());
+            (Some(old), None) => {
+                text.push_str(&format!("deleted file mode {}\n", old.mode));
+                text.push_str(&format!("index {}..{}\n", abbrev(&old.hash), NULL_ABBREV));
             }
+            (Some(old), Some(new)) => {
+                if old.mode != new.mode {
+                    text.push_str(&format!("old mode {}\nnew mode {}\n", old.mode, new.mode));
+                }
+                if let Some(source) = &change.source {
+                    let verb = if source.copy { "copy" } else { "rename" };
+                    text.push_str(&format!("similarity index {}%\n", source.similarity));
+                    text.push_str(&format!(
+                        "{} from {}\n{} to {}\n",
+                        verb, old_path, verb, path
+                    ));
+                }
+                if old.hash != new.hash {
+                    text.push_str(&format!(
+                        "index {}..{}",
+                        abbrev(&old.hash),
+                        abbrev(&new.hash)
+                    ));
+                    if old.mode == new.mode {
+                        text.push_str(&format!(" {}", new.mode));
+                    }
+                    text.push('\n');
+                }
+            }
+            (None, None) => {}
         }
-    }
 
-    for (path, _) in cur_tree_map.iter() {
-        if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
+        if let (Some(old), Some(new)) = (&change.old, &change.new)
+            && old.hash == new.hash
+        {
             continue;
         }
-        let in_work_dir = work_dir_map.get(path);
 
-        if in_work_dir.is_none() {
-            deleted_files.push(path.clone());
+        let old_content = match &change.old {
+            Some(entry) => utils::read_blob(root_path, &entry.hash)?,
+            None => Vec::new(),
+        };
+        let new_content = match &change.new {
+            Some(entry) => read_side(root_path, &change.path, entry, work_tree)?,
+            None => Vec::new(),
+        };
+        let old_label = match change.old {
+            Some(_) => format!("a/{}", old_path),
+            None => String::from("/dev/null"),
+        };
+        let new_label = match change.new {
+            Some(_) => format!("b/{}", path),
+            None => String::from("/dev/null"),
+        };
+
+        if utils::is_binary(&old_content) || utils::is_binary(&new_content) {
+            text.push_str(&format!(
+                "Binary files {} and {} differ\n",
+                old_label, new_label
+            ));
+            continue;
         }
-    }
 
-    if untracked_files.is_empty() && modified_files.is_empty() && deleted_files.is_empty() {
-        println!("No changes.");
-        return Ok(());
-    }
+        let old_string = String::from_utf8_lossy(&old_content);
+        let new_string = String::from_utf8_lossy(&new_content);
+        let hunks = TextDiff::from_lines(old_string.as_ref(), new_string.as_ref())
+            .unified_diff()
+            .context_radius(context)
+            .missing_newline_hint(true)
+            .to_string();
 
-    if !untracked_files.is_empty() {
-        println!("\nNew files Created:");
-        for file in untracked_files {
-            let lines = std::fs::read_to_string(root_path.join(&file))?
-                .lines()
-                .count();
-            println!("\t{} (+{} lines)", file.display(), lines);
+        // An empty file added or deleted has no hunks and no ---/+++ lines.
+        if !hunks.is_empty() {
+            text.push_str(&format!("--- {}\n+++ {}\n{}", old_label, new_label, hunks));
         }
     }
 
-    if !modified_files.is_empty() {
-        println!("\nModified files:");
-        for file in modified_files {
-            println!("\t{}", file.display());
-            let work_file_string = std::fs::read_to_string(root_path.join(&file))?;
-            let tree_file_string = utils::parse_blob(root_path, &cur_tree_map[&file])?;
-
-            let diff = TextDiff::from_lines(&tree_file_string, &work_file_string);
-
-            for change in diff.iter_all_changes() {
-                match change.tag() {
-                    similar::ChangeTag::Delete => {
-                        if let Some(line_no) = change.old_index() {
-                            print!("\t\t-{:>4} | {}", line_no + 1, change);
-                        }
-                    }
-                    similar::ChangeTag::Insert => {
-                        if let Some(line_no) = change.new_index() {
-                            print!("\t\t+{:>4} | {}", line_no + 1, change);
-                        }
-                    }
-                    similar::ChangeTag::Equal => {}
-                }
-            }
-        }
+    Ok(text)
+}
+
+/// Returns the unified diff (see [`unified_diff`]) between the two
+/// snapshots `mode` names, limited to `pathspecs`, with `context` lines of
+/// context and renames detected at the default threshold. Unmerged paths
+/// are listed first as `* Unmerged path <path>`.
+///
+/// The result is empty when nothing differs.
+pub fn diff(
+    root_path: &Path,
+    mode: &DiffMode,
+    pathspecs: &[String],
+    context: usize,
+) -> Result<String> {
+    diff_with_renames(
+        root_path,
+        mode,
+        pathspecs,
+        context,
+        Some(RenameOptions::default()),
+    )
+}
+
+/// Like [`diff`], but detects renames (and copies) as `renames` says, or
+/// not at all when it is `None`. Only paths within `pathspecs` are paired.
+pub fn diff_with_renames(
+    root_path: &Path,
+    mode: &DiffMode,
+    pathspecs: &[String],
+    context: usize,
+    renames: Option<RenameOptions>,
+) -> Result<String> {
+    let mut changes = diff_changes(root_path, mode, pathspecs)?;
+    if let Some(options) = renames {
+        changes = utils::detect_renames(root_path, changes, mode.new_is_work_tree(), options)?;
     }
+    let mut text = String::new();
+
+    if !matches!(mode, DiffMode::Commits(..)) {
+        let pathspecs = utils::parse_pathspecs(root_path, pathspecs)?;
+        let mut unmerged_paths: Vec<PathBuf> = utils::read_index_conflicts(root_path)?
+            .into_keys()
+            .filter(|path| utils::matches_pathspecs(path, &pathspecs))
+            .collect();
+        unmerged_paths.sort();
 
-    if !deleted_files.is_empty() {
-        println!("\nDeleted files:");
-        for file in deleted_files {
-            let lines = utils::parse_blob(root_path, &cur_tree_map[&file])?
-                .lines()
-                .count();
-            println!("\t{} (-{} lines)", file.display(), lines);
+        for path in unmerged_paths {
+            text.push_str(&format!("* Unmerged path {}\n", path.display()));
         }
     }
-    Ok(())
+
+    text.push_str(&unified_diff(
+        root_path,
+        &changes,
+        mode.new_is_work_tree(),
+        context,
+    )?);
+    Ok(text)
 }
@@ -0,0 +1,117 @@
+//! Exporting commits as mailbox patches.
+
+use crate::commands::{self, diff};
+use crate::utils;
+use anyhow::Result;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Longest subject part of a patch file name, as in Git.
+const MAX_NAME_SUBJECT: usize = 52;
+
+/// Returns the commits in `range`, parents before children: those reachable
+/// from the end of the range but not from its start. Merge commits are left
+/// out.
+fn range_commits(root_path: &Path, range: &str) -> Result<Vec<String>> {
+    let (since, until) = range.split_once("..").unwrap_or((range, "HEAD"));
+    let side = |revision: &str| {
+        let revision = if revision.is_empty() {
+            "HEAD"
+        } else {
+            revision
+        };
+        utils::resolve_commit(root_path, revision)
+    };
+    let (since, until) = (side(since)?, side(until)?);
+
+    let excluded = utils::collect_ancestors(root_path, &since)?;
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut commits = Vec::new();
+    let mut stack = vec![(until, false)];
+
+    // Depth-first, emitting each commit once all of its parents are done.
+    while let Some((commit_hash, parents_done)) = stack.pop() {
+        let parents = utils::get_commit_parents(root_path, &commit_hash)?;
+
+        if parents_done {
+            if parents.len() <= 1 {
+                commits.push(commit_hash);
+            }
+            continue;
+        }
+        if excluded.contains(&commit_hash) || !seen.insert(commit_hash.clone()) {
+            continue;
+        }
+
+        stack.push((commit_hash, true));
+        for parent_hash in parents.into_iter().rev() {
+            stack.push((parent_hash, false));
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Turns a subject into a file name part: runs of characters other than
+/// letters, digits, `.` and `_` become a single `-`.
+fn sanitize_subject(subject: &str) -> String {
+    let mut name = String::new();
+
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+
+    name.truncate(MAX_NAME_SUBJECT);
+    name.trim_end_matches(['-', '.']).to_string()
+}
+
+/// Writes each commit in `range` to `output_dir` as an mbox-formatted
+/// `NNNN-<subject>.patch` file and returns the files in order.
+///
+/// `range` is `<since>` (the commits HEAD has that `<since>` lacks) or
+/// `<since>..<until>`. Each file carries the author, author date, message
+/// and diff of its commit; see [`utils::format_mail`].
+pub fn format_patch(root_path: &Path, range: &str, output_dir: &Path) -> Result<Vec<PathBuf>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let commits = range_commits(root_path, range)?;
+    if !commits.is_empty() {
+        fs::create_dir_all(output_dir)?;
+    }
+
+    let mut files = Vec::new();
+    for (index, commit_hash) in commits.iter().enumerate() {
+        let parent = utils::get_commit_parents(root_path, commit_hash)?
+            .into_iter()
+            .next();
+        let old = utils::get_commit_entries_map(root_path, parent.as_deref())?;
+        let new = utils::get_commit_entries_map(root_path, Some(commit_hash))?;
+        let changes = utils::diff_entry_maps(&old, &new, &[]);
+        let patch = diff::unified_diff(root_path, &changes, false, commands::DEFAULT_CONTEXT)?;
+
+        let message = utils::get_commit_message(root_path, commit_hash)?;
+        let author = utils::get_commit_author(root_path, commit_hash)?;
+        let mail = utils::format_mail(
+            commit_hash,
+            &author,
+            &message,
+            index + 1,
+            commits.len(),
+            &patch,
+        )?;
+
+        let subject = sanitize_subject(&utils::mail_subject(&message));
+        let file = output_dir.join(format!("{:04}-{}.patch", index + 1, subject));
+        fs::write(&file, mail)?;
+        files.push(file);
+    }
+
+    Ok(files)
+}
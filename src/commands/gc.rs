@@ -0,0 +1,284 @@
+//! Garbage collection: repacking reachable objects and pruning unreachable ones.
+
+use crate::utils::{self, PackObject};
+use anyhow::{Result, anyhow};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Counts reported by `gc` and `repack`.
+pub struct GcSummary {
+    pub packed: usize,
+    pub deltas: usize,
+    pub removed_loose: usize,
+    pub pruned: usize,
+}
+
+fn collect_ref_hashes(dir: &Path, hashes: &mut Vec<String>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            collect_ref_hashes(&path, hashes)?;
+        } else if path.extension().is_none_or(|ext| ext != "lock") {
+            let content = fs::read_to_string(&path)?.trim().to_string();
+            if content.len() == 40 {
+                hashes.push(content);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns every object hash that anchors reachability: HEAD, all refs, reflog
+/// entries, staged blobs, and the state of an in-progress merge or bisect.
+fn collect_roots(root_path: &Path) -> Result<Vec<String>> {
+    let mut roots = Vec::new();
+
+    if let Some(head_hash) = utils::get_current_commit_hash(root_path).unwrap_or(None) {
+        roots.push(head_hash);
+    }
+
+    collect_ref_hashes(&root_path.join(".rustygit").join("refs"), &mut roots)?;
+
+    // Staged but uncommitted blobs must survive collection too.
+    roots.extend(utils::read_index_map(root_path)?.into_values());
+
+    // Reflogs keep old branch positions recoverable after a bad reset.
+    roots.extend(utils::reflog_hashes(root_path)?);
+
+    // So must the stages of unmerged paths and a commit being merged in.
+    for conflict in utils::read_index_conflicts(root_path)?.into_values() {
+        for entry in [conflict.base, conflict.ours, conflict.theirs]
+            .into_iter()
+            .flatten()
+        {
+            roots.push(entry.hash);
+        }
+    }
+    roots.extend(utils::read_merge_head(root_path)?);
+
+    // Bisect marks name commits that may no longer be on any branch.
+    if let Some(state) = utils::read_bisect_state(root_path)? {
+        roots.extend(state.bad);
+        roots.extend(state.good);
+        roots.extend(state.skip);
+    }
+
+    Ok(roots)
+}
+
+/// Walks commits, tags and trees from `roots`, returning every reachable object.
+fn collect_reachable(root_path: &Path, roots: Vec<String>) -> Result<Vec<PackObject>> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut objects: Vec<PackObject> = Vec::new();
+    let mut stack: Vec<(String, String)> = roots
+        .into_iter()
+        .map(|hash| (hash, String::new()))
+        .collect();
+
+    while let Some((hash, name_hint)) = stack.pop() {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+
+        let (object_type, data) = utils::split_object(&utils::read_object(root_path, &hash)?)?;
+
+        match object_type.as_str() {
+            "commit" | "tag" => {
+                let text = String::from_utf8_lossy(&data);
+                for line in text.lines() {
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(linked) = line
+                        .strip_prefix("tree ")
+                        .or_else(|| line.strip_prefix("parent "))
+                        .or_else(|| line.strip_prefix("object "))
+                    {
+                        stack.push((linked.trim().to_string(), String::new()));
+                    }
+                }
+            }
+            "tree" => {
+                for entry in utils::parse_tree(root_path, &hash)? {
+                    let entry_name = if name_hint.is_empty() {
+                        entry.name.clone()
+                    } else {
+                        format!("{}/{}", name_hint, entry.name)
+                    };
+                    stack.push((utils::bytes_to_hex(&entry.hash), entry_name));
+                }
+            }
+            _ => {}
+        }
+
+        objects.push(PackObject {
+            hash,
+            object_type,
+            data,
+            name_hint,
+        });
+    }
+
+    Ok(objects)
+}
+
+/// Lists loose objects as `(hash, path)` pairs.
+fn list_loose_objects(root_path: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let objects_dir = root_path.join(".rustygit").join("objects");
+    let mut loose = Vec::new();
+
+    for dir_entry in fs::read_dir(objects_dir)? {
+        let dir_entry = dir_entry?;
+        let dir_name = dir_entry.file_name().to_string_lossy().to_string();
+
+        if dir_name.len() != 2 || !dir_entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        for file_entry in fs::read_dir(dir_entry.path())? {
+            let file_entry = file_entry?;
+            let file_name = file_entry.file_name().to_string_lossy().to_string();
+
+            if file_name.len() == 38 {
+                loose.push((format!("{}{}", dir_name, file_name), file_entry.path()));
+            }
+        }
+    }
+
+    Ok(loose)
+}
+
+fn remove_empty_fanout_dirs(root_path: &Path) -> Result<()> {
+    let objects_dir = root_path.join(".rustygit").join("objects");
+
+    for dir_entry in fs::read_dir(objects_dir)? {
+        let dir_entry = dir_entry?;
+        if dir_entry.file_name().len() == 2
+            && dir_entry.file_type()?.is_dir()
+            && fs::read_dir(dir_entry.path())?.next().is_none()
+        {
+            fs::remove_dir(dir_entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_expired(modified: SystemTime, grace_period: Option<Duration>) -> bool {
+    match grace_period {
+        None => false,
+        Some(grace_period) => SystemTime::now()
+            .duration_since(modified)
+            .map(|age| age >= grace_period)
+            .unwrap_or(false),
+    }
+}
+
+/// Packs all reachable objects into a single pack and deletes redundant copies.
+///
+/// Unreachable objects found in old packs are written back as loose objects
+/// (keeping the pack's modification time) unless they are older than
+/// `grace_period`; unreachable loose objects older than `grace_period` are deleted.
+/// With `grace_period = None` nothing is pruned.
+fn repack_objects(root_path: &Path, grace_period: Option<Duration>) -> Result<GcSummary> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let reachable = collect_reachable(root_path, collect_roots(root_path)?)?;
+    let reachable_hashes: HashSet<String> = reachable.iter().map(|o| o.hash.clone()).collect();
+
+    let mut summary = GcSummary {
+        packed: reachable.len(),
+        deltas: 0,
+        removed_loose: 0,
+        pruned: 0,
+    };
+
+    // Rescue unreachable objects from packs that are about to be deleted.
+    let old_packs = utils::read_pack_indexes(root_path)?;
+    let mut rescued: HashMap<String, SystemTime> = HashMap::new();
+    for pack_index in &old_packs {
+        let pack_mtime = fs::metadata(&pack_index.pack_path)?.modified()?;
+
+        for hash in &pack_index.hashes {
+            let hash = utils::bytes_to_hex(hash);
+            if reachable_hashes.contains(&hash) || rescued.contains_key(&hash) {
+                continue;
+            }
+            if is_expired(pack_mtime, grace_period) {
+                summary.pruned += 1;
+                continue;
+            }
+            rescued.insert(hash, pack_mtime);
+        }
+    }
+    for (hash, mtime) in &rescued {
+        let object = utils::read_object(root_path, hash)?;
+        let object_path = utils::loose_object_path(root_path, hash);
+        if object_path.exists() {
+            continue;
+        }
+        crate::commands::write_object(root_path, hash, &object)?;
+        fs::File::options()
+            .write(true)
+            .open(&object_path)?
+            .set_modified(*mtime)?;
+    }
+
+    let new_pack = if reachable.is_empty() {
+        None
+    } else {
+        let (pack_path, deltas) = utils::write_pack(root_path, reachable)?;
+        summary.deltas = deltas;
+        Some(pack_path)
+    };
+
+    for pack_index in &old_packs {
+        if Some(&pack_index.pack_path) == new_pack.as_ref() {
+            continue;
+        }
+        fs::remove_file(&pack_index.pack_path)?;
+        fs::remove_file(pack_index.pack_path.with_extension("idx"))?;
+    }
+
+    for (hash, path) in list_loose_objects(root_path)? {
+        if reachable_hashes.contains(&hash) {
+            fs::remove_file(path)?;
+            summary.removed_loose += 1;
+        } else if is_expired(fs::metadata(&path)?.modified()?, grace_period) {
+            fs::remove_file(path)?;
+            summary.pruned += 1;
+        }
+    }
+
+    remove_empty_fanout_dirs(root_path)?;
+
+    Ok(summary)
+}
+
+/// Repacks every reachable object into one delta-compressed pack without pruning.
+pub fn repack(root_path: &Path) -> Result<GcSummary> {
+    repack_objects(root_path, None)
+}
+
+/// Repacks reachable objects and prunes unreachable ones older than `prune_days`.
+///
+/// `prune_days = 0` prunes every unreachable object immediately.
+pub fn gc(root_path: &Path, prune_days: u64) -> Result<GcSummary> {
+    let grace_period = Duration::from_secs(
+        prune_days
+            .checked_mul(24 * 60 * 60)
+            .ok_or_else(|| anyhow!("Prune grace period of {} days is too large.", prune_days))?,
+    );
+    repack_objects(root_path, Some(grace_period))
+}
@@ -1,26 +1,77 @@
 //! Commit history traversal and display.
 
-use crate::utils;
+use crate::utils::{self, ChangeKind, RenameOptions};
 use anyhow::Result;
-use std::{fs, path::Path};
+use std::{
+    collections::{BinaryHeap, HashSet},
+    path::Path,
+};
 
-fn get_parent_commit_hash(commit_data: &str) -> Option<String> {
-    for lines in commit_data.lines() {
-        if lines.starts_with("parent ") {
-            let parent_hash = lines[7..].trim().to_string();
-            return Some(parent_hash);
-        }
+/// Returns the paths a commit changed relative to its first parent (or
+/// to an empty tree for a root commit), one `<status>\t<path>` line each,
+/// as `log --name-status` prints them.
+///
+/// The status is `A`, `D` or `M`, or `R<score>\t<old>` / `C<score>\t<old>`
+/// for renames and copies found with `renames`. Merge commits list nothing.
+pub fn commit_name_status(
+    root_path: &Path,
+    commit_hash: &str,
+    renames: Option<RenameOptions>,
+) -> Result<String> {
+    let parents = utils::get_commit_parents(root_path, commit_hash)?;
+    if parents.len() > 1 {
+        return Ok(String::new());
+    }
+
+    let old = utils::get_commit_entries_map(root_path, parents.first().map(String::as_str))?;
+    let new = utils::get_commit_entries_map(root_path, Some(commit_hash))?;
+    let mut changes = utils::diff_entry_maps(&old, &new, &[]);
+    if let Some(options) = renames {
+        changes = utils::detect_renames(root_path, changes, false, options)?;
     }
-    None
+
+    let mut text = String::new();
+    for change in changes {
+        let path = change.path.display();
+        let line = match (change.kind(), &change.source) {
+            (ChangeKind::Added, _) => format!("A\t{}", path),
+            (ChangeKind::Deleted, _) => format!("D\t{}", path),
+            (ChangeKind::Modified, _) => format!("M\t{}", path),
+            (kind, Some(source)) => format!(
+                "{}{:03}\t{}\t{}",
+                if kind == ChangeKind::Copied { 'C' } else { 'R' },
+                source.similarity,
+                source.path.display(),
+                path
+            ),
+            (_, None) => continue,
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    Ok(text)
 }
 
 /// Traverses parent links from HEAD and prints commit history.
+///
+/// Merge commits contribute all of their parents; commits are printed
+/// newest first by committer date, each exactly once.
 pub fn log(root_path: &Path) -> Result<()> {
+    print_log(root_path, false, None)
+}
+
+/// Prints commit history like [`log`], listing after each commit the paths
+/// it changed (see [`commit_name_status`]).
+pub fn log_name_status(root_path: &Path, renames: Option<RenameOptions>) -> Result<()> {
+    print_log(root_path, true, renames)
+}
+
+fn print_log(root_path: &Path, name_status: bool, renames: Option<RenameOptions>) -> Result<()> {
     utils::ensure_repo_exists(root_path)?;
 
     let commit_hash = utils::get_current_commit_hash(root_path)?;
 
-    let mut commit_hash: String = match commit_hash {
+    let commit_hash: String = match commit_hash {
         Some(hash) => hash,
         None => {
             println!("No commits found in the repository.");
@@ -30,17 +81,33 @@ pub fn log(root_path: &Path) -> Result<()> {
 
     println!("Rusty Git Commit history:\n");
 
-    while !commit_hash.is_empty() {
-        let commit_path = root_path
-            .join(".rustygit/objects")
-            .join(&commit_hash[..2])
-            .join(&commit_hash[2..]);
-        let commit_data = fs::read_to_string(&commit_path)?;
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut queue: BinaryHeap<(i64, String)> = BinaryHeap::new();
+    queue.push((0, commit_hash));
+
+    while let Some((_, commit_hash)) = queue.pop() {
+        if !seen.insert(commit_hash.clone()) {
+            continue;
+        }
+
+        let commit_data = String::from_utf8(utils::read_object(root_path, &commit_hash)?)?;
 
         let formatted = utils::format_commit_history(&commit_data, &commit_hash)?;
         println!("{}", formatted);
+        if name_status {
+            let changes = commit_name_status(root_path, &commit_hash, renames)?;
+            if !changes.is_empty() {
+                println!("{}", changes);
+            }
+        }
 
-        commit_hash = get_parent_commit_hash(&commit_data).unwrap_or_default();
+        for parent_hash in utils::get_parent_hashes(&commit_data) {
+            if seen.contains(&parent_hash) {
+                continue;
+            }
+            let parent_data = utils::parse_commit(root_path, &parent_hash)?;
+            queue.push((utils::get_commit_timestamp(&parent_data), parent_hash));
+        }
     }
 
     Ok(())
@@ -0,0 +1,488 @@
+//! Merging another branch or commit into the current branch.
+
+use crate::utils::{ConflictEntry, IndexEntry};
+use crate::{commands, utils};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// What `merge` did to the current branch.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// The target is already contained in HEAD.
+    UpToDate,
+    /// HEAD moved forward to the target commit.
+    FastForward(String),
+    /// A merge commit with the given hash was created.
+    Merged(String),
+    /// The merge stopped with these paths unmerged; nothing was committed.
+    Conflicted(Vec<PathBuf>),
+}
+
+/// Path-level result of merging three flattened trees.
+struct TreeMerge {
+    /// Paths that resolved cleanly.
+    merged: HashMap<PathBuf, IndexEntry>,
+    /// Paths changed differently on both sides, with all three versions.
+    conflicts: HashMap<PathBuf, ConflictEntry>,
+    /// Paths that are a file on one side and a directory on the other.
+    directory_conflicts: Vec<PathBuf>,
+}
+
+/// Content-level result of merging one path changed on both sides.
+enum PathMerge {
+    Clean(IndexEntry),
+    /// Mode and content (possibly with conflict markers) for the working tree.
+    Conflicted(&'static str, Vec<u8>),
+}
+
+/// A three-way merge of flattened trees with content merges applied, ready
+/// to be written to the working tree and index.
+pub(crate) struct ContentMerge {
+    /// Paths that resolved cleanly (including line-level merges).
+    pub(crate) merged: HashMap<PathBuf, IndexEntry>,
+    /// Paths left unmerged, with their stage 1/2/3 entries.
+    pub(crate) unmerged: HashMap<PathBuf, ConflictEntry>,
+    /// Mode and working-tree content (with conflict markers) of unmerged paths.
+    pub(crate) conflict_files: HashMap<PathBuf, (&'static str, Vec<u8>)>,
+}
+
+fn resolve_merge_target(root_path: &Path, target: &str) -> Result<String> {
+    let branch_ref = format!("refs/heads/{}", target);
+
+    if utils::ref_exists(root_path, &branch_ref) {
+        return utils::read_ref(root_path, &branch_ref)?
+            .ok_or_else(|| anyhow!("Branch '{}' has no commits to merge.", target));
+    }
+
+    utils::resolve_commit(root_path, target).map_err(|err| {
+        anyhow!(
+            "'{}' is neither a valid branch name nor a commit: {}",
+            target,
+            err
+        )
+    })
+}
+
+fn ensure_no_staged_changes(
+    root_path: &Path,
+    head_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<()> {
+    // An empty index means "same as HEAD".
+    let index_entries = utils::read_index_entries(root_path)?;
+
+    if !index_entries.is_empty() && &index_entries != head_entries {
+        bail!("Cannot merge: you have staged changes. Commit them first.");
+    }
+
+    Ok(())
+}
+
+/// Merges three flattened trees path by path.
+///
+/// A path takes whichever side changed it relative to `base`; when both
+/// sides changed it differently, the path is reported as conflicting so its
+/// content can be merged line by line.
+fn merge_trees(
+    base: &HashMap<PathBuf, IndexEntry>,
+    ours: &HashMap<PathBuf, IndexEntry>,
+    theirs: &HashMap<PathBuf, IndexEntry>,
+) -> TreeMerge {
+    let all_paths: BTreeSet<&PathBuf> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+
+    let mut merged: HashMap<PathBuf, IndexEntry> = HashMap::new();
+    let mut conflicts: HashMap<PathBuf, ConflictEntry> = HashMap::new();
+
+    for path in all_paths {
+        let base_entry = base.get(path);
+        let our_entry = ours.get(path);
+        let their_entry = theirs.get(path);
+
+        let result = if our_entry == their_entry || their_entry == base_entry {
+            our_entry
+        } else if our_entry == base_entry {
+            their_entry
+        } else {
+            conflicts.insert(
+                path.clone(),
+                ConflictEntry {
+                    base: base_entry.cloned(),
+                    ours: our_entry.cloned(),
+                    theirs: their_entry.cloned(),
+                },
+            );
+            continue;
+        };
+
+        if let Some(entry) = result {
+            merged.insert(path.clone(), entry.clone());
+        }
+    }
+
+    let mut directory_conflicts: BTreeSet<PathBuf> = BTreeSet::new();
+    for path in merged.keys().chain(conflicts.keys()) {
+        for ancestor in path.ancestors().skip(1) {
+            if merged.contains_key(ancestor) || conflicts.contains_key(ancestor) {
+                directory_conflicts.insert(ancestor.to_path_buf());
+            }
+        }
+    }
+
+    TreeMerge {
+        merged,
+        conflicts,
+        directory_conflicts: directory_conflicts.into_iter().collect(),
+    }
+}
+
+/// Picks the mode a merged file gets, or `None` if both sides changed it differently.
+fn merge_mode(
+    base: Option<&IndexEntry>,
+    ours: &IndexEntry,
+    theirs: &IndexEntry,
+) -> Option<&'static str> {
+    let base_mode = base.map(|entry| entry.mode);
+
+    if ours.mode == theirs.mode || base_mode == Some(theirs.mode) {
+        Some(ours.mode)
+    } else if base_mode == Some(ours.mode) {
+        Some(theirs.mode)
+    } else {
+        None
+    }
+}
+
+fn write_blob_content(root_path: &Path, content: &[u8]) -> Result<String> {
+    let object = commands::format_object(content);
+    let hash = utils::hash_bytes(&object);
+    commands::write_object(root_path, &hash, &object)?;
+    Ok(hash)
+}
+
+/// Merges the content of a path both sides changed.
+///
+/// Text files are merged line by line. Binary files, symlinks and
+/// modify/delete conflicts cannot be merged; the side that still has the
+/// path is kept in the working tree.
+fn merge_path(
+    root_path: &Path,
+    conflict: &ConflictEntry,
+    our_label: &str,
+    their_label: &str,
+) -> Result<PathMerge> {
+    let (ours, theirs) = match (&conflict.ours, &conflict.theirs) {
+        (Some(ours), Some(theirs))
+            if ours.mode != utils::MODE_SYMLINK && theirs.mode != utils::MODE_SYMLINK =>
+        {
+            (ours, theirs)
+        }
+        (Some(ours), _) => {
+            let content = utils::read_blob(root_path, &ours.hash)?;
+            return Ok(PathMerge::Conflicted(ours.mode, content));
+        }
+        (None, Some(theirs)) => {
+            let content = utils::read_blob(root_path, &theirs.hash)?;
+            return Ok(PathMerge::Conflicted(theirs.mode, content));
+        }
+        (None, None) => bail!("Merge conflict without either side."),
+    };
+
+    // Paths added on both sides merge against an empty base.
+    let base_content = match &conflict.base {
+        Some(base) if base.mode != utils::MODE_SYMLINK => utils::read_blob(root_path, &base.hash)?,
+        _ => Vec::new(),
+    };
+    let our_content = utils::read_blob(root_path, &ours.hash)?;
+    let their_content = utils::read_blob(root_path, &theirs.hash)?;
+
+    if [&base_content, &our_content, &their_content]
+        .iter()
+        .any(|content| utils::is_binary(content))
+    {
+        return Ok(PathMerge::Conflicted(ours.mode, our_content));
+    }
+
+    let result = utils::merge_text(
+        &base_content,
+        &our_content,
+        &their_content,
+        our_label,
+        their_label,
+    );
+
+    match merge_mode(conflict.base.as_ref(), ours, theirs) {
+        Some(mode) if !result.conflicted => Ok(PathMerge::Clean(IndexEntry {
+            mode,
+            hash: write_blob_content(root_path, &result.content)?,
+        })),
+        mode => Ok(PathMerge::Conflicted(
+            mode.unwrap_or(ours.mode),
+            result.content,
+        )),
+    }
+}
+
+/// Moves the working tree, index and HEAD from `from` to `to` at `commit_hash`.
+///
+/// Callers must have run the checkout safety check against the new tree.
+/// HEAD is only moved if it still points at `old_commit`.
+fn apply_merge_result(
+    root_path: &Path,
+    commit_hash: &str,
+    old_commit: Option<&str>,
+    from: &HashMap<PathBuf, IndexEntry>,
+    to: &HashMap<PathBuf, IndexEntry>,
+    reflog_message: &str,
+) -> Result<()> {
+    utils::update_work_tree(root_path, from, to)?;
+    utils::write_index_entries(root_path, to)?;
+    utils::update_head(
+        root_path,
+        commit_hash,
+        Some(old_commit.unwrap_or(utils::NULL_HASH)),
+        reflog_message,
+    )
+}
+
+/// Merges `theirs` into `ours` relative to `base`, merging paths changed on
+/// both sides line by line; conflict markers use `our_label` and `their_label`.
+///
+/// File/directory conflicts cannot be represented and fail the merge.
+pub(crate) fn merge_entries(
+    root_path: &Path,
+    base: &HashMap<PathBuf, IndexEntry>,
+    ours: &HashMap<PathBuf, IndexEntry>,
+    theirs: &HashMap<PathBuf, IndexEntry>,
+    our_label: &str,
+    their_label: &str,
+) -> Result<ContentMerge> {
+    let tree_merge = merge_trees(base, ours, theirs);
+
+    if !tree_merge.directory_conflicts.is_empty() {
+        let paths: Vec<String> = tree_merge
+            .directory_conflicts
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        bail!(
+            "Automatic merge failed; file/directory conflict at: {}\nNothing was changed.",
+            paths.join(", ")
+        );
+    }
+
+    let mut merged = tree_merge.merged;
+    let mut unmerged: HashMap<PathBuf, ConflictEntry> = HashMap::new();
+    let mut conflict_files: HashMap<PathBuf, (&'static str, Vec<u8>)> = HashMap::new();
+
+    for (path, conflict) in tree_merge.conflicts {
+        match merge_path(root_path, &conflict, our_label, their_label)? {
+            PathMerge::Clean(entry) => {
+                merged.insert(path, entry);
+            }
+            PathMerge::Conflicted(mode, content) => {
+                conflict_files.insert(path.clone(), (mode, content));
+                unmerged.insert(path, conflict);
+            }
+        }
+    }
+
+    Ok(ContentMerge {
+        merged,
+        unmerged,
+        conflict_files,
+    })
+}
+
+/// Writes a merge result over the `our_entries` snapshot without moving HEAD.
+///
+/// Cleanly merged paths are updated as usual; unmerged paths get their
+/// conflict content in the working tree and stage 1/2/3 entries in the
+/// index. Fails before changing anything if local changes would be lost.
+pub(crate) fn write_content_merge(
+    root_path: &Path,
+    our_entries: &HashMap<PathBuf, IndexEntry>,
+    result: &ContentMerge,
+) -> Result<()> {
+    let mut target_entries = result.merged.clone();
+    for (path, (mode, content)) in &result.conflict_files {
+        let hash = utils::hash_bytes(&commands::format_object(content));
+        target_entries.insert(path.clone(), IndexEntry { mode, hash });
+    }
+    utils::overwrite_safety_check(root_path, &target_entries)?;
+
+    // Conflicted paths keep our version through the incremental update and
+    // are then overwritten with their conflict content.
+    let mut work_entries = result.merged.clone();
+    for path in result.conflict_files.keys() {
+        if let Some(entry) = our_entries.get(path) {
+            work_entries.insert(path.clone(), entry.clone());
+        }
+    }
+    utils::update_work_tree(root_path, our_entries, &work_entries)?;
+    for (path, (mode, content)) in &result.conflict_files {
+        utils::write_work_file(&root_path.join(path), mode, content)?;
+    }
+
+    utils::write_index_entries(root_path, &result.merged)?;
+    utils::write_index_conflicts(root_path, &result.unmerged)
+}
+
+/// Leaves a conflicted merge in the working tree and index for the user to resolve,
+/// with `MERGE_HEAD` recorded for the concluding `commit`.
+fn stop_on_conflicts(
+    root_path: &Path,
+    their_commit: &str,
+    message: &str,
+    our_entries: &HashMap<PathBuf, IndexEntry>,
+    result: ContentMerge,
+) -> Result<MergeOutcome> {
+    write_content_merge(root_path, our_entries, &result)?;
+    utils::write_merge_state(root_path, their_commit, message)?;
+
+    let mut paths: Vec<PathBuf> = result.unmerged.into_keys().collect();
+    paths.sort();
+    Ok(MergeOutcome::Conflicted(paths))
+}
+
+/// Merges `target` (a branch name or commit hash) into the current branch.
+///
+/// Fast-forwards when HEAD is an ancestor of the target; otherwise performs
+/// a three-way merge against the merge base and records a commit with two
+/// parents. `message` defaults to `Merge branch '<target>'`.
+///
+/// Paths changed on both sides are merged line by line. If any region still
+/// conflicts, conflict markers are written to the working tree, the index
+/// records stage 1/2/3 entries, and `MERGE_HEAD` is left for `commit`.
+pub fn merge(root_path: &Path, target: &str, message: Option<String>) -> Result<MergeOutcome> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if utils::read_merge_head(root_path)?.is_some() {
+        bail!(
+            "Cannot merge: a merge is already in progress. Commit the resolution or run 'merge --abort'."
+        );
+    }
+
+    if utils::read_symbolic_head(root_path)?.is_none() {
+        bail!("Cannot merge: HEAD is detached.");
+    }
+
+    let their_commit = resolve_merge_target(root_path, target)?;
+    let our_commit = utils::get_current_commit_hash(root_path)?;
+
+    let our_entries = utils::get_commit_entries_map(root_path, our_commit.as_deref())?;
+    ensure_no_staged_changes(root_path, &our_entries)?;
+
+    let base_commit = match &our_commit {
+        Some(our_commit) => utils::merge_base(root_path, our_commit, &their_commit)?,
+        None => None,
+    };
+
+    if base_commit.as_deref() == Some(their_commit.as_str()) {
+        return Ok(MergeOutcome::UpToDate);
+    }
+
+    let their_entries = utils::get_commit_entries_map(root_path, Some(&their_commit))?;
+
+    // With nothing committed yet, or HEAD behind the target, just move forward.
+    let our_commit = match &our_commit {
+        Some(our_commit) if base_commit.as_deref() != Some(our_commit.as_str()) => {
+            our_commit.clone()
+        }
+        _ => {
+            let tree_hash = utils::get_commit_tree_hash(root_path, &their_commit)?;
+            utils::checkout_safety_check(root_path, Some(tree_hash))?;
+            apply_merge_result(
+                root_path,
+                &their_commit,
+                our_commit.as_deref(),
+                &our_entries,
+                &their_entries,
+                &format!("merge {}: Fast-forward", target),
+            )?;
+            return Ok(MergeOutcome::FastForward(their_commit));
+        }
+    };
+
+    let base_entries = utils::get_commit_entries_map(root_path, base_commit.as_deref())?;
+    let result = merge_entries(
+        root_path,
+        &base_entries,
+        &our_entries,
+        &their_entries,
+        "HEAD",
+        target,
+    )?;
+
+    let message = message.unwrap_or_else(|| format!("Merge branch '{}'", target));
+
+    if !result.unmerged.is_empty() {
+        return stop_on_conflicts(root_path, &their_commit, &message, &our_entries, result);
+    }
+    let merged_entries = result.merged;
+
+    let tree_hash = commands::write_tree_from_entries(root_path, &merged_entries)?;
+    utils::checkout_safety_check(root_path, Some(tree_hash.clone()))?;
+
+    let merge_commit = commands::commit_tree(
+        root_path,
+        &tree_hash,
+        &[our_commit.clone(), their_commit],
+        message,
+    )?;
+
+    apply_merge_result(
+        root_path,
+        &merge_commit,
+        Some(&our_commit),
+        &our_entries,
+        &merged_entries,
+        &format!("merge {}: Merge made by three-way merge.", target),
+    )?;
+
+    Ok(MergeOutcome::Merged(merge_commit))
+}
+
+/// Abandons a conflicted merge, restoring the index and affected files to HEAD.
+///
+/// Local changes to paths the merge did not touch are preserved.
+pub fn merge_abort(root_path: &Path) -> Result<()> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if utils::read_merge_head(root_path)?.is_none() {
+        bail!("There is no merge to abort.");
+    }
+
+    discard_content_merge(root_path)?;
+    utils::clear_merge_state(root_path)
+}
+
+/// Restores the index, and every path it changed or left unmerged, to HEAD,
+/// undoing [`write_content_merge`]. Other local changes are preserved.
+pub(crate) fn discard_content_merge(root_path: &Path) -> Result<()> {
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let head_entries = utils::get_commit_entries_map(root_path, head_commit.as_deref())?;
+    let index_entries = utils::read_index_entries(root_path)?;
+    let conflicts = utils::read_index_conflicts(root_path)?;
+
+    for path in index_entries.keys().chain(conflicts.keys()) {
+        if !head_entries.contains_key(path) {
+            utils::remove_work_file(root_path, path)?;
+        }
+    }
+
+    for (path, entry) in &head_entries {
+        if index_entries.get(path) != Some(entry) || conflicts.contains_key(path) {
+            let content = utils::read_blob(root_path, &entry.hash)?;
+            utils::write_work_file(&root_path.join(path), entry.mode, &content)?;
+        }
+    }
+
+    utils::write_index_entries(root_path, &head_entries)?;
+    utils::write_index_conflicts(root_path, &HashMap::new())
+}
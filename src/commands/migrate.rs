@@ -0,0 +1,56 @@
+//! One-shot migration of legacy uncompressed loose objects.
+
+use crate::utils;
+use anyhow::{Result, bail};
+use std::{fs, path::Path};
+
+/// Rewrites every uncompressed loose object in zlib-compressed form.
+///
+/// Objects are verified against their hash before being replaced, and each
+/// rewrite goes through a temporary file so an interrupted run leaves the
+/// original object intact. Returns the number of objects converted.
+pub fn migrate_objects(root_path: &Path) -> Result<usize> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let objects_dir = root_path.join(".rustygit").join("objects");
+    let mut migrated = 0;
+
+    for dir_entry in fs::read_dir(&objects_dir)? {
+        let dir_entry = dir_entry?;
+        let dir_name = dir_entry.file_name().to_string_lossy().to_string();
+
+        // Only the two-hex-digit fan-out directories hold loose objects.
+        if dir_name.len() != 2 || !dir_entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        for file_entry in fs::read_dir(dir_entry.path())? {
+            let file_entry = file_entry?;
+            let file_name = file_entry.file_name().to_string_lossy().to_string();
+
+            if file_name.len() != 38 {
+                continue;
+            }
+
+            let object_path = file_entry.path();
+            let raw = fs::read(&object_path)?;
+
+            if utils::is_compressed(&raw) {
+                continue;
+            }
+
+            let hash = format!("{}{}", dir_name, file_name);
+            if utils::hash_bytes(&raw) != hash {
+                bail!("Object '{}' is corrupt: content does not match its hash.", hash);
+            }
+
+            let tmp_path = object_path.with_extension("tmp");
+            fs::write(&tmp_path, utils::compress(&raw)?)?;
+            fs::rename(&tmp_path, &object_path)?;
+
+            migrated += 1;
+        }
+    }
+
+    Ok(migrated)
+}
@@ -1,34 +1,112 @@
 mod add;
+mod am;
+mod apply;
+mod bisect;
 mod branch;
 mod checkout;
+mod cherry_pick;
 mod commit;
+mod config;
 mod diff;
+mod format_patch;
+mod gc;
 mod init;
 mod log;
+mod merge;
+mod migrate;
 mod object;
+mod rebase;
+mod reflog;
 mod reset;
 mod restore;
+mod rev_parse;
 mod rm;
+mod stash;
 mod status;
+mod tag;
 mod tree;
 
+pub use apply::ApplyOptions;
+pub use bisect::BisectOutcome;
+pub use cherry_pick::PickOutcome;
+pub use diff::DiffMode;
+pub use gc::GcSummary;
+pub use merge::MergeOutcome;
+pub use rebase::RebaseOutcome;
+pub use reset::ResetMode;
+pub use stash::StashApplyOutcome;
 pub use tree::TreeEntry;
 
 pub use add::add;
+pub use am::am;
+pub use apply::apply;
+pub use bisect::bisect_bad;
+pub use bisect::bisect_good;
+pub use bisect::bisect_log;
+pub use bisect::bisect_replay;
+pub use bisect::bisect_reset;
+pub use bisect::bisect_run;
+pub use bisect::bisect_skip;
+pub use bisect::bisect_start;
 pub use branch::branch;
 pub use branch::create_branch;
+pub use branch::create_branch_at;
+pub use branch::delete_branch;
+pub use branch::rename_branch;
+pub use branch::rename_current_branch;
 pub use checkout::checkout;
+pub use checkout::checkout_paths;
+pub use cherry_pick::cherry_pick;
+pub use cherry_pick::cherry_pick_abort;
+pub use cherry_pick::cherry_pick_continue;
+pub use cherry_pick::revert;
+pub use cherry_pick::revert_abort;
+pub use cherry_pick::revert_continue;
 pub use commit::commit;
+pub use commit::commit_tree;
+pub use commit::commit_tree_with_author;
 pub use commit::commit_with_all;
+pub use config::config_get;
+pub use config::config_list;
+pub use config::config_set;
+pub use config::config_unset;
+pub use diff::DEFAULT_CONTEXT;
 pub use diff::diff;
+pub use diff::diff_changes;
+pub use diff::diff_with_renames;
+pub use format_patch::format_patch;
+pub use gc::gc;
+pub use gc::repack;
 pub use init::init;
+pub use log::commit_name_status;
 pub use log::log;
+pub use log::log_name_status;
+pub use merge::merge;
+pub use merge::merge_abort;
+pub use migrate::migrate_objects;
 pub use object::format_object;
 pub use object::hash_object;
 pub use object::write_blob;
 pub use object::write_object;
+pub use rebase::rebase;
+pub use rebase::rebase_abort;
+pub use rebase::rebase_continue;
+pub use rebase::rebase_skip;
+pub use reflog::reflog;
 pub use reset::reset;
 pub use restore::restore;
+pub use restore::restore_paths;
+pub use rev_parse::rev_parse;
 pub use rm::rm;
+pub use stash::stash_apply;
+pub use stash::stash_drop;
+pub use stash::stash_list;
+pub use stash::stash_pop;
+pub use stash::stash_push;
+pub use stash::stash_show;
 pub use status::status;
+pub use tag::create_tag;
+pub use tag::delete_tag;
+pub use tag::list_tags;
 pub use tree::write_tree;
+pub use tree::write_tree_from_entries;
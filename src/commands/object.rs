@@ -14,30 +14,33 @@ pub fn format_object(contents: &[u8]) -> Vec<u8> {
     result
 }
 
-/// Writes an object into `.rustygit/objects/<2>/<38>` if it does not exist.
+/// Writes a zlib-compressed object into `.rustygit/objects/<2>/<38>` if it does not exist.
+///
+/// `data` is the uncompressed object (`<type> <size>\0<payload>`) that `hash` was computed from.
 pub fn write_object(repo_root: &Path, hash: &str, data: &[u8]) -> Result<()> {
-    let objects_dir = repo_root.join(".rustygit").join("objects");
-
-    let (dir, file) = hash.split_at(2);
-    let object_dir = objects_dir.join(dir);
-    let object_path = object_dir.join(file);
+    let object_path = utils::loose_object_path(repo_root, hash);
 
     if object_path.exists() {
         return Ok(()); // Git behavior: objects are immutable
     }
 
-    fs::create_dir_all(&object_dir)?;
-    fs::write(object_path, data)?;
+    if let Some(object_dir) = object_path.parent() {
+        fs::create_dir_all(object_dir)?;
+    }
+    fs::write(object_path, utils::compress(data)?)?;
     Ok(())
 }
 
 /// Computes and stores a blob object for `file_path`, returning its hash.
+///
+/// Symlinks are stored as blobs of their target path rather than followed.
 pub fn write_blob(repo_root: &Path, file_path: &Path) -> Result<String> {
-    if !file_path.is_file() {
+    let is_file_or_link = fs::symlink_metadata(file_path).is_ok_and(|metadata| !metadata.is_dir());
+    if !is_file_or_link {
         bail!("Could not find file: {}", file_path.display());
     }
 
-    let content = fs::read(file_path)?;
+    let content = utils::read_work_file(file_path)?;
     let object_bytes = format_object(&content);
     let hash = utils::hash_bytes(&object_bytes);
 
@@ -0,0 +1,456 @@
+//! Replaying the current branch's commits on top of another commit.
+//!
+//! The branch stays checked out and moves forward one step at a time as
+//! each commit is applied onto `onto` with a three-way merge, as in
+//! `cherry-pick`. Steps come from a todo list, which `rebase -i` lets the
+//! user edit; the state in `.rustygit/rebase-merge` lets `--continue`,
+//! `--skip` and `--abort` pick up a rebase that stopped.
+
+use crate::commands::{
+    self, ResetMode,
+    cherry_pick::{self, AppliedChange},
+    merge,
+};
+use crate::utils::{self, IndexEntry, RebaseAction, RebaseState, RebaseStep, SequencerAction};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// What `rebase` (or `--continue`/`--skip`) did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RebaseOutcome {
+    /// HEAD already contains the upstream; nothing was done.
+    UpToDate,
+    /// Every step ran; the branch (or detached HEAD) now points at this commit.
+    Finished(String),
+    /// Applying `commit` left these paths unmerged; the rebase stopped.
+    Conflicted { commit: String, paths: Vec<PathBuf> },
+    /// An `edit` step applied this commit and stopped for amending.
+    Edit(String),
+}
+
+fn subject(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+fn current_commit(root_path: &Path) -> Result<String> {
+    utils::get_current_commit_hash(root_path)?
+        .ok_or_else(|| anyhow!("Cannot rebase: HEAD has no commits."))
+}
+
+/// Refuses to start unless the index and tracked files match HEAD.
+fn ensure_clean(root_path: &Path, head_commit: &str) -> Result<()> {
+    let head_entries = utils::get_commit_entries_map(root_path, Some(head_commit))?;
+    if utils::read_effective_index(root_path)? != head_entries {
+        bail!("Cannot rebase: you have staged changes. Commit or stash them first.");
+    }
+
+    let mut work_entries = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_entries)?;
+    if head_entries
+        .iter()
+        .any(|(path, entry)| work_entries.get(path) != Some(entry))
+    {
+        bail!("Cannot rebase: you have unstaged changes. Commit or stash them first.");
+    }
+
+    Ok(())
+}
+
+/// Returns the commits to replay, oldest first: every commit reachable
+/// from `head_commit` but not from `onto`, parents before children and
+/// first parents first. Merge commits are left out, flattening the history
+/// like `rev-list --no-merges --reverse onto..HEAD`.
+fn commits_to_replay(root_path: &Path, head_commit: &str, onto: &str) -> Result<Vec<String>> {
+    let upstream = utils::collect_ancestors(root_path, onto)?;
+    let mut visited = HashSet::new();
+    let mut commits = Vec::new();
+
+    // A commit is pushed once to be expanded and once more, beneath its
+    // parents, to be emitted (with whether it is a merge) after them.
+    let mut stack: Vec<(String, Option<bool>)> = vec![(head_commit.to_string(), None)];
+    while let Some((commit_hash, is_merge)) = stack.pop() {
+        if let Some(is_merge) = is_merge {
+            if !is_merge {
+                commits.push(commit_hash);
+            }
+            continue;
+        }
+        if upstream.contains(&commit_hash) || !visited.insert(commit_hash.clone()) {
+            continue;
+        }
+
+        let parents = utils::get_commit_parents(root_path, &commit_hash)?;
+        stack.push((commit_hash, Some(parents.len() > 1)));
+        stack.extend(parents.into_iter().rev().map(|parent| (parent, None)));
+    }
+
+    Ok(commits)
+}
+
+/// Moves the working tree, index and HEAD from commit `from` to commit `to`.
+fn move_to(root_path: &Path, from: &str, to: &str, reflog_message: &str) -> Result<()> {
+    let from_entries = utils::get_commit_entries_map(root_path, Some(from))?;
+    let to_entries = utils::get_commit_entries_map(root_path, Some(to))?;
+
+    utils::update_work_tree(root_path, &from_entries, &to_entries)?;
+    utils::write_index_entries(root_path, &to_entries)?;
+    utils::update_head(root_path, to, Some(from), reflog_message)
+}
+
+/// Returns true for steps that meld a commit into the previous one.
+fn melds(action: RebaseAction) -> bool {
+    matches!(action, RebaseAction::Squash | RebaseAction::Fixup)
+}
+
+/// Commits `entries` on top of `head_commit`, or in its place when
+/// `replace_head` is set, and moves HEAD there.
+fn commit_step(
+    root_path: &Path,
+    reflog_action: &str,
+    head_commit: &str,
+    replace_head: bool,
+    entries: &HashMap<PathBuf, IndexEntry>,
+    message: String,
+) -> Result<String> {
+    let parents = if replace_head {
+        utils::get_commit_parents(root_path, head_commit)?
+    } else {
+        vec![head_commit.to_string()]
+    };
+
+    let reflog_message = format!("rebase ({}): {}", reflog_action, subject(&message));
+    let tree_hash = commands::write_tree_from_entries(root_path, entries)?;
+    let commit_hash = commands::commit_tree(root_path, &tree_hash, &parents, message)?;
+
+    utils::update_head(root_path, &commit_hash, Some(head_commit), &reflog_message)?;
+    Ok(commit_hash)
+}
+
+/// Returns the message a step commits with, letting the user edit it for
+/// `reword` and `squash`.
+fn step_message(root_path: &Path, step: &RebaseStep, head_commit: &str) -> Result<String> {
+    let message = utils::get_commit_message(root_path, &step.commit)?;
+
+    match step.action {
+        RebaseAction::Reword => utils::edit_message(root_path, &message),
+        RebaseAction::Squash => {
+            let previous = utils::get_commit_message(root_path, head_commit)?;
+            let combined = format!("{}\n\n{}", previous.trim_end(), message);
+            utils::edit_message(root_path, &combined)
+        }
+        RebaseAction::Fixup => utils::get_commit_message(root_path, head_commit),
+        _ => Ok(message),
+    }
+}
+
+/// Runs one step on top of HEAD, returning the outcome if the rebase stops.
+///
+/// A commit whose parent already is HEAD is reused as is (unless it is
+/// reworded or melded); a commit whose change is already present is dropped.
+fn run_step(
+    root_path: &Path,
+    state: &mut RebaseState,
+    step: &RebaseStep,
+) -> Result<Option<RebaseOutcome>> {
+    if step.action == RebaseAction::Drop {
+        return Ok(None);
+    }
+
+    let head_commit = current_commit(root_path)?;
+    let parents = utils::get_commit_parents(root_path, &step.commit)?;
+    let message = step_message(root_path, step, &head_commit)?;
+
+    let new_commit = match step.action {
+        RebaseAction::Pick | RebaseAction::Edit if parents == [head_commit.clone()] => {
+            let reflog_message = format!("rebase ({}): {}", step.action.name(), subject(&message));
+            move_to(root_path, &head_commit, &step.commit, &reflog_message)?;
+            step.commit.clone()
+        }
+        _ => match cherry_pick::apply_change(
+            root_path,
+            SequencerAction::Pick,
+            &step.commit,
+            &head_commit,
+        )? {
+            AppliedChange::Clean(entries) => commit_step(
+                root_path,
+                step.action.name(),
+                &head_commit,
+                melds(step.action),
+                &entries,
+                message,
+            )?,
+            AppliedChange::Empty => return Ok(None),
+            AppliedChange::Conflicted(paths) => {
+                state.message = Some(message);
+                return Ok(Some(RebaseOutcome::Conflicted {
+                    commit: step.commit.clone(),
+                    paths,
+                }));
+            }
+        },
+    };
+
+    if step.action == RebaseAction::Edit {
+        state.amend = Some(new_commit);
+        return Ok(Some(RebaseOutcome::Edit(step.commit.clone())));
+    }
+    Ok(None)
+}
+
+/// Runs the remaining todo steps, saving the state if one stops (or fails,
+/// so that it can be retried) and removing it once all are done.
+fn run_todo(root_path: &Path, mut state: RebaseState) -> Result<RebaseOutcome> {
+    while !state.todo.is_empty() {
+        let step = state.todo.remove(0);
+        state.done.push(step.clone());
+
+        match run_step(root_path, &mut state, &step) {
+            Ok(None) => {}
+            Ok(Some(outcome)) => {
+                utils::write_rebase_state(root_path, &state)?;
+                return Ok(outcome);
+            }
+            Err(err) => {
+                state.done.pop();
+                state.todo.insert(0, step);
+                utils::write_rebase_state(root_path, &state)?;
+                return Err(err);
+            }
+        }
+    }
+
+    utils::clear_rebase_state(root_path)?;
+    Ok(RebaseOutcome::Finished(current_commit(root_path)?))
+}
+
+fn todo_help(onto: &str, head_commit: &str, count: usize) -> String {
+    format!(
+        "
+# Rebase {}..{} onto {} ({} commands)
+#
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# e, edit <commit> = use commit, but stop for amending
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but discard this commit's log message
+# d, drop <commit> = remove commit
+#
+# These lines can be re-ordered; they are executed from top to bottom.
+# If you remove a line, that commit is dropped.
+# If you remove everything, the rebase is aborted.
+",
+        &onto[..7],
+        &head_commit[..7],
+        &onto[..7],
+        count
+    )
+}
+
+/// Opens the todo list of a new interactive rebase in the sequence editor
+/// and returns the edited steps.
+fn edit_todo(root_path: &Path, state: &RebaseState) -> Result<Vec<RebaseStep>> {
+    utils::write_rebase_state(root_path, state)?;
+
+    let todo_path = utils::rebase_dir(root_path).join("git-rebase-todo");
+    fs::write(
+        &todo_path,
+        format!(
+            "{}{}",
+            utils::format_rebase_todo(root_path, &state.todo)?,
+            todo_help(&state.onto, &state.orig_head, state.todo.len())
+        ),
+    )?;
+
+    utils::run_editor(root_path, &todo_path, true)?;
+    let steps = utils::parse_rebase_todo(root_path, &fs::read_to_string(&todo_path)?)?;
+
+    if steps.is_empty() {
+        bail!("Nothing to do.");
+    }
+    if let Some(first) = steps.iter().find(|step| step.action != RebaseAction::Drop)
+        && matches!(first.action, RebaseAction::Squash | RebaseAction::Fixup)
+    {
+        bail!(
+            "Cannot '{}' without a previous commit.",
+            first.action.name()
+        );
+    }
+
+    Ok(steps)
+}
+
+/// Replays the commits of the current branch that `upstream` does not
+/// contain on top of `upstream`, moving the branch to the result.
+///
+/// With `interactive`, the todo list is opened in the sequence editor first
+/// (see [`utils::run_editor`]) and may reorder, reword, edit, squash, fixup
+/// or drop commits. Refuses to start with local changes to tracked files.
+/// Conflicts and `edit` steps stop the rebase; see [`rebase_continue`],
+/// [`rebase_skip`] and [`rebase_abort`].
+pub fn rebase(root_path: &Path, upstream: &str, interactive: bool) -> Result<RebaseOutcome> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if utils::read_rebase_state(root_path)?.is_some() {
+        bail!(
+            "A rebase is already in progress.\nRun 'rebase --continue', 'rebase --skip' or 'rebase --abort'."
+        );
+    }
+    if utils::read_merge_head(root_path)?.is_some()
+        || utils::read_sequencer(root_path)?.is_some()
+        || !utils::read_index_conflicts(root_path)?.is_empty()
+    {
+        bail!("Cannot rebase: a merge, cherry-pick or revert is in progress.");
+    }
+
+    let head_commit = current_commit(root_path)?;
+    ensure_clean(root_path, &head_commit)?;
+
+    let onto = utils::resolve_commit(root_path, upstream)?;
+    if !interactive && utils::is_ancestor(root_path, &onto, &head_commit)? {
+        return Ok(RebaseOutcome::UpToDate);
+    }
+
+    let todo = commits_to_replay(root_path, &head_commit, &onto)?
+        .into_iter()
+        .map(|commit| RebaseStep {
+            action: RebaseAction::Pick,
+            commit,
+        })
+        .collect();
+
+    let onto_tree = utils::get_commit_tree_hash(root_path, &onto)?;
+    utils::checkout_safety_check(root_path, Some(onto_tree))?;
+
+    let mut state = RebaseState {
+        head_name: utils::read_symbolic_head(root_path)?,
+        onto,
+        orig_head: head_commit.clone(),
+        todo,
+        done: Vec::new(),
+        interactive,
+        message: None,
+        amend: None,
+    };
+
+    if interactive {
+        state.todo = match edit_todo(root_path, &state) {
+            Ok(steps) => steps,
+            Err(err) => {
+                utils::clear_rebase_state(root_path)?;
+                return Err(err);
+            }
+        };
+    }
+
+    move_to(
+        root_path,
+        &head_commit,
+        &state.onto,
+        &format!("rebase (start): checkout {}", upstream),
+    )?;
+    run_todo(root_path, state)
+}
+
+/// Returns the rebase in progress, checking HEAD is still where it runs.
+fn read_state(root_path: &Path) -> Result<RebaseState> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let state = utils::read_rebase_state(root_path)?
+        .ok_or_else(|| anyhow!("There is no rebase in progress."))?;
+
+    if utils::read_symbolic_head(root_path)? != state.head_name {
+        bail!(
+            "HEAD is no longer on {}; check it out again first.",
+            state.head_name.as_deref().unwrap_or("the rebased commit")
+        );
+    }
+
+    Ok(state)
+}
+
+/// Resumes a stopped rebase.
+///
+/// After a conflict, the resolved index is committed with the stopped
+/// commit's message (unless it matches HEAD, e.g. because the user committed
+/// already). After an `edit` step, staged changes are amended into the
+/// commit. Then the remaining steps run.
+pub fn rebase_continue(root_path: &Path) -> Result<RebaseOutcome> {
+    let mut state = read_state(root_path)?;
+
+    let conflicts = utils::read_index_conflicts(root_path)?;
+    if !conflicts.is_empty() {
+        let mut paths: Vec<String> = conflicts.keys().map(|p| p.display().to_string()).collect();
+        paths.sort();
+        bail!(
+            "Cannot continue: unmerged paths: {}\nResolve the conflicts and 'add' each file first.",
+            paths.join(", ")
+        );
+    }
+
+    let head_commit = current_commit(root_path)?;
+    let head_entries = utils::get_commit_entries_map(root_path, Some(&head_commit))?;
+    let index_entries = utils::read_effective_index(root_path)?;
+
+    if let Some(message) = state.message.take() {
+        let action = state
+            .done
+            .last()
+            .map_or(RebaseAction::Pick, |step| step.action);
+        if index_entries != head_entries {
+            commit_step(
+                root_path,
+                action.name(),
+                &head_commit,
+                melds(action),
+                &index_entries,
+                message,
+            )?;
+        }
+    } else if let Some(amend) = state.amend.take()
+        && index_entries != head_entries
+    {
+        if head_commit != amend {
+            bail!("You have staged changes. Commit them first, then run 'rebase --continue'.");
+        }
+        let message = utils::get_commit_message(root_path, &head_commit)?;
+        commit_step(
+            root_path,
+            "amend",
+            &head_commit,
+            true,
+            &index_entries,
+            message,
+        )?;
+    }
+
+    run_todo(root_path, state)
+}
+
+/// Drops the step a rebase stopped at, restoring the files it changed, and
+/// runs the remaining steps.
+pub fn rebase_skip(root_path: &Path) -> Result<RebaseOutcome> {
+    let mut state = read_state(root_path)?;
+
+    merge::discard_content_merge(root_path)?;
+    state.message = None;
+    state.amend = None;
+
+    run_todo(root_path, state)
+}
+
+/// Abandons a rebase, moving HEAD back to where it started.
+///
+/// Local changes to paths the rebase did not touch are preserved.
+pub fn rebase_abort(root_path: &Path) -> Result<()> {
+    let state = read_state(root_path)?;
+
+    merge::discard_content_merge(root_path)?;
+    commands::reset(root_path, &state.orig_head, ResetMode::Keep)?;
+    utils::clear_rebase_state(root_path)
+}
@@ -0,0 +1,39 @@
+//! Reflog display (`reflog [ref]`).
+
+use crate::utils;
+use anyhow::{Result, bail};
+use std::path::Path;
+
+/// Returns the reflog of `ref_name` (default `HEAD`), newest entry first, as
+/// `<short-hash> <ref>@{N}: <message>` lines.
+///
+/// Branch names may be given without the `refs/heads/` prefix.
+pub fn reflog(root_path: &Path, ref_name: Option<&str>) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let display_name = ref_name.unwrap_or("HEAD");
+    let full_name = if display_name == "HEAD" || display_name.starts_with("refs/") {
+        display_name.to_string()
+    } else {
+        format!("refs/heads/{}", display_name)
+    };
+
+    if full_name != "HEAD" && !utils::ref_exists(root_path, &full_name) {
+        bail!("Reference '{}' does not exist.", display_name);
+    }
+
+    Ok(utils::read_reflog(root_path, &full_name)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(n, entry)| {
+            format!(
+                "{} {}@{{{}}}: {}",
+                &entry.new_hash[..entry.new_hash.len().min(7)],
+                display_name,
+                n,
+                entry.message
+            )
+        })
+        .collect())
+}
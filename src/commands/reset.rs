@@ -1,80 +1,95 @@
-//! HEAD/index reset operations (`--soft` and mixed/default).
-
-use crate::utils;
-use anyhow::{Result, bail};
-use std::{fs, path::Path};
-
-fn resolve_commit(root_path: &Path, target: &str) -> Result<String> {
-    if target.len() != 40 {
-        bail!("Invalid commit hash '{}'.", target);
-    }
-
-    let object_path = root_path
-        .join(".rustygit")
-        .join("objects")
-        .join(&target[..2])
-        .join(&target[2..]);
-
-    if !object_path.exists() {
-        bail!("Commit '{}' does not exist.", target);
-    }
-
-    let _ = utils::parse_commit(root_path, target)?;
-    Ok(target.to_string())
+//! HEAD/index/worktree reset operations (`--soft`, mixed, `--hard`, `--keep`).
+
+use crate::utils::{self, IndexEntry};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// How much of the repository `reset` moves along with HEAD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetMode {
+    /// Move HEAD only; index and working tree are unchanged.
+    Soft,
+    /// Move HEAD and replace the index with the target tree.
+    Mixed,
+    /// Like `Mixed`, and rewrite tracked files to match the target,
+    /// discarding local changes. Untracked files are left alone.
+    Hard,
+    /// Like `Hard` for files that differ between HEAD and the target, but
+    /// refuses if any of them has local changes; other local changes are kept.
+    Keep,
 }
 
-fn update_head_to_commit(root_path: &Path, commit_hash: &str) -> Result<()> {
-    let head_path = root_path.join(".rustygit").join("HEAD");
-    let head_content = fs::read_to_string(&head_path)?;
-
-    if head_content.starts_with("ref: ") {
-        let ref_path = head_content[5..].trim();
-        let branch_ref_path = root_path.join(".rustygit").join(ref_path);
-        fs::write(branch_ref_path, format!("{}\n", commit_hash))?;
-    } else {
-        fs::write(head_path, format!("{}\n", commit_hash))?;
+/// Rewrites every tracked path to `target_entries`, removing paths that are
+/// tracked in HEAD or the index (including unmerged ones) but not in the target.
+fn reset_work_tree_hard(
+    root_path: &Path,
+    head_entries: &HashMap<PathBuf, IndexEntry>,
+    target_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<()> {
+    let index_entries = utils::read_index_entries(root_path)?;
+    let conflicts = utils::read_index_conflicts(root_path)?;
+
+    for path in head_entries
+        .keys()
+        .chain(index_entries.keys())
+        .chain(conflicts.keys())
+    {
+        if !target_entries.contains_key(path) {
+            utils::remove_work_file(root_path, path)?;
+        }
     }
 
-    Ok(())
-}
-
-fn set_index_to_commit_tree(root_path: &Path, commit_hash: &str) -> Result<()> {
-    let commit_content = utils::parse_commit(root_path, commit_hash)?;
-    let n_idx = commit_content
-        .find('\n')
-        .ok_or_else(|| anyhow::anyhow!("Malformed commit object: missing newline after tree"))?;
-
-    let tree_hash = &commit_content[5..n_idx];
-
-    let mut tree_map = std::collections::HashMap::new();
-    utils::get_tree_files_map(root_path, Path::new(""), tree_hash, &mut tree_map)?;
-    utils::write_index_map(root_path, &tree_map)?;
+    for (path, entry) in target_entries {
+        let content = utils::read_blob(root_path, &entry.hash)?;
+        utils::write_work_file(&root_path.join(path), entry.mode, &content)?;
+    }
 
     Ok(())
 }
 
-/// Resets repository references to `target`.
-///
-/// - `soft = true`: move HEAD only (index/worktree unchanged)
-/// - `soft = false`: move HEAD and reset index to target commit tree
-pub fn reset(root_path: &Path, target: &str, soft: bool) -> Result<()> {
+/// Resets repository references to `target`, any revision expression
+/// accepted by [`utils::resolve_commit`], updating the index and working
+/// tree as selected by `mode`.
+pub fn reset(root_path: &Path, target: &str, mode: ResetMode) -> Result<()> {
     utils::ensure_repo_exists(root_path)?;
 
-    let target_commit = resolve_commit(root_path, target)?;
+    let target_commit = utils::resolve_commit(root_path, target)?;
+    let current_commit = utils::get_current_commit_hash(root_path)?;
+
+    let head_entries = utils::get_commit_entries_map(root_path, current_commit.as_deref())?;
+    let target_entries = utils::get_commit_entries_map(root_path, Some(&target_commit))?;
 
-    if soft {
-        let index_map = utils::read_index_map(root_path)?;
-        if index_map.is_empty() {
-            if let Some(current_commit) = utils::get_current_commit_hash(root_path)? {
-                set_index_to_commit_tree(root_path, &current_commit)?;
+    match mode {
+        ResetMode::Soft => {
+            // An empty index stands for "same as HEAD"; pin it before HEAD moves.
+            if utils::read_index_map(root_path)?.is_empty() && !head_entries.is_empty() {
+                utils::write_index_entries(root_path, &head_entries)?;
             }
         }
+        ResetMode::Mixed => {}
+        ResetMode::Hard => reset_work_tree_hard(root_path, &head_entries, &target_entries)?,
+        ResetMode::Keep => {
+            let target_tree_hash = utils::get_commit_tree_hash(root_path, &target_commit)?;
+            utils::checkout_safety_check(root_path, Some(target_tree_hash))?;
+            utils::update_work_tree(root_path, &head_entries, &target_entries)?;
+        }
     }
 
-    update_head_to_commit(root_path, &target_commit)?;
-
-    if !soft {
-        set_index_to_commit_tree(root_path, &target_commit)?;
+    utils::update_head(
+        root_path,
+        &target_commit,
+        Some(current_commit.as_deref().unwrap_or(utils::NULL_HASH)),
+        &format!("reset: moving to {}", target),
+    )?;
+
+    // As in Git, every mode but --soft abandons an in-progress merge.
+    if mode != ResetMode::Soft {
+        utils::write_index_entries(root_path, &target_entries)?;
+        utils::write_index_conflicts(root_path, &HashMap::new())?;
+        utils::clear_merge_state(root_path)?;
     }
 
     Ok(())
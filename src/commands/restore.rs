@@ -1,45 +1,126 @@
-//! Restores working-directory files from index state.
+//! Restores working-directory files and index entries from the index or a commit.
 
 use crate::utils;
-use anyhow::{Result, anyhow, bail};
-use std::{fs, path::Path};
+use anyhow::{Result, bail};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
 
 /// Restores `file` from index to working directory.
 ///
 /// If the path is not staged in index, an existing working file is removed.
 pub fn restore(root_path: &Path, file: &Path) -> Result<()> {
+    let spec = file.to_string_lossy().to_string();
+    restore_paths(root_path, &[spec], None, false, true).map(|_| ())
+}
+
+/// Restores the paths selected by `pathspecs` from `source` and returns them.
+///
+/// `source` is any revision; without one, the working tree is restored from
+/// the index and the index from HEAD. `staged` writes the index and
+/// `worktree` the working tree (the default when neither is set). Selected
+/// paths missing from the source are removed from each destination.
+///
+/// Pathspecs are matched against the source and the index. The one
+/// exception is a plain restore from the index (no `source`, not `staged`),
+/// where a literal path to an untracked file is restored to "absent" as
+/// [`restore`] always has; with a source or `staged`, a path known to
+/// neither is an error and is never deleted.
+pub fn restore_paths(
+    root_path: &Path,
+    pathspecs: &[String],
+    source: Option<&str>,
+    staged: bool,
+    worktree: bool,
+) -> Result<Vec<PathBuf>> {
     utils::ensure_repo_exists(root_path)?;
 
-    let file_path = if file.is_absolute() {
-        file.to_path_buf()
-    } else {
-        root_path.join(file)
+    let worktree = worktree || !staged;
+    let pathspecs = utils::parse_pathspecs(root_path, pathspecs)?;
+
+    let mut index_entries = utils::read_effective_index(root_path)?;
+    let conflicts = utils::read_index_conflicts(root_path)?;
+
+    let source_entries = match source {
+        Some(source) => {
+            let commit_hash = utils::resolve_commit(root_path, source)?;
+            utils::get_commit_entries_map(root_path, Some(&commit_hash))?
+        }
+        None if staged => {
+            let head_commit = utils::get_current_commit_hash(root_path)?;
+            utils::get_commit_entries_map(root_path, head_commit.as_deref())?
+        }
+        None => index_entries.clone(),
     };
 
-    let relative_path = file_path
-        .strip_prefix(root_path)
-        .map_err(|_| anyhow!("File must be inside the repository root."))?
-        .to_path_buf();
+    let mut paths = BTreeSet::new();
+    for pathspec in &pathspecs {
+        let known = source_entries
+            .keys()
+            .chain(index_entries.keys())
+            .chain(conflicts.keys());
 
-    let index_map = utils::read_index_map(root_path)?;
+        let mut matched = false;
+        for path in known.filter(|path| pathspec.matches(path)) {
+            paths.insert(path.clone());
+            matched = true;
+        }
 
-    if let Some(blob_hash) = index_map.get(&relative_path) {
-        let content = utils::parse_blob(root_path, blob_hash)?;
+        if let Some(path) = pathspec.literal_path()
+            && source.is_none()
+            && !staged
+            && fs::symlink_metadata(root_path.join(path)).is_ok_and(|metadata| !metadata.is_dir())
+        {
+            paths.insert(path.to_path_buf());
+            matched = true;
+        }
 
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
+        if !matched {
+            bail!(
+                "pathspec '{}' did not match any file(s) known to rustygit",
+                pathspec.as_str()
+            );
         }
+    }
 
-        fs::write(file_path, content)?;
-        return Ok(());
+    if source.is_none()
+        && !staged
+        && let Some(path) = paths.iter().find(|path| conflicts.contains_key(*path))
+    {
+        bail!("Cannot restore '{}': path is unmerged.", path.display());
     }
 
-    if file_path.exists() {
-        if !file_path.is_file() {
-            bail!("Could not restore '{}': not a file.", file.display());
+    for path in &paths {
+        let entry = source_entries.get(path);
+
+        if worktree {
+            match entry {
+                Some(entry) => {
+                    let content = utils::read_blob(root_path, &entry.hash)?;
+                    utils::write_work_file(&root_path.join(path), entry.mode, &content)?;
+                }
+                None => {
+                    let full_path = root_path.join(path);
+                    if fs::symlink_metadata(&full_path).is_ok_and(|metadata| !metadata.is_dir()) {
+                        utils::remove_work_file(root_path, path)?;
+                    }
+                }
+            }
         }
-        fs::remove_file(file_path)?;
+
+        if staged {
+            match entry {
+                Some(entry) => index_entries.insert(path.clone(), entry.clone()),
+                None => index_entries.remove(path),
+            };
+        }
+    }
+
+    if staged {
+        utils::write_index_entries(root_path, &index_entries)?;
     }
 
-    Ok(())
+    Ok(paths.into_iter().collect())
 }
@@ -0,0 +1,15 @@
+//! Revision expression resolution (`rev-parse`).
+
+use crate::utils;
+use anyhow::Result;
+use std::path::Path;
+
+/// Resolves each revision expression to a full object hash, in order.
+pub fn rev_parse(root_path: &Path, revisions: &[String]) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    revisions
+        .iter()
+        .map(|revision| utils::resolve_revision(root_path, revision))
+        .collect()
+}
@@ -22,6 +22,16 @@ pub fn rm(root_path: &Path, file: &Path) -> Result<()> {
         .map_err(|_| anyhow!("File must be inside the repository root."))?
         .to_path_buf();
 
+    // Removing an unmerged path resolves its conflict as a deletion.
+    let mut conflicts = utils::read_index_conflicts(root_path)?;
+    if conflicts.remove(&relative_path).is_some() {
+        utils::write_index_conflicts(root_path, &conflicts)?;
+        if fs::symlink_metadata(&file_path).is_ok_and(|metadata| !metadata.is_dir()) {
+            fs::remove_file(&file_path)?;
+        }
+        return Ok(());
+    }
+
     let mut index_map = utils::read_index_map(root_path)?;
     let Some(index_hash) = index_map.get(&relative_path).cloned() else {
         bail!(
@@ -33,20 +43,20 @@ pub fn rm(root_path: &Path, file: &Path) -> Result<()> {
     let mut work_dir_map = HashMap::new();
     utils::get_work_dir_map(root_path, Path::new(""), &mut work_dir_map)?;
 
-    if let Some(work_hash) = work_dir_map.get(&relative_path) {
-        if work_hash != &index_hash {
-            bail!(
-                "Cannot remove '{}': file has local modifications not staged in index.",
-                file.display()
-            );
-        }
+    if let Some(work_hash) = work_dir_map.get(&relative_path)
+        && work_hash != &index_hash
+    {
+        bail!(
+            "Cannot remove '{}': file has local modifications not staged in index.",
+            file.display()
+        );
     }
 
     index_map.remove(&relative_path);
     utils::write_index_map(root_path, &index_map)?;
 
-    if file_path.exists() {
-        if !file_path.is_file() {
+    if let Ok(metadata) = fs::symlink_metadata(&file_path) {
+        if metadata.is_dir() {
             bail!("Could not remove '{}': not a file.", file.display());
         }
         fs::remove_file(file_path)?;
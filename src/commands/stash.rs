@@ -0,0 +1,370 @@
+//! Stashing local changes as commits under `refs/stash`.
+//!
+//! As in Git, a stash is a commit `W` holding the working tree, whose
+//! parents are the HEAD commit, a commit `I` holding the index and,
+//! optionally, a parentless commit `U` holding the untracked files. The
+//! stack itself is the reflog of `refs/stash`: `stash@{0}` is the newest.
+
+use crate::commands::{
+    self,
+    merge::{self, ContentMerge},
+};
+use crate::utils::{self, IndexEntry, ReflogEntry};
+use anyhow::{Result, anyhow, bail};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+const STASH_REF: &str = "refs/stash";
+
+/// What `stash apply` (or `pop`) did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StashApplyOutcome {
+    /// All changes were applied.
+    Applied,
+    /// These paths were left with conflict markers; the stash was kept.
+    Conflicted(Vec<PathBuf>),
+}
+
+/// Describes HEAD for stash messages: `<branch>: <short hash> <subject>`.
+fn describe_head(root_path: &Path, head_commit: &str) -> Result<String> {
+    let branch = utils::current_branch(root_path)?.unwrap_or_else(|| "(no branch)".to_string());
+    let message = utils::get_commit_message(root_path, head_commit)?;
+    let subject = message.lines().next().unwrap_or_default();
+
+    Ok(format!("{}: {} {}", branch, &head_commit[..7], subject))
+}
+
+/// Returns the tracked working-tree snapshot: every path in `index_entries`
+/// with its current working-file content, writing their blobs.
+/// Paths deleted from the working tree are left out.
+fn snapshot_tracked_files(
+    root_path: &Path,
+    index_entries: &HashMap<PathBuf, IndexEntry>,
+    work_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let mut snapshot = HashMap::new();
+
+    for path in index_entries.keys() {
+        let Some(work_entry) = work_entries.get(path) else {
+            continue;
+        };
+        let hash = commands::write_blob(root_path, &root_path.join(path))?;
+        snapshot.insert(
+            path.clone(),
+            IndexEntry {
+                mode: work_entry.mode,
+                hash,
+            },
+        );
+    }
+
+    Ok(snapshot)
+}
+
+/// Returns the untracked, non-ignored files, writing their blobs.
+fn snapshot_untracked_files(
+    root_path: &Path,
+    index_entries: &HashMap<PathBuf, IndexEntry>,
+    work_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let ignore_rules = utils::parse_ignore_file(root_path)?;
+    let mut snapshot = HashMap::new();
+
+    for (path, work_entry) in work_entries {
+        let full_path = root_path.join(path);
+        if index_entries.contains_key(path)
+            || utils::is_ignored(&full_path, root_path, &ignore_rules)
+        {
+            continue;
+        }
+        let hash = commands::write_blob(root_path, &full_path)?;
+        snapshot.insert(
+            path.clone(),
+            IndexEntry {
+                mode: work_entry.mode,
+                hash,
+            },
+        );
+    }
+
+    Ok(snapshot)
+}
+
+/// Saves local changes on the stash stack and resets them to HEAD.
+///
+/// Records the index and the tracked files in the working tree, and with
+/// `include_untracked` also untracked (but not ignored) files, which are
+/// then removed. `message` replaces the default `WIP on <branch>: ...`
+/// description. Returns the stash commit, or `None` when there is nothing
+/// to save.
+pub fn stash_push(
+    root_path: &Path,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<Option<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let head_commit = utils::get_current_commit_hash(root_path)?
+        .ok_or_else(|| anyhow!("You do not have the initial commit yet."))?;
+    if !utils::read_index_conflicts(root_path)?.is_empty() {
+        bail!("Cannot stash: you have unmerged paths. Resolve them first.");
+    }
+
+    let head_entries = utils::get_commit_entries_map(root_path, Some(&head_commit))?;
+    let index_entries = utils::read_effective_index(root_path)?;
+    let mut work_entries = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_entries)?;
+
+    let tracked = snapshot_tracked_files(root_path, &index_entries, &work_entries)?;
+    let untracked = if include_untracked {
+        snapshot_untracked_files(root_path, &index_entries, &work_entries)?
+    } else {
+        HashMap::new()
+    };
+
+    if index_entries == head_entries && tracked == head_entries && untracked.is_empty() {
+        return Ok(None);
+    }
+
+    let head_description = describe_head(root_path, &head_commit)?;
+
+    let index_tree = commands::write_tree_from_entries(root_path, &index_entries)?;
+    let index_commit = commands::commit_tree(
+        root_path,
+        &index_tree,
+        std::slice::from_ref(&head_commit),
+        format!("index on {}\n", head_description),
+    )?;
+
+    let mut parents = vec![head_commit.clone(), index_commit];
+    if !untracked.is_empty() {
+        let untracked_tree = commands::write_tree_from_entries(root_path, &untracked)?;
+        parents.push(commands::commit_tree(
+            root_path,
+            &untracked_tree,
+            &[],
+            format!("untracked files on {}\n", head_description),
+        )?);
+    }
+
+    let stash_message = match message {
+        Some(message) => {
+            let branch =
+                utils::current_branch(root_path)?.unwrap_or_else(|| "(no branch)".to_string());
+            format!("On {}: {}", branch, message)
+        }
+        None => format!("WIP on {}", head_description),
+    };
+    let work_tree = commands::write_tree_from_entries(root_path, &tracked)?;
+    let stash_commit = commands::commit_tree(
+        root_path,
+        &work_tree,
+        &parents,
+        format!("{}\n", stash_message),
+    )?;
+
+    let old_stash = utils::read_ref(root_path, STASH_REF)?;
+    utils::update_ref(
+        root_path,
+        STASH_REF,
+        &stash_commit,
+        Some(old_stash.as_deref().unwrap_or(utils::NULL_HASH)),
+        &stash_message,
+    )?;
+
+    commands::reset(root_path, "HEAD", commands::ResetMode::Hard)?;
+    for path in untracked.keys() {
+        utils::remove_work_file(root_path, path)?;
+    }
+
+    Ok(Some(stash_commit))
+}
+
+/// Parses `stash@{N}` or a bare `N` into a stack position (default 0).
+fn parse_stash_index(stash: Option<&str>) -> Result<usize> {
+    let Some(stash) = stash else {
+        return Ok(0);
+    };
+
+    let digits = stash
+        .strip_prefix("stash@{")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .unwrap_or(stash);
+
+    digits
+        .parse()
+        .map_err(|_| anyhow!("'{}' is not a stash reference.", stash))
+}
+
+/// Returns the stash stack, oldest first as in the reflog.
+fn read_stash_stack(root_path: &Path) -> Result<Vec<ReflogEntry>> {
+    if !utils::ref_exists(root_path, STASH_REF) {
+        return Ok(Vec::new());
+    }
+    utils::read_reflog(root_path, STASH_REF)
+}
+
+/// Resolves `stash` (`stash@{N}`, `N`, or `None` for the newest) to its stack
+/// position and commit.
+fn resolve_stash(root_path: &Path, stash: Option<&str>) -> Result<(usize, String)> {
+    let n = parse_stash_index(stash)?;
+    let stack = read_stash_stack(root_path)?;
+
+    if stack.is_empty() {
+        bail!("No stash entries found.");
+    }
+    let entry = stack
+        .iter()
+        .rev()
+        .nth(n)
+        .ok_or_else(|| anyhow!("stash@{{{}}} does not exist.", n))?;
+
+    Ok((n, entry.new_hash.clone()))
+}
+
+/// Lists stash entries newest first as `stash@{N}: <message>`.
+pub fn stash_list(root_path: &Path) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    Ok(read_stash_stack(root_path)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(n, entry)| format!("stash@{{{}}}: {}", n, entry.message))
+        .collect())
+}
+
+/// Lists the paths a stash changes relative to the commit it was made on,
+/// as `A`/`M`/`D` status lines in path order.
+pub fn stash_show(root_path: &Path, stash: Option<&str>) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let (_, stash_commit) = resolve_stash(root_path, stash)?;
+    let parents = utils::get_commit_parents(root_path, &stash_commit)?;
+    let base_entries =
+        utils::get_commit_entries_map(root_path, parents.first().map(String::as_str))?;
+    let mut stash_entries = utils::get_commit_entries_map(root_path, Some(&stash_commit))?;
+    if let Some(untracked_commit) = parents.get(2) {
+        stash_entries.extend(utils::get_commit_entries_map(
+            root_path,
+            Some(untracked_commit),
+        )?);
+    }
+
+    let paths: BTreeSet<&PathBuf> = base_entries.keys().chain(stash_entries.keys()).collect();
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let status = match (base_entries.get(path), stash_entries.get(path)) {
+                (None, Some(_)) => "A",
+                (Some(_), None) => "D",
+                (Some(base), Some(stashed)) if base != stashed => "M",
+                _ => return None,
+            };
+            Some(format!("{}\t{}", status, path.display()))
+        })
+        .collect())
+}
+
+/// Re-applies a stash on top of the current HEAD, keeping it on the stack.
+///
+/// Changes are merged three-way against the commit the stash was made on,
+/// so they apply cleanly even after HEAD has moved; local changes to the
+/// same paths make it fail before anything is written. Files added in the
+/// stash are staged; other changes are left unstaged. Conflicts are left
+/// with markers and index stages.
+pub fn stash_apply(root_path: &Path, stash: Option<&str>) -> Result<StashApplyOutcome> {
+    utils::ensure_repo_exists(root_path)?;
+
+    if utils::read_merge_head(root_path)?.is_some()
+        || !utils::read_index_conflicts(root_path)?.is_empty()
+    {
+        bail!("Cannot apply a stash while there are unmerged paths.");
+    }
+
+    let (n, stash_commit) = resolve_stash(root_path, stash)?;
+    let parents = utils::get_commit_parents(root_path, &stash_commit)?;
+    let base_commit = parents
+        .first()
+        .ok_or_else(|| anyhow!("stash@{{{}}} is not a stash commit.", n))?;
+
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let head_entries = utils::get_commit_entries_map(root_path, head_commit.as_deref())?;
+    if utils::read_effective_index(root_path)? != head_entries {
+        bail!("Cannot apply a stash: you have staged changes. Commit or stash them first.");
+    }
+
+    let base_entries = utils::get_commit_entries_map(root_path, Some(base_commit))?;
+    let mut stash_entries = utils::get_commit_entries_map(root_path, Some(&stash_commit))?;
+    let untracked_entries = match parents.get(2) {
+        Some(untracked_commit) => utils::get_commit_entries_map(root_path, Some(untracked_commit))?,
+        None => HashMap::new(),
+    };
+    stash_entries.extend(untracked_entries.clone());
+
+    let result: ContentMerge = merge::merge_entries(
+        root_path,
+        &base_entries,
+        &head_entries,
+        &stash_entries,
+        "Updated upstream",
+        "Stashed changes",
+    )?;
+    merge::write_content_merge(root_path, &head_entries, &result)?;
+
+    // Like Git, only additions stay staged; untracked files come back
+    // untracked and unmerged paths keep their conflict stages.
+    let mut index_entries = head_entries.clone();
+    for (path, entry) in &result.merged {
+        if !head_entries.contains_key(path) && !untracked_entries.contains_key(path) {
+            index_entries.insert(path.clone(), entry.clone());
+        }
+    }
+    index_entries.retain(|path, _| !result.unmerged.contains_key(path));
+    utils::write_index_entries(root_path, &index_entries)?;
+
+    if result.unmerged.is_empty() {
+        return Ok(StashApplyOutcome::Applied);
+    }
+
+    let mut paths: Vec<PathBuf> = result.unmerged.into_keys().collect();
+    paths.sort();
+    Ok(StashApplyOutcome::Conflicted(paths))
+}
+
+/// Removes a stash from the stack and returns its commit.
+pub fn stash_drop(root_path: &Path, stash: Option<&str>) -> Result<String> {
+    utils::ensure_repo_exists(root_path)?;
+
+    // The stack lives in the reflog, so it is read and rewritten under the
+    // ref's lock along with the ref itself.
+    let stash_lock = utils::lock_ref(root_path, STASH_REF)?;
+    let (n, stash_commit) = resolve_stash(root_path, stash)?;
+    let mut stack = read_stash_stack(root_path)?;
+    stack.remove(stack.len() - 1 - n);
+
+    let old_top = utils::read_ref(root_path, STASH_REF)?;
+    let mut transaction = utils::RefTransaction::new();
+    transaction.with_lock(stash_lock);
+    match stack.last() {
+        Some(top) => {
+            utils::write_reflog(root_path, STASH_REF, &stack)?;
+            transaction.update(STASH_REF, &top.new_hash, old_top.as_deref(), None)
+        }
+        None => transaction.delete(STASH_REF, old_top.as_deref()),
+    }
+    .commit(root_path)?;
+
+    Ok(stash_commit)
+}
+
+/// Applies a stash and drops it unless the apply stopped on conflicts.
+pub fn stash_pop(root_path: &Path, stash: Option<&str>) -> Result<StashApplyOutcome> {
+    let outcome = stash_apply(root_path, stash)?;
+    if outcome == StashApplyOutcome::Applied {
+        stash_drop(root_path, stash)?;
+    }
+    Ok(outcome)
+}
@@ -1,6 +1,6 @@
 //! Repository status reporting across HEAD, index, and working directory.
 
-use crate::utils::{self, IgnoreRule};
+use crate::utils::{self, ConflictEntry, IgnoreRule, IndexEntry, RenameOptions, TreeChange};
 use anyhow::{Result, bail};
 use std::{
     collections::HashMap,
@@ -8,38 +8,96 @@ use std::{
     path::{Path, PathBuf},
 };
 
-/// Returns a formatted status report with staged, modified, deleted, and untracked files.
+fn describe_conflict(conflict: &ConflictEntry) -> &'static str {
+    match (&conflict.base, &conflict.ours, &conflict.theirs) {
+        (_, None, _) => "deleted by us",
+        (_, _, None) => "deleted by them",
+        (None, _, _) => "both added",
+        _ => "both modified",
+    }
+}
+
+/// Pairs `deleted` paths (entries in `old`) with `added` paths (entries in
+/// `new`) into renames, taking the paired paths out of both lists. Returns
+/// the `(old, new)` path pairs.
+fn pair_renames(
+    root_path: &Path,
+    old: &HashMap<PathBuf, IndexEntry>,
+    deleted: &mut Vec<PathBuf>,
+    new: &HashMap<PathBuf, IndexEntry>,
+    added: &mut Vec<PathBuf>,
+    work_tree: bool,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if deleted.is_empty() || added.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let deletions = deleted.iter().map(|path| TreeChange {
+        path: path.clone(),
+        old: old.get(path).cloned(),
+        new: None,
+        source: None,
+    });
+    let additions = added.iter().map(|path| TreeChange {
+        path: path.clone(),
+        old: None,
+        new: new.get(path).cloned(),
+        source: None,
+    });
+    let changes = utils::detect_renames(
+        root_path,
+        deletions.chain(additions).collect(),
+        work_tree,
+        RenameOptions::default(),
+    )?;
+
+    let renames: Vec<(PathBuf, PathBuf)> = changes
+        .into_iter()
+        .filter_map(|change| Some((change.source?.path, change.path)))
+        .collect();
+    deleted.retain(|path| !renames.iter().any(|(old_path, _)| old_path == path));
+    added.retain(|path| !renames.iter().any(|(_, new_path)| new_path == path));
+    Ok(renames)
+}
+
+/// Returns a formatted status report with unmerged, staged, modified, deleted, and untracked files.
+///
+/// Deleted paths whose content reappears under a new path (staged, or as an
+/// untracked file) are reported as `renamed: <old> -> <new>` instead.
 pub fn status(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<String> {
-    let mut work_dir_map: HashMap<PathBuf, String> = HashMap::new();
-    utils::get_work_dir_map(root_path, Path::new(""), &mut work_dir_map)?;
-    let index_map = utils::read_index_map(root_path)?;
+    // Entries compare both blob hash and file mode, so permission-only
+    // changes (e.g. `chmod +x`) are reported too.
+    let mut work_dir_map: HashMap<PathBuf, IndexEntry> = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_dir_map)?;
+    let index_map = utils::read_index_entries(root_path)?;
+    let conflicts = utils::read_index_conflicts(root_path)?;
 
     let cur_tree_hash = utils::get_current_tree_hash(root_path)?;
-    let mut cur_tree_map: HashMap<PathBuf, String> = HashMap::new();
+    let mut cur_tree_map: HashMap<PathBuf, IndexEntry> = HashMap::new();
     if let Some(hash) = cur_tree_hash {
-        utils::get_tree_files_map(root_path, Path::new(""), &hash, &mut cur_tree_map)?;
+        utils::get_tree_entries_map(root_path, Path::new(""), &hash, &mut cur_tree_map)?;
     }
 
-    let effective_index_map: HashMap<PathBuf, String> = if index_map.is_empty() {
+    let effective_index_map: HashMap<PathBuf, IndexEntry> = if index_map.is_empty() {
         cur_tree_map.clone()
     } else {
         index_map.clone()
     };
 
-    let mut clean: bool = true;
+    let mut clean: bool = conflicts.is_empty();
     let mut staged_files: Vec<PathBuf> = Vec::new();
     let mut modified_files: Vec<PathBuf> = Vec::new();
     let mut deleted_files: Vec<PathBuf> = Vec::new();
     let mut untracked_files: Vec<PathBuf> = Vec::new();
 
-    for (path, index_hash) in index_map.iter() {
+    for (path, index_entry) in index_map.iter() {
         if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
             continue;
         }
 
         match cur_tree_map.get(path) {
-            Some(current_hash) => {
-                if current_hash != index_hash {
+            Some(current_entry) => {
+                if current_entry != index_entry {
                     staged_files.push(path.clone());
                     clean = false;
                 }
@@ -51,15 +109,17 @@ pub fn status(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<String
         }
     }
 
-    for (path, work_hash) in work_dir_map.iter() {
-        if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
+    for (path, work_entry) in work_dir_map.iter() {
+        if conflicts.contains_key(path)
+            || utils::is_ignored(&root_path.join(path), root_path, ignore_rules)
+        {
             continue;
         }
         let in_index = effective_index_map.get(path);
 
         match in_index {
-            Some(index_hash) => {
-                if work_hash != index_hash {
+            Some(index_entry) => {
+                if work_entry != index_entry {
                     modified_files.push(path.clone());
                     clean = false;
                 }
@@ -72,7 +132,9 @@ pub fn status(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<String
     }
 
     for (path, _) in effective_index_map.iter() {
-        if utils::is_ignored(&root_path.join(path), root_path, ignore_rules) {
+        if conflicts.contains_key(path)
+            || utils::is_ignored(&root_path.join(path), root_path, ignore_rules)
+        {
             continue;
         }
         let in_work_dir = work_dir_map.get(path);
@@ -88,37 +150,98 @@ pub fn status(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<String
     deleted_files.sort();
     untracked_files.sort();
 
+    // Staged deletions are not listed, but can be the source of a rename.
+    let mut staged_deletions: Vec<PathBuf> = if index_map.is_empty() {
+        Vec::new()
+    } else {
+        let mut paths: Vec<PathBuf> = cur_tree_map
+            .keys()
+            .filter(|path| {
+                !index_map.contains_key(*path)
+                    && !utils::is_ignored(&root_path.join(path), root_path, ignore_rules)
+            })
+            .cloned()
+            .collect();
+        paths.sort();
+        paths
+    };
+    let mut staged_additions: Vec<PathBuf> = staged_files
+        .iter()
+        .filter(|path| !cur_tree_map.contains_key(*path))
+        .cloned()
+        .collect();
+    let staged_renames: HashMap<PathBuf, PathBuf> = pair_renames(
+        root_path,
+        &cur_tree_map,
+        &mut staged_deletions,
+        &index_map,
+        &mut staged_additions,
+        false,
+    )?
+    .into_iter()
+    .map(|(old_path, new_path)| (new_path, old_path))
+    .collect();
+    let renamed_files = pair_renames(
+        root_path,
+        &effective_index_map,
+        &mut deleted_files,
+        &work_dir_map,
+        &mut untracked_files,
+        true,
+    )?;
+
     let head_path = root_path.join(".rustygit").join("HEAD");
     let head_content = fs::read_to_string(&head_path)?;
     let mut output_string = String::new();
 
-    if !head_content.starts_with("ref: ") {
+    if let Some(ref_path) = head_content.strip_prefix("ref: ") {
+        let Some(branch_name) = ref_path.trim().strip_prefix("refs/heads/") else {
+            bail!("HEAD could not be resolved.")
+        };
+        output_string.push_str(&format!("On Branch: {}\n\n", branch_name));
+    } else {
         output_string.push_str(&format!(
             "Branch: None\tHEAD is in a DETACHED state on commit: {}\n\n",
             head_content
         ));
-    } else {
-        let ref_path = head_content[5..].trim();
-        let branch_name = if ref_path.starts_with("refs/heads/") {
-            &ref_path["refs/heads/".len()..]
-        } else {
-            bail!("HEAD could not be resolved.")
-        };
-        output_string.push_str(&format!("On Branch: {}\n\n", branch_name));
     }
 
     if clean {
         output_string.push_str("Working directory clean.\n");
     } else {
+        if !conflicts.is_empty() {
+            let mut unmerged: Vec<(&PathBuf, &ConflictEntry)> = conflicts.iter().collect();
+            unmerged.sort_by(|a, b| a.0.cmp(b.0));
+
+            output_string.push_str("\tUnmerged paths:\n");
+            for (file, conflict) in unmerged {
+                output_string.push_str(&format!(
+                    "\t\t{}:\t{}\n",
+                    describe_conflict(conflict),
+                    file.display()
+                ));
+            }
+        }
+
         if !staged_files.is_empty() {
             output_string.push_str("\tStaged files:\n");
             for file in &staged_files {
-                output_string.push_str(&format!("\t\t{}\n", file.display()));
+                match staged_renames.get(file) {
+                    Some(old_file) => output_string.push_str(&format!(
+                        "\t\trenamed: {} -> {}\n",
+                        old_file.display(),
+                        file.display()
+                    )),
+                    None => output_string.push_str(&format!("\t\t{}\n", file.display())),
+                }
             }
         }
 
-        if !modified_files.is_empty() {
+        if !(modified_files.is_empty() && deleted_files.is_empty() && renamed_files.is_empty()) {
             output_string.push_str("\tChanges not staged for commit:\n");
+        }
+
+        if !modified_files.is_empty() {
             output_string.push_str("\t\tModified files:\n");
             for file in &modified_files {
                 output_string.push_str(&format!("\t\t\t{}\n", file.display()));
@@ -126,15 +249,23 @@ pub fn status(root_path: &Path, ignore_rules: &Vec<IgnoreRule>) -> Result<String
         }
 
         if !deleted_files.is_empty() {
-            if modified_files.is_empty() {
-                output_string.push_str("\tChanges not staged for commit:\n");
-            }
             output_string.push_str("\t\tDeleted files:\n");
             for file in &deleted_files {
                 output_string.push_str(&format!("\t\t\t{}\n", file.display()));
             }
         }
 
+        if !renamed_files.is_empty() {
+            output_string.push_str("\t\tRenamed files:\n");
+            for (old_file, new_file) in &renamed_files {
+                output_string.push_str(&format!(
+                    "\t\t\trenamed: {} -> {}\n",
+                    old_file.display(),
+                    new_file.display()
+                ));
+            }
+        }
+
         if !untracked_files.is_empty() {
             output_string.push_str("\tUntracked files:\n");
             for file in &untracked_files {
@@ -0,0 +1,107 @@
+//! Lightweight and annotated tags under `refs/tags`.
+
+use crate::{commands, utils};
+use anyhow::{Result, bail};
+use glob::Pattern;
+use std::path::Path;
+
+/// Writes an annotated tag object pointing at `target_hash` and returns its hash.
+fn write_tag_object(
+    root_path: &Path,
+    tag_name: &str,
+    target_hash: &str,
+    message: &str,
+) -> Result<String> {
+    let (target_type, _) = utils::split_object(&utils::read_object(root_path, target_hash)?)?;
+    let tagger = utils::committer_signature(root_path)?;
+
+    let mut data = format!(
+        "object {}\ntype {}\ntag {}\ntagger {}\n\n{}",
+        target_hash,
+        target_type,
+        tag_name,
+        tagger.format(),
+        message
+    );
+    if !data.ends_with('\n') {
+        data.push('\n');
+    }
+
+    let mut content: Vec<u8> = Vec::new();
+    content.extend_from_slice(format!("tag {}\0", data.len()).as_bytes());
+    content.extend_from_slice(data.as_bytes());
+
+    let hash = utils::hash_bytes(&content);
+    commands::write_object(root_path, &hash, &content)?;
+
+    Ok(hash)
+}
+
+/// Creates tag `tag_name` at `target` (a revision, default `HEAD`).
+///
+/// With a `message` an annotated tag object (`object`/`type`/`tag`/`tagger`
+/// headers followed by the message) is written and the ref points at it;
+/// otherwise the ref points straight at the target as a lightweight tag.
+/// Returns the hash stored in the ref.
+pub fn create_tag(
+    root_path: &Path,
+    tag_name: &str,
+    target: Option<&str>,
+    message: Option<&str>,
+) -> Result<String> {
+    utils::ensure_repo_exists(root_path)?;
+    utils::check_ref_name(tag_name)?;
+
+    let tag_ref = format!("refs/tags/{}", tag_name);
+    if utils::ref_exists(root_path, &tag_ref) {
+        bail!("Tag '{}' already exists.", tag_name);
+    }
+
+    let target_hash = utils::resolve_revision(root_path, target.unwrap_or("HEAD"))?;
+
+    let ref_hash = match message {
+        Some(message) => write_tag_object(root_path, tag_name, &target_hash, message)?,
+        None => target_hash,
+    };
+
+    utils::update_ref(
+        root_path,
+        &tag_ref,
+        &ref_hash,
+        Some(utils::NULL_HASH),
+        "tag: created",
+    )?;
+
+    Ok(ref_hash)
+}
+
+/// Returns tag names in sorted order, optionally filtered by a glob such as `v1.*`.
+pub fn list_tags(root_path: &Path, pattern: Option<&str>) -> Result<Vec<String>> {
+    utils::ensure_repo_exists(root_path)?;
+
+    let pattern = pattern.map(Pattern::new).transpose()?;
+
+    Ok(utils::list_refs(root_path, "refs/tags")?
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| pattern.as_ref().is_none_or(|pattern| pattern.matches(name)))
+        .collect())
+}
+
+/// Deletes tag `tag_name` and returns the hash it pointed to.
+///
+/// Annotated tag objects are left in the object store for `gc` to prune.
+pub fn delete_tag(root_path: &Path, tag_name: &str) -> Result<String> {
+    utils::ensure_repo_exists(root_path)?;
+    utils::check_ref_name(tag_name)?;
+
+    let tag_ref = format!("refs/tags/{}", tag_name);
+    if !utils::ref_exists(root_path, &tag_ref) {
+        bail!("Tag '{}' not found.", tag_name);
+    }
+
+    let old_hash = utils::read_ref(root_path, &tag_ref)?.unwrap_or_default();
+    utils::delete_ref(root_path, &tag_ref, Some(&old_hash))?;
+
+    Ok(old_hash)
+}
@@ -1,6 +1,6 @@
 //! Tree object construction from index entries.
 
-use crate::utils::IgnoreRule;
+use crate::utils::{IgnoreRule, IndexEntry};
 use crate::{commands, utils};
 use anyhow::Result;
 use std::{
@@ -32,7 +32,7 @@ fn format_tree(entries: &[TreeEntry]) -> Vec<u8> {
 
 fn write_tree_from_index(
     repo_root: &Path,
-    index_map: &HashMap<PathBuf, String>,
+    index_map: &HashMap<PathBuf, IndexEntry>,
     prefix: &Path,
 ) -> Result<String> {
     // The index stores flat file paths; this routine recursively groups entries
@@ -40,7 +40,7 @@ fn write_tree_from_index(
     let mut entries: Vec<TreeEntry> = Vec::new();
     let mut child_dirs: BTreeSet<String> = BTreeSet::new();
 
-    for (path, hash) in index_map {
+    for (path, index_entry) in index_map {
         let Ok(relative) = path.strip_prefix(prefix) else {
             continue;
         };
@@ -54,9 +54,9 @@ fn write_tree_from_index(
 
         if components.next().is_none() {
             entries.push(TreeEntry {
-                mode: "100644",
+                mode: index_entry.mode,
                 name: first_name,
-                hash: utils::hex_to_bytes(hash),
+                hash: utils::hex_to_bytes(&index_entry.hash),
             });
         } else {
             child_dirs.insert(first_name);
@@ -72,7 +72,7 @@ fn write_tree_from_index(
 
         let tree_hash = write_tree_from_index(repo_root, index_map, &child_prefix)?;
         entries.push(TreeEntry {
-            mode: "40000",
+            mode: utils::MODE_TREE,
             name: directory,
             hash: utils::hex_to_bytes(&tree_hash),
         });
@@ -92,6 +92,14 @@ pub fn write_tree(repo_root: &Path, path: &Path, ignore_rules: &Vec<IgnoreRule>)
     let _ = path;
     let _ = ignore_rules;
 
-    let index_map = utils::read_index_map(repo_root)?;
+    let index_map = utils::read_index_entries(repo_root)?;
     write_tree_from_index(repo_root, &index_map, Path::new(""))
 }
+
+/// Builds and writes the root tree object for an explicit `path -> entry` snapshot.
+pub fn write_tree_from_entries(
+    repo_root: &Path,
+    entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<String> {
+    write_tree_from_index(repo_root, entries, Path::new(""))
+}
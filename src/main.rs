@@ -1,17 +1,46 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use rustygit::utils::IgnoreRule;
+use clap::{Args, Parser, Subcommand};
+use rustygit::utils::{ConfigScope, IgnoreRule};
 use rustygit::{commands, utils};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "rustygit")]
 #[command(about = "A simple Git implementation in Rust", long_about = None)]
-struct CLI {
+struct Cli {
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Rename and copy detection flags shared by `diff` and `log`.
+#[derive(Args)]
+struct RenameArgs {
+    /// Detect renames of files at least PCT% similar (50 by default).
+    #[arg(short = 'M', long = "find-renames", value_name = "PCT", num_args = 0..=1, require_equals = true, default_missing_value = "50", value_parser = parse_percent)]
+    find_renames: Option<u8>,
+    /// Detect copies as well as renames, at least PCT% similar.
+    #[arg(short = 'C', long = "find-copies", value_name = "PCT", num_args = 0..=1, require_equals = true, default_missing_value = "50", value_parser = parse_percent)]
+    find_copies: Option<u8>,
+    /// Show renames as a deletion and an addition.
+    #[arg(long, conflicts_with_all = ["find_renames", "find_copies"])]
+    no_renames: bool,
+}
+
+impl RenameArgs {
+    fn options(&self) -> Option<utils::RenameOptions> {
+        if self.no_renames {
+            return None;
+        }
+        Some(utils::RenameOptions {
+            threshold: self
+                .find_copies
+                .or(self.find_renames)
+                .unwrap_or(utils::DEFAULT_RENAME_THRESHOLD),
+            copies: self.find_copies.is_some(),
+        })
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize a new Rusty Git repository
@@ -42,18 +71,37 @@ enum Commands {
         /// File to remove
         file: PathBuf,
     },
-    /// Restore a file from the index
+    /// Restore files in the working tree and/or index
+    ///
+    /// Without options, working files are restored from the index. Paths
+    /// may be files, directories or globs such as `src/*.rs`.
     Restore {
-        /// File to restore
-        file: PathBuf,
+        /// Files, directories or globs to restore
+        #[arg(required = true)]
+        pathspecs: Vec<String>,
+        /// Revision to restore from (default: the index, or HEAD with `--staged`)
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Restore the index
+        #[arg(short = 'S', long)]
+        staged: bool,
+        /// Restore the working tree (the default unless `--staged` is given)
+        #[arg(short = 'W', long)]
+        worktree: bool,
     },
     /// Reset HEAD to a commit, optionally preserving index
     Reset {
-        /// Target commit hash
+        /// Target revision (hash, abbreviated hash, branch, tag, `HEAD~2`, ...)
         target: String,
         /// Soft reset (move HEAD only)
-        #[arg(long)]
+        #[arg(long, conflicts_with_all = ["hard", "keep"])]
         soft: bool,
+        /// Hard reset (also rewrite tracked files, discarding local changes)
+        #[arg(long, conflicts_with = "keep")]
+        hard: bool,
+        /// Reset index and working tree, refusing if local changes would be lost
+        #[arg(long)]
+        keep: bool,
     },
     /// Write the current directory tree as a Git object
     ///
@@ -76,32 +124,613 @@ enum Commands {
     /// Logs the commit history
     ///
     /// This command displays the commit history of the repository.
-    Log,
+    /// `--name-status` lists the paths each commit changed, with renames
+    /// shown as `R<score>\t<old>\t<new>`.
+    Log {
+        /// List the paths each commit added (A), deleted (D), modified (M),
+        /// renamed (R) or copied (C).
+        #[arg(long)]
+        name_status: bool,
+        #[command(flatten)]
+        renames: RenameArgs,
+    },
     /// Checkout a specific commit or branch
     ///
     /// This command updates the working directory to match the specified commit or branch
     /// and moves the head to that commit.
     Checkout {
-        /// The target commit hash or branch name to checkout.
-        target: String,
+        /// The branch name, `-` for the previous branch, or any revision to check out detached.
+        /// With paths, the revision to restore them from.
+        #[arg(required_unless_present = "paths")]
+        target: Option<String>,
+        /// Files, directories or globs to restore instead of switching (after `--`)
+        #[arg(last = true)]
+        paths: Vec<String>,
     },
-    /// Create a new branch
+    /// List, create, delete, rename or move branches
     ///
-    /// This command creates a new branch pointing to the current commit.
+    /// Without arguments this lists branches. With a name it creates a branch
+    /// at the current commit (or at the given start point).
     Branch {
-        /// The name of the branch to create.
+        /// The name of the branch to create, delete or move (the new name with `-m`).
         branch_name: Option<String>,
+
+        /// Revision to start the branch at, or the new name for `-m old new`.
+        start_point: Option<String>,
+
+        /// Delete a branch that is fully merged into HEAD.
+        #[arg(short, long, requires = "branch_name", conflicts_with_all = ["force_delete", "rename", "force"])]
+        delete: bool,
+
+        /// Delete a branch even if it is not merged.
+        #[arg(short = 'D', requires = "branch_name", conflicts_with_all = ["rename", "force"])]
+        force_delete: bool,
+
+        /// Rename a branch (`-m new` renames the current branch).
+        #[arg(
+            short = 'm',
+            long = "move",
+            requires = "branch_name",
+            conflicts_with = "force"
+        )]
+        rename: bool,
+
+        /// Move an existing branch to the start point.
+        #[arg(short, long, requires = "branch_name")]
+        force: bool,
+    },
+    /// Create, list or delete tags
+    ///
+    /// Without a name (or with `--list`) this lists tags. `-a`/`-m` write an
+    /// annotated tag object; otherwise the tag is a lightweight ref.
+    Tag {
+        /// Tag name to create or delete, or a glob pattern when listing.
+        name: Option<String>,
+
+        /// Revision to tag (defaults to HEAD).
+        target: Option<String>,
+
+        /// Create an annotated tag object.
+        #[arg(short, requires = "message")]
+        annotate: bool,
+
+        /// Message for an annotated tag (implies `-a`).
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Delete the named tag.
+        #[arg(short, long, requires = "name", conflicts_with_all = ["target", "annotate", "message", "list"])]
+        delete: bool,
+
+        /// List tags, optionally matching the pattern given as the name.
+        #[arg(short, long, conflicts_with_all = ["target", "annotate", "message"])]
+        list: bool,
     },
     /// Show the status of the working directory
     ///
     /// This command displays the status of files in the working directory,
     /// indicating which files are staged, modified, or untracked.
     Status,
-    Diff,
+    /// Show changes between the working tree, the index and commits
+    ///
+    /// Changes are printed as a unified diff that tools such as `patch` accept.
+    /// Without revisions this compares the working tree with the index, so
+    /// it shows what is not yet staged; `--cached` compares the index with
+    /// HEAD (or a given commit). One revision compares the working tree with
+    /// that commit, and two revisions (or `<rev1>..<rev2>`) compare the two
+    /// commits. Paths after `--` limit the output. Moved files are shown as
+    /// renames when at least half of their content is unchanged.
+    Diff {
+        /// Compare the index with HEAD (or the given revision).
+        #[arg(long, visible_alias = "staged")]
+        cached: bool,
+        /// Lines of context around each change.
+        #[arg(short = 'U', long = "unified", value_name = "N", default_value_t = commands::DEFAULT_CONTEXT)]
+        context: usize,
+        #[command(flatten)]
+        renames: RenameArgs,
+        /// `<rev>`, `<rev1> <rev2>` or `<rev1>..<rev2>`.
+        revisions: Vec<String>,
+        /// Limit the diff to these paths.
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    /// Apply a patch to the working tree or the index
+    ///
+    /// This command reads a unified diff, as printed by `diff` or `diff -u`,
+    /// including new, deleted and renamed files and mode changes. Hunks must
+    /// match exactly; nothing is changed unless the whole patch applies.
+    Apply {
+        /// The patch file, or `-` for standard input.
+        patch: PathBuf,
+        /// Apply the patch to the index instead of the working tree.
+        #[arg(long)]
+        cached: bool,
+        /// Only check that the patch applies.
+        #[arg(long)]
+        check: bool,
+        /// Apply the patch in reverse.
+        #[arg(short = 'R', long)]
+        reverse: bool,
+    },
+    /// Write commits as mailbox patch files
+    ///
+    /// Each commit in the range becomes an mbox-formatted `NNNN-<subject>.patch`
+    /// file holding its author, date, message and diff, ready for `am`.
+    FormatPatch {
+        /// `<since>` (the commits HEAD has since then) or `<since>..<until>`.
+        range: String,
+        /// Directory to write the patch files to.
+        #[arg(
+            short,
+            long = "output-directory",
+            value_name = "DIR",
+            default_value = "."
+        )]
+        output_directory: PathBuf,
+    },
+    /// Commit patches from mailbox files
+    ///
+    /// Each patch written by `format-patch` is applied to the index and the
+    /// working tree and committed with its original author and date.
+    Am {
+        /// The mbox files to apply, in order.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Compress legacy loose objects
+    ///
+    /// This command rewrites objects stored by older versions without
+    /// compression into Git's zlib-deflated loose object format.
+    MigrateObjects,
+    /// Pack reachable objects and prune unreachable ones
+    ///
+    /// This command writes every object reachable from HEAD, refs and the
+    /// index into a single delta-compressed pack, deletes redundant loose
+    /// objects, and prunes unreachable objects older than the grace period.
+    Gc {
+        /// Grace period in days for unreachable objects, or `now`.
+        #[arg(long, default_value = "14", value_parser = parse_prune_days)]
+        prune: u64,
+    },
+    /// Pack reachable objects without pruning anything
+    Repack,
+    /// Merge a branch or commit into the current branch
+    ///
+    /// This command finds the merge base of HEAD and the target, fast-forwards
+    /// when possible, and otherwise records a merge commit with two parents.
+    Merge {
+        /// The branch name or revision to merge.
+        #[arg(required_unless_present = "abort")]
+        target: Option<String>,
+
+        /// Message for the merge commit.
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Abandon a conflicted merge and restore HEAD.
+        #[arg(long, conflicts_with_all = ["target", "message"])]
+        abort: bool,
+    },
+    /// Apply the changes introduced by existing commits
+    ///
+    /// Each commit is applied to HEAD with a three-way merge and committed
+    /// with its original message and a "(cherry picked from ...)" line.
+    CherryPick {
+        /// The commits to pick, in order.
+        #[arg(required_unless_present_any = ["continue_", "abort"])]
+        revisions: Vec<String>,
+
+        /// Commit the resolved conflicts and pick the remaining commits.
+        #[arg(long = "continue", conflicts_with_all = ["revisions", "abort"])]
+        continue_: bool,
+
+        /// Abandon the cherry-pick and restore HEAD.
+        #[arg(long, conflicts_with = "revisions")]
+        abort: bool,
+    },
+    /// Revert the changes introduced by existing commits
+    ///
+    /// Each commit's change is undone on HEAD with a three-way merge and
+    /// recorded as a new "Revert" commit.
+    Revert {
+        /// The commits to revert, in order.
+        #[arg(required_unless_present_any = ["continue_", "abort"])]
+        revisions: Vec<String>,
+
+        /// Commit the resolved conflicts and revert the remaining commits.
+        #[arg(long = "continue", conflicts_with_all = ["revisions", "abort"])]
+        continue_: bool,
+
+        /// Abandon the revert and restore HEAD.
+        #[arg(long, conflicts_with = "revisions")]
+        abort: bool,
+    },
+    /// Replay the current branch's commits on top of another commit
+    ///
+    /// Commits not contained in the upstream are applied one by one onto it
+    /// and the branch is moved to the result. With `-i`, the list of commits
+    /// is opened in an editor first to reorder, reword, edit, squash, fixup
+    /// or drop them.
+    Rebase {
+        /// The commit to replay onto.
+        #[arg(required_unless_present_any = ["continue_", "skip", "abort"])]
+        upstream: Option<String>,
+
+        /// Edit the todo list before starting.
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Commit the resolved conflicts (or amended changes) and go on.
+        #[arg(long = "continue", conflicts_with_all = ["upstream", "interactive", "skip", "abort"])]
+        continue_: bool,
+
+        /// Drop the commit the rebase stopped at and go on.
+        #[arg(long, conflicts_with_all = ["upstream", "interactive", "abort"])]
+        skip: bool,
+
+        /// Abandon the rebase and restore the original branch.
+        #[arg(long, conflicts_with_all = ["upstream", "interactive"])]
+        abort: bool,
+    },
+    /// Find the commit that introduced a bug by binary search
+    ///
+    /// After `bisect start`, mark a bad commit and a good one; each step
+    /// then checks out a commit halfway between them to be marked in turn,
+    /// until the first bad commit is found.
+    Bisect {
+        #[command(subcommand)]
+        action: BisectAction,
+    },
+    /// Show the reflog of HEAD or a branch
+    ///
+    /// Each line shows where the ref pointed after an update and why, newest
+    /// first; `<ref>@{N}` can be passed to other commands to recover it.
+    Reflog {
+        /// Branch name or `HEAD` (the default).
+        ref_name: Option<String>,
+    },
+    /// Resolve revision expressions to full object hashes
+    ///
+    /// Accepts `HEAD`, `@`, branch and tag names, abbreviated hashes of at
+    /// least four characters, `@{-N}`, and `~N` / `^N` ancestry suffixes.
+    RevParse {
+        /// Revisions to resolve.
+        #[arg(required = true)]
+        revisions: Vec<String>,
+    },
+    /// Set local changes aside and re-apply them later
+    ///
+    /// Without a subcommand this saves the index and working tree as a new
+    /// stash entry (like `stash push`) and resets them to HEAD.
+    #[command(args_conflicts_with_subcommands = true)]
+    Stash {
+        /// Description to use instead of `WIP on <branch>: ...`.
+        #[arg(short, long)]
+        message: Option<String>,
+        /// Also stash (and remove) untracked files.
+        #[arg(short = 'u', long)]
+        include_untracked: bool,
+
+        #[command(subcommand)]
+        action: Option<StashAction>,
+    },
+    /// Get, set, unset or list configuration values
+    ///
+    /// Values are read from `.rustygit/config` first and then from the
+    /// global `~/.rustygitconfig`. Keys look like `user.name`.
+    Config {
+        /// List every configured value.
+        #[arg(short, long)]
+        list: bool,
+
+        /// Write to the global `~/.rustygitconfig` instead of the repository config.
+        #[arg(long, global = true)]
+        global: bool,
+
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the value of a key
+    Get {
+        /// Key such as `user.name`.
+        key: String,
+    },
+    /// Set a key to a value
+    Set {
+        /// Key such as `user.name`.
+        key: String,
+        /// Value to store.
+        value: String,
+    },
+    /// Remove a key
+    Unset {
+        /// Key such as `user.name`.
+        key: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum StashAction {
+    /// Save local changes as a new stash entry
+    Push {
+        /// Description to use instead of `WIP on <branch>: ...`.
+        #[arg(short, long)]
+        message: Option<String>,
+        /// Also stash (and remove) untracked files.
+        #[arg(short = 'u', long)]
+        include_untracked: bool,
+    },
+    /// List stash entries, newest first
+    List,
+    /// Show the files a stash entry changes
+    Show {
+        /// `stash@{N}` or `N` (default: the newest entry).
+        stash: Option<String>,
+    },
+    /// Apply a stash entry, keeping it on the stack
+    Apply {
+        /// `stash@{N}` or `N` (default: the newest entry).
+        stash: Option<String>,
+    },
+    /// Apply a stash entry and drop it if it applied cleanly
+    Pop {
+        /// `stash@{N}` or `N` (default: the newest entry).
+        stash: Option<String>,
+    },
+    /// Remove a stash entry
+    Drop {
+        /// `stash@{N}` or `N` (default: the newest entry).
+        stash: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BisectAction {
+    /// Start a bisect session, optionally marking a bad and good commits
+    Start {
+        /// A commit that has the bug.
+        bad: Option<String>,
+        /// Commits that do not have the bug.
+        good: Vec<String>,
+    },
+    /// Mark commits (default: HEAD) as not having the bug
+    Good {
+        /// Commits to mark.
+        revisions: Vec<String>,
+    },
+    /// Mark a commit (default: HEAD) as having the bug
+    Bad {
+        /// Commit to mark.
+        revision: Option<String>,
+    },
+    /// Mark commits (default: HEAD) as untestable
+    Skip {
+        /// Commits to mark.
+        revisions: Vec<String>,
+    },
+    /// End the session and return to the original branch
+    Reset {
+        /// Commit or branch to check out instead.
+        commit: Option<String>,
+    },
+    /// Show the commands run so far
+    Log,
+    /// Re-run the commands of a saved bisect log
+    Replay {
+        /// File written from `bisect log`.
+        file: PathBuf,
+    },
+    /// Mark each commit automatically by a command's exit status
+    ///
+    /// Status 0 means good, 125 skip, and any other status below 128 bad.
+    Run {
+        /// Command and arguments, run through `sh`.
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+}
+
+fn print_bisect_outcome(root_path: &Path, outcome: &commands::BisectOutcome) -> Result<()> {
+    match outcome {
+        commands::BisectOutcome::Waiting {
+            bad: false,
+            good: 0,
+        } => {
+            println!("status: waiting for both good and bad commits");
+        }
+        commands::BisectOutcome::Waiting { bad: true, .. } => {
+            println!("status: waiting for good commit(s), bad commit known");
+        }
+        commands::BisectOutcome::Waiting { good, .. } => {
+            println!(
+                "status: waiting for bad commit, {} good commit(s) known",
+                good
+            );
+        }
+        commands::BisectOutcome::Testing {
+            commit,
+            remaining,
+            steps,
+        } => {
+            let message = utils::get_commit_message(root_path, commit)?;
+            println!(
+                "Bisecting: {} revision(s) left to test after this (roughly {} step(s))",
+                remaining, steps
+            );
+            println!(
+                "[{}] {}",
+                commit,
+                message.lines().next().unwrap_or_default()
+            );
+        }
+        commands::BisectOutcome::FirstBad(commit) => {
+            println!("{} is the first bad commit", commit);
+            for line in utils::get_commit_message(root_path, commit)?.lines() {
+                println!("    {}", line);
+            }
+        }
+        commands::BisectOutcome::OnlySkipped(commits) => {
+            println!("There are only 'skip'ped commits left to test.");
+            println!("The first bad commit could be any of:");
+            for commit in commits {
+                println!("{}", commit);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_stash_apply(outcome: commands::StashApplyOutcome) -> Result<()> {
+    if let commands::StashApplyOutcome::Conflicted(paths) = outcome {
+        for path in paths {
+            println!("CONFLICT: Merge conflict in {}", path.display());
+        }
+        anyhow::bail!("The stash entry is kept in case you need it again.");
+    }
+    Ok(())
+}
+
+fn stash_push(root_path: &Path, message: Option<&str>, include_untracked: bool) -> Result<()> {
+    match commands::stash_push(root_path, message, include_untracked)? {
+        Some(_) => {
+            let stash = commands::stash_list(root_path)?.remove(0);
+            let description = stash.split_once(": ").map_or("", |(_, rest)| rest);
+            println!("Saved working directory and index state {}", description);
+        }
+        None => println!("No local changes to save"),
+    }
+    Ok(())
+}
+
+fn print_pick_outcomes(
+    root_path: &Path,
+    command: &str,
+    outcomes: Vec<commands::PickOutcome>,
+) -> Result<()> {
+    for outcome in outcomes {
+        match outcome {
+            commands::PickOutcome::Committed { commit, .. } => {
+                let message = utils::get_commit_message(root_path, &commit)?;
+                println!(
+                    "[{}] {}",
+                    &commit[..7],
+                    message.lines().next().unwrap_or_default()
+                );
+            }
+            commands::PickOutcome::Empty(original) => {
+                println!("Skipped {}: no changes to apply", &original[..7]);
+            }
+            commands::PickOutcome::Conflicted { original, paths } => {
+                for path in paths {
+                    println!("CONFLICT: Merge conflict in {}", path.display());
+                }
+                anyhow::bail!(
+                    "Could not apply {}; fix conflicts, 'add' the files and run '{} --continue' (or '{} --abort').",
+                    &original[..7],
+                    command,
+                    command
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_rebase_outcome(root_path: &Path, outcome: commands::RebaseOutcome) -> Result<()> {
+    let describe = |commit: &str| -> Result<String> {
+        let message = utils::get_commit_message(root_path, commit)?;
+        Ok(format!(
+            "{}... {}",
+            &commit[..7],
+            message.lines().next().unwrap_or_default()
+        ))
+    };
+
+    match outcome {
+        commands::RebaseOutcome::UpToDate => println!("Current branch is up to date."),
+        commands::RebaseOutcome::Finished(_) => {
+            let head_name = utils::read_symbolic_head(root_path)?;
+            println!(
+                "Successfully rebased and updated {}.",
+                head_name.as_deref().unwrap_or("detached HEAD")
+            );
+        }
+        commands::RebaseOutcome::Edit(commit) => {
+            println!(
+                "Stopped at {}\nYou can amend the commit now: 'add' your changes and run 'rebase --continue'.",
+                describe(&commit)?
+            );
+        }
+        commands::RebaseOutcome::Conflicted { commit, paths } => {
+            for path in paths {
+                println!("CONFLICT: Merge conflict in {}", path.display());
+            }
+            anyhow::bail!(
+                "Could not apply {}\nFix conflicts, 'add' the files and run 'rebase --continue' (or 'rebase --skip', 'rebase --abort').",
+                describe(&commit)?
+            );
+        }
+    }
+    Ok(())
+}
+
+fn parse_prune_days(value: &str) -> Result<u64, String> {
+    if value == "now" {
+        return Ok(0);
+    }
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("expected a number of days or 'now', got '{}'", value))
+}
+
+/// Rewrites `-M<pct>` and `-C<pct>` as `-M=<pct>` and `-C=<pct>`.
+///
+/// Their values are optional, so they must be attached with `=` for clap
+/// not to take the next argument (such as a revision) as the value.
+fn attach_percent_values(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut after_separator = false;
+
+    args.map(|arg| {
+        if arg == "--" {
+            after_separator = true;
+        }
+        match arg.get(..2) {
+            Some("-M" | "-C")
+                if !after_separator && arg[2..].starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                format!("{}={}", &arg[..2], &arg[2..])
+            }
+            _ => arg,
+        }
+    })
+    .collect()
+}
+
+/// Parses a similarity threshold such as `80` or `80%`.
+fn parse_percent(value: &str) -> Result<u8, String> {
+    value
+        .strip_suffix('%')
+        .unwrap_or(value)
+        .parse::<u8>()
+        .ok()
+        .filter(|percent| *percent <= 100)
+        .ok_or_else(|| format!("expected a percentage from 0 to 100, got '{}'", value))
+}
+
+fn print_gc_summary(summary: &commands::GcSummary) {
+    println!(
+        "Packed {} objects ({} deltas)\nRemoved {} redundant loose objects\nPruned {} unreachable objects",
+        summary.packed, summary.deltas, summary.removed_loose, summary.pruned
+    );
 }
 
 fn main() -> Result<()> {
-    let cli = CLI::parse();
+    let cli = Cli::parse_from(attach_percent_values(std::env::args()));
     let root_path = std::env::current_dir()?;
 
     match cli.command {
@@ -121,17 +750,40 @@ fn main() -> Result<()> {
             commands::rm(&root_path, &file)?;
             println!("Removed {}", file.display());
         }
-        Commands::Restore { file } => {
-            commands::restore(&root_path, &file)?;
-            println!("Restored {}", file.display());
+        Commands::Restore {
+            pathspecs,
+            source,
+            staged,
+            worktree,
+        } => {
+            let paths = commands::restore_paths(
+                &root_path,
+                &pathspecs,
+                source.as_deref(),
+                staged,
+                worktree,
+            )?;
+            for path in paths {
+                println!("Restored {}", path.display());
+            }
         }
-        Commands::Reset { target, soft } => {
-            commands::reset(&root_path, &target, soft)?;
-            if soft {
-                println!("Soft reset to {}", target);
+        Commands::Reset {
+            target,
+            soft,
+            hard,
+            keep,
+        } => {
+            let (mode, label) = if soft {
+                (commands::ResetMode::Soft, "Soft")
+            } else if hard {
+                (commands::ResetMode::Hard, "Hard")
+            } else if keep {
+                (commands::ResetMode::Keep, "Keep")
             } else {
-                println!("Mixed reset to {}", target);
-            }
+                (commands::ResetMode::Mixed, "Mixed")
+            };
+            commands::reset(&root_path, &target, mode)?;
+            println!("{} reset to {}", label, target);
         }
         Commands::WriteTree => {
             let ignore_rules: Vec<IgnoreRule> = utils::parse_ignore_file(&root_path)?;
@@ -145,29 +797,356 @@ fn main() -> Result<()> {
 
             println!("Committed successfully!\nHash: {}", commit_hash?);
         }
-        Commands::Log => {
-            commands::log(&root_path)?;
+        Commands::Log {
+            name_status,
+            renames,
+        } => {
+            if name_status {
+                commands::log_name_status(&root_path, renames.options())?;
+            } else {
+                commands::log(&root_path)?;
+            }
         }
-        Commands::Checkout { target } => {
-            commands::checkout(&root_path, &target)?;
+        Commands::Checkout { target, paths } => {
+            if paths.is_empty() {
+                commands::checkout(&root_path, target.as_deref().unwrap_or_default())?;
+            } else {
+                let paths = commands::checkout_paths(&root_path, target.as_deref(), &paths)?;
+                println!("Updated {} path(s)", paths.len());
+            }
         }
-        Commands::Branch { branch_name } => match branch_name {
+        Commands::Branch {
+            branch_name,
+            start_point,
+            delete,
+            force_delete,
+            rename,
+            force,
+        } => match branch_name {
+            Some(branch_name) if delete || force_delete => {
+                let old_hash = commands::delete_branch(&root_path, &branch_name, force_delete)?;
+                println!(
+                    "Deleted branch {} (was {}).",
+                    branch_name,
+                    &old_hash[..old_hash.len().min(7)]
+                );
+            }
+            Some(branch_name) if rename => match start_point {
+                Some(new_name) => {
+                    commands::rename_branch(&root_path, &branch_name, &new_name)?;
+                    println!("Renamed branch '{}' to '{}'", branch_name, new_name);
+                }
+                None => {
+                    commands::rename_current_branch(&root_path, &branch_name)?;
+                    println!("Renamed current branch to '{}'", branch_name);
+                }
+            },
             Some(branch_name) => {
-                commands::create_branch(&root_path, &branch_name)?;
-                println!("Branch '{}' created successfully", branch_name);
+                let commit_hash = commands::create_branch_at(
+                    &root_path,
+                    &branch_name,
+                    start_point.as_deref(),
+                    force,
+                )?;
+                if force {
+                    println!("Branch '{}' set to {}", branch_name, commit_hash);
+                } else {
+                    println!("Branch '{}' created successfully", branch_name);
+                }
             }
             None => {
                 commands::branch(&root_path)?;
             }
         },
+        Commands::Tag {
+            name,
+            target,
+            annotate: _,
+            message,
+            delete,
+            list,
+        } => match name {
+            Some(name) if delete => {
+                let old_hash = commands::delete_tag(&root_path, &name)?;
+                println!("Deleted tag '{}' (was {})", name, &old_hash[..7]);
+            }
+            Some(name) if !list => {
+                commands::create_tag(&root_path, &name, target.as_deref(), message.as_deref())?;
+            }
+            pattern => {
+                for tag_name in commands::list_tags(&root_path, pattern.as_deref())? {
+                    println!("{}", tag_name);
+                }
+            }
+        },
         Commands::Status => {
             let ignore_rules: Vec<IgnoreRule> = utils::parse_ignore_file(&root_path)?;
             let status = commands::status(&root_path, &ignore_rules)?;
             println!("\n{}", status);
         }
-        Commands::Diff => {
-            let ignore_rules: Vec<IgnoreRule> = utils::parse_ignore_file(&root_path)?;
-            commands::diff(&root_path, &ignore_rules)?;
+        Commands::Diff {
+            cached,
+            context,
+            renames,
+            revisions,
+            paths,
+        } => {
+            let mode = commands::DiffMode::from_args(cached, &revisions)?;
+            print!(
+                "{}",
+                commands::diff_with_renames(&root_path, &mode, &paths, context, renames.options())?
+            );
+        }
+        Commands::Apply {
+            patch,
+            cached,
+            check,
+            reverse,
+        } => {
+            let options = commands::ApplyOptions {
+                cached,
+                check,
+                reverse,
+            };
+            commands::apply(&root_path, &patch, options)?;
+        }
+        Commands::FormatPatch {
+            range,
+            output_directory,
+        } => {
+            let output_directory = root_path.join(output_directory);
+            for file in commands::format_patch(&root_path, &range, &output_directory)? {
+                let file = file.strip_prefix(&root_path).unwrap_or(&file);
+                println!("{}", file.display());
+            }
+        }
+        Commands::Am { files } => {
+            for commit in commands::am(&root_path, &files)? {
+                let message = utils::get_commit_message(&root_path, &commit)?;
+                println!("Applying: {}", message.lines().next().unwrap_or_default());
+            }
+        }
+        Commands::MigrateObjects => {
+            let migrated = commands::migrate_objects(&root_path)?;
+            println!("Compressed {} loose objects", migrated);
+        }
+        Commands::Gc { prune } => {
+            let summary = commands::gc(&root_path, prune)?;
+            print_gc_summary(&summary);
+        }
+        Commands::Repack => {
+            let summary = commands::repack(&root_path)?;
+            print_gc_summary(&summary);
+        }
+        Commands::Merge {
+            target,
+            message,
+            abort,
+        } => {
+            if abort {
+                commands::merge_abort(&root_path)?;
+                println!("Merge aborted");
+                return Ok(());
+            }
+
+            let target = target.unwrap_or_default();
+            match commands::merge(&root_path, &target, message)? {
+                commands::MergeOutcome::UpToDate => println!("Already up to date."),
+                commands::MergeOutcome::FastForward(hash) => {
+                    println!("Fast-forward\nHash: {}", hash)
+                }
+                commands::MergeOutcome::Merged(hash) => {
+                    println!("Merge made successfully!\nHash: {}", hash)
+                }
+                commands::MergeOutcome::Conflicted(paths) => {
+                    for path in paths {
+                        println!("CONFLICT: Merge conflict in {}", path.display());
+                    }
+                    anyhow::bail!(
+                        "Automatic merge failed; fix conflicts, 'add' the files and then commit the result."
+                    );
+                }
+            }
+        }
+        Commands::CherryPick {
+            revisions,
+            continue_,
+            abort,
+        } => {
+            if abort {
+                commands::cherry_pick_abort(&root_path)?;
+                println!("Cherry-pick aborted");
+                return Ok(());
+            }
+
+            let outcomes = if continue_ {
+                commands::cherry_pick_continue(&root_path)?
+            } else {
+                commands::cherry_pick(&root_path, &revisions)?
+            };
+            print_pick_outcomes(&root_path, "cherry-pick", outcomes)?;
+        }
+        Commands::Revert {
+            revisions,
+            continue_,
+            abort,
+        } => {
+            if abort {
+                commands::revert_abort(&root_path)?;
+                println!("Revert aborted");
+                return Ok(());
+            }
+
+            let outcomes = if continue_ {
+                commands::revert_continue(&root_path)?
+            } else {
+                commands::revert(&root_path, &revisions)?
+            };
+            print_pick_outcomes(&root_path, "revert", outcomes)?;
+        }
+        Commands::Rebase {
+            upstream,
+            interactive,
+            continue_,
+            skip,
+            abort,
+        } => {
+            if abort {
+                commands::rebase_abort(&root_path)?;
+                println!("Rebase aborted");
+                return Ok(());
+            }
+
+            let outcome = if continue_ {
+                commands::rebase_continue(&root_path)?
+            } else if skip {
+                commands::rebase_skip(&root_path)?
+            } else {
+                commands::rebase(
+                    &root_path,
+                    upstream.as_deref().unwrap_or_default(),
+                    interactive,
+                )?
+            };
+            print_rebase_outcome(&root_path, outcome)?;
+        }
+        Commands::Bisect { action } => match action {
+            BisectAction::Start { bad, good } => {
+                let outcome = commands::bisect_start(&root_path, bad.as_deref(), &good)?;
+                print_bisect_outcome(&root_path, &outcome)?;
+            }
+            BisectAction::Good { revisions } => {
+                let outcome = commands::bisect_good(&root_path, &revisions)?;
+                print_bisect_outcome(&root_path, &outcome)?;
+            }
+            BisectAction::Bad { revision } => {
+                let outcome = commands::bisect_bad(&root_path, revision.as_deref())?;
+                print_bisect_outcome(&root_path, &outcome)?;
+            }
+            BisectAction::Skip { revisions } => {
+                let outcome = commands::bisect_skip(&root_path, &revisions)?;
+                print_bisect_outcome(&root_path, &outcome)?;
+            }
+            BisectAction::Reset { commit } => {
+                commands::bisect_reset(&root_path, commit.as_deref())?;
+                match utils::current_branch(&root_path)? {
+                    Some(branch_name) => println!("Switched to branch '{}'", branch_name),
+                    None => println!(
+                        "HEAD is now at {}",
+                        utils::get_current_commit_hash(&root_path)?.unwrap_or_default()
+                    ),
+                }
+            }
+            BisectAction::Log => print!("{}", commands::bisect_log(&root_path)?),
+            BisectAction::Replay { file } => {
+                let outcome = commands::bisect_replay(&root_path, &file)?;
+                print_bisect_outcome(&root_path, &outcome)?;
+            }
+            BisectAction::Run { command } => {
+                for outcome in commands::bisect_run(&root_path, &command)? {
+                    print_bisect_outcome(&root_path, &outcome)?;
+                }
+            }
+        },
+        Commands::Reflog { ref_name } => {
+            for line in commands::reflog(&root_path, ref_name.as_deref())? {
+                println!("{}", line);
+            }
+        }
+        Commands::RevParse { revisions } => {
+            for hash in commands::rev_parse(&root_path, &revisions)? {
+                println!("{}", hash);
+            }
+        }
+        Commands::Stash {
+            message,
+            include_untracked,
+            action,
+        } => match action {
+            None => stash_push(&root_path, message.as_deref(), include_untracked)?,
+            Some(StashAction::Push {
+                message,
+                include_untracked,
+            }) => stash_push(&root_path, message.as_deref(), include_untracked)?,
+            Some(StashAction::List) => {
+                for line in commands::stash_list(&root_path)? {
+                    println!("{}", line);
+                }
+            }
+            Some(StashAction::Show { stash }) => {
+                for line in commands::stash_show(&root_path, stash.as_deref())? {
+                    println!("{}", line);
+                }
+            }
+            Some(StashAction::Apply { stash }) => {
+                print_stash_apply(commands::stash_apply(&root_path, stash.as_deref())?)?;
+            }
+            Some(StashAction::Pop { stash }) => {
+                let outcome = commands::stash_pop(&root_path, stash.as_deref())?;
+                if outcome == commands::StashApplyOutcome::Applied {
+                    println!("Dropped {}", stash.as_deref().unwrap_or("stash@{0}"));
+                }
+                print_stash_apply(outcome)?;
+            }
+            Some(StashAction::Drop { stash }) => {
+                let hash = commands::stash_drop(&root_path, stash.as_deref())?;
+                println!(
+                    "Dropped {} ({})",
+                    stash.as_deref().unwrap_or("stash@{0}"),
+                    hash
+                );
+            }
+        },
+        Commands::Config {
+            list,
+            global,
+            action,
+        } => {
+            let scope = if global {
+                ConfigScope::Global
+            } else {
+                ConfigScope::Local
+            };
+
+            match action {
+                Some(ConfigAction::Get { key }) => {
+                    println!("{}", commands::config_get(&root_path, &key)?);
+                }
+                Some(ConfigAction::Set { key, value }) => {
+                    commands::config_set(&root_path, scope, &key, &value)?;
+                }
+                Some(ConfigAction::Unset { key }) => {
+                    commands::config_unset(&root_path, scope, &key)?;
+                }
+                None if list => {
+                    for line in commands::config_list(&root_path)? {
+                        println!("{}", line);
+                    }
+                }
+                None => {
+                    anyhow::bail!("Specify one of get, set, unset or --list.");
+                }
+            }
         }
     }
 
@@ -0,0 +1,122 @@
+//! State of a `bisect` session, kept in `.rustygit/BISECT_*` files.
+//!
+//! - `BISECT_START`: the branch (or detached commit) checked out when the
+//!   session started, restored by `bisect reset`.
+//! - `BISECT_BAD`: the commit marked bad, if any.
+//! - `BISECT_GOOD` / `BISECT_SKIP`: commits marked good or skipped, one per line.
+//! - `BISECT_LOG`: the commands run so far, replayable with `bisect replay`.
+
+use anyhow::Result;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const BISECT_FILES: [&str; 5] = [
+    "BISECT_START",
+    "BISECT_BAD",
+    "BISECT_GOOD",
+    "BISECT_SKIP",
+    "BISECT_LOG",
+];
+
+/// A bisect session in progress.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BisectState {
+    /// The branch name, or commit hash when detached, to return to on reset.
+    pub start: String,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skip: Vec<String>,
+}
+
+fn bisect_file(root_path: &Path, name: &str) -> PathBuf {
+    root_path.join(".rustygit").join(name)
+}
+
+fn read_hashes(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn write_hashes(path: &Path, hashes: &[String]) -> Result<()> {
+    if hashes.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    let content: String = hashes.iter().map(|hash| format!("{}\n", hash)).collect();
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Returns the bisect session in progress, if any.
+pub fn read_bisect_state(root_path: &Path) -> Result<Option<BisectState>> {
+    let start_path = bisect_file(root_path, "BISECT_START");
+
+    if !start_path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(BisectState {
+        start: fs::read_to_string(start_path)?.trim().to_string(),
+        bad: read_hashes(&bisect_file(root_path, "BISECT_BAD"))?
+            .into_iter()
+            .next(),
+        good: read_hashes(&bisect_file(root_path, "BISECT_GOOD"))?,
+        skip: read_hashes(&bisect_file(root_path, "BISECT_SKIP"))?,
+    }))
+}
+
+/// Records `state` as the bisect session in progress.
+pub fn write_bisect_state(root_path: &Path, state: &BisectState) -> Result<()> {
+    fs::write(
+        bisect_file(root_path, "BISECT_START"),
+        format!("{}\n", state.start),
+    )?;
+    write_hashes(&bisect_file(root_path, "BISECT_BAD"), state.bad.as_slice())?;
+    write_hashes(&bisect_file(root_path, "BISECT_GOOD"), &state.good)?;
+    write_hashes(&bisect_file(root_path, "BISECT_SKIP"), &state.skip)
+}
+
+/// Appends `line` to `BISECT_LOG`.
+pub fn append_bisect_log(root_path: &Path, line: &str) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(bisect_file(root_path, "BISECT_LOG"))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Returns the contents of `BISECT_LOG`, empty when there is none.
+pub fn read_bisect_log(root_path: &Path) -> Result<String> {
+    let path = bisect_file(root_path, "BISECT_LOG");
+
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Removes every bisect state file.
+pub fn clear_bisect_state(root_path: &Path) -> Result<()> {
+    for name in BISECT_FILES {
+        let path = bisect_file(root_path, name);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Commit graph traversal: parents, ancestry, and merge-base computation.
+
+use crate::utils::{self, Signature};
+use anyhow::{Result, anyhow};
+use std::{
+    collections::{BinaryHeap, HashSet},
+    path::Path,
+};
+
+/// Returns the tree hash a commit points to.
+pub fn get_commit_tree_hash(root_path: &Path, commit_hash: &str) -> Result<String> {
+    let commit_content = utils::parse_commit(root_path, commit_hash)?;
+
+    commit_content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("tree "))
+        .map(|tree_hash| tree_hash.trim().to_string())
+        .ok_or_else(|| {
+            anyhow!(
+                "Malformed commit object: missing tree.\nHash: {}",
+                commit_hash
+            )
+        })
+}
+
+/// Returns every `parent` hash of a commit payload, in order.
+pub fn get_parent_hashes(commit_content: &str) -> Vec<String> {
+    commit_content
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.strip_prefix("parent "))
+        .map(|parent_hash| parent_hash.trim().to_string())
+        .collect()
+}
+
+/// Returns the committer timestamp of a commit payload, or 0 if it has none.
+pub fn get_commit_timestamp(commit_content: &str) -> i64 {
+    commit_content
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix("committer "))
+        .and_then(|committer| committer.split_whitespace().rev().nth(1))
+        .and_then(|timestamp| timestamp.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Returns the message of a commit: everything after the header.
+pub fn get_commit_message(root_path: &Path, commit_hash: &str) -> Result<String> {
+    let commit_content = utils::parse_commit(root_path, commit_hash)?;
+
+    Ok(commit_content
+        .split_once("\n\n")
+        .map(|(_, message)| message.to_string())
+        .unwrap_or_default())
+}
+
+/// Returns the author of a commit.
+pub fn get_commit_author(root_path: &Path, commit_hash: &str) -> Result<Signature> {
+    utils::parse_commit(root_path, commit_hash)?
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix("author "))
+        .and_then(Signature::parse)
+        .ok_or_else(|| {
+            anyhow!(
+                "Malformed commit object: missing author.\nHash: {}",
+                commit_hash
+            )
+        })
+}
+
+/// Returns the parents of a commit.
+pub fn get_commit_parents(root_path: &Path, commit_hash: &str) -> Result<Vec<String>> {
+    Ok(get_parent_hashes(&utils::parse_commit(
+        root_path,
+        commit_hash,
+    )?))
+}
+
+/// Returns every commit reachable from `start`, including `start` itself.
+pub fn collect_ancestors(root_path: &Path, start: &str) -> Result<HashSet<String>> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut stack = vec![start.to_string()];
+
+    while let Some(commit_hash) = stack.pop() {
+        if seen.insert(commit_hash.clone()) {
+            stack.extend(get_commit_parents(root_path, &commit_hash)?);
+        }
+    }
+
+    Ok(seen)
+}
+
+/// Returns true if `ancestor` is reachable from `descendant` (or equal to it).
+pub fn is_ancestor(root_path: &Path, ancestor: &str, descendant: &str) -> Result<bool> {
+    Ok(collect_ancestors(root_path, descendant)?.contains(ancestor))
+}
+
+/// Finds the best common ancestor of two commits.
+///
+/// Candidates are common ancestors that are not themselves ancestors of
+/// another common ancestor. If a criss-cross history leaves several, the one
+/// with the newest committer timestamp is chosen.
+pub fn merge_base(root_path: &Path, first: &str, second: &str) -> Result<Option<String>> {
+    let first_ancestors = collect_ancestors(root_path, first)?;
+    let common: HashSet<String> = collect_ancestors(root_path, second)?
+        .into_iter()
+        .filter(|commit_hash| first_ancestors.contains(commit_hash))
+        .collect();
+
+    // Anything strictly below a common ancestor cannot be a best merge base.
+    let mut redundant: HashSet<String> = HashSet::new();
+    for commit_hash in &common {
+        let mut stack = get_commit_parents(root_path, commit_hash)?;
+        while let Some(parent_hash) = stack.pop() {
+            if redundant.insert(parent_hash.clone()) {
+                stack.extend(get_commit_parents(root_path, &parent_hash)?);
+            }
+        }
+    }
+
+    let mut best: BinaryHeap<(i64, String)> = BinaryHeap::new();
+    for commit_hash in common.difference(&redundant) {
+        let timestamp = get_commit_timestamp(&utils::parse_commit(root_path, commit_hash)?);
+        best.push((timestamp, commit_hash.clone()));
+    }
+
+    Ok(best.pop().map(|(_, commit_hash)| commit_hash))
+}
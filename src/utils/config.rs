@@ -0,0 +1,333 @@
+//! INI-style configuration files: `.rustygit/config` and the global `~/.rustygitconfig`.
+
+use crate::utils::LockFile;
+use anyhow::{Result, anyhow, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Which configuration file a `set`/`unset` applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigScope {
+    Local,
+    Global,
+}
+
+/// One `[section]` or `[section "subsection"]` block.
+struct ConfigSection {
+    /// Lowercased section name plus optional subsection, e.g. `user` or `branch.main`.
+    name: String,
+    entries: Vec<(String, String)>,
+}
+
+/// Returns the config file path for `scope`.
+///
+/// The global file lives in `$HOME/.rustygitconfig` (`%USERPROFILE%` on Windows).
+pub fn config_path(root_path: &Path, scope: ConfigScope) -> Result<PathBuf> {
+    match scope {
+        ConfigScope::Local => Ok(root_path.join(".rustygit").join("config")),
+        ConfigScope::Global => {
+            let home = std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .ok_or_else(|| anyhow!("Could not determine the home directory."))?;
+            Ok(PathBuf::from(home).join(".rustygitconfig"))
+        }
+    }
+}
+
+/// Splits `section[.subsection].key` into the section name used in files and the key.
+fn split_key(key: &str) -> Result<(String, String)> {
+    let (section, name) = key
+        .rsplit_once('.')
+        .ok_or_else(|| anyhow!("Invalid config key '{}': expected <section>.<key>.", key))?;
+
+    if section.is_empty() || name.is_empty() {
+        bail!("Invalid config key '{}': expected <section>.<key>.", key);
+    }
+
+    // Section and key names are case-insensitive; subsections are not.
+    let section = match section.split_once('.') {
+        Some((base, subsection)) => format!("{}.{}", base.to_lowercase(), subsection),
+        None => section.to_lowercase(),
+    };
+
+    Ok((section, name.to_lowercase()))
+}
+
+/// What one line of a config file holds.
+enum ConfigLine {
+    Section(String),
+    Entry(String, String),
+    /// Blank lines and comments.
+    Other,
+}
+
+fn parse_section_header(line: &str) -> Result<String> {
+    let malformed = || anyhow!("Malformed config section header: {}", line);
+
+    // The header ends at the first `]` outside a quoted subsection; only a
+    // comment may follow it.
+    let mut in_quotes = false;
+    let close = line
+        .char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c == ']' && !in_quotes
+        })
+        .map(|(position, _)| position)
+        .ok_or_else(malformed)?;
+    let rest = line[close + 1..].trim_start();
+    if !(rest.is_empty() || rest.starts_with('#') || rest.starts_with(';')) {
+        return Err(malformed());
+    }
+
+    let inner = line[..close]
+        .strip_prefix('[')
+        .ok_or_else(malformed)?
+        .trim();
+    match inner.split_once(' ') {
+        Some((base, subsection)) => {
+            let subsection = subsection.trim().trim_matches('"');
+            Ok(format!("{}.{}", base.to_lowercase(), subsection))
+        }
+        None => Ok(inner.to_lowercase()),
+    }
+}
+
+/// Parses the text after `=`. Double quotes keep whitespace and `#`/`;`
+/// inside them, backslash escapes are decoded, and an unquoted `#` or `;`
+/// starts a comment.
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    // Length of `value` up to its last quoted or non-whitespace character.
+    let mut kept = 0;
+    let mut in_quotes = false;
+    let mut chars = raw.trim_start().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                kept = value.len();
+                continue;
+            }
+            '#' | ';' if !in_quotes => break,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('b') => value.push('\u{8}'),
+                Some(escaped) => value.push(escaped),
+                None => {}
+            },
+            c => value.push(c),
+        }
+        if in_quotes || !c.is_whitespace() {
+            kept = value.len();
+        }
+    }
+
+    value.truncate(kept);
+    value
+}
+
+/// Escapes `value` for writing, quoting it when whitespace at either end or
+/// a comment character would otherwise be lost on the next read.
+fn format_value(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    let needs_quotes = value.starts_with(char::is_whitespace)
+        || value.ends_with(char::is_whitespace)
+        || value.contains(['#', ';']);
+
+    if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn parse_line(raw: &str) -> Result<ConfigLine> {
+    let line = raw.trim();
+
+    if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+        return Ok(ConfigLine::Other);
+    }
+    if line.starts_with('[') {
+        return Ok(ConfigLine::Section(parse_section_header(line)?));
+    }
+
+    // A bare key (no `=`) is a boolean true, as in Git.
+    let key_end = line.find(['=', '#', ';']).unwrap_or(line.len());
+    let key = line[..key_end].trim().to_lowercase();
+    let value = match line[key_end..].strip_prefix('=') {
+        Some(value) => parse_value(value),
+        None => "true".to_string(),
+    };
+    Ok(ConfigLine::Entry(key, value))
+}
+
+fn read_config_file(path: &Path) -> Result<Vec<ConfigSection>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut sections: Vec<ConfigSection> = Vec::new();
+
+    for raw in content.lines() {
+        match parse_line(raw)? {
+            ConfigLine::Section(name) => sections.push(ConfigSection {
+                name,
+                entries: Vec::new(),
+            }),
+            ConfigLine::Entry(key, value) => sections
+                .last_mut()
+                .ok_or_else(|| anyhow!("Config entry outside of a section: {}", raw.trim()))?
+                .entries
+                .push((key, value)),
+            ConfigLine::Other => {}
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Rewrites every `key` line of `section` in the file at `path`: the last
+/// one becomes `value` and the others are removed, or all are removed when
+/// `value` is `None`. A missing key is added at the end of the last such
+/// section. Comments and all other lines are kept as they are.
+///
+/// The file stays locked from the read through the write. Returns whether
+/// the key was already set.
+fn edit_config_file(path: &Path, section: &str, key: &str, value: Option<&str>) -> Result<bool> {
+    let mut lock = LockFile::acquire(path)?;
+    let content = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+    let mut matches = Vec::new();
+    // Where a new entry for `section` goes: after its last line.
+    let mut insert_at = None;
+    let mut current = None;
+    for (position, line) in lines.iter().enumerate() {
+        match parse_line(line)? {
+            ConfigLine::Section(name) => {
+                if name == section {
+                    insert_at = Some(position + 1);
+                }
+                current = Some(name);
+            }
+            ConfigLine::Entry(name, _) if current.as_deref() == Some(section) => {
+                insert_at = Some(position + 1);
+                if name == key {
+                    matches.push(position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let found = !matches.is_empty();
+    let new_line = value.map(|value| format!("\t{} = {}", key, format_value(value)));
+    match (new_line, matches.pop()) {
+        (Some(new_line), Some(last)) => lines[last] = new_line,
+        (Some(new_line), None) => match insert_at {
+            Some(position) => lines.insert(position, new_line),
+            None => {
+                lines.push(match section.split_once('.') {
+                    Some((base, subsection)) => format!("[{} \"{}\"]", base, subsection),
+                    None => format!("[{}]", section),
+                });
+                lines.push(new_line);
+            }
+        },
+        (None, Some(last)) => matches.push(last),
+        (None, None) => return Ok(false),
+    }
+    for position in matches.into_iter().rev() {
+        lines.remove(position);
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    lock.write(content.as_bytes())?;
+    lock.commit()?;
+    Ok(found)
+}
+
+fn lookup(sections: &[ConfigSection], section: &str, key: &str) -> Option<String> {
+    // Later definitions win, matching Git's last-one-wins semantics.
+    sections
+        .iter()
+        .filter(|s| s.name == section)
+        .flat_map(|s| s.entries.iter())
+        .filter(|(k, _)| k == key)
+        .map(|(_, v)| v.clone())
+        .next_back()
+}
+
+/// Looks up `key`, preferring the repository config over the global config.
+pub fn get_config_value(root_path: &Path, key: &str) -> Result<Option<String>> {
+    let (section, name) = split_key(key)?;
+
+    let local = read_config_file(&config_path(root_path, ConfigScope::Local)?)?;
+    if let Some(value) = lookup(&local, &section, &name) {
+        return Ok(Some(value));
+    }
+
+    let global = match config_path(root_path, ConfigScope::Global) {
+        Ok(path) => read_config_file(&path)?,
+        Err(_) => Vec::new(),
+    };
+    Ok(lookup(&global, &section, &name))
+}
+
+/// Sets `key = value` in the config file for `scope`, replacing any existing value.
+pub fn set_config_value(
+    root_path: &Path,
+    scope: ConfigScope,
+    key: &str,
+    value: &str,
+) -> Result<()> {
+    let (section, name) = split_key(key)?;
+    let path = config_path(root_path, scope)?;
+    edit_config_file(&path, &section, &name, Some(value))?;
+    Ok(())
+}
+
+/// Removes `key` from the config file for `scope`. Returns false if it was not set.
+pub fn unset_config_value(root_path: &Path, scope: ConfigScope, key: &str) -> Result<bool> {
+    let (section, name) = split_key(key)?;
+    let path = config_path(root_path, scope)?;
+    edit_config_file(&path, &section, &name, None)
+}
+
+/// Lists all `key=value` pairs, global entries first and then repository entries.
+pub fn list_config(root_path: &Path) -> Result<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+
+    let mut paths = Vec::new();
+    if let Ok(global_path) = config_path(root_path, ConfigScope::Global) {
+        paths.push(global_path);
+    }
+    paths.push(config_path(root_path, ConfigScope::Local)?);
+
+    for path in paths {
+        for section in read_config_file(&path)? {
+            for (key, value) in section.entries {
+                pairs.push((format!("{}.{}", section.name, key), value));
+            }
+        }
+    }
+
+    Ok(pairs)
+}
@@ -26,6 +26,22 @@ pub fn get_time() -> (i64, String) {
 ///
 /// Returns something like: `Wed Dec  6 12:34:56 2025 +0530`.
 pub fn format_commit_date(epoch_secs: i64, tz: &str) -> Option<String> {
+    Some(
+        date_with_offset(epoch_secs, tz)?
+            .format("%a %b %e %H:%M:%S %Y %z")
+            .to_string(),
+    )
+}
+
+/// Formats `(unix_timestamp, timezone_offset)` as an RFC 2822 date for mail
+/// headers, like `Wed, 6 Dec 2025 12:34:56 +0530`.
+pub fn format_rfc2822_date(epoch_secs: i64, tz: &str) -> Option<String> {
+    Some(date_with_offset(epoch_secs, tz)?.to_rfc2822())
+}
+
+/// Returns the moment `epoch_secs` in the timezone `tz` (`+0530`, `-0700`);
+/// a malformed timezone is treated as UTC.
+fn date_with_offset(epoch_secs: i64, tz: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
     let utc_dt = chrono::DateTime::from_timestamp(epoch_secs, 0)?;
 
     // Parse timezone offset like +0530 / -0700
@@ -46,10 +62,46 @@ pub fn format_commit_date(epoch_secs: i64, tz: &str) -> Option<String> {
     };
 
     let offset = chrono::FixedOffset::east_opt(offset_minutes * 60)?;
-    Some(
-        utc_dt
-            .with_timezone(&offset)
-            .format("%a %b %e %H:%M:%S %Y %z")
-            .to_string(),
-    )
+    Some(utc_dt.with_timezone(&offset))
+}
+
+/// Parses a date given in an environment override such as `RUSTYGIT_AUTHOR_DATE`.
+///
+/// Accepts Git's internal `<unix_timestamp> <+hhmm>` form (optionally prefixed
+/// with `@`), a bare `@<unix_timestamp>` (UTC), RFC 3339 and RFC 2822 dates.
+/// Returns `(unix_timestamp, timezone_offset)` like [`get_time`].
+pub fn parse_date(value: &str) -> Option<(i64, String)> {
+    let value = value.trim();
+    let raw = value.strip_prefix('@').unwrap_or(value);
+
+    let mut parts = raw.split_whitespace();
+    if let Some(Ok(timestamp)) = parts.next().map(str::parse::<i64>) {
+        return match (parts.next(), parts.next()) {
+            (None, _) => Some((timestamp, String::from("+0000"))),
+            (Some(tz), None) if is_timezone(tz) => Some((timestamp, tz.to_string())),
+            _ => None,
+        };
+    }
+
+    let parsed = chrono::DateTime::parse_from_rfc3339(value)
+        .or_else(|_| chrono::DateTime::parse_from_rfc2822(value))
+        .ok()?;
+
+    let offset_secs = parsed.offset().local_minus_utc();
+    let sign = if offset_secs >= 0 { '+' } else { '-' };
+    let offset_secs = offset_secs.abs();
+    let timezone = format!(
+        "{}{:02}{:02}",
+        sign,
+        offset_secs / 3600,
+        (offset_secs % 3600) / 60
+    );
+
+    Some((parsed.timestamp(), timezone))
+}
+
+fn is_timezone(tz: &str) -> bool {
+    tz.len() == 5
+        && (tz.starts_with('+') || tz.starts_with('-'))
+        && tz[1..].chars().all(|c| c.is_ascii_digit())
 }
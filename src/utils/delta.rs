@@ -0,0 +1,193 @@
+//! Git delta encoding: computing and applying copy/insert instruction streams.
+
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashMap;
+
+/// Block size used to index the delta base when searching for copies.
+const BLOCK_SIZE: usize = 16;
+/// Largest copy a single instruction can encode without the size wrapping to 0x10000.
+const MAX_COPY_SIZE: usize = 0xffff;
+/// Largest literal run a single insert instruction can carry.
+const MAX_INSERT_SIZE: usize = 0x7f;
+
+fn read_delta_size(delta: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut size = 0u64;
+    let mut shift = 0;
+
+    loop {
+        let byte = *delta
+            .get(*pos)
+            .ok_or_else(|| anyhow!("Delta data is truncated"))?;
+        *pos += 1;
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+/// Applies a Git delta (copy/insert instruction stream) to `base`.
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let mut pos = 0;
+    let base_size = read_delta_size(delta, &mut pos)?;
+    let result_size = read_delta_size(delta, &mut pos)?;
+
+    if base_size != base.len() as u64 {
+        bail!("Delta base size mismatch");
+    }
+
+    let mut result = Vec::with_capacity(result_size as usize);
+
+    while pos < delta.len() {
+        let opcode = delta[pos];
+        pos += 1;
+
+        if opcode & 0x80 != 0 {
+            // Copy from base: bits 0-3 select offset bytes, bits 4-6 select size bytes.
+            let mut offset = 0usize;
+            let mut size = 0usize;
+
+            for i in 0..4 {
+                if opcode & (1 << i) != 0 {
+                    let byte = *delta
+                        .get(pos)
+                        .ok_or_else(|| anyhow!("Delta data is truncated"))?;
+                    offset |= (byte as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if opcode & (1 << (4 + i)) != 0 {
+                    let byte = *delta
+                        .get(pos)
+                        .ok_or_else(|| anyhow!("Delta data is truncated"))?;
+                    size |= (byte as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+
+            let chunk = base
+                .get(offset..offset + size)
+                .ok_or_else(|| anyhow!("Delta copy is out of bounds"))?;
+            result.extend_from_slice(chunk);
+        } else if opcode != 0 {
+            // Insert the next `opcode` literal bytes.
+            let size = opcode as usize;
+            let chunk = delta
+                .get(pos..pos + size)
+                .ok_or_else(|| anyhow!("Delta data is truncated"))?;
+            result.extend_from_slice(chunk);
+            pos += size;
+        } else {
+            bail!("Invalid delta opcode 0");
+        }
+    }
+
+    if result.len() as u64 != result_size {
+        bail!("Delta result size mismatch");
+    }
+
+    Ok(result)
+}
+
+fn write_delta_size(out: &mut Vec<u8>, mut size: usize) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+
+        if size == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn flush_insert(out: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    for chunk in pending.chunks(MAX_INSERT_SIZE) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+    pending.clear();
+}
+
+fn write_copy(out: &mut Vec<u8>, offset: usize, size: usize) {
+    let mut opcode = 0x80u8;
+    let mut args = Vec::new();
+
+    for i in 0..4 {
+        let byte = ((offset >> (8 * i)) & 0xff) as u8;
+        if byte != 0 {
+            opcode |= 1 << i;
+            args.push(byte);
+        }
+    }
+    for i in 0..3 {
+        let byte = ((size >> (8 * i)) & 0xff) as u8;
+        if byte != 0 {
+            opcode |= 1 << (4 + i);
+            args.push(byte);
+        }
+    }
+
+    out.push(opcode);
+    out.extend_from_slice(&args);
+}
+
+/// Computes a delta that rebuilds `target` from `base`.
+///
+/// The base is indexed in fixed-size blocks; the target is scanned for block
+/// matches, which are extended forward and emitted as copies, while
+/// unmatched bytes become literal inserts.
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_delta_size(&mut out, base.len());
+    write_delta_size(&mut out, target.len());
+
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for (i, block) in base.chunks_exact(BLOCK_SIZE).enumerate() {
+        blocks.entry(block).or_insert(i * BLOCK_SIZE);
+    }
+
+    let mut pending: Vec<u8> = Vec::new();
+    let mut pos = 0;
+
+    while pos < target.len() {
+        let base_offset = target
+            .get(pos..pos + BLOCK_SIZE)
+            .and_then(|block| blocks.get(block).copied());
+
+        let Some(base_offset) = base_offset else {
+            pending.push(target[pos]);
+            pos += 1;
+            continue;
+        };
+
+        let mut length = BLOCK_SIZE;
+        while base_offset + length < base.len()
+            && pos + length < target.len()
+            && base[base_offset + length] == target[pos + length]
+        {
+            length += 1;
+        }
+
+        flush_insert(&mut out, &mut pending);
+
+        let mut copied = 0;
+        while copied < length {
+            let size = (length - copied).min(MAX_COPY_SIZE);
+            write_copy(&mut out, base_offset + copied, size);
+            copied += size;
+        }
+
+        pos += length;
+    }
+
+    flush_insert(&mut out, &mut pending);
+    out
+}
@@ -0,0 +1,88 @@
+//! Launching the user's editor for todo lists and commit messages.
+
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use std::{fs, path::Path, process::Command};
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Returns the editor command line.
+///
+/// For sequence (todo list) edits, `RUSTYGIT_SEQUENCE_EDITOR` and the
+/// `sequence.editor` config key are tried first. Then `RUSTYGIT_EDITOR`,
+/// `core.editor`, `VISUAL` and `EDITOR` are used, falling back to `vi`.
+fn editor_command(root_path: &Path, sequence: bool) -> Result<String> {
+    if sequence {
+        if let Some(editor) = env_value("RUSTYGIT_SEQUENCE_EDITOR") {
+            return Ok(editor);
+        }
+        if let Some(editor) = utils::get_config_value(root_path, "sequence.editor")? {
+            return Ok(editor);
+        }
+    }
+
+    if let Some(editor) = env_value("RUSTYGIT_EDITOR") {
+        return Ok(editor);
+    }
+    if let Some(editor) = utils::get_config_value(root_path, "core.editor")? {
+        return Ok(editor);
+    }
+
+    Ok(env_value("VISUAL")
+        .or_else(|| env_value("EDITOR"))
+        .unwrap_or_else(|| String::from("vi")))
+}
+
+/// Opens `file` in the editor and waits for it to exit.
+///
+/// The editor command is run through `sh`, so it may carry arguments
+/// (e.g. `code --wait`). `sequence` selects the todo-list editor.
+pub fn run_editor(root_path: &Path, file: &Path, sequence: bool) -> Result<()> {
+    let editor = editor_command(root_path, sequence)?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(file)
+        .current_dir(root_path)
+        .status()
+        .map_err(|err| anyhow!("Unable to start editor '{}': {}", editor, err))?;
+
+    if !status.success() {
+        bail!("There was a problem with the editor '{}'.", editor);
+    }
+    Ok(())
+}
+
+/// Lets the user edit a commit message, starting from `message`.
+///
+/// Lines starting with `#` are removed; an empty result is an error.
+pub fn edit_message(root_path: &Path, message: &str) -> Result<String> {
+    let file = root_path.join(".rustygit").join("COMMIT_EDITMSG");
+    fs::write(
+        &file,
+        format!(
+            "{}\n\n# Please enter the commit message for your changes. Lines starting\n# with '#' will be ignored, and an empty message aborts the commit.\n",
+            message.trim_end()
+        ),
+    )?;
+
+    run_editor(root_path, &file, false)?;
+
+    let content = fs::read_to_string(&file)?;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+
+    let message = lines.join("\n").trim().to_string();
+    if message.is_empty() {
+        bail!("Aborting commit due to empty commit message.");
+    }
+    Ok(format!("{}\n", message))
+}
@@ -0,0 +1,108 @@
+//! File modes recorded in trees and the index, and how they map onto the working tree.
+
+use anyhow::{Result, anyhow};
+use std::{fs, path::Path};
+
+/// Regular, non-executable file.
+pub const MODE_FILE: &str = "100644";
+/// Regular file with the executable bit set.
+pub const MODE_EXECUTABLE: &str = "100755";
+/// Symbolic link; the blob stores the link target.
+pub const MODE_SYMLINK: &str = "120000";
+/// Subdirectory (tree entries only).
+pub const MODE_TREE: &str = "40000";
+
+/// Validates a mode string and returns its canonical static form.
+pub fn parse_mode(mode: &str) -> Result<&'static str> {
+    match mode {
+        MODE_FILE => Ok(MODE_FILE),
+        MODE_EXECUTABLE => Ok(MODE_EXECUTABLE),
+        MODE_SYMLINK => Ok(MODE_SYMLINK),
+        MODE_TREE => Ok(MODE_TREE),
+        other => Err(anyhow!("Unsupported file mode: {other}")),
+    }
+}
+
+/// Determines the mode a working-tree path should be recorded with.
+///
+/// Symlinks are not followed. A file is executable when its owner execute
+/// bit is set; on non-Unix platforms every regular file is `100644`.
+pub fn work_file_mode(path: &Path) -> Result<&'static str> {
+    let metadata = fs::symlink_metadata(path)?;
+
+    if metadata.file_type().is_symlink() {
+        return Ok(MODE_SYMLINK);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o100 != 0 {
+            return Ok(MODE_EXECUTABLE);
+        }
+    }
+
+    Ok(MODE_FILE)
+}
+
+/// Reads the bytes a working-tree path contributes to its blob.
+///
+/// For symlinks this is the link target rather than the file it points to.
+pub fn read_work_file(path: &Path) -> Result<Vec<u8>> {
+    let metadata = fs::symlink_metadata(path)?;
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path)?;
+        return Ok(target.to_string_lossy().into_owned().into_bytes());
+    }
+
+    Ok(fs::read(path)?)
+}
+
+/// Materializes blob `content` at `path` with the given tree `mode`.
+///
+/// Any existing file or symlink at `path` is replaced. On Unix, symlinks are
+/// recreated as links and the execute bits of regular files follow `mode`;
+/// elsewhere, symlinks are written as plain files holding the target path.
+pub fn write_work_file(path: &Path, mode: &str, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if let Ok(metadata) = fs::symlink_metadata(path)
+        && !metadata.is_dir()
+    {
+        fs::remove_file(path)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if mode == MODE_SYMLINK {
+            let target = String::from_utf8_lossy(content).into_owned();
+            std::os::unix::fs::symlink(target, path)?;
+            return Ok(());
+        }
+
+        fs::write(path, content)?;
+
+        // Start from the umask-derived permissions and only toggle execute
+        // bits, granting execute wherever read is allowed (like Git).
+        let current = fs::metadata(path)?.permissions().mode();
+        let permissions = if mode == MODE_EXECUTABLE {
+            current | ((current & 0o444) >> 2)
+        } else {
+            current & !0o111
+        };
+        fs::set_permissions(path, fs::Permissions::from_mode(permissions))?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -2,13 +2,13 @@ use anyhow::{Result, bail};
 use std::path::Path;
 
 pub fn ensure_repo_exists(path: &Path) -> Result<()> {
-    let mut paths = Vec::new();
-
-    paths.push(path.join(".rustygit"));
-    paths.push(path.join(".rustygit/objects"));
-    paths.push(path.join(".rustygit/refs"));
-    paths.push(path.join(".rustygit/refs/heads"));
-    paths.push(path.join(".rustygit/HEAD"));
+    let paths = vec![
+        path.join(".rustygit"),
+        path.join(".rustygit/objects"),
+        path.join(".rustygit/refs"),
+        path.join(".rustygit/refs/heads"),
+        path.join(".rustygit/HEAD"),
+    ];
 
     for path in paths {
         if !path.exists() {
@@ -0,0 +1,90 @@
+//! Author and committer identities for new commits.
+
+use crate::utils;
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+/// A name, email and timestamp as recorded on `author`/`committer` lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub timezone: String,
+}
+
+impl Signature {
+    /// Formats the signature as `Name <email> <unix_timestamp> <+hhmm>`.
+    pub fn format(&self) -> String {
+        format!(
+            "{} <{}> {} {}",
+            self.name, self.email, self.timestamp, self.timezone
+        )
+    }
+
+    /// Parses the `Name <email> <unix_timestamp> <+hhmm>` form written by
+    /// [`Signature::format`].
+    pub fn parse(value: &str) -> Option<Signature> {
+        let (name, rest) = value.split_once('<')?;
+        let (email, date) = rest.split_once('>')?;
+        let mut date = date.split_whitespace();
+
+        Some(Signature {
+            name: name.trim().to_string(),
+            email: email.to_string(),
+            timestamp: date.next()?.parse().ok()?,
+            timezone: date.next()?.to_string(),
+        })
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Builds a signature for `role` (`AUTHOR` or `COMMITTER`).
+///
+/// `RUSTYGIT_<role>_NAME`, `_EMAIL` and `_DATE` take precedence over
+/// `user.name`/`user.email` from the config. Without either, the login name
+/// and `<login>@localhost` are used so commits never fail for lack of setup.
+fn signature_for(root_path: &Path, role: &str) -> Result<Signature> {
+    let login = env_value("USER")
+        .or_else(|| env_value("USERNAME"))
+        .unwrap_or_else(|| String::from("unknown"));
+
+    let name = match env_value(&format!("RUSTYGIT_{}_NAME", role)) {
+        Some(name) => name,
+        None => utils::get_config_value(root_path, "user.name")?.unwrap_or_else(|| login.clone()),
+    };
+    let email = match env_value(&format!("RUSTYGIT_{}_EMAIL", role)) {
+        Some(email) => email,
+        None => utils::get_config_value(root_path, "user.email")?
+            .unwrap_or_else(|| format!("{}@localhost", login)),
+    };
+
+    let date_var = format!("RUSTYGIT_{}_DATE", role);
+    let (timestamp, timezone) = match env_value(&date_var) {
+        Some(date) => utils::parse_date(&date)
+            .ok_or_else(|| anyhow!("Invalid date in {}: {}", date_var, date))?,
+        None => utils::get_time(),
+    };
+
+    Ok(Signature {
+        name,
+        email,
+        timestamp,
+        timezone,
+    })
+}
+
+/// Returns the author identity for a new commit.
+pub fn author_signature(root_path: &Path) -> Result<Signature> {
+    signature_for(root_path, "AUTHOR")
+}
+
+/// Returns the committer identity for a new commit.
+pub fn committer_signature(root_path: &Path) -> Result<Signature> {
+    signature_for(root_path, "COMMITTER")
+}
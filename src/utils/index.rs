@@ -1,5 +1,6 @@
 //! Index file read/write helpers.
 
+use crate::utils::{self, LockFile};
 use anyhow::{Result, anyhow, bail};
 use std::{
     collections::HashMap,
@@ -7,19 +8,51 @@ use std::{
     path::{Path, PathBuf},
 };
 
+/// A staged path: its blob hash and the file mode it will be committed with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub mode: &'static str,
+    pub hash: String,
+}
+
+/// The stage 1/2/3 entries recorded for a path left unmerged by a merge.
+///
+/// A side is `None` when the path does not exist there (e.g. deleted by
+/// one side, or added by both with no common ancestor).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConflictEntry {
+    pub base: Option<IndexEntry>,
+    pub ours: Option<IndexEntry>,
+    pub theirs: Option<IndexEntry>,
+}
+
 fn index_path(root_path: &Path) -> PathBuf {
     root_path.join(".rustygit").join("index")
 }
 
-/// Loads `.rustygit/index` into a `path -> blob_hash` map.
-pub fn read_index_map(root_path: &Path) -> Result<HashMap<PathBuf, String>> {
+fn is_hash(value: &str) -> bool {
+    value.len() == 40 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+type IndexContents = (
+    HashMap<PathBuf, IndexEntry>,
+    HashMap<PathBuf, ConflictEntry>,
+);
+
+/// Parses the whole index: stage 0 entries and unmerged (stage 1-3) entries.
+///
+/// Entries are stored as `<mode> <hash> <path>`; unmerged stages append the
+/// stage number to the mode (`<mode>:<stage> <hash> <path>`). Lines written
+/// before modes were recorded (`<hash> <path>`) are read as `100644` files.
+fn read_index_file(root_path: &Path) -> Result<IndexContents> {
     let path = index_path(root_path);
+    let mut map: HashMap<PathBuf, IndexEntry> = HashMap::new();
+    let mut conflicts: HashMap<PathBuf, ConflictEntry> = HashMap::new();
 
     if !path.exists() {
-        return Ok(HashMap::new());
+        return Ok((map, conflicts));
     }
 
-    let mut map: HashMap<PathBuf, String> = HashMap::new();
     let content = fs::read_to_string(path)?;
 
     for line in content.lines() {
@@ -27,43 +60,198 @@ pub fn read_index_map(root_path: &Path) -> Result<HashMap<PathBuf, String>> {
             continue;
         }
 
-        let (hash, rel_path) = line
+        let (first, rest) = line
             .split_once(' ')
             .ok_or_else(|| anyhow!("Malformed index entry: {}", line))?;
 
-        if hash.len() != 40 {
+        let (mode_field, hash, rel_path) = if is_hash(first) {
+            (utils::MODE_FILE, first, rest)
+        } else {
+            let (hash, rel_path) = rest
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("Malformed index entry: {}", line))?;
+            (first, hash, rel_path)
+        };
+
+        if !is_hash(hash) {
             bail!("Malformed index entry hash: {}", line);
         }
 
-        map.insert(PathBuf::from(rel_path), hash.to_string());
+        let (mode, stage) = match mode_field.split_once(':') {
+            Some((mode, stage)) => (mode, stage),
+            None => (mode_field, "0"),
+        };
+        let entry = IndexEntry {
+            mode: utils::parse_mode(mode)?,
+            hash: hash.to_string(),
+        };
+
+        let rel_path = PathBuf::from(rel_path);
+        match stage {
+            "0" => {
+                map.insert(rel_path, entry);
+            }
+            "1" => conflicts.entry(rel_path).or_default().base = Some(entry),
+            "2" => conflicts.entry(rel_path).or_default().ours = Some(entry),
+            "3" => conflicts.entry(rel_path).or_default().theirs = Some(entry),
+            _ => bail!("Malformed index entry stage: {}", line),
+        }
     }
 
-    Ok(map)
+    Ok((map, conflicts))
 }
 
-/// Persists the full index map to `.rustygit/index` in sorted path order.
-pub fn write_index_map(root_path: &Path, map: &HashMap<PathBuf, String>) -> Result<()> {
-    let mut entries: Vec<(&PathBuf, &String)> = map.iter().collect();
-    entries.sort_by(|a, b| a.0.cmp(b.0));
+/// Takes `.rustygit/index.lock`. Callers hold it from reading the index
+/// until [`write_index_file`] commits, so that concurrent writers cannot
+/// drop each other's changes.
+fn lock_index(root_path: &Path) -> Result<LockFile> {
+    LockFile::acquire(&index_path(root_path))
+}
+
+fn write_index_file(
+    mut lock: LockFile,
+    map: &HashMap<PathBuf, IndexEntry>,
+    conflicts: &HashMap<PathBuf, ConflictEntry>,
+) -> Result<()> {
+    let mut lines: Vec<(&PathBuf, u8, &IndexEntry)> =
+        map.iter().map(|(path, entry)| (path, 0, entry)).collect();
+
+    for (path, conflict) in conflicts {
+        let stages = [
+            (1, &conflict.base),
+            (2, &conflict.ours),
+            (3, &conflict.theirs),
+        ];
+        for (stage, entry) in stages {
+            if let Some(entry) = entry {
+                lines.push((path, stage, entry));
+            }
+        }
+    }
+    lines.sort_by(|a, b| a.0.cmp(b.0).then(a.1.cmp(&b.1)));
 
     let mut content = String::new();
-    for (path, hash) in entries {
-        content.push_str(&format!("{} {}\n", hash, path.display()));
+    for (path, stage, entry) in lines {
+        if stage == 0 {
+            content.push_str(&format!(
+                "{} {} {}\n",
+                entry.mode,
+                entry.hash,
+                path.display()
+            ));
+        } else {
+            content.push_str(&format!(
+                "{}:{} {} {}\n",
+                entry.mode,
+                stage,
+                entry.hash,
+                path.display()
+            ));
+        }
     }
 
-    fs::write(index_path(root_path), content)?;
-    Ok(())
+    lock.write(content.as_bytes())?;
+    lock.commit()
 }
 
-/// Inserts or replaces a single staged index entry.
-pub fn stage_index_entry(root_path: &Path, path: &Path, hash: &str) -> Result<()> {
-    let mut map = read_index_map(root_path)?;
-    map.insert(path.to_path_buf(), hash.to_string());
-    write_index_map(root_path, &map)
+/// Loads the stage 0 entries of `.rustygit/index` into a `path -> (mode, blob_hash)` map.
+///
+/// Unmerged paths are not included; see [`read_index_conflicts`].
+pub fn read_index_entries(root_path: &Path) -> Result<HashMap<PathBuf, IndexEntry>> {
+    Ok(read_index_file(root_path)?.0)
+}
+
+/// Persists the stage 0 index in sorted path order.
+///
+/// Unmerged entries are kept only for paths not present in `map`, so staging
+/// a path resolves its conflict.
+pub fn write_index_entries(root_path: &Path, map: &HashMap<PathBuf, IndexEntry>) -> Result<()> {
+    update_index_entries(root_path, |entries| {
+        entries.clone_from(map);
+        Ok(())
+    })
+}
+
+/// Reads the stage 0 entries, applies `update` to them and writes them back,
+/// holding the index lock throughout.
+///
+/// As with [`write_index_entries`], unmerged entries are kept only for paths
+/// not present afterwards.
+pub fn update_index_entries(
+    root_path: &Path,
+    update: impl FnOnce(&mut HashMap<PathBuf, IndexEntry>) -> Result<()>,
+) -> Result<()> {
+    let lock = lock_index(root_path)?;
+    let (mut map, mut conflicts) = read_index_file(root_path)?;
+    update(&mut map)?;
+    conflicts.retain(|path, _| !map.contains_key(path));
+    write_index_file(lock, &map, &conflicts)
+}
+
+/// Loads the unmerged paths recorded in the index.
+pub fn read_index_conflicts(root_path: &Path) -> Result<HashMap<PathBuf, ConflictEntry>> {
+    Ok(read_index_file(root_path)?.1)
+}
+
+/// Replaces the unmerged paths recorded in the index, keeping stage 0 entries.
+pub fn write_index_conflicts(
+    root_path: &Path,
+    conflicts: &HashMap<PathBuf, ConflictEntry>,
+) -> Result<()> {
+    let lock = lock_index(root_path)?;
+    let (mut map, _) = read_index_file(root_path)?;
+    map.retain(|path, _| !conflicts.contains_key(path));
+    write_index_file(lock, &map, conflicts)
+}
+
+/// Loads `.rustygit/index` into a `path -> blob_hash` map.
+pub fn read_index_map(root_path: &Path) -> Result<HashMap<PathBuf, String>> {
+    Ok(read_index_entries(root_path)?
+        .into_iter()
+        .map(|(path, entry)| (path, entry.hash))
+        .collect())
 }
 
-/// Clears index contents.
-pub fn clear_index(root_path: &Path) -> Result<()> {
-    fs::write(index_path(root_path), "")?;
-    Ok(())
+/// Persists a `path -> blob_hash` map as the full index.
+///
+/// Paths already in the index keep their recorded mode; new paths are staged
+/// as regular `100644` files.
+pub fn write_index_map(root_path: &Path, map: &HashMap<PathBuf, String>) -> Result<()> {
+    update_index_entries(root_path, |entries| {
+        *entries = map
+            .iter()
+            .map(|(path, hash)| {
+                let mode = entries
+                    .get(path)
+                    .map_or(utils::MODE_FILE, |entry| entry.mode);
+                (
+                    path.clone(),
+                    IndexEntry {
+                        mode,
+                        hash: hash.clone(),
+                    },
+                )
+            })
+            .collect();
+        Ok(())
+    })
+}
+
+/// Inserts or replaces a single staged index entry.
+pub fn stage_index_entry(
+    root_path: &Path,
+    path: &Path,
+    hash: &str,
+    mode: &'static str,
+) -> Result<()> {
+    update_index_entries(root_path, |map| {
+        map.insert(
+            path.to_path_buf(),
+            IndexEntry {
+                mode,
+                hash: hash.to_string(),
+            },
+        );
+        Ok(())
+    })
 }
@@ -0,0 +1,88 @@
+//! Lock files for atomic updates of refs and the index.
+//!
+//! Like Git, a writer creates `<file>.lock` exclusively, writes the new
+//! content there, and renames it over `<file>`. A second writer fails to
+//! create the lock instead of interleaving with the first, and a crash
+//! leaves the original file untouched.
+
+use anyhow::{Result, anyhow};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/// An exclusively held `<file>.lock`, removed on drop unless committed.
+pub struct LockFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    file: File,
+    committed: bool,
+}
+
+impl LockFile {
+    /// Creates `<path>.lock`, failing if another process holds it.
+    pub fn acquire(path: &Path) -> Result<LockFile> {
+        let mut lock_name = path.as_os_str().to_owned();
+        lock_name.push(".lock");
+        let lock_path = PathBuf::from(lock_name);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .map_err(|err| match err.kind() {
+                ErrorKind::AlreadyExists => anyhow!(
+                    "Unable to create '{}': File exists.\nAnother rustygit process seems to be running in this repository. If it crashed, remove the file manually to continue.",
+                    lock_path.display()
+                ),
+                _ => anyhow!("Unable to create '{}': {}", lock_path.display(), err),
+            })?;
+
+        Ok(LockFile {
+            path: path.to_path_buf(),
+            lock_path,
+            file,
+            committed: false,
+        })
+    }
+
+    /// Returns the path of the file this lock protects.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `content` to the lock file; nothing is visible until [`LockFile::commit`].
+    pub fn write(&mut self, content: &[u8]) -> Result<()> {
+        self.file.write_all(content)?;
+        Ok(())
+    }
+
+    /// Flushes the lock file to disk and renames it over the target.
+    pub fn commit(mut self) -> Result<()> {
+        self.file.sync_all()?;
+        fs::rename(&self.lock_path, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // An uncommitted lock means the update was abandoned or failed.
+        if !self.committed {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// Replaces `path` with `content` through a lock file.
+pub fn write_file_locked(path: &Path, content: &[u8]) -> Result<()> {
+    let mut lock = LockFile::acquire(path)?;
+    lock.write(content)?;
+    lock.commit()
+}
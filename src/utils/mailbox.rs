@@ -0,0 +1,244 @@
+//! Patches as mail messages in mbox files, written by `format-patch` and
+//! read back by `am`.
+//!
+//! Each message starts with a `From <commit> Mon Sep 17 00:00:00 2001`
+//! separator line, followed by `From:`, `Date:` and `Subject:` headers
+//! taken from the commit's author and message. The message body comes next,
+//! then a `---` line and the diff.
+
+use crate::utils::{self, Signature};
+use anyhow::{Result, anyhow, bail};
+
+/// The fixed date on the separator line, as written by Git.
+const MBOX_DATE: &str = "Mon Sep 17 00:00:00 2001";
+
+/// A commit read back from a mailbox.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MailPatch {
+    /// The name, email and date from the `From:` and `Date:` headers.
+    pub author: Signature,
+    /// The subject without its `[PATCH ...]` prefix.
+    pub subject: String,
+    /// The message body between the headers and the `---` line.
+    pub body: String,
+    /// Everything from the `---` line on: the diff.
+    pub diff: String,
+}
+
+impl MailPatch {
+    /// Returns the commit message: the subject, then the body if any.
+    pub fn message(&self) -> String {
+        let body = self.body.trim_matches('\n').trim_end();
+
+        if body.is_empty() {
+            self.subject.clone()
+        } else {
+            format!("{}\n\n{}", self.subject, body)
+        }
+    }
+}
+
+/// Returns true for the line that starts a message: `From <hash> ...`.
+fn is_separator(line: &str) -> bool {
+    line.strip_prefix("From ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .is_some_and(|hash| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Quotes a display name containing characters that are special in mail
+/// addresses.
+fn quote_name(name: &str) -> String {
+    if name.contains(|c: char| "()<>[]:;@\\,.\"".contains(c)) {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        name.to_string()
+    }
+}
+
+fn unquote_name(name: &str) -> String {
+    let name = name.trim();
+
+    match name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => name.to_string(),
+    }
+}
+
+/// Returns the subject a commit message is mailed with: its first
+/// paragraph on one line.
+pub fn mail_subject(message: &str) -> String {
+    let message = message.trim();
+    let paragraph = message
+        .split_once("\n\n")
+        .map_or(message, |(first, _)| first);
+    paragraph
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats one commit as a mail message.
+///
+/// The subject is the first paragraph of `message` on one line, prefixed
+/// with `[PATCH]`, or `[PATCH n/m]` when `total` commits are sent.
+pub fn format_mail(
+    commit_hash: &str,
+    author: &Signature,
+    message: &str,
+    number: usize,
+    total: usize,
+    diff: &str,
+) -> Result<String> {
+    let date = utils::format_rfc2822_date(author.timestamp, &author.timezone)
+        .ok_or_else(|| anyhow!("Invalid author date in commit {}", commit_hash))?;
+
+    let message = message.trim();
+    let subject = mail_subject(message);
+    let body = message.split_once("\n\n").map_or("", |(_, body)| body);
+    let prefix = if total == 1 {
+        String::from("[PATCH]")
+    } else {
+        let width = total.to_string().len();
+        format!("[PATCH {:0width$}/{}]", number, total, width = width)
+    };
+
+    let mut mail = format!("From {} {}\n", commit_hash, MBOX_DATE);
+    mail.push_str(&format!(
+        "From: {} <{}>\n",
+        quote_name(&author.name),
+        author.email
+    ));
+    mail.push_str(&format!("Date: {}\n", date));
+    mail.push_str(&format!("Subject: {} {}\n", prefix, subject));
+    if !(author.name.is_ascii() && message.is_ascii()) {
+        mail.push_str("MIME-Version: 1.0\n");
+        mail.push_str("Content-Type: text/plain; charset=UTF-8\n");
+        mail.push_str("Content-Transfer-Encoding: 8bit\n");
+    }
+    mail.push('\n');
+
+    let body = body.trim_start_matches('\n');
+    if !body.is_empty() {
+        mail.push_str(body);
+        mail.push('\n');
+    }
+    mail.push_str("---\n");
+    mail.push_str(diff);
+    mail.push_str(&format!("-- \nrustygit {}\n\n", env!("CARGO_PKG_VERSION")));
+
+    Ok(mail)
+}
+
+/// Removes `Re:` and `[...]` prefixes such as `[PATCH 2/3]` from a subject.
+fn clean_subject(subject: &str) -> String {
+    let mut subject = subject.trim();
+
+    loop {
+        if subject.starts_with('[')
+            && let Some(end) = subject.find(']')
+        {
+            subject = subject[end + 1..].trim_start();
+        } else if subject
+            .get(..3)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:"))
+        {
+            subject = subject[3..].trim_start();
+        } else {
+            return subject.to_string();
+        }
+    }
+}
+
+/// Parses one message (without its separator line).
+fn parse_message(lines: &[&str]) -> Result<MailPatch> {
+    let mut headers: Vec<String> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(line) = lines.get(pos) {
+        let line = line.trim_end_matches(['\n', '\r']);
+        pos += 1;
+
+        if line.is_empty() {
+            break;
+        }
+        match headers.last_mut() {
+            // Folded header lines continue the previous header.
+            Some(previous) if line.starts_with([' ', '\t']) => {
+                previous.push(' ');
+                previous.push_str(line.trim());
+            }
+            _ => headers.push(line.to_string()),
+        }
+    }
+
+    let header = |name: &str| {
+        headers.iter().find_map(|header| {
+            let (key, value) = header.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    };
+
+    let from = header("From").ok_or_else(|| anyhow!("Patch has no 'From:' header"))?;
+    let (name, email) = from
+        .split_once('<')
+        .and_then(|(name, rest)| Some((name, rest.split_once('>')?.0)))
+        .ok_or_else(|| anyhow!("Patch has an invalid 'From:' header: '{}'", from))?;
+    let date = header("Date").ok_or_else(|| anyhow!("Patch has no 'Date:' header"))?;
+    let (timestamp, timezone) = utils::parse_date(date)
+        .ok_or_else(|| anyhow!("Patch has an invalid 'Date:' header: '{}'", date))?;
+    let subject = header("Subject").unwrap_or_default();
+
+    let rest = &lines[pos.min(lines.len())..];
+    let diff_start = rest
+        .iter()
+        .position(|line| {
+            let line = line.trim_end_matches(['\n', '\r']);
+            line == "---" || line.starts_with("diff --git ") || line.starts_with("Index: ")
+        })
+        .unwrap_or(rest.len());
+
+    Ok(MailPatch {
+        author: Signature {
+            name: unquote_name(name),
+            email: email.trim().to_string(),
+            timestamp,
+            timezone,
+        },
+        subject: clean_subject(subject),
+        body: rest[..diff_start].concat(),
+        diff: rest[diff_start..].concat(),
+    })
+}
+
+/// Splits an mbox file into its messages and parses each one.
+///
+/// A file without separator lines is read as a single message.
+pub fn parse_mailbox(text: &str) -> Result<Vec<MailPatch>> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let starts: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| is_separator(line))
+        .map(|(index, _)| index)
+        .collect();
+
+    if starts.is_empty() {
+        if text.trim().is_empty() {
+            bail!("Mailbox holds no patches");
+        }
+        return Ok(vec![parse_message(&lines)?]);
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(number, &start)| {
+            let end = starts.get(number + 1).copied().unwrap_or(lines.len());
+            parse_message(&lines[start + 1..end])
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+//! State left behind by a merge, cherry-pick or revert that stopped on conflicts.
+//!
+//! `.rustygit/MERGE_HEAD` holds the commit being merged in,
+//! `.rustygit/CHERRY_PICK_HEAD` or `.rustygit/REVERT_HEAD` the commit being
+//! picked or reverted, and `.rustygit/MERGE_MSG` the message the concluding
+//! commit should use.
+
+use crate::utils::SequencerAction;
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn merge_head_path(root_path: &Path) -> PathBuf {
+    root_path.join(".rustygit").join("MERGE_HEAD")
+}
+
+fn merge_msg_path(root_path: &Path) -> PathBuf {
+    root_path.join(".rustygit").join("MERGE_MSG")
+}
+
+fn pick_head_path(root_path: &Path, action: SequencerAction) -> PathBuf {
+    root_path.join(".rustygit").join(action.head_file())
+}
+
+/// Returns the commit recorded in `MERGE_HEAD`, if a merge is in progress.
+pub fn read_merge_head(root_path: &Path) -> Result<Option<String>> {
+    let path = merge_head_path(root_path);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let commit_hash = fs::read_to_string(path)?.trim().to_string();
+    Ok((!commit_hash.is_empty()).then_some(commit_hash))
+}
+
+/// Returns the message saved in `MERGE_MSG`, if any.
+pub fn read_merge_msg(root_path: &Path) -> Result<Option<String>> {
+    let path = merge_msg_path(root_path);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(fs::read_to_string(path)?))
+}
+
+/// Records an in-progress merge of `their_commit`.
+pub fn write_merge_state(root_path: &Path, their_commit: &str, message: &str) -> Result<()> {
+    fs::write(merge_head_path(root_path), format!("{}\n", their_commit))?;
+    fs::write(merge_msg_path(root_path), message)?;
+    Ok(())
+}
+
+/// Returns the commit recorded in `CHERRY_PICK_HEAD` or `REVERT_HEAD`, if a
+/// cherry-pick or revert stopped on conflicts.
+pub fn read_pick_head(root_path: &Path) -> Result<Option<(SequencerAction, String)>> {
+    for action in [SequencerAction::Pick, SequencerAction::Revert] {
+        let path = pick_head_path(root_path, action);
+
+        if path.exists() {
+            let commit_hash = fs::read_to_string(path)?.trim().to_string();
+            return Ok(Some((action, commit_hash)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Records a stopped cherry-pick or revert of `commit_hash`.
+pub fn write_pick_state(
+    root_path: &Path,
+    action: SequencerAction,
+    commit_hash: &str,
+    message: &str,
+) -> Result<()> {
+    fs::write(
+        pick_head_path(root_path, action),
+        format!("{}\n", commit_hash),
+    )?;
+    fs::write(merge_msg_path(root_path), message)?;
+    Ok(())
+}
+
+/// Removes `MERGE_HEAD`, `CHERRY_PICK_HEAD`, `REVERT_HEAD` and `MERGE_MSG` if present.
+pub fn clear_merge_state(root_path: &Path) -> Result<()> {
+    for path in [
+        merge_head_path(root_path),
+        pick_head_path(root_path, SequencerAction::Pick),
+        pick_head_path(root_path, SequencerAction::Revert),
+        merge_msg_path(root_path),
+    ] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
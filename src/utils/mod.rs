@@ -1,20 +1,106 @@
+mod bisect_state;
+mod commit_graph;
+mod config;
 mod date_time;
+mod delta;
+mod editor;
+mod file_mode;
 mod filesystem;
 mod hashing;
+mod identity;
 mod ignore;
 mod index;
+mod lockfile;
+mod mailbox;
+mod merge_state;
+mod object_store;
+mod pack;
 mod parse;
+mod patch;
+mod pathspec;
+mod rebase_state;
+mod reflog;
+mod refs;
+mod revision;
 mod safety_checks;
+mod sequencer;
 mod status;
 mod string_format;
+mod text_merge;
+mod tree_diff;
+mod worktree;
 
+pub use bisect_state::BisectState;
+pub use config::ConfigScope;
+pub use identity::Signature;
 pub use ignore::IgnoreRule;
+pub use index::ConflictEntry;
+pub use index::IndexEntry;
+pub use mailbox::MailPatch;
+pub use patch::FilePatch;
+pub use patch::Hunk;
+pub use patch::HunkLine;
+pub use rebase_state::RebaseAction;
+pub use rebase_state::RebaseState;
+pub use rebase_state::RebaseStep;
+pub use reflog::ReflogEntry;
+pub use sequencer::Sequencer;
+pub use sequencer::SequencerAction;
+pub use tree_diff::ChangeKind;
+pub use tree_diff::ChangeSource;
+pub use tree_diff::RenameOptions;
+pub use tree_diff::TreeChange;
 
 // Re-exporting utility functions
 
+// Bisect State Utilities
+pub use bisect_state::append_bisect_log;
+pub use bisect_state::clear_bisect_state;
+pub use bisect_state::read_bisect_log;
+pub use bisect_state::read_bisect_state;
+pub use bisect_state::write_bisect_state;
+
+// Commit Graph Utilities
+pub use commit_graph::collect_ancestors;
+pub use commit_graph::get_commit_author;
+pub use commit_graph::get_commit_message;
+pub use commit_graph::get_commit_parents;
+pub use commit_graph::get_commit_timestamp;
+pub use commit_graph::get_commit_tree_hash;
+pub use commit_graph::get_parent_hashes;
+pub use commit_graph::is_ancestor;
+pub use commit_graph::merge_base;
+
+// Config Utilities
+pub use config::config_path;
+pub use config::get_config_value;
+pub use config::list_config;
+pub use config::set_config_value;
+pub use config::unset_config_value;
+
 // Date and Time Utilities
 pub use date_time::format_commit_date;
+pub use date_time::format_rfc2822_date;
 pub use date_time::get_time;
+pub use date_time::parse_date;
+
+// Delta Utilities
+pub use delta::apply_delta;
+pub use delta::create_delta;
+
+// Editor Utilities
+pub use editor::edit_message;
+pub use editor::run_editor;
+
+// File Mode Utilities
+pub use file_mode::MODE_EXECUTABLE;
+pub use file_mode::MODE_FILE;
+pub use file_mode::MODE_SYMLINK;
+pub use file_mode::MODE_TREE;
+pub use file_mode::parse_mode;
+pub use file_mode::read_work_file;
+pub use file_mode::work_file_mode;
+pub use file_mode::write_work_file;
 
 // Filesystem Utilities
 pub use filesystem::ensure_repo_exists;
@@ -24,25 +110,123 @@ pub use hashing::bytes_to_hex;
 pub use hashing::hash_bytes;
 pub use hashing::hex_to_bytes;
 
+// Identity Utilities
+pub use identity::author_signature;
+pub use identity::committer_signature;
+
 // Ignore Utilities
 pub use ignore::is_ignored;
 pub use ignore::parse_ignore_file;
 
+// Mailbox Utilities
+pub use mailbox::format_mail;
+pub use mailbox::mail_subject;
+pub use mailbox::parse_mailbox;
+
+// Merge State Utilities
+pub use merge_state::clear_merge_state;
+pub use merge_state::read_merge_head;
+pub use merge_state::read_merge_msg;
+pub use merge_state::read_pick_head;
+pub use merge_state::write_merge_state;
+pub use merge_state::write_pick_state;
+
+// Object Store Utilities
+pub use object_store::compress;
+pub use object_store::decompress;
+pub use object_store::find_objects_by_prefix;
+pub use object_store::is_compressed;
+pub use object_store::loose_object_path;
+pub use object_store::object_exists;
+pub use object_store::read_object;
+
+// Lock File Utilities
+pub use lockfile::LockFile;
+pub use lockfile::write_file_locked;
+
+// Pack Utilities
+pub use pack::PackIndex;
+pub use pack::PackObject;
+pub use pack::read_pack_index;
+pub use pack::read_pack_indexes;
+pub use pack::split_object;
+pub use pack::write_pack;
+
 // Parsing Utilities
+pub use parse::is_binary;
 pub use parse::parse_blob;
 pub use parse::parse_commit;
 pub use parse::parse_tree;
+pub use parse::read_blob;
+
+// Patch Utilities
+pub use patch::apply_hunks;
+pub use patch::parse_patch;
+
+// Pathspec Utilities
+pub use pathspec::Pathspec;
+pub use pathspec::parse_pathspecs;
+
+// Rebase State Utilities
+pub use rebase_state::clear_rebase_state;
+pub use rebase_state::format_rebase_todo;
+pub use rebase_state::parse_rebase_todo;
+pub use rebase_state::read_rebase_state;
+pub use rebase_state::rebase_dir;
+pub use rebase_state::write_rebase_state;
+
+// Reflog Utilities
+pub use reflog::NULL_HASH;
+pub use reflog::append_reflog;
+pub use reflog::delete_reflog;
+pub use reflog::previous_checkout;
+pub use reflog::read_reflog;
+pub use reflog::reflog_hashes;
+pub use reflog::write_reflog;
+
+// Reference Utilities
+pub use refs::RefTransaction;
+pub use refs::check_ref_name;
+pub use refs::current_branch;
+pub use refs::delete_ref;
+pub use refs::list_refs;
+pub use refs::lock_ref;
+pub use refs::read_ref;
+pub use refs::read_symbolic_head;
+pub use refs::ref_exists;
+pub use refs::rename_ref;
+pub use refs::set_head_to_branch;
+pub use refs::update_head;
+pub use refs::update_ref;
+
+// Revision Utilities
+pub use revision::resolve_commit;
+pub use revision::resolve_previous_checkout;
+pub use revision::resolve_revision;
+
+// Sequencer Utilities
+pub use sequencer::clear_sequencer;
+pub use sequencer::read_sequencer;
+pub use sequencer::write_sequencer;
 
 // Status Utilities
+pub use status::get_commit_entries_map;
 pub use status::get_current_commit_hash;
 pub use status::get_current_tree_hash;
+pub use status::get_tree_entries_map;
 pub use status::get_tree_files_map;
+pub use status::get_work_dir_entries;
 pub use status::get_work_dir_map;
+pub use status::read_effective_index;
 
 // Index Utilities
-pub use index::clear_index;
+pub use index::read_index_conflicts;
+pub use index::read_index_entries;
 pub use index::read_index_map;
 pub use index::stage_index_entry;
+pub use index::update_index_entries;
+pub use index::write_index_conflicts;
+pub use index::write_index_entries;
 pub use index::write_index_map;
 
 // String Formatting Utilities
@@ -50,3 +234,18 @@ pub use string_format::format_commit_history;
 
 // Safety Check Utilities
 pub use safety_checks::checkout_safety_check;
+pub use safety_checks::overwrite_safety_check;
+
+// Text Merge Utilities
+pub use text_merge::TextMergeResult;
+pub use text_merge::merge_text;
+
+// Tree Diff Utilities
+pub use tree_diff::DEFAULT_RENAME_THRESHOLD;
+pub use tree_diff::detect_renames;
+pub use tree_diff::diff_entry_maps;
+pub use tree_diff::matches_pathspecs;
+
+// Working Tree Utilities
+pub use worktree::remove_work_file;
+pub use worktree::update_work_tree;
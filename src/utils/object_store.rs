@@ -0,0 +1,120 @@
+//! Object store lookups and zlib (de)compression of loose objects.
+
+use crate::utils::pack;
+use anyhow::{Context, Result, bail};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Returns the loose object path `.rustygit/objects/<2>/<38>` for `hash`.
+pub fn loose_object_path(root_path: &Path, hash: &str) -> PathBuf {
+    root_path
+        .join(".rustygit")
+        .join("objects")
+        .join(&hash[..2])
+        .join(&hash[2..])
+}
+
+/// Returns true when raw on-disk bytes look like a zlib stream.
+///
+/// Legacy uncompressed objects always start with an ASCII type name
+/// (`blob`, `tree`, `commit`, `tag`), so they never collide with the zlib
+/// header byte `0x78`.
+pub fn is_compressed(raw: &[u8]) -> bool {
+    raw.len() >= 2 && raw[0] == 0x78 && (u16::from(raw[0]) << 8 | u16::from(raw[1])) % 31 == 0
+}
+
+/// Deflates object bytes the same way Git stores loose objects.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Inflates a zlib stream back into raw object bytes.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = ZlibDecoder::new(data);
+    let mut out = Vec::new();
+    decoder
+        .read_to_end(&mut out)
+        .context("Failed to inflate object data")?;
+    Ok(out)
+}
+
+fn is_full_hash(hash: &str) -> bool {
+    hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Returns true if an object with `hash` exists as a loose object or in a pack.
+pub fn object_exists(root_path: &Path, hash: &str) -> bool {
+    if !is_full_hash(hash) {
+        return false;
+    }
+
+    loose_object_path(root_path, hash).is_file()
+        || pack::packed_object_exists(root_path, hash).unwrap_or(false)
+}
+
+/// Reads an object (`<type> <size>\0<payload>`) from the object store.
+///
+/// Loose objects are looked up first: compressed ones are inflated
+/// transparently and objects written by older versions without compression
+/// are returned as-is. Otherwise every `objects/pack/*.idx` is searched and
+/// the object is resolved out of its packfile, including delta chains.
+pub fn read_object(root_path: &Path, hash: &str) -> Result<Vec<u8>> {
+    if !is_full_hash(hash) {
+        bail!("Invalid object hash '{}'.", hash);
+    }
+
+    let object_path = loose_object_path(root_path, hash);
+
+    if object_path.is_file() {
+        let raw = fs::read(&object_path)?;
+
+        return if is_compressed(&raw) {
+            decompress(&raw)
+        } else {
+            Ok(raw)
+        };
+    }
+
+    pack::read_object_from_packs(root_path, hash)?
+        .with_context(|| format!("Object '{}' not found.", hash))
+}
+
+/// Returns every stored object hash (loose or packed) starting with `prefix`.
+///
+/// `prefix` must be at least two hex digits; the result is sorted and deduplicated.
+pub fn find_objects_by_prefix(root_path: &Path, prefix: &str) -> Result<Vec<String>> {
+    let prefix = prefix.to_ascii_lowercase();
+
+    if prefix.len() < 2 || prefix.len() > 40 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid object hash prefix '{}'.", prefix);
+    }
+
+    let mut matches: Vec<String> = Vec::new();
+
+    let fanout_dir = root_path
+        .join(".rustygit")
+        .join("objects")
+        .join(&prefix[..2]);
+    if fanout_dir.is_dir() {
+        for entry in fs::read_dir(fanout_dir)? {
+            let file_name = entry?.file_name().to_string_lossy().to_string();
+            if file_name.len() == 38 && file_name.starts_with(&prefix[2..]) {
+                matches.push(format!("{}{}", &prefix[..2], file_name));
+            }
+        }
+    }
+
+    for pack_index in pack::read_pack_indexes(root_path)? {
+        matches.extend(pack_index.hashes_with_prefix(&prefix));
+    }
+
+    matches.sort();
+    matches.dedup();
+    Ok(matches)
+}
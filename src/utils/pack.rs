@@ -0,0 +1,566 @@
+//! Git packfiles (`objects/pack/*.pack`) and v2 pack indexes (`*.idx`): reading and writing.
+
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use flate2::bufread::ZlibDecoder;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+const IDX_MAGIC: [u8; 4] = [0xff, b't', b'O', b'c'];
+
+/// Longest delta chain resolved when reading before the pack is treated as
+/// corrupt (its bases must form a cycle).
+const MAX_READ_DELTA_DEPTH: usize = 4096;
+
+/// A parsed pack index with the size and modification time of the `.idx`
+/// file it was parsed from.
+type CachedPackIndex = (u64, SystemTime, Arc<PackIndex>);
+
+/// Pack indexes already parsed by this process, by `.idx` path.
+static PACK_INDEXES: LazyLock<Mutex<HashMap<PathBuf, CachedPackIndex>>> =
+    LazyLock::new(Default::default);
+
+// Packed object type codes.
+pub(crate) const OBJ_COMMIT: u8 = 1;
+pub(crate) const OBJ_TREE: u8 = 2;
+pub(crate) const OBJ_BLOB: u8 = 3;
+pub(crate) const OBJ_TAG: u8 = 4;
+pub(crate) const OBJ_OFS_DELTA: u8 = 6;
+pub(crate) const OBJ_REF_DELTA: u8 = 7;
+
+/// A parsed v2 pack index: sorted object hashes and their pack offsets.
+pub struct PackIndex {
+    pub pack_path: PathBuf,
+    /// `fanout[b]` is the number of hashes whose first byte is at most `b`.
+    pub fanout: [u32; 256],
+    pub hashes: Vec<[u8; 20]>,
+    pub offsets: Vec<u64>,
+}
+
+impl PackIndex {
+    /// Returns the positions of the hashes starting with `first_byte`.
+    fn fanout_range(&self, first_byte: u8) -> Range<usize> {
+        let end = self.fanout[first_byte as usize] as usize;
+        let start = match first_byte {
+            0 => 0,
+            byte => self.fanout[byte as usize - 1] as usize,
+        };
+        start..end
+    }
+
+    /// Returns the pack offset of `hash`, if the pack contains it.
+    pub fn find_offset(&self, hash: &[u8; 20]) -> Option<u64> {
+        let range = self.fanout_range(hash[0]);
+        self.hashes[range.clone()]
+            .binary_search(hash)
+            .ok()
+            .map(|position| self.offsets[range.start + position])
+    }
+
+    /// Returns the hex hashes in the pack that start with the hex `prefix`
+    /// (at least two characters).
+    pub fn hashes_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let Some(first_byte) = prefix
+            .get(..2)
+            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        else {
+            return Vec::new();
+        };
+
+        self.hashes[self.fanout_range(first_byte)]
+            .iter()
+            .map(|hash| utils::bytes_to_hex(hash))
+            .filter(|hash| hash.starts_with(prefix))
+            .collect()
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32> {
+    let slice = bytes
+        .get(at..at + 4)
+        .ok_or_else(|| anyhow!("Pack index is truncated"))?;
+    Ok(u32::from_be_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+/// Parses a version 2 `.idx` file.
+pub fn read_pack_index(idx_path: &Path) -> Result<PackIndex> {
+    let bytes = fs::read(idx_path)?;
+
+    if bytes.len() < 8 || bytes[..4] != IDX_MAGIC {
+        bail!("Unsupported pack index format: {}", idx_path.display());
+    }
+    if read_u32(&bytes, 4)? != 2 {
+        bail!("Unsupported pack index version: {}", idx_path.display());
+    }
+
+    let fanout_start = 8;
+    let mut fanout = [0u32; 256];
+    for (byte, entry) in fanout.iter_mut().enumerate() {
+        *entry = read_u32(&bytes, fanout_start + byte * 4)?;
+    }
+    if fanout.windows(2).any(|pair| pair[0] > pair[1]) {
+        bail!("Pack index fanout is corrupt: {}", idx_path.display());
+    }
+    let count = fanout[255] as usize;
+
+    let hashes_start = fanout_start + 256 * 4;
+    let crc_start = hashes_start + count * 20;
+    let offsets_start = crc_start + count * 4;
+    let large_offsets_start = offsets_start + count * 4;
+
+    if bytes.len() < large_offsets_start + 40 {
+        bail!("Pack index is truncated: {}", idx_path.display());
+    }
+
+    let mut hashes = Vec::with_capacity(count);
+    for i in 0..count {
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&bytes[hashes_start + i * 20..hashes_start + (i + 1) * 20]);
+        hashes.push(hash);
+    }
+
+    let mut offsets = Vec::with_capacity(count);
+    for i in 0..count {
+        let offset = read_u32(&bytes, offsets_start + i * 4)?;
+
+        // MSB set means the remaining bits index into the 8-byte offset table.
+        if offset & 0x8000_0000 != 0 {
+            let large_index = (offset & 0x7fff_ffff) as usize;
+            let at = large_offsets_start + large_index * 8;
+            let high = read_u32(&bytes, at)? as u64;
+            let low = read_u32(&bytes, at + 4)? as u64;
+            offsets.push((high << 32) | low);
+        } else {
+            offsets.push(offset as u64);
+        }
+    }
+
+    Ok(PackIndex {
+        pack_path: idx_path.with_extension("pack"),
+        fanout,
+        hashes,
+        offsets,
+    })
+}
+
+/// Returns the parsed index at `idx_path`, parsing it only if this process
+/// has not already done so since the file last changed.
+fn cached_pack_index(idx_path: &Path) -> Result<Arc<PackIndex>> {
+    let metadata = fs::metadata(idx_path)?;
+    let (len, modified) = (metadata.len(), metadata.modified()?);
+    let mut cache = PACK_INDEXES.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some((cached_len, cached_modified, pack_index)) = cache.get(idx_path)
+        && (*cached_len, *cached_modified) == (len, modified)
+    {
+        return Ok(Arc::clone(pack_index));
+    }
+
+    let pack_index = Arc::new(read_pack_index(idx_path)?);
+    cache.insert(
+        idx_path.to_path_buf(),
+        (len, modified, Arc::clone(&pack_index)),
+    );
+    Ok(pack_index)
+}
+
+/// Loads every `objects/pack/*.idx` in the repository.
+///
+/// Each index is parsed once per process and then served from a cache.
+pub fn read_pack_indexes(root_path: &Path) -> Result<Vec<Arc<PackIndex>>> {
+    let pack_dir = root_path.join(".rustygit").join("objects").join("pack");
+
+    if !pack_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut idx_paths: Vec<PathBuf> = fs::read_dir(&pack_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "idx"))
+        .collect();
+    idx_paths.sort();
+
+    idx_paths
+        .iter()
+        .map(|idx_path| cached_pack_index(idx_path))
+        .collect()
+}
+
+/// Returns the object type name for a non-delta packed type code.
+pub(crate) fn type_name(type_code: u8) -> Result<&'static str> {
+    match type_code {
+        OBJ_COMMIT => Ok("commit"),
+        OBJ_TREE => Ok("tree"),
+        OBJ_BLOB => Ok("blob"),
+        OBJ_TAG => Ok("tag"),
+        other => Err(anyhow!("Unsupported packed object type: {other}")),
+    }
+}
+
+fn read_byte(reader: &mut impl Read) -> Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Reads a packed object header: 3-bit type plus a little-endian base-128 size.
+fn read_entry_header(reader: &mut impl Read) -> Result<(u8, u64)> {
+    let mut byte = read_byte(reader)?;
+    let type_code = (byte >> 4) & 0x07;
+    let mut size = (byte & 0x0f) as u64;
+    let mut shift = 4;
+
+    while byte & 0x80 != 0 {
+        byte = read_byte(reader)?;
+        let bits = (byte & 0x7f) as u64;
+        if shift >= u64::BITS || bits > u64::MAX >> shift {
+            bail!("Corrupt pack: object size header is too long");
+        }
+        size |= bits << shift;
+        shift += 7;
+    }
+
+    Ok((type_code, size))
+}
+
+/// Reads the OFS_DELTA base distance (big-endian base-128 with an add-one bias).
+fn read_ofs_delta_distance(reader: &mut impl Read) -> Result<u64> {
+    let mut byte = read_byte(reader)?;
+    let mut distance = (byte & 0x7f) as u64;
+
+    while byte & 0x80 != 0 {
+        if distance >= u64::MAX >> 7 {
+            bail!("Corrupt pack: OFS_DELTA distance is too long");
+        }
+        byte = read_byte(reader)?;
+        distance = ((distance + 1) << 7) | (byte & 0x7f) as u64;
+    }
+
+    Ok(distance)
+}
+
+fn inflate_entry(reader: &mut impl BufRead, size: u64) -> Result<Vec<u8>> {
+    // The header size is untrusted: read at most one byte past it rather
+    // than reserving it up front.
+    let mut data = Vec::new();
+    ZlibDecoder::new(reader)
+        .take(size.saturating_add(1))
+        .read_to_end(&mut data)?;
+
+    if data.len() as u64 != size {
+        bail!("Packed object size mismatch");
+    }
+
+    Ok(data)
+}
+
+/// Reads and fully resolves the object at `offset` in `pack_path`.
+///
+/// Returns the object type name and its payload (without the `<type> <size>\0` header).
+/// Delta chains are followed iteratively; a chain longer than
+/// `MAX_READ_DELTA_DEPTH` (which a corrupt pack with cyclic bases produces)
+/// is an error.
+pub fn read_packed_object(
+    root_path: &Path,
+    pack_path: &Path,
+    offset: u64,
+) -> Result<(String, Vec<u8>)> {
+    let mut pack_path = pack_path.to_path_buf();
+    let mut offset = offset;
+    let mut deltas = Vec::new();
+
+    let (base_type, mut data) = loop {
+        if deltas.len() > MAX_READ_DELTA_DEPTH {
+            bail!(
+                "Corrupt pack: delta chain is longer than {}",
+                MAX_READ_DELTA_DEPTH
+            );
+        }
+
+        let mut reader = BufReader::new(File::open(&pack_path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let (type_code, size) = read_entry_header(&mut reader)?;
+
+        match type_code {
+            OBJ_OFS_DELTA => {
+                let distance = read_ofs_delta_distance(&mut reader)?;
+                if distance == 0 {
+                    bail!("Corrupt pack: OFS_DELTA entry is its own base");
+                }
+                let base_offset = offset
+                    .checked_sub(distance)
+                    .ok_or_else(|| anyhow!("OFS_DELTA base offset is out of range"))?;
+                deltas.push(inflate_entry(&mut reader, size)?);
+                offset = base_offset;
+            }
+            OBJ_REF_DELTA => {
+                let mut base_hash = [0u8; 20];
+                reader.read_exact(&mut base_hash)?;
+                deltas.push(inflate_entry(&mut reader, size)?);
+
+                // The base may live in this pack, another pack, or as a loose object.
+                match find_packed_entry(root_path, &base_hash)? {
+                    Some((base_pack, base_offset)) => {
+                        pack_path = base_pack;
+                        offset = base_offset;
+                    }
+                    None => {
+                        let base_hash = utils::bytes_to_hex(&base_hash);
+                        break split_object(&utils::read_object(root_path, &base_hash)?)?;
+                    }
+                }
+            }
+            other => {
+                let type_name = type_name(other)?;
+                break (type_name.to_string(), inflate_entry(&mut reader, size)?);
+            }
+        }
+    };
+
+    for delta in deltas.iter().rev() {
+        data = utils::apply_delta(&data, delta)?;
+    }
+    Ok((base_type, data))
+}
+
+/// Returns the pack and offset of `hash` in the first pack that lists it.
+fn find_packed_entry(root_path: &Path, hash: &[u8; 20]) -> Result<Option<(PathBuf, u64)>> {
+    for pack_index in read_pack_indexes(root_path)? {
+        if let Some(offset) = pack_index.find_offset(hash) {
+            return Ok(Some((pack_index.pack_path.clone(), offset)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Splits raw object bytes (`<type> <size>\0<payload>`) into type name and payload.
+pub fn split_object(object: &[u8]) -> Result<(String, Vec<u8>)> {
+    let nul_idx = object
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| anyhow!("Object missing NUL separator"))?;
+
+    let header = std::str::from_utf8(&object[..nul_idx])
+        .map_err(|e| anyhow!("Object header is not valid UTF-8: {e}"))?;
+    let (object_type, _) = header
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("Malformed object header: {header}"))?;
+
+    Ok((object_type.to_string(), object[nul_idx + 1..].to_vec()))
+}
+
+/// Looks up `hash` across all packs, returning the raw object bytes if found.
+pub fn read_object_from_packs(root_path: &Path, hash: &str) -> Result<Option<Vec<u8>>> {
+    let Some((pack_path, offset)) = find_packed_entry(root_path, &utils::hex_to_bytes(hash))?
+    else {
+        return Ok(None);
+    };
+
+    let (object_type, payload) = read_packed_object(root_path, &pack_path, offset)?;
+    let mut object = format!("{} {}\0", object_type, payload.len()).into_bytes();
+    object.extend_from_slice(&payload);
+    Ok(Some(object))
+}
+
+/// Returns true if any pack index lists `hash`.
+pub fn packed_object_exists(root_path: &Path, hash: &str) -> Result<bool> {
+    let hash_bytes = utils::hex_to_bytes(hash);
+
+    for pack_index in read_pack_indexes(root_path)? {
+        if pack_index.find_offset(&hash_bytes).is_some() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Number of preceding same-type objects tried as delta bases.
+const DELTA_WINDOW: usize = 10;
+/// Longest delta chain written into a pack.
+const MAX_DELTA_DEPTH: usize = 50;
+
+/// An object queued for packing.
+pub struct PackObject {
+    pub hash: String,
+    pub object_type: String,
+    pub data: Vec<u8>,
+    /// Path the object was reached through; similar names make good delta pairs.
+    pub name_hint: String,
+}
+
+fn type_code(object_type: &str) -> Result<u8> {
+    match object_type {
+        "commit" => Ok(OBJ_COMMIT),
+        "tree" => Ok(OBJ_TREE),
+        "blob" => Ok(OBJ_BLOB),
+        "tag" => Ok(OBJ_TAG),
+        other => Err(anyhow!("Cannot pack object of type '{other}'")),
+    }
+}
+
+fn write_entry_header(out: &mut Vec<u8>, type_code: u8, size: usize) {
+    let mut size = size;
+    let mut byte = (type_code << 4) | (size & 0x0f) as u8;
+    size >>= 4;
+
+    while size > 0 {
+        out.push(byte | 0x80);
+        byte = (size & 0x7f) as u8;
+        size >>= 7;
+    }
+    out.push(byte);
+}
+
+fn write_ofs_delta_distance(out: &mut Vec<u8>, distance: u64) {
+    let mut distance = distance;
+    let mut bytes = vec![(distance & 0x7f) as u8];
+    distance >>= 7;
+
+    while distance > 0 {
+        distance -= 1;
+        bytes.push(0x80 | (distance & 0x7f) as u8);
+        distance >>= 7;
+    }
+
+    bytes.reverse();
+    out.extend_from_slice(&bytes);
+}
+
+fn build_pack_index(entries: &mut [([u8; 20], u32, u64)], pack_checksum: &[u8; 20]) -> Vec<u8> {
+    entries.sort_by_key(|entry| entry.0);
+
+    let mut idx = IDX_MAGIC.to_vec();
+    idx.extend_from_slice(&2u32.to_be_bytes());
+
+    let mut fanout = [0u32; 256];
+    for (hash, _, _) in entries.iter() {
+        fanout[hash[0] as usize] += 1;
+    }
+    let mut running = 0;
+    for count in fanout {
+        running += count;
+        idx.extend_from_slice(&running.to_be_bytes());
+    }
+
+    for (hash, _, _) in entries.iter() {
+        idx.extend_from_slice(hash);
+    }
+    for (_, crc, _) in entries.iter() {
+        idx.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    let mut large_offsets: Vec<u64> = Vec::new();
+    for (_, _, offset) in entries.iter() {
+        if *offset < 0x8000_0000 {
+            idx.extend_from_slice(&(*offset as u32).to_be_bytes());
+        } else {
+            idx.extend_from_slice(&(0x8000_0000 | large_offsets.len() as u32).to_be_bytes());
+            large_offsets.push(*offset);
+        }
+    }
+    for offset in large_offsets {
+        idx.extend_from_slice(&offset.to_be_bytes());
+    }
+
+    idx.extend_from_slice(pack_checksum);
+    let idx_checksum = utils::hex_to_bytes(&utils::hash_bytes(&idx));
+    idx.extend_from_slice(&idx_checksum);
+    idx
+}
+
+/// Writes `objects` into a new delta-compressed `pack-<checksum>.pack` with a v2 `.idx`.
+///
+/// Objects are ordered by type, name and descending size so that each
+/// object can be stored as an OFS_DELTA against one of the preceding objects
+/// in a small window. Returns the pack path and the number of deltified objects.
+pub fn write_pack(root_path: &Path, mut objects: Vec<PackObject>) -> Result<(PathBuf, usize)> {
+    objects.sort_by(|a, b| {
+        a.object_type
+            .cmp(&b.object_type)
+            .then_with(|| a.name_hint.cmp(&b.name_hint))
+            .then_with(|| b.data.len().cmp(&a.data.len()))
+    });
+
+    let mut pack = b"PACK".to_vec();
+    pack.extend_from_slice(&2u32.to_be_bytes());
+    pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+    let mut index_entries: Vec<([u8; 20], u32, u64)> = Vec::with_capacity(objects.len());
+    // (offset, delta depth) for each object written so far, parallel to `objects`.
+    let mut written: Vec<(u64, usize)> = Vec::with_capacity(objects.len());
+    let mut delta_count = 0;
+
+    for (i, object) in objects.iter().enumerate() {
+        let offset = pack.len() as u64;
+
+        let mut best: Option<(usize, Vec<u8>)> = None;
+        for j in (i.saturating_sub(DELTA_WINDOW)..i).rev() {
+            let base = &objects[j];
+            if base.object_type != object.object_type || written[j].1 >= MAX_DELTA_DEPTH {
+                continue;
+            }
+
+            let delta = utils::create_delta(&base.data, &object.data);
+            let best_len = best.as_ref().map_or(object.data.len() / 2, |(_, d)| d.len());
+            if delta.len() < best_len {
+                best = Some((j, delta));
+            }
+        }
+
+        let mut entry = Vec::new();
+        let depth = match best {
+            Some((base_index, delta)) => {
+                write_entry_header(&mut entry, OBJ_OFS_DELTA, delta.len());
+                write_ofs_delta_distance(&mut entry, offset - written[base_index].0);
+                entry.extend(utils::compress(&delta)?);
+                delta_count += 1;
+                written[base_index].1 + 1
+            }
+            None => {
+                write_entry_header(&mut entry, type_code(&object.object_type)?, object.data.len());
+                entry.extend(utils::compress(&object.data)?);
+                0
+            }
+        };
+
+        index_entries.push((
+            utils::hex_to_bytes(&object.hash),
+            crc32fast::hash(&entry),
+            offset,
+        ));
+        written.push((offset, depth));
+        pack.extend(entry);
+    }
+
+    let pack_checksum = utils::hex_to_bytes(&utils::hash_bytes(&pack));
+    pack.extend_from_slice(&pack_checksum);
+    let idx = build_pack_index(&mut index_entries, &pack_checksum);
+
+    let pack_dir = root_path.join(".rustygit").join("objects").join("pack");
+    fs::create_dir_all(&pack_dir)?;
+
+    let pack_name = format!("pack-{}", utils::bytes_to_hex(&pack_checksum));
+    let pack_path = pack_dir.join(format!("{}.pack", pack_name));
+    let idx_path = pack_dir.join(format!("{}.idx", pack_name));
+
+    // The pack must be in place before its index makes it visible to readers,
+    // and the `.tmp` suffix keeps both out of `read_pack_indexes` until then.
+    let tmp_pack = pack_dir.join(format!("{}.pack.tmp", pack_name));
+    let tmp_idx = pack_dir.join(format!("{}.idx.tmp", pack_name));
+    fs::write(&tmp_pack, pack)?;
+    fs::rename(&tmp_pack, &pack_path)?;
+    fs::write(&tmp_idx, idx)?;
+    fs::rename(&tmp_idx, &idx_path)?;
+
+    Ok((pack_path, delta_count))
+}
@@ -1,37 +1,46 @@
 //! Parsers for blob, tree, and commit objects.
 
 use crate::commands::TreeEntry;
+use crate::utils;
 use anyhow::{Result, anyhow};
-use std::fs;
 use std::path::Path;
 
-/// Reads and parses a blob object into file content string.
-pub fn parse_blob(root_path: &Path, blob_hash: &str) -> Result<String> {
-    let blob_path = root_path
-        .join(".rustygit/objects")
-        .join(&blob_hash[..2])
-        .join(&blob_hash[2..]);
+/// Number of leading bytes inspected when deciding whether content is binary.
+const BINARY_SNIFF_LEN: usize = 8000;
 
-    let blob_content = fs::read(&blob_path)?;
-    let blob_str = String::from_utf8(blob_content)?;
+/// Returns true if `content` looks binary, using Git's heuristic of a NUL
+/// byte within the first 8000 bytes.
+pub fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(BINARY_SNIFF_LEN).any(|&b| b == 0)
+}
 
-    let nul_idx = blob_str
-        .find('\0')
+/// Reads a blob object and returns its raw file bytes.
+///
+/// This is the API for materializing file content; it never assumes the
+/// content is text.
+pub fn read_blob(root_path: &Path, blob_hash: &str) -> Result<Vec<u8>> {
+    let object = utils::read_object(root_path, blob_hash)?;
+
+    let nul_idx = object
+        .iter()
+        .position(|&b| b == 0)
         .ok_or_else(|| anyhow!("Blob object missing NUL separator"))?;
 
-    let blob_content = blob_str[nul_idx + 1..].to_string();
+    if !object.starts_with(b"blob ") {
+        return Err(anyhow!("Error parsing blob object.\nHash: {}", blob_hash));
+    }
+
+    Ok(object[nul_idx + 1..].to_vec())
+}
 
-    Ok(blob_content)
+/// Reads a blob object as UTF-8 text, failing if the content is not valid UTF-8.
+pub fn parse_blob(root_path: &Path, blob_hash: &str) -> Result<String> {
+    Ok(String::from_utf8(read_blob(root_path, blob_hash)?)?)
 }
 
 /// Reads and parses a commit object payload (without object header).
 pub fn parse_commit(root_path: &Path, commit_hash: &str) -> Result<String> {
-    let commit_hash_path = root_path
-        .join(".rustygit/objects")
-        .join(&commit_hash[..2])
-        .join(&commit_hash[2..]);
-
-    let commit_content = fs::read(&commit_hash_path)?;
+    let commit_content = utils::read_object(root_path, commit_hash)?;
     let commit_str = String::from_utf8(commit_content)?;
 
     let nul_idx = commit_str
@@ -54,14 +63,9 @@ pub fn parse_commit(root_path: &Path, commit_hash: &str) -> Result<String> {
 
 /// Parses raw tree object bytes into structured tree entries.
 pub fn parse_tree(root_path: &Path, tree_hash: &str) -> Result<Vec<TreeEntry>> {
-    let tree_path = root_path
-        .join(".rustygit/objects")
-        .join(&tree_hash[..2])
-        .join(&tree_hash[2..]);
-
     // NOTE: Tree objects are not valid UTF-8 because they embed 20 raw hash bytes per entry.
     // So parse as bytes, and only decode the textual portions (mode + name) as UTF-8.
-    let tree_bytes = fs::read(&tree_path)?;
+    let tree_bytes = utils::read_object(root_path, tree_hash)?;
 
     let nul_idx = tree_bytes
         .iter()
@@ -99,11 +103,8 @@ pub fn parse_tree(root_path: &Path, tree_hash: &str) -> Result<Vec<TreeEntry>> {
             .split_once(' ')
             .ok_or_else(|| anyhow!("Malformed tree entry header (expected \"<mode> <name>\")"))?;
 
-        let mode: &'static str = match mode {
-            "100644" => "100644",
-            "40000" => "40000",
-            other => return Err(anyhow!("Unsupported tree entry mode: {other}")),
-        };
+        let mode = utils::parse_mode(mode)
+            .map_err(|_| anyhow!("Unsupported tree entry mode: {mode}"))?;
 
         // Move past "{mode} {name}\0"
         rest = &rest[entry_nul + 1..];
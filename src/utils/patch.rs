@@ -0,0 +1,389 @@
+//! Parsing and applying unified diffs, plain (`diff -u`) or Git-style.
+//!
+//! A patch is split into one [`FilePatch`] per file. Git extended headers
+//! (`new file mode`, `deleted file mode`, `old mode`/`new mode`,
+//! `rename from`/`rename to`, `copy from`/`copy to`) are understood; other text between files,
+//! such as mail headers or a commit message, is skipped. Paths lose their
+//! first component (`a/`, `b/`), like `patch -p1`.
+
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// One line of a hunk, with its line terminator unless the patch marks it
+/// `\ No newline at end of file`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(Vec<u8>),
+    Delete(Vec<u8>),
+    Insert(Vec<u8>),
+}
+
+/// An `@@ -old_start,old_count +new_start,new_count @@` block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Returns the lines the hunk expects to find (context and deletions).
+    fn old_lines(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Delete(text) => Some(text.as_slice()),
+                HunkLine::Insert(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the lines the hunk leaves behind (context and insertions).
+    fn new_lines(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Insert(text) => Some(text.as_slice()),
+                HunkLine::Delete(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// The changes a patch makes to one file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// The path before the change; `None` when the file is created.
+    pub old_path: Option<PathBuf>,
+    /// The path after the change; `None` when the file is deleted.
+    pub new_path: Option<PathBuf>,
+    pub old_mode: Option<&'static str>,
+    pub new_mode: Option<&'static str>,
+    pub hunks: Vec<Hunk>,
+    /// Set for `Binary files ... differ` and `GIT binary patch` sections.
+    pub binary: bool,
+    /// Set for `copy from`/`copy to` sections: the old path is kept.
+    pub copy: bool,
+}
+
+impl FilePatch {
+    /// Returns the patch that undoes this one.
+    pub fn reversed(&self) -> FilePatch {
+        let hunks = self
+            .hunks
+            .iter()
+            .map(|hunk| Hunk {
+                old_start: hunk.new_start,
+                old_count: hunk.new_count,
+                new_start: hunk.old_start,
+                new_count: hunk.old_count,
+                lines: hunk
+                    .lines
+                    .iter()
+                    .map(|line| match line {
+                        HunkLine::Context(text) => HunkLine::Context(text.clone()),
+                        HunkLine::Delete(text) => HunkLine::Insert(text.clone()),
+                        HunkLine::Insert(text) => HunkLine::Delete(text.clone()),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        FilePatch {
+            old_path: self.new_path.clone(),
+            new_path: self.old_path.clone(),
+            old_mode: self.new_mode,
+            new_mode: self.old_mode,
+            hunks,
+            binary: self.binary,
+            copy: self.copy,
+        }
+    }
+
+    /// Returns the path the patch is reported under: the new path, or the
+    /// old one for deletions.
+    pub fn path(&self) -> &Path {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or(Path::new(""))
+    }
+}
+
+fn text(line: &[u8]) -> String {
+    String::from_utf8_lossy(line)
+        .trim_end_matches(['\n', '\r'])
+        .to_string()
+}
+
+/// Turns a path from a patch header into a repository path: drops a
+/// trailing tab-separated timestamp and the first component (if there is
+/// more than one), and maps `/dev/null` to `None`.
+fn patch_path(raw: &str) -> Result<Option<PathBuf>> {
+    let raw = raw.split('\t').next().unwrap_or_default().trim_end();
+
+    if raw == "/dev/null" {
+        return Ok(None);
+    }
+
+    let stripped = raw.split_once('/').map_or(raw, |(_, rest)| rest);
+    repo_path(stripped).map(Some)
+}
+
+/// Validates a path named by a patch: it must be relative, stay inside the
+/// working tree and not touch `.rustygit`.
+fn repo_path(raw: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(raw);
+    let is_safe = !raw.is_empty()
+        && path.components().all(|component| match component {
+            Component::Normal(name) => name != ".rustygit",
+            _ => false,
+        });
+
+    if !is_safe {
+        bail!("Patch touches an invalid path: '{}'", raw);
+    }
+    Ok(path)
+}
+
+/// Parses the `a/<path> b/<path>` part of a `diff --git` line.
+fn git_header_paths(rest: &str) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    let Some((old, new)) = rest.split_once(" b/") else {
+        bail!("Malformed patch header: 'diff --git {}'", rest);
+    };
+    let old = old.strip_prefix("a/").unwrap_or(old);
+    Ok((Some(repo_path(old)?), Some(repo_path(new)?)))
+}
+
+/// Parses `<start>[,<count>]`; the count defaults to 1.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Result<Hunk> {
+    let malformed = || anyhow!("Malformed hunk header: '{}'", line);
+    let mut fields = line
+        .strip_prefix("@@ ")
+        .ok_or_else(malformed)?
+        .split_whitespace();
+    let old = fields.next().and_then(|field| field.strip_prefix('-'));
+    let new = fields.next().and_then(|field| field.strip_prefix('+'));
+    let (old_start, old_count) = old.and_then(parse_range).ok_or_else(malformed)?;
+    let (new_start, new_count) = new.and_then(parse_range).ok_or_else(malformed)?;
+
+    Ok(Hunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        lines: Vec::new(),
+    })
+}
+
+/// Reads the hunk starting at `lines[*pos]`, leaving `*pos` after it.
+fn parse_hunk(lines: &[&[u8]], pos: &mut usize) -> Result<Hunk> {
+    let header = text(lines[*pos]);
+    let mut hunk = parse_hunk_header(&header)?;
+    *pos += 1;
+
+    let (mut old_seen, mut new_seen) = (0, 0);
+    while old_seen < hunk.old_count || new_seen < hunk.new_count {
+        let Some(line) = lines.get(*pos) else {
+            bail!("Patch ends in the middle of hunk '{}'", header);
+        };
+        *pos += 1;
+
+        let (marker, body) = line.split_first().unwrap_or((&b'\n', &[]));
+        let parsed = match marker {
+            b' ' => HunkLine::Context(body.to_vec()),
+            // Some tools strip the space from empty context lines.
+            b'\n' => HunkLine::Context(line.to_vec()),
+            b'\r' if body == b"\n" => HunkLine::Context(line.to_vec()),
+            b'-' => HunkLine::Delete(body.to_vec()),
+            b'+' => HunkLine::Insert(body.to_vec()),
+            b'\\' => {
+                strip_newline(&mut hunk);
+                continue;
+            }
+            _ => bail!("Unexpected line in hunk '{}': '{}'", header, text(line)),
+        };
+
+        match parsed {
+            HunkLine::Context(_) => {
+                old_seen += 1;
+                new_seen += 1;
+            }
+            HunkLine::Delete(_) => old_seen += 1,
+            HunkLine::Insert(_) => new_seen += 1,
+        }
+        if old_seen > hunk.old_count || new_seen > hunk.new_count {
+            bail!("Hunk '{}' has more lines than its header says", header);
+        }
+        hunk.lines.push(parsed);
+    }
+
+    if lines.get(*pos).is_some_and(|line| line.starts_with(b"\\")) {
+        strip_newline(&mut hunk);
+        *pos += 1;
+    }
+    Ok(hunk)
+}
+
+/// Applies a `\ No newline at end of file` marker to the last hunk line.
+fn strip_newline(hunk: &mut Hunk) {
+    if let Some(HunkLine::Context(text) | HunkLine::Delete(text) | HunkLine::Insert(text)) =
+        hunk.lines.last_mut()
+        && text.last() == Some(&b'\n')
+    {
+        text.pop();
+    }
+}
+
+/// Parses the extended header lines of a `diff --git` section into `patch`,
+/// leaving `*pos` at the first line that is not one.
+fn parse_git_header(lines: &[&[u8]], pos: &mut usize, patch: &mut FilePatch) -> Result<()> {
+    while let Some(line) = lines.get(*pos) {
+        let line = text(line);
+
+        if let Some(mode) = line.strip_prefix("new file mode ") {
+            patch.old_path = None;
+            patch.new_mode = Some(utils::parse_mode(mode.trim())?);
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            patch.new_path = None;
+            patch.old_mode = Some(utils::parse_mode(mode.trim())?);
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            patch.old_mode = Some(utils::parse_mode(mode.trim())?);
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            patch.new_mode = Some(utils::parse_mode(mode.trim())?);
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            patch.old_path = Some(repo_path(path)?);
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            patch.new_path = Some(repo_path(path)?);
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            patch.old_path = Some(repo_path(path)?);
+            patch.copy = true;
+        } else if let Some(path) = line.strip_prefix("copy to ") {
+            patch.new_path = Some(repo_path(path)?);
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            patch.binary = true;
+        } else if !(line.starts_with("index ")
+            || line.starts_with("similarity index ")
+            || line.starts_with("dissimilarity index ")
+            || (patch.binary && !line.starts_with("diff ")))
+        {
+            break;
+        }
+        *pos += 1;
+    }
+    Ok(())
+}
+
+/// Parses every file section of `patch`.
+///
+/// Fails on malformed hunks and on paths that are absolute, contain `..`
+/// or point into `.rustygit`.
+pub fn parse_patch(patch: &[u8]) -> Result<Vec<FilePatch>> {
+    let lines: Vec<&[u8]> = patch.split_inclusive(|&byte| byte == b'\n').collect();
+    let mut patches = Vec::new();
+    let mut pos = 0;
+
+    while pos < lines.len() {
+        let line = text(lines[pos]);
+        let mut file_patch = FilePatch::default();
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            // The header and its extended lines name the paths; the
+            // ---/+++ lines that may follow repeat them.
+            (file_patch.old_path, file_patch.new_path) = git_header_paths(rest)?;
+            pos += 1;
+            parse_git_header(&lines, &mut pos, &mut file_patch)?;
+            if lines.get(pos).is_some_and(|line| line.starts_with(b"--- ")) {
+                pos += 2;
+            }
+        } else if line.starts_with("--- ")
+            && let Some(next) = lines.get(pos + 1).map(|next| text(next))
+            && let Some(new) = next.strip_prefix("+++ ")
+        {
+            // Like Git, name a changed file after its new path: the old one
+            // is often a backup such as `file.orig`.
+            file_patch.old_path = patch_path(&line[4..])?;
+            file_patch.new_path = patch_path(new)?;
+            if file_patch.old_path.is_some() && file_patch.new_path.is_some() {
+                file_patch.old_path = file_patch.new_path.clone();
+            }
+            pos += 2;
+        } else {
+            pos += 1;
+            continue;
+        }
+
+        while lines.get(pos).is_some_and(|line| line.starts_with(b"@@ ")) {
+            file_patch.hunks.push(parse_hunk(&lines, &mut pos)?);
+        }
+
+        if file_patch.old_path.is_none() && file_patch.new_path.is_none() {
+            bail!("Patch section names no file");
+        }
+        patches.push(file_patch);
+    }
+
+    Ok(patches)
+}
+
+/// Finds where `expected` occurs in `lines`, searching outwards from
+/// `wanted` but never before `min`.
+fn find_hunk(lines: &[&[u8]], expected: &[&[u8]], wanted: usize, min: usize) -> Option<usize> {
+    let max = lines.len().checked_sub(expected.len())?;
+    let matches_at =
+        |at: usize| at >= min && at <= max && lines[at..at + expected.len()] == *expected;
+    let wanted = wanted.clamp(min, max.max(min));
+
+    (0..=lines.len()).find_map(|offset| {
+        if matches_at(wanted + offset) {
+            Some(wanted + offset)
+        } else if offset <= wanted && matches_at(wanted - offset) {
+            Some(wanted - offset)
+        } else {
+            None
+        }
+    })
+}
+
+/// Applies `hunks` to `content`.
+///
+/// Every context and deleted line must match exactly (no fuzz). A hunk may
+/// be found above or below the line its header names, as long as hunks
+/// stay in order. Fails with the header line of the first hunk that does
+/// not apply.
+pub fn apply_hunks(content: &[u8], hunks: &[Hunk]) -> Result<Vec<u8>> {
+    let lines: Vec<&[u8]> = content.split_inclusive(|&byte| byte == b'\n').collect();
+    let mut result = Vec::with_capacity(content.len());
+    let mut pos = 0;
+
+    for hunk in hunks {
+        let expected = hunk.old_lines();
+        // A hunk that only inserts names the line it goes after.
+        let wanted = if hunk.old_count == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+
+        let Some(at) = find_hunk(&lines, &expected, wanted, pos) else {
+            bail!("hunk at line {} does not apply", hunk.old_start);
+        };
+
+        result.extend(lines[pos..at].concat());
+        result.extend(hunk.new_lines().concat());
+        pos = at + expected.len();
+    }
+
+    result.extend(lines[pos..].concat());
+    Ok(result)
+}
@@ -0,0 +1,93 @@
+//! Pathspecs: literal paths, directory prefixes and globs naming tracked files.
+
+use anyhow::{Result, anyhow};
+use glob::{MatchOptions, Pattern};
+use std::path::{Component, Path, PathBuf};
+
+/// A parsed pathspec, relative to the repository root.
+///
+/// A spec without `*`, `?` or `[` names a file or everything under a
+/// directory; `.` names the whole tree. Otherwise it is a glob whose `*`
+/// also matches across `/`, as in Git.
+#[derive(Clone, Debug)]
+pub struct Pathspec {
+    spec: String,
+    path: PathBuf,
+    pattern: Option<Pattern>,
+}
+
+impl Pathspec {
+    /// Parses `spec`, which may be absolute or relative to `root_path`.
+    pub fn parse(root_path: &Path, spec: &str) -> Result<Pathspec> {
+        let spec_path = Path::new(spec);
+        let relative = if spec_path.is_absolute() {
+            spec_path
+                .strip_prefix(root_path)
+                .map_err(|_| anyhow!("'{}' is outside the repository root.", spec))?
+        } else {
+            spec_path
+        };
+
+        let path: PathBuf = relative
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect();
+        if path
+            .components()
+            .any(|component| matches!(component, Component::ParentDir))
+        {
+            return Err(anyhow!("'{}' is outside the repository root.", spec));
+        }
+
+        let text = path.to_string_lossy().replace('\\', "/");
+        let pattern = if text.contains(['*', '?', '[']) {
+            Some(Pattern::new(&text)?)
+        } else {
+            None
+        };
+
+        Ok(Pathspec {
+            spec: spec.to_string(),
+            path,
+            pattern,
+        })
+    }
+
+    /// Returns the spec as given on the command line.
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+
+    /// Returns the root-relative path of a literal (non-glob) spec.
+    pub fn literal_path(&self) -> Option<&Path> {
+        self.pattern.is_none().then_some(self.path.as_path())
+    }
+
+    /// Returns true if `path` (relative to the root) is selected by this spec.
+    pub fn matches(&self, path: &Path) -> bool {
+        match &self.pattern {
+            Some(pattern) => {
+                let options = MatchOptions {
+                    case_sensitive: true,
+                    require_literal_separator: false,
+                    require_literal_leading_dot: false,
+                };
+                path.ancestors()
+                    .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                    .any(|ancestor| {
+                        pattern
+                            .matches_with(&ancestor.to_string_lossy().replace('\\', "/"), options)
+                    })
+            }
+            None => path.starts_with(&self.path),
+        }
+    }
+}
+
+/// Parses every spec in `specs`; see [`Pathspec::parse`].
+pub fn parse_pathspecs(root_path: &Path, specs: &[String]) -> Result<Vec<Pathspec>> {
+    specs
+        .iter()
+        .map(|spec| Pathspec::parse(root_path, spec))
+        .collect()
+}
@@ -0,0 +1,223 @@
+//! State of a rebase in progress, kept in `.rustygit/rebase-merge`.
+//!
+//! - `head-name`: the branch being rebased, or `detached HEAD`.
+//! - `onto`: the commit the branch is replayed onto.
+//! - `orig-head`: where HEAD pointed before the rebase, for `--abort`.
+//! - `git-rebase-todo`: steps still to run; `done`: steps already run, the
+//!   last of which is the one a stopped rebase is on.
+//! - `interactive`: present for `rebase -i`.
+//! - `message`: the message to commit a conflicted step with.
+//! - `amend`: the commit an `edit` step stopped at.
+
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// What a rebase does with one commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebaseAction {
+    /// Use the commit.
+    Pick,
+    /// Use the commit, but edit its message.
+    Reword,
+    /// Use the commit, but stop for amending.
+    Edit,
+    /// Meld the commit into the previous one, combining their messages.
+    Squash,
+    /// Like `Squash`, but keep only the previous commit's message.
+    Fixup,
+    /// Leave the commit out.
+    Drop,
+}
+
+impl RebaseAction {
+    /// Returns the todo-list keyword.
+    pub fn name(self) -> &'static str {
+        match self {
+            RebaseAction::Pick => "pick",
+            RebaseAction::Reword => "reword",
+            RebaseAction::Edit => "edit",
+            RebaseAction::Squash => "squash",
+            RebaseAction::Fixup => "fixup",
+            RebaseAction::Drop => "drop",
+        }
+    }
+
+    /// Parses a todo-list keyword or its one-letter abbreviation.
+    pub fn parse(word: &str) -> Option<RebaseAction> {
+        match word {
+            "pick" | "p" => Some(RebaseAction::Pick),
+            "reword" | "r" => Some(RebaseAction::Reword),
+            "edit" | "e" => Some(RebaseAction::Edit),
+            "squash" | "s" => Some(RebaseAction::Squash),
+            "fixup" | "f" => Some(RebaseAction::Fixup),
+            "drop" | "d" => Some(RebaseAction::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a rebase todo list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RebaseStep {
+    pub action: RebaseAction,
+    pub commit: String,
+}
+
+/// A rebase in progress.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RebaseState {
+    /// The branch being rebased (`refs/heads/...`), or `None` when detached.
+    pub head_name: Option<String>,
+    pub onto: String,
+    pub orig_head: String,
+    pub todo: Vec<RebaseStep>,
+    pub done: Vec<RebaseStep>,
+    pub interactive: bool,
+    /// Message for committing the resolution of a conflicted step.
+    pub message: Option<String>,
+    /// The commit an `edit` step stopped at.
+    pub amend: Option<String>,
+}
+
+/// Returns the directory holding the rebase state.
+pub fn rebase_dir(root_path: &Path) -> PathBuf {
+    root_path.join(".rustygit").join("rebase-merge")
+}
+
+/// Parses a todo list: one `<action> <commit> [<subject>]` per line, with
+/// blank lines and `#` comments ignored. Commits may be abbreviated.
+pub fn parse_rebase_todo(root_path: &Path, text: &str) -> Result<Vec<RebaseStep>> {
+    let mut steps = Vec::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let word = words.next().unwrap_or_default();
+        let action = RebaseAction::parse(word)
+            .ok_or_else(|| anyhow!("Invalid command '{}' in todo line: '{}'", word, line))?;
+        let commit = words
+            .next()
+            .ok_or_else(|| anyhow!("Missing commit in todo line: '{}'", line))?;
+        let commit = utils::resolve_commit(root_path, commit)
+            .map_err(|err| anyhow!("Invalid commit in todo line '{}': {}", line, err))?;
+
+        steps.push(RebaseStep { action, commit });
+    }
+
+    Ok(steps)
+}
+
+fn format_steps(root_path: &Path, steps: &[RebaseStep], hash_len: usize) -> Result<String> {
+    let mut text = String::new();
+
+    for step in steps {
+        let message = utils::get_commit_message(root_path, &step.commit)?;
+        text.push_str(&format!(
+            "{} {} {}\n",
+            step.action.name(),
+            &step.commit[..hash_len.min(step.commit.len())],
+            message.lines().next().unwrap_or_default()
+        ));
+    }
+
+    Ok(text)
+}
+
+/// Formats steps as todo lines for editing: `<action> <short hash> <subject>`.
+pub fn format_rebase_todo(root_path: &Path, steps: &[RebaseStep]) -> Result<String> {
+    format_steps(root_path, steps, 7)
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(path)?))
+}
+
+fn write_optional(path: &Path, content: Option<&str>) -> Result<()> {
+    match content {
+        Some(content) => fs::write(path, content)?,
+        None if path.exists() => fs::remove_file(path)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Returns the rebase in progress, if any.
+pub fn read_rebase_state(root_path: &Path) -> Result<Option<RebaseState>> {
+    let dir = rebase_dir(root_path);
+
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    let read_hash = |name: &str| -> Result<String> {
+        let hash = fs::read_to_string(dir.join(name))?.trim().to_string();
+        if hash.is_empty() {
+            bail!("Corrupt rebase state: '{}' is empty.", name);
+        }
+        Ok(hash)
+    };
+
+    let head_name = fs::read_to_string(dir.join("head-name"))?
+        .trim()
+        .to_string();
+
+    Ok(Some(RebaseState {
+        head_name: head_name.starts_with("refs/").then_some(head_name),
+        onto: read_hash("onto")?,
+        orig_head: read_hash("orig-head")?,
+        todo: parse_rebase_todo(root_path, &fs::read_to_string(dir.join("git-rebase-todo"))?)?,
+        done: parse_rebase_todo(root_path, &fs::read_to_string(dir.join("done"))?)?,
+        interactive: dir.join("interactive").exists(),
+        message: read_optional(&dir.join("message"))?,
+        amend: read_optional(&dir.join("amend"))?.map(|hash| hash.trim().to_string()),
+    }))
+}
+
+/// Records `state` as the rebase in progress.
+pub fn write_rebase_state(root_path: &Path, state: &RebaseState) -> Result<()> {
+    let dir = rebase_dir(root_path);
+    fs::create_dir_all(&dir)?;
+
+    let head_name = state.head_name.as_deref().unwrap_or("detached HEAD");
+    fs::write(dir.join("head-name"), format!("{}\n", head_name))?;
+    fs::write(dir.join("onto"), format!("{}\n", state.onto))?;
+    fs::write(dir.join("orig-head"), format!("{}\n", state.orig_head))?;
+    fs::write(
+        dir.join("git-rebase-todo"),
+        format_steps(root_path, &state.todo, usize::MAX)?,
+    )?;
+    fs::write(
+        dir.join("done"),
+        format_steps(root_path, &state.done, usize::MAX)?,
+    )?;
+    write_optional(&dir.join("interactive"), state.interactive.then_some(""))?;
+    write_optional(&dir.join("message"), state.message.as_deref())?;
+    write_optional(
+        &dir.join("amend"),
+        state
+            .amend
+            .as_ref()
+            .map(|hash| format!("{}\n", hash))
+            .as_deref(),
+    )
+}
+
+/// Removes the rebase state if present.
+pub fn clear_rebase_state(root_path: &Path) -> Result<()> {
+    let dir = rebase_dir(root_path);
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
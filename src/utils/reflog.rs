@@ -0,0 +1,184 @@
+//! Reference logs under `.rustygit/logs`, in Git's reflog line format.
+
+use crate::utils;
+use anyhow::{Result, anyhow};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// The all-zero hash recorded as the old value of a newly created ref.
+pub const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+
+/// One reflog line: `<old> <new> <name> <<email>> <timestamp> <tz>\t<message>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReflogEntry {
+    pub old_hash: String,
+    pub new_hash: String,
+    /// `Name <email> <timestamp> <tz>` of whoever moved the ref.
+    pub identity: String,
+    pub message: String,
+}
+
+fn reflog_path(root_path: &Path, ref_name: &str) -> PathBuf {
+    root_path.join(".rustygit").join("logs").join(ref_name)
+}
+
+/// Appends an entry to the log of `ref_name` (e.g. `HEAD` or `refs/heads/main`).
+///
+/// `old_hash` is `None` when the ref did not point anywhere before.
+pub fn append_reflog(
+    root_path: &Path,
+    ref_name: &str,
+    old_hash: Option<&str>,
+    new_hash: &str,
+    message: &str,
+) -> Result<()> {
+    let path = reflog_path(root_path, ref_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let identity = utils::committer_signature(root_path)?.format();
+    let message = message.lines().next().unwrap_or_default();
+    let line = format!(
+        "{} {} {}\t{}\n",
+        old_hash.unwrap_or(NULL_HASH),
+        new_hash,
+        identity,
+        message
+    );
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Replaces the log of `ref_name` with `entries`, e.g. when a branch is renamed.
+pub fn write_reflog(root_path: &Path, ref_name: &str, entries: &[ReflogEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let path = reflog_path(root_path, ref_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content: String = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {} {}\t{}\n",
+                entry.old_hash, entry.new_hash, entry.identity, entry.message
+            )
+        })
+        .collect();
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Removes the log of `ref_name`, if any, along with log directories it leaves empty.
+pub fn delete_reflog(root_path: &Path, ref_name: &str) -> Result<()> {
+    let logs_dir = root_path.join(".rustygit").join("logs");
+    let path = reflog_path(root_path, ref_name);
+
+    if !path.is_file() {
+        return Ok(());
+    }
+    fs::remove_file(&path)?;
+
+    let mut parent = path.parent();
+    while let Some(dir) = parent {
+        if dir == logs_dir || !dir.starts_with(&logs_dir) || fs::read_dir(dir)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(dir)?;
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+fn collect_reflog_hashes(dir: &Path, hashes: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            collect_reflog_hashes(&entry.path(), hashes)?;
+            continue;
+        }
+
+        for line in fs::read_to_string(entry.path())?.lines() {
+            for hash in line.split(' ').take(2) {
+                if hash.len() == 40 && hash != NULL_HASH {
+                    hashes.push(hash.to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns every old and new hash recorded in any reflog, for reachability.
+pub fn reflog_hashes(root_path: &Path) -> Result<Vec<String>> {
+    let logs_dir = root_path.join(".rustygit").join("logs");
+    let mut hashes = Vec::new();
+
+    if logs_dir.is_dir() {
+        collect_reflog_hashes(&logs_dir, &mut hashes)?;
+    }
+    Ok(hashes)
+}
+
+/// Reads the log of `ref_name`, oldest entry first. A missing log is empty.
+pub fn read_reflog(root_path: &Path, ref_name: &str) -> Result<Vec<ReflogEntry>> {
+    let path = reflog_path(root_path, ref_name);
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+        let mut parts = header.splitn(3, ' ');
+        let (Some(old_hash), Some(new_hash), Some(identity)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(anyhow!("Malformed reflog entry: {}", line));
+        };
+
+        entries.push(ReflogEntry {
+            old_hash: old_hash.to_string(),
+            new_hash: new_hash.to_string(),
+            identity: identity.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Returns the branch name or commit hash that was checked out `n` switches ago.
+///
+/// This walks `checkout: moving from <from> to <to>` entries in the HEAD log,
+/// newest first, the same way Git resolves `@{-n}`.
+pub fn previous_checkout(root_path: &Path, n: usize) -> Result<String> {
+    read_reflog(root_path, "HEAD")?
+        .iter()
+        .rev()
+        .filter_map(|entry| entry.message.strip_prefix("checkout: moving from "))
+        .filter_map(|moved| moved.split_once(" to "))
+        .map(|(from, _)| from.to_string())
+        .nth(n.saturating_sub(1))
+        .filter(|_| n > 0)
+        .ok_or_else(|| anyhow!("Only fewer than {} checkouts have been recorded.", n))
+}
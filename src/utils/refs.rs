@@ -0,0 +1,424 @@
+//! Reference naming rules and the shared API for reading and moving refs.
+//!
+//! Commands never write files under `.rustygit/refs` or `HEAD` directly; they
+//! go through [`update_ref`], [`delete_ref`], [`rename_ref`] and the HEAD
+//! helpers here, which also keep the reflogs under `.rustygit/logs` current.
+//! Every write goes through a `<ref>.lock` file (see [`RefTransaction`]), so
+//! concurrent processes cannot clobber each other's updates.
+
+use crate::utils::{self, LockFile};
+use anyhow::{Result, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Checks that `name` is usable as a branch or tag name.
+///
+/// This follows the core of Git's `check-ref-format` rules: no empty or
+/// dot-prefixed components, no `..`, `@{`, `.lock` suffixes, whitespace,
+/// control characters or any of `~^:?*[\`, and no leading `-`.
+pub fn check_ref_name(name: &str) -> Result<()> {
+    let invalid = name.is_empty()
+        || name == "@"
+        || name.starts_with('-')
+        || name.ends_with('.')
+        || name.contains("..")
+        || name.contains("@{")
+        || name
+            .chars()
+            .any(|c| c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(c))
+        || name.split('/').any(|component| {
+            component.is_empty() || component.starts_with('.') || component.ends_with(".lock")
+        });
+
+    if invalid {
+        bail!("'{}' is not a valid reference name.", name);
+    }
+    Ok(())
+}
+
+fn ref_path(root_path: &Path, ref_name: &str) -> PathBuf {
+    root_path.join(".rustygit").join(ref_name)
+}
+
+/// Returns true if the ref file exists, even when it is unborn (empty).
+pub fn ref_exists(root_path: &Path, ref_name: &str) -> bool {
+    ref_path(root_path, ref_name).is_file()
+}
+
+/// Reads the hash a ref such as `refs/heads/main` points to.
+///
+/// Returns `None` if the ref does not exist or has no commits yet.
+pub fn read_ref(root_path: &Path, ref_name: &str) -> Result<Option<String>> {
+    let path = ref_path(root_path, ref_name);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let hash = fs::read_to_string(path)?.trim().to_string();
+    Ok((!hash.is_empty()).then_some(hash))
+}
+
+/// Returns true for refs whose updates are recorded in a reflog: `HEAD`,
+/// branches and the stash, as with Git's default `core.logAllRefUpdates`.
+fn has_reflog(ref_name: &str) -> bool {
+    ref_name == "HEAD" || ref_name == "refs/stash" || ref_name.starts_with("refs/heads/")
+}
+
+/// Reads the commit `ref_name` currently holds; `HEAD` is followed when attached.
+fn current_value(root_path: &Path, ref_name: &str) -> Result<Option<String>> {
+    if ref_name == "HEAD" {
+        Ok(utils::get_current_commit_hash(root_path).unwrap_or(None))
+    } else {
+        read_ref(root_path, ref_name)
+    }
+}
+
+/// Removes directories under `refs/` that `path` leaves empty, stopping at
+/// `refs/heads`, `refs/tags` and other top-level namespaces.
+fn remove_empty_parents(root_path: &Path, path: &Path) -> Result<()> {
+    let refs_root = root_path.join(".rustygit").join("refs");
+
+    let mut parent = path.parent();
+    while let Some(dir) = parent {
+        if !dir.starts_with(&refs_root)
+            || dir == refs_root
+            || dir.parent() == Some(refs_root.as_path())
+            || fs::read_dir(dir)?.next().is_some()
+        {
+            break;
+        }
+        fs::remove_dir(dir)?;
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+struct RefUpdate {
+    ref_name: String,
+    /// The new hash (empty for unborn), or `None` to delete the ref.
+    new_hash: Option<String>,
+    expected_old: Option<String>,
+    message: Option<String>,
+}
+
+/// A set of ref updates applied together under `<ref>.lock` files.
+///
+/// [`RefTransaction::commit`] locks every ref first, then checks each
+/// expected old value while holding the locks, so a concurrent writer either
+/// makes the whole transaction fail or waits for it; nothing is written
+/// unless every check passes. An expected value of [`utils::NULL_HASH`]
+/// means the ref must not exist yet (or be unborn).
+#[derive(Default)]
+pub struct RefTransaction {
+    updates: Vec<RefUpdate>,
+    /// Locks taken with [`lock_ref`] before the transaction was built, used
+    /// instead of acquiring them again and held until the commit is done.
+    held_locks: Vec<LockFile>,
+}
+
+impl RefTransaction {
+    pub fn new() -> RefTransaction {
+        RefTransaction::default()
+    }
+
+    /// Hands over a lock from [`lock_ref`] for the transaction to use.
+    pub fn with_lock(&mut self, lock: LockFile) -> &mut RefTransaction {
+        self.held_locks.push(lock);
+        self
+    }
+
+    /// Queues pointing `ref_name` at `new_hash`; `message` (if any) goes to the reflogs.
+    pub fn update(
+        &mut self,
+        ref_name: &str,
+        new_hash: &str,
+        expected_old: Option<&str>,
+        message: Option<&str>,
+    ) -> &mut RefTransaction {
+        self.updates.push(RefUpdate {
+            ref_name: ref_name.to_string(),
+            new_hash: Some(new_hash.to_string()),
+            expected_old: expected_old.map(str::to_string),
+            message: message.map(str::to_string),
+        });
+        self
+    }
+
+    /// Queues removing `ref_name` and its reflog.
+    pub fn delete(&mut self, ref_name: &str, expected_old: Option<&str>) -> &mut RefTransaction {
+        self.updates.push(RefUpdate {
+            ref_name: ref_name.to_string(),
+            new_hash: None,
+            expected_old: expected_old.map(str::to_string),
+            message: None,
+        });
+        self
+    }
+
+    /// Locks, verifies and applies all queued updates.
+    pub fn commit(&mut self, root_path: &Path) -> Result<()> {
+        // A fixed lock order keeps two transactions over the same refs from
+        // each holding a lock the other needs.
+        self.updates.sort_by(|a, b| a.ref_name.cmp(&b.ref_name));
+        if let Some(pair) = self
+            .updates
+            .windows(2)
+            .find(|pair| pair[0].ref_name == pair[1].ref_name)
+        {
+            bail!(
+                "Reference '{}' is updated twice in one transaction.",
+                pair[0].ref_name
+            );
+        }
+
+        let mut held_locks = std::mem::take(&mut self.held_locks);
+        let mut locks = Vec::new();
+        for update in &self.updates {
+            let path = ref_path(root_path, &update.ref_name);
+            locks.push(
+                match held_locks.iter().position(|lock| lock.path() == path) {
+                    Some(position) => held_locks.swap_remove(position),
+                    None => LockFile::acquire(&path)?,
+                },
+            );
+        }
+
+        let mut old_values = Vec::new();
+        for update in &self.updates {
+            let old_hash = current_value(root_path, &update.ref_name)?;
+
+            if update.new_hash.is_none() && !ref_exists(root_path, &update.ref_name) {
+                bail!("Reference '{}' does not exist.", update.ref_name);
+            }
+            if let Some(expected) = &update.expected_old {
+                let actual = old_hash.as_deref().unwrap_or(utils::NULL_HASH);
+                if actual != expected {
+                    bail!(
+                        "Cannot update ref '{}': expected {}, but it is {}.",
+                        update.ref_name,
+                        expected,
+                        actual
+                    );
+                }
+            }
+            old_values.push(old_hash);
+        }
+
+        let head_ref = read_symbolic_head(root_path)?;
+
+        for ((update, mut lock), old_hash) in self.updates.iter().zip(locks).zip(old_values) {
+            let Some(new_hash) = &update.new_hash else {
+                let path = lock.path().to_path_buf();
+                fs::remove_file(&path)?;
+                drop(lock);
+                remove_empty_parents(root_path, &path)?;
+                utils::delete_reflog(root_path, &update.ref_name)?;
+                continue;
+            };
+
+            lock.write(new_hash.as_bytes())?;
+            lock.commit()?;
+
+            let Some(message) = &update.message else {
+                continue;
+            };
+            if new_hash.is_empty() {
+                continue;
+            }
+            if has_reflog(&update.ref_name) {
+                utils::append_reflog(
+                    root_path,
+                    &update.ref_name,
+                    old_hash.as_deref(),
+                    new_hash,
+                    message,
+                )?;
+            }
+            if update.ref_name != "HEAD" && head_ref.as_deref() == Some(update.ref_name.as_str()) {
+                utils::append_reflog(root_path, "HEAD", old_hash.as_deref(), new_hash, message)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locks `ref_name` ahead of a [`RefTransaction`], for callers that must
+/// read state the ref guards (where HEAD points, or a reflog) before
+/// deciding on the update. Pass the lock on with [`RefTransaction::with_lock`].
+pub fn lock_ref(root_path: &Path, ref_name: &str) -> Result<LockFile> {
+    LockFile::acquire(&ref_path(root_path, ref_name))
+}
+
+/// Points `ref_name` (e.g. `refs/heads/main`, or `HEAD` to detach) at `hash`,
+/// creating the ref and any parent directories as needed.
+///
+/// When `expected_old` is given the ref must still hold that value (see
+/// [`RefTransaction`]). `message` is the reason recorded in the reflog of the
+/// ref, and of `HEAD` too when HEAD is attached to it. An empty `hash` leaves
+/// the ref unborn and is not logged.
+pub fn update_ref(
+    root_path: &Path,
+    ref_name: &str,
+    hash: &str,
+    expected_old: Option<&str>,
+    message: &str,
+) -> Result<()> {
+    RefTransaction::new()
+        .update(ref_name, hash, expected_old, Some(message))
+        .commit(root_path)
+}
+
+/// Removes `ref_name`, its reflog, and any directories under `refs/` it leaves empty.
+pub fn delete_ref(root_path: &Path, ref_name: &str, expected_old: Option<&str>) -> Result<()> {
+    RefTransaction::new()
+        .delete(ref_name, expected_old)
+        .commit(root_path)
+}
+
+/// Moves `old_name` to `new_name`, keeping the hash (or unborn state) it
+/// holds and its reflog, to which `message` is appended.
+pub fn rename_ref(root_path: &Path, old_name: &str, new_name: &str, message: &str) -> Result<()> {
+    if !ref_exists(root_path, old_name) {
+        bail!("Reference '{}' does not exist.", old_name);
+    }
+    if ref_exists(root_path, new_name) {
+        bail!("Reference '{}' already exists.", new_name);
+    }
+
+    let hash = read_ref(root_path, old_name)?;
+    let log = utils::read_reflog(root_path, old_name)?;
+    let expected = hash.as_deref().unwrap_or(utils::NULL_HASH);
+
+    RefTransaction::new()
+        .delete(old_name, Some(expected))
+        .update(
+            new_name,
+            hash.as_deref().unwrap_or_default(),
+            Some(utils::NULL_HASH),
+            None,
+        )
+        .commit(root_path)?;
+
+    utils::write_reflog(root_path, new_name, &log)?;
+    if let Some(hash) = hash
+        && has_reflog(new_name)
+    {
+        utils::append_reflog(root_path, new_name, Some(&hash), &hash, message)?;
+    }
+    Ok(())
+}
+
+fn collect_refs(dir: &Path, name_prefix: &str, refs: &mut Vec<(String, String)>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}{}", name_prefix, entry.file_name().to_string_lossy());
+
+        if entry.file_type()?.is_dir() {
+            collect_refs(&entry.path(), &format!("{}/", name), refs)?;
+        } else if !name.ends_with(".lock") {
+            let hash = fs::read_to_string(entry.path())?.trim().to_string();
+            refs.push((name, hash));
+        }
+    }
+    Ok(())
+}
+
+/// Returns `(name, hash)` for every ref under `prefix` (e.g. `refs/heads`),
+/// sorted by name, with names relative to `prefix`. Unborn refs have an
+/// empty hash.
+pub fn list_refs(root_path: &Path, prefix: &str) -> Result<Vec<(String, String)>> {
+    let mut refs = Vec::new();
+    let dir = ref_path(root_path, prefix);
+    if dir.is_dir() {
+        collect_refs(&dir, "", &mut refs)?;
+    }
+
+    refs.sort();
+    Ok(refs)
+}
+
+/// Returns the ref HEAD is attached to (e.g. `refs/heads/main`), or `None` when detached.
+pub fn read_symbolic_head(root_path: &Path) -> Result<Option<String>> {
+    let head_content = fs::read_to_string(ref_path(root_path, "HEAD"))?;
+
+    Ok(head_content
+        .trim()
+        .strip_prefix("ref: ")
+        .map(|ref_name| ref_name.trim().to_string()))
+}
+
+/// Returns the name of the checked-out branch, or `None` when HEAD is detached.
+pub fn current_branch(root_path: &Path) -> Result<Option<String>> {
+    Ok(read_symbolic_head(root_path)?.and_then(|ref_name| {
+        ref_name
+            .strip_prefix("refs/heads/")
+            .map(|branch_name| branch_name.to_string())
+    }))
+}
+
+/// Attaches HEAD to `refs/heads/<branch_name>`, logging the switch in the HEAD
+/// reflog with `message` (`None` for bookkeeping such as a rename, which
+/// does not change the current commit).
+///
+/// HEAD is locked while it is read and replaced. When `expected_old` is
+/// given, the commit HEAD resolves to must still be that value, as in
+/// [`update_ref`].
+pub fn set_head_to_branch(
+    root_path: &Path,
+    branch_name: &str,
+    expected_old: Option<&str>,
+    message: Option<&str>,
+) -> Result<()> {
+    let branch_ref = format!("refs/heads/{}", branch_name);
+    let mut head_lock = lock_ref(root_path, "HEAD")?;
+    let old_hash = utils::get_current_commit_hash(root_path).unwrap_or(None);
+
+    if let Some(expected) = expected_old {
+        let actual = old_hash.as_deref().unwrap_or(utils::NULL_HASH);
+        if actual != expected {
+            bail!(
+                "Cannot update ref 'HEAD': expected {}, but it is {}.",
+                expected,
+                actual
+            );
+        }
+    }
+
+    head_lock.write(format!("ref: {}\n", branch_ref).as_bytes())?;
+    head_lock.commit()?;
+
+    if let Some(message) = message {
+        let new_hash = read_ref(root_path, &branch_ref)?;
+        utils::append_reflog(
+            root_path,
+            "HEAD",
+            old_hash.as_deref(),
+            new_hash.as_deref().unwrap_or(utils::NULL_HASH),
+            message,
+        )?;
+    }
+    Ok(())
+}
+
+/// Moves the current commit to `commit_hash`: the checked-out branch when
+/// HEAD is attached, otherwise HEAD itself. `expected_old` is checked as in
+/// [`update_ref`] and `message` goes to the reflogs.
+pub fn update_head(
+    root_path: &Path,
+    commit_hash: &str,
+    expected_old: Option<&str>,
+    message: &str,
+) -> Result<()> {
+    // HEAD stays locked from resolving it until the update is done, so it
+    // cannot be pointed elsewhere in between.
+    let head_lock = lock_ref(root_path, "HEAD")?;
+    let ref_name = read_symbolic_head(root_path)?.unwrap_or_else(|| "HEAD".to_string());
+
+    RefTransaction::new()
+        .with_lock(head_lock)
+        .update(&ref_name, commit_hash, expected_old, Some(message))
+        .commit(root_path)
+}
@@ -0,0 +1,250 @@
+//! Revision expressions: `HEAD`, `@`, branch and tag names, abbreviated
+//! hashes, `@{-N}`, `ref@{N}`, and the `~N` / `^N` ancestry suffixes.
+
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use std::path::Path;
+
+/// Shortest abbreviated hash accepted on its own.
+const MIN_PREFIX_LEN: usize = 4;
+
+fn is_hex(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Reads a ref, returning `None` if it does not exist and failing if it is unborn.
+fn read_ref(root_path: &Path, ref_name: &str) -> Result<Option<String>> {
+    if !utils::ref_exists(root_path, ref_name) {
+        return Ok(None);
+    }
+
+    match utils::read_ref(root_path, ref_name)? {
+        Some(hash) => Ok(Some(hash)),
+        None => bail!("Reference '{}' does not point to a commit yet.", ref_name),
+    }
+}
+
+/// Returns the object type (`commit`, `tree`, `blob`, `tag`) and payload of `hash`.
+fn read_typed_object(root_path: &Path, hash: &str) -> Result<(String, Vec<u8>)> {
+    utils::split_object(&utils::read_object(root_path, hash)?)
+}
+
+/// Parses `@{-N}`, returning `N`.
+fn parse_previous_checkout(expr: &str) -> Option<usize> {
+    expr.strip_prefix("@{-")?.strip_suffix('}')?.parse().ok()
+}
+
+/// Returns the branch name or commit hash `@{-N}` refers to, or `None` if
+/// `expr` is not of that form.
+pub fn resolve_previous_checkout(root_path: &Path, expr: &str) -> Result<Option<String>> {
+    match parse_previous_checkout(expr) {
+        Some(n) if n > 0 => Ok(Some(utils::previous_checkout(root_path, n)?)),
+        Some(_) => bail!("Invalid revision '{}'.", expr),
+        None => Ok(None),
+    }
+}
+
+/// Splits `ref@{N}` into the ref part (possibly empty) and `N`.
+fn parse_reflog_selector(expr: &str) -> Option<(&str, usize)> {
+    let (name, selector) = expr.strip_suffix('}')?.rsplit_once("@{")?;
+    if selector.is_empty() || !selector.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((name, selector.parse().ok()?))
+}
+
+/// Returns the value `name` had `n` updates ago, according to its reflog.
+///
+/// An empty name means the current branch (or HEAD when detached).
+fn resolve_reflog_entry(root_path: &Path, name: &str, n: usize) -> Result<String> {
+    let ref_name = match name {
+        "" => utils::read_symbolic_head(root_path)?.unwrap_or_else(|| "HEAD".to_string()),
+        "HEAD" => "HEAD".to_string(),
+        _ if name.starts_with("refs/") => name.to_string(),
+        _ if utils::ref_exists(root_path, &format!("refs/{}", name)) => format!("refs/{}", name),
+        _ => format!("refs/heads/{}", name),
+    };
+
+    let entries = utils::read_reflog(root_path, &ref_name)?;
+    if entries.is_empty() {
+        bail!("Reference '{}' has no reflog.", ref_name);
+    }
+
+    entries
+        .iter()
+        .rev()
+        .nth(n)
+        .map(|entry| entry.new_hash.clone())
+        .ok_or_else(|| anyhow!("Log for '{}' only has {} entries.", ref_name, entries.len()))
+}
+
+/// Resolves an abbreviated (or full) object hash, failing when it matches
+/// nothing or more than one object.
+fn resolve_hash_prefix(root_path: &Path, prefix: &str) -> Result<Option<String>> {
+    if prefix.len() < MIN_PREFIX_LEN || prefix.len() > 40 || !is_hex(prefix) {
+        return Ok(None);
+    }
+
+    let mut matches = utils::find_objects_by_prefix(root_path, prefix)?;
+    match matches.len() {
+        0 => Ok(None),
+        1 => Ok(matches.pop()),
+        _ => bail!(
+            "Short hash '{}' is ambiguous. Candidates:\n  {}",
+            prefix,
+            matches.join("\n  ")
+        ),
+    }
+}
+
+/// Resolves the part of an expression before any `~` or `^` suffix.
+fn resolve_base(root_path: &Path, base: &str) -> Result<String> {
+    if base == "HEAD" || base == "@" {
+        return utils::get_current_commit_hash(root_path)?
+            .ok_or_else(|| anyhow!("HEAD does not point to a commit yet."));
+    }
+
+    if let Some(previous) = resolve_previous_checkout(root_path, base)? {
+        return resolve_base(root_path, &previous);
+    }
+
+    if let Some((name, n)) = parse_reflog_selector(base) {
+        return resolve_reflog_entry(root_path, name, n);
+    }
+
+    if base.starts_with("refs/") {
+        return read_ref(root_path, base)?.ok_or_else(|| anyhow!("Unknown revision '{}'.", base));
+    }
+
+    // Names are looked up as refs first so a branch called `cafe` still wins
+    // over an abbreviated hash, as in Git.
+    let mut found = Vec::new();
+    for ref_name in [
+        format!("refs/{}", base),
+        format!("refs/tags/{}", base),
+        format!("refs/heads/{}", base),
+    ] {
+        if let Some(hash) = read_ref(root_path, &ref_name)? {
+            found.push((ref_name, hash));
+        }
+    }
+
+    match found.len() {
+        0 => {}
+        1 => return Ok(found.remove(0).1),
+        _ => bail!(
+            "Reference name '{}' is ambiguous: {}. Use the full reference name.",
+            base,
+            found
+                .iter()
+                .map(|(ref_name, _)| ref_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+
+    resolve_hash_prefix(root_path, &base.to_ascii_lowercase())?
+        .ok_or_else(|| anyhow!("Unknown revision '{}'.", base))
+}
+
+/// Follows annotated tags until a commit is reached.
+fn peel_to_commit(root_path: &Path, hash: &str) -> Result<String> {
+    let mut hash = hash.to_string();
+
+    loop {
+        let (object_type, payload) = read_typed_object(root_path, &hash)?;
+        match object_type.as_str() {
+            "commit" => return Ok(hash),
+            "tag" => {
+                let payload = String::from_utf8_lossy(&payload);
+                hash = payload
+                    .lines()
+                    .next()
+                    .and_then(|line| line.strip_prefix("object "))
+                    .map(|target| target.trim().to_string())
+                    .ok_or_else(|| anyhow!("Malformed tag object.\nHash: {}", hash))?;
+            }
+            other => bail!("Object '{}' is a {}, not a commit.", hash, other),
+        }
+    }
+}
+
+/// Splits `~N` / `^N` suffixes into (operator, count) pairs.
+fn parse_suffixes(expr: &str, suffixes: &str) -> Result<Vec<(char, usize)>> {
+    let mut parsed = Vec::new();
+    let mut chars = suffixes.chars().peekable();
+
+    while let Some(op) = chars.next() {
+        if op != '~' && op != '^' {
+            bail!("Invalid revision '{}'.", expr);
+        }
+
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+            digits.push(digit);
+        }
+
+        let count = if digits.is_empty() {
+            1
+        } else {
+            digits
+                .parse()
+                .map_err(|_| anyhow!("Invalid revision '{}'.", expr))?
+        };
+        parsed.push((op, count));
+    }
+
+    Ok(parsed)
+}
+
+/// Resolves a revision expression to a full object hash.
+///
+/// Supported forms are `HEAD` (or `@`), `refs/...`, branch and tag names,
+/// unique hash prefixes of at least four characters, `@{-N}` (the Nth
+/// previously checked-out branch or commit), `ref@{N}` (the value of `ref`
+/// N updates ago, from its reflog), and any chain of `~N` (Nth
+/// first-parent ancestor) and `^N` (Nth parent, `^0` being the commit itself)
+/// suffixes. Plain refs are returned unpeeled, so a tag name yields the tag
+/// object itself.
+pub fn resolve_revision(root_path: &Path, expr: &str) -> Result<String> {
+    let expr = expr.trim();
+    let split_at = expr.find(['~', '^']).unwrap_or(expr.len());
+    let (base, suffixes) = expr.split_at(split_at);
+
+    if base.is_empty() {
+        bail!("Invalid revision '{}'.", expr);
+    }
+
+    let mut hash = resolve_base(root_path, base)?;
+
+    for (op, count) in parse_suffixes(expr, suffixes)? {
+        hash = peel_to_commit(root_path, &hash)?;
+
+        match (op, count) {
+            ('^', 0) => {}
+            ('^', n) => {
+                let parents = utils::get_commit_parents(root_path, &hash)?;
+                hash = parents
+                    .get(n - 1)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Revision '{}' has no parent {}.", expr, n))?;
+            }
+            (_, n) => {
+                for _ in 0..n {
+                    hash = utils::get_commit_parents(root_path, &hash)?
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| anyhow!("Revision '{}' goes past the root commit.", expr))?;
+                }
+            }
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Resolves a revision expression and peels it to a commit hash.
+pub fn resolve_commit(root_path: &Path, expr: &str) -> Result<String> {
+    let hash = resolve_revision(root_path, expr)?;
+    peel_to_commit(root_path, &hash)
+}
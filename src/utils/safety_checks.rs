@@ -1,6 +1,6 @@
 //! Safety checks that prevent destructive overwrites.
 
-use crate::utils;
+use crate::utils::{self, IndexEntry};
 use anyhow::{Result, bail};
 use std::{
     collections::HashMap,
@@ -9,25 +9,33 @@ use std::{
 
 /// Verifies checkout would not overwrite untracked or modified local files.
 pub fn checkout_safety_check(root_path: &Path, target_tree_hash: Option<String>) -> Result<()> {
-    let current_tree_hash = utils::get_current_tree_hash(root_path)?;
-
-    let mut current_tree_map: HashMap<PathBuf, String> = HashMap::new();
-    let mut target_tree_map: HashMap<PathBuf, String> = HashMap::new();
-    let mut working_dir_map: HashMap<PathBuf, String> = HashMap::new();
-
-    if let Some(hash) = current_tree_hash {
-        utils::get_tree_files_map(root_path, Path::new(""), &hash, &mut current_tree_map)?;
-    }
+    let mut target_entries: HashMap<PathBuf, IndexEntry> = HashMap::new();
 
     if let Some(hash) = target_tree_hash {
-        utils::get_tree_files_map(root_path, Path::new(""), &hash, &mut target_tree_map)?;
+        utils::get_tree_entries_map(root_path, Path::new(""), &hash, &mut target_entries)?;
     }
 
-    utils::get_work_dir_map(root_path, Path::new(""), &mut working_dir_map)?;
+    overwrite_safety_check(root_path, &target_entries)
+}
+
+/// Verifies that replacing the HEAD snapshot with `target_entries` would not
+/// overwrite untracked or modified local files.
+///
+/// A local change is any difference from HEAD in content or mode, including
+/// a tracked file deleted from the working tree.
+pub fn overwrite_safety_check(
+    root_path: &Path,
+    target_entries: &HashMap<PathBuf, IndexEntry>,
+) -> Result<()> {
+    let head_commit = utils::get_current_commit_hash(root_path)?;
+    let current_entries = utils::get_commit_entries_map(root_path, head_commit.as_deref())?;
+
+    let mut work_entries: HashMap<PathBuf, IndexEntry> = HashMap::new();
+    utils::get_work_dir_entries(root_path, Path::new(""), &mut work_entries)?;
 
-    for (path, work_hash) in working_dir_map.iter() {
-        let in_current = current_tree_map.get(path);
-        let in_target = target_tree_map.get(path);
+    for (path, work_entry) in work_entries.iter() {
+        let in_current = current_entries.get(path);
+        let in_target = target_entries.get(path);
 
         if in_current.is_none() && in_target.is_some() {
             bail!(
@@ -36,18 +44,28 @@ pub fn checkout_safety_check(root_path: &Path, target_tree_hash: Option<String>)
             );
         }
 
-        if let Some(cur_hash) = in_current {
-            if work_hash != cur_hash {
-                match in_target {
-                    Some(target_hash) if target_hash == cur_hash => {}
-                    _ => {
-                        bail!(
-                            "Local changes to '{}' would be overwritten by checkout",
-                            path.display()
-                        );
-                    }
-                }
-            }
+        if let Some(current) = in_current
+            && work_entry != current
+            && in_target != Some(current)
+        {
+            bail!(
+                "Local changes to '{}' would be overwritten by checkout",
+                path.display()
+            );
+        }
+    }
+
+    // A deleted file would come back if the target changes it.
+    for (path, current) in current_entries.iter() {
+        if !work_entries.contains_key(path)
+            && target_entries
+                .get(path)
+                .is_some_and(|target| target != current)
+        {
+            bail!(
+                "Local changes to '{}' would be overwritten by checkout",
+                path.display()
+            );
         }
     }
 
@@ -0,0 +1,120 @@
+//! State of a `cherry-pick` or `revert` sequence that stopped on conflicts.
+//!
+//! `.rustygit/sequencer/head` holds the commit HEAD pointed at before the
+//! sequence started, for `--abort`, `.rustygit/sequencer/action` whether it
+//! picks or reverts, and `.rustygit/sequencer/todo` the commits still to be
+//! applied, one `pick <hash>` or `revert <hash>` per line. The commit that
+//! stopped is recorded separately in `CHERRY_PICK_HEAD` or `REVERT_HEAD`
+//! (see `merge_state`).
+
+use anyhow::{Result, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Whether a sequence applies commits or their inverses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequencerAction {
+    Pick,
+    Revert,
+}
+
+impl SequencerAction {
+    /// Returns the todo-list keyword: `pick` or `revert`.
+    pub fn name(self) -> &'static str {
+        match self {
+            SequencerAction::Pick => "pick",
+            SequencerAction::Revert => "revert",
+        }
+    }
+
+    /// Returns the command running the sequence: `cherry-pick` or `revert`.
+    pub fn command(self) -> &'static str {
+        match self {
+            SequencerAction::Pick => "cherry-pick",
+            SequencerAction::Revert => "revert",
+        }
+    }
+
+    /// Returns the file recording the commit a stopped sequence is applying.
+    pub fn head_file(self) -> &'static str {
+        match self {
+            SequencerAction::Pick => "CHERRY_PICK_HEAD",
+            SequencerAction::Revert => "REVERT_HEAD",
+        }
+    }
+}
+
+/// A sequence of commits being cherry-picked or reverted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sequencer {
+    pub action: SequencerAction,
+    /// The commit HEAD pointed at before the sequence started.
+    pub original_head: String,
+    /// Commits still to be applied, in order.
+    pub todo: Vec<String>,
+}
+
+fn sequencer_dir(root_path: &Path) -> PathBuf {
+    root_path.join(".rustygit").join("sequencer")
+}
+
+/// Returns the sequence in progress, if any.
+pub fn read_sequencer(root_path: &Path) -> Result<Option<Sequencer>> {
+    let dir = sequencer_dir(root_path);
+
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    let original_head = fs::read_to_string(dir.join("head"))?.trim().to_string();
+    let action = match fs::read_to_string(dir.join("action"))?.trim() {
+        "pick" => SequencerAction::Pick,
+        "revert" => SequencerAction::Revert,
+        other => bail!("Unknown sequencer action '{}'.", other),
+    };
+
+    let mut todo = Vec::new();
+    for line in fs::read_to_string(dir.join("todo"))?.lines() {
+        match line.split_once(' ') {
+            Some((name, commit_hash)) if name == action.name() => {
+                todo.push(commit_hash.trim().to_string())
+            }
+            _ => bail!("Malformed sequencer todo line: '{}'", line),
+        }
+    }
+
+    Ok(Some(Sequencer {
+        action,
+        original_head,
+        todo,
+    }))
+}
+
+/// Records `sequencer` as the sequence in progress.
+pub fn write_sequencer(root_path: &Path, sequencer: &Sequencer) -> Result<()> {
+    let dir = sequencer_dir(root_path);
+    fs::create_dir_all(&dir)?;
+
+    let todo: String = sequencer
+        .todo
+        .iter()
+        .map(|commit_hash| format!("{} {}\n", sequencer.action.name(), commit_hash))
+        .collect();
+
+    fs::write(dir.join("head"), format!("{}\n", sequencer.original_head))?;
+    fs::write(dir.join("action"), format!("{}\n", sequencer.action.name()))?;
+    fs::write(dir.join("todo"), todo)?;
+    Ok(())
+}
+
+/// Removes the sequencer state if present.
+pub fn clear_sequencer(root_path: &Path) -> Result<()> {
+    let dir = sequencer_dir(root_path);
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 //! Helpers for resolving commit/tree state and materializing path-hash maps.
 
+use crate::utils::IndexEntry;
 use crate::{commands, utils};
 use anyhow::{Result, bail};
 use std::{
@@ -13,8 +14,8 @@ pub fn get_current_commit_hash(root_path: &Path) -> Result<Option<String>> {
     let head_path = root_path.join(".rustygit").join("HEAD");
     let head_content = std::fs::read_to_string(head_path)?.trim().to_string();
 
-    let ref_path = if head_content.starts_with("ref: ") {
-        head_content[5..].trim()
+    let ref_path = if let Some(ref_path) = head_content.strip_prefix("ref: ") {
+        ref_path.trim()
     } else {
         return Ok(Some(head_content));
     };
@@ -52,38 +53,93 @@ pub fn get_current_tree_hash(root_path: &Path) -> Result<Option<String>> {
     }
 }
 
-/// Recursively expands a tree object into a `path -> blob_hash` map.
-pub fn get_tree_files_map(
+/// Recursively expands a tree object into a `path -> (mode, blob_hash)` map.
+pub fn get_tree_entries_map(
     root_path: &Path,
     path: &Path,
     tree_hash: &str,
-    files_map: &mut HashMap<PathBuf, String>,
+    entries_map: &mut HashMap<PathBuf, IndexEntry>,
 ) -> Result<()> {
     let tree_entries = utils::parse_tree(root_path, tree_hash)?;
 
     for entry in tree_entries {
         let entry_path = path.join(&entry.name);
 
-        if entry.mode == "40000" {
-            get_tree_files_map(
+        if entry.mode == utils::MODE_TREE {
+            get_tree_entries_map(
                 root_path,
                 &entry_path,
                 &utils::bytes_to_hex(&entry.hash),
-                files_map,
+                entries_map,
             )?;
         } else {
-            files_map.insert(entry_path, utils::bytes_to_hex(&entry.hash));
+            entries_map.insert(
+                entry_path,
+                IndexEntry {
+                    mode: entry.mode,
+                    hash: utils::bytes_to_hex(&entry.hash),
+                },
+            );
         }
     }
 
     Ok(())
 }
 
-/// Recursively scans the working directory into a `path -> blob_hash` map.
-pub fn get_work_dir_map(
+/// Expands the tree of `commit_hash` into a `path -> (mode, blob_hash)` map;
+/// `None` (an unborn branch) gives an empty map.
+pub fn get_commit_entries_map(
+    root_path: &Path,
+    commit_hash: Option<&str>,
+) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let mut entries = HashMap::new();
+
+    if let Some(commit_hash) = commit_hash {
+        let tree_hash = utils::get_commit_tree_hash(root_path, commit_hash)?;
+        get_tree_entries_map(root_path, Path::new(""), &tree_hash, &mut entries)?;
+    }
+
+    Ok(entries)
+}
+
+/// Returns the stage 0 index entries, or the HEAD tree while the index is
+/// empty (`commit` clears it, and an empty index then stands for HEAD).
+pub fn read_effective_index(root_path: &Path) -> Result<HashMap<PathBuf, IndexEntry>> {
+    let index_entries = utils::read_index_entries(root_path)?;
+    if !index_entries.is_empty() {
+        return Ok(index_entries);
+    }
+
+    let head_commit = get_current_commit_hash(root_path)?;
+    get_commit_entries_map(root_path, head_commit.as_deref())
+}
+
+/// Recursively expands a tree object into a `path -> blob_hash` map.
+pub fn get_tree_files_map(
     root_path: &Path,
     path: &Path,
-    map: &mut HashMap<PathBuf, String>,
+    tree_hash: &str,
+    files_map: &mut HashMap<PathBuf, String>,
+) -> Result<()> {
+    let mut entries_map = HashMap::new();
+    get_tree_entries_map(root_path, path, tree_hash, &mut entries_map)?;
+
+    files_map.extend(
+        entries_map
+            .into_iter()
+            .map(|(entry_path, entry)| (entry_path, entry.hash)),
+    );
+
+    Ok(())
+}
+
+/// Recursively scans the working directory into a `path -> (mode, blob_hash)` map.
+///
+/// Symlinks are recorded as links (hashing their target path) and never followed.
+pub fn get_work_dir_entries(
+    root_path: &Path,
+    path: &Path,
+    map: &mut HashMap<PathBuf, IndexEntry>,
 ) -> Result<()> {
     let dir = fs::read_dir(root_path.join(path))?;
 
@@ -96,14 +152,39 @@ pub fn get_work_dir_map(
         }
 
         if entry.file_type()?.is_dir() {
-            get_work_dir_map(&root_path, &entry_path, map)?;
+            get_work_dir_entries(root_path, &entry_path, map)?;
         } else {
-            let file_content = fs::read(root_path.join(&entry_path))?;
+            let full_path = root_path.join(&entry_path);
+            let file_content = utils::read_work_file(&full_path)?;
             let blob_content = commands::format_object(&file_content);
             let blob_hash = utils::hash_bytes(&blob_content);
 
-            map.insert(entry_path, blob_hash);
+            map.insert(
+                entry_path,
+                IndexEntry {
+                    mode: utils::work_file_mode(&full_path)?,
+                    hash: blob_hash,
+                },
+            );
         }
     }
     Ok(())
 }
+
+/// Recursively scans the working directory into a `path -> blob_hash` map.
+pub fn get_work_dir_map(
+    root_path: &Path,
+    path: &Path,
+    map: &mut HashMap<PathBuf, String>,
+) -> Result<()> {
+    let mut entries_map = HashMap::new();
+    get_work_dir_entries(root_path, path, &mut entries_map)?;
+
+    map.extend(
+        entries_map
+            .into_iter()
+            .map(|(entry_path, entry)| (entry_path, entry.hash)),
+    );
+
+    Ok(())
+}
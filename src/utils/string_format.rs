@@ -17,6 +17,7 @@ use crate::utils;
 /// Output format (plain text example):
 /// ```text
 /// commit <hash>
+/// Merge: <parent1> <parent2>      (merge commits only, abbreviated hashes)
 /// Author: Name <email>
 /// Date:   Wed Dec  6 12:34:56 2025 +0530
 ///
@@ -38,6 +39,7 @@ pub fn format_commit_history(commit_data: &str, hash: &str) -> Result<String> {
 
     let mut author: Option<String> = None;
     let mut date: Option<String> = None;
+    let parents = utils::get_parent_hashes(data);
 
     let mut out = String::new();
     out.push_str(&format!("commit {}\n", hash));
@@ -49,6 +51,10 @@ pub fn format_commit_history(commit_data: &str, hash: &str) -> Result<String> {
 
         if !in_message {
             if line.is_empty() {
+                if parents.len() > 1 {
+                    let short: Vec<&str> = parents.iter().map(|p| &p[..p.len().min(7)]).collect();
+                    out.push_str(&format!("Merge: {}\n", short.join(" ")));
+                }
                 if let Some(a) = author.as_deref() {
                     out.push_str(&format!("Author: {}\n", a));
                 }
@@ -0,0 +1,185 @@
+//! Line-level three-way merging of file contents.
+
+use similar::{Algorithm, DiffOp};
+
+/// A run of base lines `[start, end)` that one side replaced with `lines`.
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a [u8]>,
+}
+
+/// Result of merging one file.
+pub struct TextMergeResult {
+    /// Merged bytes, including conflict markers when `conflicted`.
+    pub content: Vec<u8>,
+    /// True if at least one region was changed differently on both sides.
+    pub conflicted: bool,
+}
+
+fn split_lines(content: &[u8]) -> Vec<&[u8]> {
+    content.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Collects the changes `side` made to `base`, coalescing touching edits.
+fn collect_hunks<'a>(base: &[&'a [u8]], side: &[&'a [u8]]) -> Vec<Hunk<'a>> {
+    let mut hunks: Vec<Hunk<'a>> = Vec::new();
+
+    for op in similar::capture_diff_slices(Algorithm::Myers, base, side) {
+        let (old_range, new_range) = match op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => (old_index..old_index + old_len, 0..0),
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => (old_index..old_index, new_index..new_index + new_len),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => (
+                old_index..old_index + old_len,
+                new_index..new_index + new_len,
+            ),
+        };
+
+        match hunks.last_mut() {
+            Some(last) if last.end == old_range.start => {
+                last.end = old_range.end;
+                last.lines.extend_from_slice(&side[new_range]);
+            }
+            _ => hunks.push(Hunk {
+                start: old_range.start,
+                end: old_range.end,
+                lines: side[new_range].to_vec(),
+            }),
+        }
+    }
+
+    hunks
+}
+
+/// Rebuilds one side's version of base lines `[start, end)` from its hunks.
+fn apply_hunks<'a>(
+    base: &[&'a [u8]],
+    hunks: &[Hunk<'a>],
+    start: usize,
+    end: usize,
+) -> Vec<&'a [u8]> {
+    let mut lines = Vec::new();
+    let mut cursor = start;
+
+    for hunk in hunks {
+        lines.extend_from_slice(&base[cursor..hunk.start]);
+        lines.extend_from_slice(&hunk.lines);
+        cursor = hunk.end;
+    }
+    lines.extend_from_slice(&base[cursor..end]);
+
+    lines
+}
+
+fn push_lines(out: &mut Vec<u8>, lines: &[&[u8]]) {
+    for line in lines {
+        out.extend_from_slice(line);
+    }
+}
+
+fn push_marker(out: &mut Vec<u8>, marker: &str, label: &str) {
+    // Markers always start on their own line, even after a final line
+    // without a trailing newline.
+    if !out.is_empty() && !out.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+    out.extend_from_slice(marker.as_bytes());
+    if !label.is_empty() {
+        out.push(b' ');
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(b'\n');
+}
+
+/// Merges `ours` and `theirs`, both derived from `base`, line by line.
+///
+/// Changes that touch or overlap the same base lines on both sides are
+/// conflicts unless both sides made the identical change. Conflicting
+/// regions are written as
+/// `<<<<<<< our_label`, our lines, `=======`, their lines, `>>>>>>> their_label`.
+pub fn merge_text(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    our_label: &str,
+    their_label: &str,
+) -> TextMergeResult {
+    let base_lines = split_lines(base);
+    let our_lines = split_lines(ours);
+    let their_lines = split_lines(theirs);
+
+    let our_hunks = collect_hunks(&base_lines, &our_lines);
+    let their_hunks = collect_hunks(&base_lines, &their_lines);
+
+    let mut content = Vec::new();
+    let mut conflicted = false;
+    let mut cursor = 0;
+    let (mut i, mut j) = (0, 0);
+
+    while i < our_hunks.len() || j < their_hunks.len() {
+        // Start a region at the earliest pending hunk, then absorb every hunk
+        // from either side that overlaps or touches it.
+        let start = match (our_hunks.get(i), their_hunks.get(j)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => unreachable!(),
+        };
+        let mut end = start;
+        let (first_i, first_j) = (i, j);
+
+        loop {
+            if let Some(hunk) = our_hunks.get(i).filter(|h| h.start <= end) {
+                end = end.max(hunk.end);
+                i += 1;
+            } else if let Some(hunk) = their_hunks.get(j).filter(|h| h.start <= end) {
+                end = end.max(hunk.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        let ours_in_region = &our_hunks[first_i..i];
+        let theirs_in_region = &their_hunks[first_j..j];
+
+        push_lines(&mut content, &base_lines[cursor..start]);
+
+        let our_version = apply_hunks(&base_lines, ours_in_region, start, end);
+        let their_version = apply_hunks(&base_lines, theirs_in_region, start, end);
+
+        if theirs_in_region.is_empty() || our_version == their_version {
+            push_lines(&mut content, &our_version);
+        } else if ours_in_region.is_empty() {
+            push_lines(&mut content, &their_version);
+        } else {
+            conflicted = true;
+            push_marker(&mut content, "<<<<<<<", our_label);
+            push_lines(&mut content, &our_version);
+            push_marker(&mut content, "=======", "");
+            push_lines(&mut content, &their_version);
+            push_marker(&mut content, ">>>>>>>", their_label);
+        }
+
+        cursor = end;
+    }
+
+    push_lines(&mut content, &base_lines[cursor..]);
+
+    TextMergeResult {
+        content,
+        conflicted,
+    }
+}
@@ -0,0 +1,307 @@
+//! Tree diffs: comparing two `path -> (mode, blob_hash)` snapshots.
+//!
+//! Snapshots come from commit trees (`get_commit_entries_map`), the index
+//! (`read_effective_index`) or the working tree (`get_work_dir_entries`),
+//! so the same comparison serves every `diff` mode. [`detect_renames`] then
+//! pairs deleted and added paths with similar content into renames (and,
+//! optionally, added paths with modified ones into copies).
+
+use crate::utils::{self, IndexEntry, Pathspec};
+use anyhow::Result;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, hash_map::Entry},
+    path::{Path, PathBuf},
+};
+
+/// Rename detection threshold used unless `-M<pct>` gives another.
+pub const DEFAULT_RENAME_THRESHOLD: u8 = 50;
+
+/// Hash of the empty blob.
+const EMPTY_BLOB_HASH: &str = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+
+/// How a path changed between two snapshots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    /// Content and/or mode changed.
+    Modified,
+    /// Moved from another path, possibly with changes.
+    Renamed,
+    /// Copied from another path, possibly with changes.
+    Copied,
+}
+
+/// The path a renamed or copied entry came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangeSource {
+    pub path: PathBuf,
+    /// How much of the content the two sides share, in percent.
+    pub similarity: u8,
+    /// Set for copies: the source path still exists.
+    pub copy: bool,
+}
+
+/// A path whose entry differs between the old and new snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeChange {
+    /// The path in the new snapshot, or the old one for deletions.
+    pub path: PathBuf,
+    /// The entry in the old snapshot (at `source.path` for renames and
+    /// copies); `None` when the path was added.
+    pub old: Option<IndexEntry>,
+    /// The entry in the new snapshot; `None` when the path was deleted.
+    pub new: Option<IndexEntry>,
+    /// Where the entry was renamed or copied from, if anywhere.
+    pub source: Option<ChangeSource>,
+}
+
+impl TreeChange {
+    pub fn kind(&self) -> ChangeKind {
+        match (&self.old, &self.new, &self.source) {
+            (_, _, Some(source)) if source.copy => ChangeKind::Copied,
+            (_, _, Some(_)) => ChangeKind::Renamed,
+            (None, _, _) => ChangeKind::Added,
+            (_, None, _) => ChangeKind::Deleted,
+            _ => ChangeKind::Modified,
+        }
+    }
+
+    /// Returns the path in the old snapshot.
+    pub fn old_path(&self) -> &Path {
+        self.source
+            .as_ref()
+            .map_or(self.path.as_path(), |source| source.path.as_path())
+    }
+}
+
+/// How [`detect_renames`] pairs paths up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenameOptions {
+    /// Minimum similarity, in percent, of a rename or copy.
+    pub threshold: u8,
+    /// Also look for added paths copied from modified or renamed ones.
+    pub copies: bool,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        RenameOptions {
+            threshold: DEFAULT_RENAME_THRESHOLD,
+            copies: false,
+        }
+    }
+}
+
+/// Returns true if `path` is selected by any of `pathspecs`, or if there
+/// are none.
+pub fn matches_pathspecs(path: &Path, pathspecs: &[Pathspec]) -> bool {
+    pathspecs.is_empty() || pathspecs.iter().any(|pathspec| pathspec.matches(path))
+}
+
+/// Compares two snapshots, returning the changed paths sorted by path.
+///
+/// An entry whose blob or mode differs is a modification. Only paths
+/// selected by `pathspecs` are compared (every path when it is empty).
+pub fn diff_entry_maps(
+    old: &HashMap<PathBuf, IndexEntry>,
+    new: &HashMap<PathBuf, IndexEntry>,
+    pathspecs: &[Pathspec],
+) -> Vec<TreeChange> {
+    let paths: BTreeSet<&PathBuf> = old.keys().chain(new.keys()).collect();
+
+    paths
+        .into_iter()
+        .filter(|path| matches_pathspecs(path, pathspecs))
+        .filter_map(|path| {
+            let old_entry = old.get(path);
+            let new_entry = new.get(path);
+
+            (old_entry != new_entry).then(|| TreeChange {
+                path: path.clone(),
+                old: old_entry.cloned(),
+                new: new_entry.cloned(),
+                source: None,
+            })
+        })
+        .collect()
+}
+
+/// Returns how similar two contents are, in percent: the bytes of the lines
+/// they have in common over the size of the larger one.
+fn similarity(old: &[u8], new: &[u8]) -> u8 {
+    let larger = old.len().max(new.len());
+    if larger == 0 {
+        return 100;
+    }
+
+    let mut old_lines: HashMap<&[u8], usize> = HashMap::new();
+    for line in old.split_inclusive(|&byte| byte == b'\n') {
+        *old_lines.entry(line).or_default() += 1;
+    }
+
+    let mut common = 0;
+    for line in new.split_inclusive(|&byte| byte == b'\n') {
+        if let Some(count) = old_lines.get_mut(line)
+            && *count > 0
+        {
+            *count -= 1;
+            common += line.len();
+        }
+    }
+
+    (common * 100 / larger) as u8
+}
+
+/// Pairs deleted paths with added paths of similar content into renames.
+///
+/// Identical blobs are paired first, then the remaining pairs from most to
+/// least similar, down to `options.threshold`. With `options.copies`, added
+/// paths may also be copies of modified paths, or of a deleted path that
+/// was already renamed. Empty files are left alone. The new side is read
+/// from the working tree when `work_tree` is set. Returns the changes
+/// sorted by path, each renamed or copied path once under its new name.
+pub fn detect_renames(
+    root_path: &Path,
+    changes: Vec<TreeChange>,
+    work_tree: bool,
+    options: RenameOptions,
+) -> Result<Vec<TreeChange>> {
+    // Empty files are never paired up.
+    let candidate = |entry: &Option<IndexEntry>| {
+        entry
+            .as_ref()
+            .filter(|entry| entry.hash != EMPTY_BLOB_HASH)
+            .cloned()
+    };
+    let added: Vec<(usize, IndexEntry)> = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| change.old.is_none())
+        .filter_map(|(index, change)| Some((index, candidate(&change.new)?)))
+        .collect();
+    let sources: Vec<(usize, IndexEntry)> = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| change.new.is_none() || options.copies)
+        .filter_map(|(index, change)| Some((index, candidate(&change.old)?)))
+        .collect();
+
+    if added.is_empty() || sources.is_empty() {
+        return Ok(changes);
+    }
+
+    // (added, source, similarity) for every pair at or above the threshold.
+    let mut pairs: Vec<(usize, usize, u8)> = Vec::new();
+    let mut old_contents: HashMap<usize, Vec<u8>> = HashMap::new();
+
+    for (target, new_entry) in &added {
+        let exact: Vec<usize> = sources
+            .iter()
+            .filter(|(_, old_entry)| old_entry.hash == new_entry.hash)
+            .map(|(source, _)| *source)
+            .collect();
+        if !exact.is_empty() {
+            pairs.extend(exact.into_iter().map(|source| (*target, source, 100)));
+            continue;
+        }
+
+        let new_content = if work_tree {
+            utils::read_work_file(&root_path.join(&changes[*target].path))?
+        } else {
+            utils::read_blob(root_path, &new_entry.hash)?
+        };
+
+        for (source, old_entry) in &sources {
+            if let Entry::Vacant(slot) = old_contents.entry(*source) {
+                slot.insert(utils::read_blob(root_path, &old_entry.hash)?);
+            }
+            let old_content = &old_contents[source];
+
+            // Sizes too far apart cannot reach the threshold.
+            let (smaller, larger) = if old_content.len() < new_content.len() {
+                (old_content.len(), new_content.len())
+            } else {
+                (new_content.len(), old_content.len())
+            };
+            if smaller * 100 < larger * usize::from(options.threshold) {
+                continue;
+            }
+
+            let score = similarity(old_content, &new_content);
+            if score >= options.threshold {
+                pairs.push((*target, *source, score));
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        b.2.cmp(&a.2)
+            .then_with(|| changes[a.0].path.cmp(&changes[b.0].path))
+            .then_with(|| changes[a.1].path.cmp(&changes[b.1].path))
+    });
+
+    let mut matched: HashMap<usize, ChangeSource> = HashMap::new();
+    let mut renamed: HashSet<usize> = HashSet::new();
+    let mut copied_from: HashMap<usize, usize> = HashMap::new();
+
+    for (target, source, score) in pairs {
+        if matched.contains_key(&target) {
+            continue;
+        }
+
+        let copy = changes[source].new.is_some() || renamed.contains(&source);
+        if copy && !options.copies {
+            continue;
+        }
+        if !copy {
+            renamed.insert(source);
+        }
+        copied_from.insert(target, source);
+        matched.insert(
+            target,
+            ChangeSource {
+                path: changes[source].path.clone(),
+                similarity: score,
+                copy,
+            },
+        );
+    }
+
+    // A deleted path copied as well as renamed keeps the rename for its
+    // last target by path, so that applying the changes in order copies it
+    // before it goes away.
+    for &source in &renamed {
+        let last = copied_from
+            .iter()
+            .filter(|&(_, &from)| from == source)
+            .map(|(&target, _)| target)
+            .max_by(|&a, &b| changes[a].path.cmp(&changes[b].path));
+        for (target, change_source) in matched.iter_mut() {
+            if copied_from[target] == source {
+                change_source.copy = Some(*target) != last;
+            }
+        }
+    }
+
+    let old_entries: HashMap<usize, Option<IndexEntry>> = copied_from
+        .iter()
+        .map(|(&target, &source)| (target, changes[source].old.clone()))
+        .collect();
+
+    let mut result: Vec<TreeChange> = changes
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !renamed.contains(index))
+        .map(|(index, mut change)| {
+            if let Some(source) = matched.remove(&index) {
+                change.old = old_entries[&index].clone();
+                change.source = Some(source);
+            }
+            change
+        })
+        .collect();
+
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(result)
+}
@@ -0,0 +1,64 @@
+//! Incremental working-tree updates between two tree snapshots.
+
+use crate::utils::{self, IndexEntry};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Removes `path` and then any directories above it that became empty.
+pub fn remove_work_file(root_path: &Path, path: &Path) -> Result<()> {
+    let full_path = root_path.join(path);
+
+    if fs::symlink_metadata(&full_path).is_ok() {
+        fs::remove_file(&full_path)?;
+    }
+
+    let mut parent = full_path.parent();
+    while let Some(dir) = parent {
+        if dir == root_path || fs::read_dir(dir).map_or(true, |mut it| it.next().is_some()) {
+            break;
+        }
+        fs::remove_dir(dir)?;
+        parent = dir.parent();
+    }
+
+    Ok(())
+}
+
+/// Moves the working tree from the `from` snapshot to the `to` snapshot.
+///
+/// Only paths whose mode or blob differ are touched: removed paths are
+/// deleted, and added or changed paths are written from the object store.
+/// Files outside both snapshots (untracked or ignored) are left alone.
+pub fn update_work_tree(
+    root_path: &Path,
+    from: &HashMap<PathBuf, IndexEntry>,
+    to: &HashMap<PathBuf, IndexEntry>,
+) -> Result<()> {
+    for path in from.keys() {
+        if !to.contains_key(path) {
+            remove_work_file(root_path, path)?;
+        }
+    }
+
+    for (path, entry) in to {
+        if from.get(path) == Some(entry) {
+            continue;
+        }
+
+        // A directory whose tracked files were removed above may need to
+        // give way to a file; untracked leftovers make this fail loudly.
+        let full_path = root_path.join(path);
+        if full_path.is_dir() && !full_path.is_symlink() {
+            fs::remove_dir(&full_path)?;
+        }
+
+        let content = utils::read_blob(root_path, &entry.hash)?;
+        utils::write_work_file(&full_path, entry.mode, &content)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use assert_cmd::Command;
+use rustygit::commands;
+use rustygit::utils::{self, ConfigScope};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn head(repo_root: &Path) -> String {
+    utils::get_current_commit_hash(repo_root).unwrap().unwrap()
+}
+
+fn set_identity(repo_root: &Path, name: &str, email: &str) {
+    utils::set_config_value(repo_root, ConfigScope::Local, "user.name", name).unwrap();
+    utils::set_config_value(repo_root, ConfigScope::Local, "user.email", email).unwrap();
+}
+
+/// Creates a repository holding the same base commit as every other one.
+fn setup_repo(repo_root: &Path, name: &str, email: &str) -> String {
+    fs::create_dir_all(repo_root).unwrap();
+    commands::init(repo_root).unwrap();
+    set_identity(repo_root, name, email);
+    fs::write(repo_root.join("greeting.txt"), "hello\n").unwrap();
+    fs::write(repo_root.join("old.txt"), "old\n").unwrap();
+    commit_all(repo_root, "Initial commit")
+}
+
+#[test]
+fn format_patch_output_recreates_the_commits() {
+    let dir = tempdir().unwrap();
+    let contractor = dir.path().join("contractor");
+    let maintainer = dir.path().join("maintainer");
+    let base = setup_repo(&contractor, "Carol Contractor", "carol@example.com");
+    setup_repo(&maintainer, "Max Maintainer", "max@example.com");
+
+    fs::write(contractor.join("greeting.txt"), "hello\nworld\n").unwrap();
+    commit_all(&contractor, "Add world to the greeting");
+    fs::create_dir_all(contractor.join("docs")).unwrap();
+    fs::write(contractor.join("docs/notes.md"), "# Notes\n").unwrap();
+    commands::rm(&contractor, Path::new("old.txt")).unwrap();
+    commit_all(
+        &contractor,
+        "Replace old.txt with notes\n\nThe notes live in docs/ now.",
+    );
+    let outgoing = dir.path().join("outgoing");
+    let files = commands::format_patch(&contractor, &base, &outgoing).unwrap();
+
+    let commits = commands::am(&maintainer, &files).unwrap();
+
+    assert_eq!(commits.len(), 2);
+    assert_eq!(head(&maintainer), commits[1]);
+    assert_eq!(
+        utils::get_commit_parents(&maintainer, &commits[1]).unwrap(),
+        vec![commits[0].clone()]
+    );
+    assert_eq!(
+        utils::get_commit_tree_hash(&maintainer, &commits[1]).unwrap(),
+        utils::get_commit_tree_hash(&contractor, &head(&contractor)).unwrap()
+    );
+
+    for (index, revision) in ["HEAD~1", "HEAD"].iter().enumerate() {
+        let original = utils::resolve_commit(&contractor, revision).unwrap();
+        assert_eq!(
+            utils::get_commit_author(&maintainer, &commits[index]).unwrap(),
+            utils::get_commit_author(&contractor, &original).unwrap()
+        );
+        assert_eq!(
+            utils::get_commit_message(&maintainer, &commits[index]).unwrap(),
+            utils::get_commit_message(&contractor, &original).unwrap()
+        );
+    }
+    let commit = utils::parse_commit(&maintainer, &commits[1]).unwrap();
+    assert!(commit.contains("\ncommitter Max Maintainer <max@example.com> "));
+
+    // The working tree and index follow the new commits.
+    assert_eq!(
+        fs::read_to_string(maintainer.join("greeting.txt")).unwrap(),
+        "hello\nworld\n"
+    );
+    assert!(!maintainer.join("old.txt").exists());
+    assert!(maintainer.join("docs/notes.md").exists());
+    let index = utils::read_effective_index(&maintainer).unwrap();
+    assert_eq!(
+        index,
+        utils::get_commit_entries_map(&maintainer, Some(&commits[1])).unwrap()
+    );
+}
+
+#[test]
+fn keeps_the_author_date_of_a_handwritten_mailbox() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    setup_repo(&repo_root, "Max Maintainer", "max@example.com");
+
+    let mailbox = dir.path().join("mbox");
+    fs::write(
+        &mailbox,
+        "\
+From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001
+From: \"Doe, Jane\" <jane@example.com>
+Date: Thu, 7 Apr 2005 15:13:13 -0700
+Subject: [PATCH v2 1/1] Re: Shout the
+ greeting
+
+Louder is better.
+---
+ greeting.txt | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/greeting.txt b/greeting.txt
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1 @@
+-hello
++HELLO
+--
+2.40.0
+
+",
+    )
+    .unwrap();
+
+    let commits = commands::am(&repo_root, std::slice::from_ref(&mailbox)).unwrap();
+
+    let author = utils::get_commit_author(&repo_root, &commits[0]).unwrap();
+    assert_eq!(author.name, "Doe, Jane");
+    assert_eq!(author.email, "jane@example.com");
+    assert_eq!(author.timestamp, 1112911993);
+    assert_eq!(author.timezone, "-0700");
+    assert_eq!(
+        utils::get_commit_message(&repo_root, &commits[0]).unwrap(),
+        "Shout the greeting\n\nLouder is better."
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("greeting.txt")).unwrap(),
+        "HELLO\n"
+    );
+}
+
+#[test]
+fn stops_at_the_first_patch_that_does_not_apply() {
+    let dir = tempdir().unwrap();
+    let contractor = dir.path().join("contractor");
+    let maintainer = dir.path().join("maintainer");
+    let base = setup_repo(&contractor, "Carol Contractor", "carol@example.com");
+    setup_repo(&maintainer, "Max Maintainer", "max@example.com");
+
+    fs::write(contractor.join("new.txt"), "new\n").unwrap();
+    commit_all(&contractor, "Add new.txt");
+    fs::write(contractor.join("greeting.txt"), "hello there\n").unwrap();
+    commit_all(&contractor, "Change the greeting");
+    let files = commands::format_patch(&contractor, &base, &dir.path().join("out")).unwrap();
+
+    fs::write(maintainer.join("greeting.txt"), "howdy\n").unwrap();
+    let diverged = commit_all(&maintainer, "Diverge");
+
+    let err = commands::am(&maintainer, &files).unwrap_err().to_string();
+
+    assert!(
+        err.starts_with(
+            "Patch failed at 0002 Change the greeting: patch failed: greeting.txt: hunk at line 1"
+        ),
+        "{}",
+        err
+    );
+    assert!(
+        err.contains("1 patch(es) before it were committed."),
+        "{}",
+        err
+    );
+    assert_eq!(
+        utils::get_commit_parents(&maintainer, &head(&maintainer)).unwrap(),
+        vec![diverged]
+    );
+    assert_eq!(
+        fs::read_to_string(maintainer.join("greeting.txt")).unwrap(),
+        "howdy\n"
+    );
+    assert!(maintainer.join("new.txt").exists());
+}
+
+#[test]
+fn refuses_staged_changes_and_unstaged_changes_to_patched_files() {
+    let dir = tempdir().unwrap();
+    let contractor = dir.path().join("contractor");
+    let maintainer = dir.path().join("maintainer");
+    let base = setup_repo(&contractor, "Carol Contractor", "carol@example.com");
+    let maintainer_base = setup_repo(&maintainer, "Max Maintainer", "max@example.com");
+
+    fs::write(contractor.join("greeting.txt"), "hello\nworld\n").unwrap();
+    commit_all(&contractor, "Add world to the greeting");
+    let files = commands::format_patch(&contractor, &base, &dir.path().join("out")).unwrap();
+
+    fs::write(maintainer.join("old.txt"), "staged\n").unwrap();
+    commands::add(&maintainer, Path::new("old.txt")).unwrap();
+    let err = commands::am(&maintainer, &files).unwrap_err();
+    assert!(err.to_string().contains("staged changes"), "{}", err);
+
+    commands::reset(&maintainer, "HEAD", commands::ResetMode::Hard).unwrap();
+    fs::write(maintainer.join("greeting.txt"), "hello\nlocal edit\n").unwrap();
+    let err = commands::am(&maintainer, &files).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("greeting.txt: does not match index"),
+        "{}",
+        err
+    );
+    assert_eq!(head(&maintainer), maintainer_base);
+    assert_eq!(
+        fs::read_to_string(maintainer.join("greeting.txt")).unwrap(),
+        "hello\nlocal edit\n"
+    );
+}
+
+#[test]
+fn cli_am_prints_applied_subjects() {
+    let dir = tempdir().unwrap();
+    let contractor = dir.path().join("contractor");
+    let maintainer = dir.path().join("maintainer");
+    setup_repo(&contractor, "Carol Contractor", "carol@example.com");
+    setup_repo(&maintainer, "Max Maintainer", "max@example.com");
+
+    fs::write(contractor.join("greeting.txt"), "hi\n").unwrap();
+    commit_all(&contractor, "Say hi");
+    let files = commands::format_patch(&contractor, "HEAD~1", &dir.path().join("out")).unwrap();
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&maintainer)
+        .arg("am")
+        .arg(&files[0])
+        .assert()
+        .success()
+        .stdout("Applying: Say hi\n");
+    assert_eq!(
+        fs::read_to_string(maintainer.join("greeting.txt")).unwrap(),
+        "hi\n"
+    );
+}
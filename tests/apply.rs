@@ -0,0 +1,350 @@
+use assert_cmd::Command;
+use rustygit::commands::{self, ApplyOptions, DiffMode};
+use rustygit::utils;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn numbered_lines(count: usize) -> String {
+    (1..=count)
+        .map(|number| format!("line {}\n", number))
+        .collect()
+}
+
+fn write_patch(dir: &Path, patch: &str) -> std::path::PathBuf {
+    let patch_file = dir.join("change.patch");
+    fs::write(&patch_file, patch).unwrap();
+    patch_file
+}
+
+/// Writes the files every test repository starts from.
+fn write_base(repo_root: &Path) {
+    fs::write(repo_root.join("long.txt"), numbered_lines(20)).unwrap();
+    fs::write(repo_root.join("old.txt"), "going away\n").unwrap();
+    fs::create_dir_all(repo_root.join("src")).unwrap();
+    fs::write(repo_root.join("src/lib.rs"), "fn main() {}").unwrap();
+}
+
+#[test]
+fn diff_output_applies_to_another_repository() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("source");
+    let target = dir.path().join("target");
+
+    for repo_root in [&source, &target] {
+        fs::create_dir_all(repo_root).unwrap();
+        commands::init(repo_root).unwrap();
+        write_base(repo_root);
+        commit_all(repo_root, "base");
+    }
+
+    let long = numbered_lines(20)
+        .replace("line 2\n", "line two\n")
+        .replace("line 18\n", "line 18\nline 18.5\n");
+    fs::write(source.join("long.txt"), &long).unwrap();
+    fs::write(source.join("src/lib.rs"), "fn main() {\n    run();\n}\n").unwrap();
+    fs::write(source.join("src/new.rs"), "pub fn run() {}\n").unwrap();
+    commands::rm(&source, Path::new("old.txt")).unwrap();
+    commit_all(&source, "change");
+
+    let mode = DiffMode::from_args(false, &[String::from("HEAD~1..HEAD")]).unwrap();
+    let patch = commands::diff(&source, &mode, &[], commands::DEFAULT_CONTEXT).unwrap();
+    let patch_file = write_patch(dir.path(), &patch);
+
+    let paths = commands::apply(&target, &patch_file, ApplyOptions::default()).unwrap();
+
+    assert_eq!(paths.len(), 4);
+    assert_eq!(fs::read_to_string(target.join("long.txt")).unwrap(), long);
+    assert!(!target.join("old.txt").exists());
+    assert_eq!(
+        fs::read_to_string(target.join("src/lib.rs")).unwrap(),
+        "fn main() {\n    run();\n}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(target.join("src/new.rs")).unwrap(),
+        "pub fn run() {}\n"
+    );
+
+    // Applying to the working tree leaves the index alone.
+    let index = utils::read_index_entries(&target).unwrap();
+    assert!(index.contains_key(Path::new("old.txt")));
+    assert!(!index.contains_key(Path::new("src/new.rs")));
+
+    // The same patch no longer applies, and reversing it restores the base.
+    let err = commands::apply(&target, &patch_file, ApplyOptions::default()).unwrap_err();
+    assert!(
+        err.to_string().starts_with("patch failed: long.txt"),
+        "{}",
+        err
+    );
+
+    let reverse = ApplyOptions {
+        reverse: true,
+        ..ApplyOptions::default()
+    };
+    commands::apply(&target, &patch_file, reverse).unwrap();
+    assert_eq!(
+        fs::read_to_string(target.join("long.txt")).unwrap(),
+        numbered_lines(20)
+    );
+    assert_eq!(
+        fs::read_to_string(target.join("src/lib.rs")).unwrap(),
+        "fn main() {}"
+    );
+    assert!(target.join("old.txt").exists());
+    assert!(!target.join("src/new.rs").exists());
+}
+
+#[test]
+fn check_reports_failure_and_changes_nothing() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    commands::init(&repo_root).unwrap();
+    write_base(&repo_root);
+    commit_all(&repo_root, "base");
+
+    // The first file applies, the second does not.
+    let patch = "\
+diff --git a/old.txt b/old.txt
+--- a/old.txt
++++ b/old.txt
+@@ -1 +1 @@
+-going away
++staying
+diff --git a/long.txt b/long.txt
+--- a/long.txt
++++ b/long.txt
+@@ -4,3 +4,3 @@
+ line 4
+-line five
++line 5
+ line 6
+";
+    let patch_file = write_patch(dir.path(), patch);
+
+    for check in [true, false] {
+        let options = ApplyOptions {
+            check,
+            ..ApplyOptions::default()
+        };
+        let err = commands::apply(&repo_root, &patch_file, options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "patch failed: long.txt: hunk at line 4 does not apply"
+        );
+        assert_eq!(
+            fs::read_to_string(repo_root.join("old.txt")).unwrap(),
+            "going away\n"
+        );
+    }
+
+    let good_patch = patch.split("diff --git a/long.txt").next().unwrap();
+    let patch_file = write_patch(dir.path(), good_patch);
+    let check = ApplyOptions {
+        check: true,
+        ..ApplyOptions::default()
+    };
+    assert_eq!(
+        commands::apply(&repo_root, &patch_file, check).unwrap(),
+        vec![Path::new("old.txt").to_path_buf()]
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("old.txt")).unwrap(),
+        "going away\n"
+    );
+}
+
+#[test]
+fn plain_patch_with_moved_hunks_and_missing_newline() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    commands::init(&repo_root).unwrap();
+    write_base(&repo_root);
+
+    // Three lines were added at the top since the patch was made.
+    let long = format!("new 1\nnew 2\nnew 3\n{}", numbered_lines(20));
+    fs::write(repo_root.join("long.txt"), &long).unwrap();
+
+    let patch = "\
+Some description that is not part of the diff.
+--- long.txt.orig\t2024-01-01 10:00:00.000000000 +0000
++++ long.txt\t2024-01-02 10:00:00.000000000 +0000
+@@ -9,3 +9,3 @@
+ line 9
+-line 10
++line ten
+ line 11
+--- project.orig/src/lib.rs
++++ project/src/lib.rs
+@@ -1 +1,3 @@
+-fn main() {}
+\\ No newline at end of file
++fn main() {
++    run();
++}
+";
+    let patch_file = write_patch(dir.path(), patch);
+
+    commands::apply(&repo_root, &patch_file, ApplyOptions::default()).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(repo_root.join("long.txt")).unwrap(),
+        long.replace("line 10\n", "line ten\n")
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("src/lib.rs")).unwrap(),
+        "fn main() {\n    run();\n}\n"
+    );
+}
+
+#[test]
+fn cached_applies_renames_to_the_index_only() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    commands::init(&repo_root).unwrap();
+    write_base(&repo_root);
+    commit_all(&repo_root, "base");
+
+    let patch = "\
+diff --git a/old.txt b/docs/kept.txt
+similarity index 50%
+rename from old.txt
+rename to docs/kept.txt
+index 1111111..2222222 100644
+--- a/old.txt
++++ b/docs/kept.txt
+@@ -1 +1,2 @@
+ going away
++but kept
+";
+    let patch_file = write_patch(dir.path(), patch);
+    let cached = ApplyOptions {
+        cached: true,
+        ..ApplyOptions::default()
+    };
+
+    commands::apply(&repo_root, &patch_file, cached).unwrap();
+
+    let index = utils::read_index_entries(&repo_root).unwrap();
+    assert!(!index.contains_key(Path::new("old.txt")));
+    let entry = &index[Path::new("docs/kept.txt")];
+    assert_eq!(entry.mode, utils::MODE_FILE);
+    assert_eq!(
+        utils::read_blob(&repo_root, &entry.hash).unwrap(),
+        b"going away\nbut kept\n"
+    );
+    assert!(repo_root.join("old.txt").exists());
+    assert!(!repo_root.join("docs").exists());
+
+    // Rename and mode-only patches need no hunks.
+    let patch = "\
+diff --git a/docs/kept.txt b/kept.txt
+similarity index 100%
+rename from docs/kept.txt
+rename to kept.txt
+diff --git a/long.txt b/long.txt
+old mode 100644
+new mode 100755
+";
+    let patch_file = write_patch(dir.path(), patch);
+    commands::apply(&repo_root, &patch_file, cached).unwrap();
+
+    let index = utils::read_index_entries(&repo_root).unwrap();
+    assert!(index.contains_key(Path::new("kept.txt")));
+    assert!(!index.contains_key(Path::new("docs/kept.txt")));
+    assert_eq!(index[Path::new("long.txt")].mode, utils::MODE_EXECUTABLE);
+}
+
+#[cfg(unix)]
+#[test]
+fn mode_changes_apply_to_the_working_tree() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    commands::init(&repo_root).unwrap();
+    write_base(&repo_root);
+
+    let patch = "\
+diff --git a/run.sh b/run.sh
+new file mode 100755
+index 0000000..1234567
+--- /dev/null
++++ b/run.sh
+@@ -0,0 +1,2 @@
++#!/bin/sh
++echo hi
+";
+    let patch_file = write_patch(dir.path(), patch);
+    commands::apply(&repo_root, &patch_file, ApplyOptions::default()).unwrap();
+
+    let mode = fs::metadata(repo_root.join("run.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_ne!(mode & 0o100, 0);
+    assert_eq!(
+        fs::read_to_string(repo_root.join("run.sh")).unwrap(),
+        "#!/bin/sh\necho hi\n"
+    );
+}
+
+#[test]
+fn rejects_paths_outside_the_working_tree() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    commands::init(&repo_root).unwrap();
+
+    for path in ["../escape.txt", ".rustygit/HEAD"] {
+        let patch = format!("--- /dev/null\n+++ b/{}\n@@ -0,0 +1 @@\n+text\n", path);
+        let patch_file = write_patch(dir.path(), &patch);
+        let err = commands::apply(&repo_root, &patch_file, ApplyOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("invalid path"), "{}", err);
+    }
+    assert!(!dir.path().join("escape.txt").exists());
+}
+
+#[test]
+fn cli_apply_reads_standard_input() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    commands::init(repo_root).unwrap();
+    write_base(repo_root);
+    commit_all(repo_root, "base");
+
+    fs::write(repo_root.join("old.txt"), "changed\n").unwrap();
+    let mode = DiffMode::WorkTree;
+    let patch = commands::diff(repo_root, &mode, &[], commands::DEFAULT_CONTEXT).unwrap();
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(repo_root)
+        .args(["apply", "-R", "-"])
+        .write_stdin(patch.clone())
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(repo_root.join("old.txt")).unwrap(),
+        "going away\n"
+    );
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(repo_root)
+        .args(["apply", "--check", "-R", "-"])
+        .write_stdin(patch)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("patch failed: old.txt"), "{}", stderr);
+}
@@ -0,0 +1,84 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::fs;
+use tempfile::tempdir;
+
+const PNG_BYTES: &[u8] = &[
+    0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0xff, 0xfe,
+];
+
+#[test]
+fn checkout_restores_binary_file_exactly() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("image.png"), PNG_BYTES).unwrap();
+    commands::add(&repo_root, &repo_root.join("image.png")).unwrap();
+    let first = commands::commit(&repo_root, "First".to_string(), &vec![]).unwrap();
+
+    fs::write(repo_root.join("image.png"), b"replaced").unwrap();
+    commands::add(&repo_root, &repo_root.join("image.png")).unwrap();
+    commands::commit(&repo_root, "Second".to_string(), &vec![]).unwrap();
+
+    commands::checkout(&repo_root, &first).unwrap();
+
+    assert_eq!(fs::read(repo_root.join("image.png")).unwrap(), PNG_BYTES);
+}
+
+#[test]
+fn restore_recovers_binary_file_from_index() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("image.png"), PNG_BYTES).unwrap();
+    commands::add(&repo_root, &repo_root.join("image.png")).unwrap();
+
+    fs::remove_file(repo_root.join("image.png")).unwrap();
+    commands::restore(&repo_root, &repo_root.join("image.png")).unwrap();
+
+    assert_eq!(fs::read(repo_root.join("image.png")).unwrap(), PNG_BYTES);
+}
+
+#[test]
+fn read_blob_returns_bytes_and_parse_blob_rejects_non_utf8() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("image.png"), PNG_BYTES).unwrap();
+    let hash = commands::write_blob(&repo_root, &repo_root.join("image.png")).unwrap();
+
+    assert_eq!(utils::read_blob(&repo_root, &hash).unwrap(), PNG_BYTES);
+    assert!(utils::parse_blob(&repo_root, &hash).is_err());
+    assert!(utils::is_binary(PNG_BYTES));
+    assert!(!utils::is_binary(b"plain text\n"));
+}
+
+#[test]
+fn diff_reports_binary_files_differ() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("image.png"), PNG_BYTES).unwrap();
+    commands::add(repo_root, &repo_root.join("image.png")).unwrap();
+    commands::commit(repo_root, "initial".to_string(), &vec![]).unwrap();
+
+    let mut changed = PNG_BYTES.to_vec();
+    changed.push(0x00);
+    fs::write(repo_root.join("image.png"), changed).unwrap();
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(repo_root)
+        .arg("diff")
+        .assert()
+        .success();
+    let output = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    assert!(output.contains("diff --git a/image.png b/image.png\n"));
+    assert!(output.contains("Binary files a/image.png and b/image.png differ\n"));
+    assert!(!output.contains("@@"));
+}
@@ -0,0 +1,308 @@
+use assert_cmd::Command;
+use rustygit::commands::{self, BisectOutcome};
+use rustygit::utils;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn head(repo_root: &Path) -> String {
+    utils::get_current_commit_hash(repo_root).unwrap().unwrap()
+}
+
+/// Creates a linear history of `count` commits on `main`, each writing its
+/// number to `value.txt`; `bug.txt` exists from commit number `first_bad`
+/// on. Returns the commits, oldest first.
+fn setup_history(repo_root: &Path, count: usize, first_bad: usize) -> Vec<String> {
+    commands::init(repo_root).unwrap();
+
+    (1..=count)
+        .map(|number| {
+            fs::write(repo_root.join("value.txt"), format!("{}\n", number)).unwrap();
+            if number == first_bad {
+                fs::write(repo_root.join("bug.txt"), "bug\n").unwrap();
+            }
+            commit_all(repo_root, &format!("commit {}", number))
+        })
+        .collect()
+}
+
+fn has_bug(repo_root: &Path) -> bool {
+    repo_root.join("bug.txt").exists()
+}
+
+#[test]
+fn finds_the_first_bad_commit_by_manual_marks() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 10, 7);
+
+    let mut outcome =
+        commands::bisect_start(&repo_root, Some("HEAD"), &[commits[0].clone()]).unwrap();
+    let mut tested = 0;
+    while let BisectOutcome::Testing { commit, .. } = &outcome {
+        assert_eq!(&head(&repo_root), commit);
+        assert_eq!(utils::read_symbolic_head(&repo_root).unwrap(), None);
+        outcome = if has_bug(&repo_root) {
+            commands::bisect_bad(&repo_root, None).unwrap()
+        } else {
+            commands::bisect_good(&repo_root, &[]).unwrap()
+        };
+        tested += 1;
+    }
+
+    assert_eq!(outcome, BisectOutcome::FirstBad(commits[6].clone()));
+    assert!(tested <= 4, "tested {} commits", tested);
+    assert!(repo_root.join(".rustygit/BISECT_START").exists());
+
+    commands::bisect_reset(&repo_root, None).unwrap();
+
+    assert_eq!(
+        utils::read_symbolic_head(&repo_root).unwrap().as_deref(),
+        Some("refs/heads/main")
+    );
+    assert_eq!(head(&repo_root), commits[9]);
+    assert_eq!(
+        fs::read_to_string(repo_root.join("value.txt")).unwrap(),
+        "10\n"
+    );
+    for name in ["BISECT_START", "BISECT_BAD", "BISECT_GOOD", "BISECT_LOG"] {
+        assert!(!repo_root.join(".rustygit").join(name).exists(), "{}", name);
+    }
+}
+
+#[test]
+fn waits_for_good_and_bad_commits_and_checks_out_the_midpoint() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 9, 5);
+
+    assert_eq!(
+        commands::bisect_start(&repo_root, None, &[]).unwrap(),
+        BisectOutcome::Waiting {
+            bad: false,
+            good: 0
+        }
+    );
+    assert_eq!(
+        commands::bisect_good(&repo_root, &[commits[0].clone()]).unwrap(),
+        BisectOutcome::Waiting {
+            bad: false,
+            good: 1
+        }
+    );
+    assert_eq!(head(&repo_root), commits[8]);
+
+    let outcome = commands::bisect_bad(&repo_root, None).unwrap();
+
+    // Eight suspects (commits 2 to 9): commit 5 splits them in half.
+    assert_eq!(
+        outcome,
+        BisectOutcome::Testing {
+            commit: commits[4].clone(),
+            remaining: 3,
+            steps: 2,
+        }
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("value.txt")).unwrap(),
+        "5\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join(".rustygit/BISECT_BAD")).unwrap(),
+        format!("{}\n", commits[8])
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join(".rustygit/BISECT_GOOD")).unwrap(),
+        format!("{}\n", commits[0])
+    );
+}
+
+#[test]
+fn skipped_commits_are_not_tested() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 4, 3);
+
+    let outcome = commands::bisect_start(&repo_root, Some("HEAD"), &[commits[0].clone()]).unwrap();
+    let BisectOutcome::Testing { commit: first, .. } = outcome else {
+        panic!("unexpected outcome: {:?}", outcome);
+    };
+
+    let outcome = commands::bisect_skip(&repo_root, &[]).unwrap();
+    let BisectOutcome::Testing { commit: second, .. } = outcome else {
+        panic!("unexpected outcome: {:?}", outcome);
+    };
+    assert_ne!(first, second);
+    assert_eq!(head(&repo_root), second);
+
+    // Skipping the last testable commit leaves the culprit undecided.
+    let outcome = commands::bisect_skip(&repo_root, &[]).unwrap();
+    let BisectOutcome::OnlySkipped(mut candidates) = outcome else {
+        panic!("unexpected outcome: {:?}", outcome);
+    };
+    candidates.sort();
+    let mut expected = commits[1..].to_vec();
+    expected.sort();
+    assert_eq!(candidates, expected);
+}
+
+#[test]
+fn run_marks_commits_by_exit_status() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 12, 4);
+
+    commands::bisect_start(&repo_root, Some("main"), &[commits[0].clone()]).unwrap();
+    let outcomes = commands::bisect_run(
+        &repo_root,
+        &[
+            "test".to_string(),
+            "!".to_string(),
+            "-e".to_string(),
+            "bug.txt".to_string(),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        outcomes.last(),
+        Some(&BisectOutcome::FirstBad(commits[3].clone()))
+    );
+    assert!(
+        outcomes[..outcomes.len() - 1]
+            .iter()
+            .all(|outcome| matches!(outcome, BisectOutcome::Testing { .. }))
+    );
+
+    // A status of 128 or above stops the run.
+    commands::bisect_reset(&repo_root, None).unwrap();
+    commands::bisect_start(&repo_root, Some("main"), &[commits[0].clone()]).unwrap();
+    let err = commands::bisect_run(
+        &repo_root,
+        &["sh".to_string(), "-c".to_string(), "exit 130".to_string()],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("status 130"));
+
+    // Arguments reach the command as given, spaces included.
+    commands::bisect_reset(&repo_root, None).unwrap();
+    commands::bisect_start(&repo_root, Some("main"), &[commits[0].clone()]).unwrap();
+    let outcomes = commands::bisect_run(
+        &repo_root,
+        &[
+            "sh".to_string(),
+            "-c".to_string(),
+            "test ! -e bug.txt".to_string(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        outcomes.last(),
+        Some(&BisectOutcome::FirstBad(commits[3].clone()))
+    );
+}
+
+#[test]
+fn log_can_be_replayed_after_reset() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 16, 11);
+
+    commands::bisect_start(&repo_root, None, &[]).unwrap();
+    commands::bisect_bad(&repo_root, Some("main")).unwrap();
+    commands::bisect_good(&repo_root, &[commits[1].clone()]).unwrap();
+    let expected = commands::bisect_bad(&repo_root, None).unwrap();
+    let tested = head(&repo_root);
+
+    let log = commands::bisect_log(&repo_root).unwrap();
+    assert!(log.starts_with("rustygit bisect start\n"));
+    assert!(log.contains(&format!("rustygit bisect good {}\n", commits[1])));
+    assert!(log.contains(&format!("# bad: [{}] commit 16\n", commits[15])));
+
+    let log_file = dir.path().join("bisect.log");
+    fs::write(&log_file, &log).unwrap();
+    commands::bisect_reset(&repo_root, None).unwrap();
+    assert_eq!(head(&repo_root), commits[15]);
+
+    let outcome = commands::bisect_replay(&repo_root, &log_file).unwrap();
+
+    assert_eq!(outcome, expected);
+    assert_eq!(head(&repo_root), tested);
+    assert_eq!(commands::bisect_log(&repo_root).unwrap(), log);
+
+    // Replaying resets a session in progress; `reset` still returns to main.
+    commands::bisect_replay(&repo_root, &log_file).unwrap();
+    commands::bisect_reset(&repo_root, None).unwrap();
+    assert_eq!(
+        utils::current_branch(&repo_root).unwrap().as_deref(),
+        Some("main")
+    );
+}
+
+#[test]
+fn refuses_marks_without_a_session_and_a_second_start() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 3, 2);
+
+    let err = commands::bisect_good(&repo_root, &[]).unwrap_err();
+    assert!(err.to_string().contains("bisect start"));
+    assert!(commands::bisect_reset(&repo_root, None).is_err());
+
+    commands::bisect_start(&repo_root, None, &[]).unwrap();
+    assert!(commands::bisect_start(&repo_root, None, &[]).is_err());
+
+    // Marking a good commit that descends from the bad one is an error.
+    commands::bisect_bad(&repo_root, Some(&commits[0])).unwrap();
+    assert!(commands::bisect_good(&repo_root, &[commits[2].clone()]).is_err());
+}
+
+#[test]
+fn cli_bisect_session() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = setup_history(&repo_root, 8, 6);
+
+    let run = |args: &[&str]| {
+        let assert = Command::cargo_bin("rustygit")
+            .unwrap()
+            .current_dir(&repo_root)
+            .args(args)
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    assert_eq!(
+        run(&["bisect", "start"]),
+        "status: waiting for both good and bad commits\n"
+    );
+    assert_eq!(
+        run(&["bisect", "bad"]),
+        "status: waiting for good commit(s), bad commit known\n"
+    );
+    let stdout = run(&["bisect", "good", &commits[0]]);
+    assert!(
+        stdout
+            .starts_with("Bisecting: 2 revision(s) left to test after this (roughly 2 step(s))\n"),
+        "{}",
+        stdout
+    );
+
+    let stdout = run(&["bisect", "run", "test", "!", "-e", "bug.txt"]);
+    assert!(
+        stdout.ends_with(&format!(
+            "{} is the first bad commit\n    commit 6\n",
+            commits[5]
+        )),
+        "{}",
+        stdout
+    );
+    assert!(run(&["bisect", "log"]).contains("# first bad commit: ["));
+
+    assert_eq!(run(&["bisect", "reset"]), "Switched to branch 'main'\n");
+}
@@ -58,3 +58,144 @@ fn list_branches() {
 
     assert!(output.is_ok());
 }
+
+fn commit_all(repo_root: &std::path::Path, message: &str) -> String {
+    commands::add(repo_root, std::path::Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn branch_exists(repo_root: &std::path::Path, name: &str) -> bool {
+    repo_root.join(".rustygit/refs/heads").join(name).exists()
+}
+
+#[test]
+fn delete_refuses_unmerged_branch_unless_forced() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    let base = commit_all(&repo_root, "base");
+
+    commands::create_branch(&repo_root, "merged").unwrap();
+    commands::create_branch(&repo_root, "topic").unwrap();
+    commands::checkout(&repo_root, "topic").unwrap();
+    fs::write(repo_root.join("a.txt"), "topic\n").unwrap();
+    let topic = commit_all(&repo_root, "topic work");
+
+    let err = commands::delete_branch(&repo_root, "topic", false).unwrap_err();
+    assert!(err.to_string().contains("checked out"));
+
+    commands::checkout(&repo_root, "main").unwrap();
+
+    let err = commands::delete_branch(&repo_root, "topic", false).unwrap_err();
+    assert!(err.to_string().contains("not fully merged"));
+    assert!(branch_exists(&repo_root, "topic"));
+
+    assert_eq!(
+        commands::delete_branch(&repo_root, "merged", false).unwrap(),
+        base
+    );
+    assert_eq!(
+        commands::delete_branch(&repo_root, "topic", true).unwrap(),
+        topic
+    );
+    assert!(!branch_exists(&repo_root, "merged"));
+    assert!(!branch_exists(&repo_root, "topic"));
+
+    assert!(commands::delete_branch(&repo_root, "topic", true).is_err());
+}
+
+#[test]
+fn rename_updates_head_when_checked_out() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    let base = commit_all(&repo_root, "base");
+    commands::create_branch(&repo_root, "other").unwrap();
+
+    commands::rename_branch(&repo_root, "main", "trunk").unwrap();
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head, "ref: refs/heads/trunk\n");
+    assert!(!branch_exists(&repo_root, "main"));
+
+    commands::rename_branch(&repo_root, "other", "feature/other").unwrap();
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head, "ref: refs/heads/trunk\n");
+
+    let moved = fs::read_to_string(repo_root.join(".rustygit/refs/heads/feature/other")).unwrap();
+    assert_eq!(moved.trim(), base);
+
+    assert!(commands::rename_branch(&repo_root, "trunk", "feature/other").is_err());
+
+    commands::rename_current_branch(&repo_root, "main").unwrap();
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head, "ref: refs/heads/main\n");
+}
+
+#[test]
+fn force_moves_existing_branch() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    let first = commit_all(&repo_root, "first");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(&repo_root, "second");
+
+    assert_eq!(
+        commands::create_branch_at(&repo_root, "release", Some("HEAD~1"), false).unwrap(),
+        first
+    );
+    assert!(commands::create_branch_at(&repo_root, "release", None, false).is_err());
+
+    assert_eq!(
+        commands::create_branch_at(&repo_root, "release", Some("main"), true).unwrap(),
+        second
+    );
+    let content = fs::read_to_string(repo_root.join(".rustygit/refs/heads/release")).unwrap();
+    assert_eq!(content.trim(), second);
+
+    let err = commands::create_branch_at(&repo_root, "main", Some(&first), true).unwrap_err();
+    assert!(err.to_string().contains("current branch"));
+}
+
+#[test]
+fn rejects_invalid_branch_names() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    for name in ["bad name", "a..b", "-x", "topic.lock"] {
+        assert!(commands::create_branch(&repo_root, name).is_err());
+    }
+}
+
+#[test]
+fn reset_moves_the_branch_or_detached_head() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    let first = commit_all(&repo_root, "first");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(&repo_root, "second");
+
+    let main_ref = repo_root.join(".rustygit/refs/heads/main");
+    assert_eq!(fs::read_to_string(&main_ref).unwrap().trim(), second);
+
+    commands::reset(&repo_root, &first, commands::ResetMode::Mixed).unwrap();
+    assert_eq!(fs::read_to_string(&main_ref).unwrap().trim(), first);
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+
+    // Detached HEAD moves HEAD itself.
+    commands::checkout(&repo_root, &second).unwrap();
+    commands::reset(&repo_root, &first, commands::ResetMode::Soft).unwrap();
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head.trim(), first);
+    assert_eq!(fs::read_to_string(&main_ref).unwrap().trim(), first);
+}
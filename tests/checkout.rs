@@ -1,4 +1,4 @@
-use rustygit::commands;
+use rustygit::{commands, utils};
 use std::fs;
 use tempfile::tempdir;
 
@@ -133,3 +133,108 @@ fn checkout_branch() {
     assert_eq!(content, "one");
     assert!(head_content.contains("refs/heads/new-branch"));
 }
+
+fn commit_all(repo_root: &std::path::Path, message: &str) -> String {
+    commands::add(repo_root, std::path::Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+#[test]
+fn checkout_keeps_untracked_and_ignored_files() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join(".rustygitignore"), "build/\n").unwrap();
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    fs::create_dir_all(repo_root.join("src")).unwrap();
+    fs::write(repo_root.join("src/lib.rs"), b"lib").unwrap();
+    commit_all(&repo_root, "First");
+    commands::create_branch(&repo_root, "old").unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    fs::write(repo_root.join("src/new.rs"), b"new").unwrap();
+    let second = commit_all(&repo_root, "Second");
+
+    fs::create_dir_all(repo_root.join("build")).unwrap();
+    fs::write(repo_root.join("build/out.bin"), b"artifact").unwrap();
+    fs::write(repo_root.join("notes.txt"), b"untracked").unwrap();
+
+    commands::checkout(&repo_root, "old").unwrap();
+
+    assert_eq!(fs::read_to_string(repo_root.join("a.txt")).unwrap(), "one");
+    assert!(!repo_root.join("src/new.rs").exists());
+    assert_eq!(
+        fs::read_to_string(repo_root.join("src/lib.rs")).unwrap(),
+        "lib"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("build/out.bin")).unwrap(),
+        "artifact"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("notes.txt")).unwrap(),
+        "untracked"
+    );
+
+    let head_commit = utils::get_current_commit_hash(&repo_root).unwrap();
+    let head_entries = utils::get_commit_entries_map(&repo_root, head_commit.as_deref()).unwrap();
+    assert_eq!(utils::read_index_entries(&repo_root).unwrap(), head_entries);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    assert_eq!(
+        utils::read_index_entries(&repo_root).unwrap(),
+        utils::get_commit_entries_map(&repo_root, Some(&second)).unwrap()
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("src/new.rs")).unwrap(),
+        "new"
+    );
+}
+
+#[test]
+fn checkout_carries_unrelated_staged_changes_and_refuses_conflicting_ones() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    fs::write(repo_root.join("b.txt"), b"b").unwrap();
+    commit_all(&repo_root, "First");
+    commands::create_branch(&repo_root, "old").unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    commit_all(&repo_root, "Second");
+
+    // a.txt differs between the branches, so a staged edit to it blocks the switch.
+    fs::write(repo_root.join("a.txt"), b"staged").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    let err = commands::checkout(&repo_root, "old").unwrap_err();
+    assert!(err.to_string().contains("a.txt"));
+    assert_eq!(
+        utils::current_branch(&repo_root).unwrap().as_deref(),
+        Some("main")
+    );
+
+    // Staged changes to paths both branches agree on move along.
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    fs::write(repo_root.join("b.txt"), b"b staged").unwrap();
+    fs::write(repo_root.join("c.txt"), b"c").unwrap();
+    commands::add(&repo_root, &repo_root.join("b.txt")).unwrap();
+    commands::add(&repo_root, &repo_root.join("c.txt")).unwrap();
+
+    commands::checkout(&repo_root, "old").unwrap();
+
+    let index = utils::read_index_map(&repo_root).unwrap();
+    assert_eq!(
+        index[std::path::Path::new("b.txt")],
+        utils::hash_bytes(b"blob 8\0b staged")
+    );
+    assert!(index.contains_key(std::path::Path::new("c.txt")));
+    assert_eq!(fs::read_to_string(repo_root.join("a.txt")).unwrap(), "one");
+    assert_eq!(
+        fs::read_to_string(repo_root.join("b.txt")).unwrap(),
+        "b staged"
+    );
+}
@@ -0,0 +1,234 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn read(repo_root: &Path, file: &str) -> String {
+    fs::read_to_string(repo_root.join(file)).unwrap()
+}
+
+/// Creates `main` with a base commit and a `feature` branch with two
+/// commits: one editing the last line of `a.txt`, one adding `b.txt`.
+/// Leaves `main` checked out and returns the two feature commits.
+fn setup_feature(repo_root: &Path) -> (String, String) {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    commit_all(repo_root, "base");
+    commands::create_branch(repo_root, "feature").unwrap();
+
+    commands::checkout(repo_root, "feature").unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE\n").unwrap();
+    let edit = commit_all(repo_root, "shout three");
+    fs::write(repo_root.join("b.txt"), "b\n").unwrap();
+    let add = commit_all(repo_root, "add b");
+
+    commands::checkout(repo_root, "main").unwrap();
+    (edit, add)
+}
+
+fn message(repo_root: &Path, commit_hash: &str) -> String {
+    utils::get_commit_message(repo_root, commit_hash).unwrap()
+}
+
+#[test]
+fn picks_a_commit_onto_head_with_its_message() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, _) = setup_feature(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "ONE\ntwo\nthree\n").unwrap();
+    let head = commit_all(&repo_root, "shout one");
+
+    let outcomes = commands::cherry_pick(&repo_root, std::slice::from_ref(&edit)).unwrap();
+
+    let [commands::PickOutcome::Committed { original, commit }] = &outcomes[..] else {
+        panic!("unexpected outcomes: {:?}", outcomes);
+    };
+    assert_eq!(original, &edit);
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap().as_ref(),
+        Some(commit)
+    );
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, commit).unwrap(),
+        vec![head]
+    );
+    assert_eq!(
+        message(&repo_root, commit),
+        format!("shout three\n\n(cherry picked from commit {})\n", edit)
+    );
+    assert_eq!(read(&repo_root, "a.txt"), "ONE\ntwo\nTHREE\n");
+    assert!(!repo_root.join("b.txt").exists());
+}
+
+#[test]
+fn picks_several_commits_in_order_and_skips_empty_ones() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, add) = setup_feature(&repo_root);
+
+    let outcomes =
+        commands::cherry_pick(&repo_root, &[edit.clone(), add.clone(), edit.clone()]).unwrap();
+
+    assert_eq!(outcomes.len(), 3);
+    assert!(
+        matches!(&outcomes[0], commands::PickOutcome::Committed { original, .. } if original == &edit)
+    );
+    assert!(
+        matches!(&outcomes[1], commands::PickOutcome::Committed { original, .. } if original == &add)
+    );
+    assert_eq!(outcomes[2], commands::PickOutcome::Empty(edit));
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nTHREE\n");
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+    assert!(!repo_root.join(".rustygit/sequencer").exists());
+}
+
+#[test]
+fn stops_on_conflict_and_continues_after_resolution() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, add) = setup_feature(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    let head = commit_all(&repo_root, "exclaim three");
+
+    let outcomes = commands::cherry_pick(&repo_root, &[edit.clone(), add.clone()]).unwrap();
+
+    assert_eq!(
+        outcomes,
+        vec![commands::PickOutcome::Conflicted {
+            original: edit.clone(),
+            paths: vec![PathBuf::from("a.txt")],
+        }]
+    );
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(head.clone())
+    );
+    assert!(read(&repo_root, "a.txt").contains("<<<<<<< HEAD\n"));
+    assert!(
+        commands::cherry_pick(&repo_root, std::slice::from_ref(&add))
+            .unwrap_err()
+            .to_string()
+            .contains("already in progress")
+    );
+    assert!(commands::cherry_pick_continue(&repo_root).is_err());
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE!\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+    let outcomes = commands::cherry_pick_continue(&repo_root).unwrap();
+
+    let [
+        commands::PickOutcome::Committed {
+            commit: resolved, ..
+        },
+        commands::PickOutcome::Committed { commit: added, .. },
+    ] = &outcomes[..]
+    else {
+        panic!("unexpected outcomes: {:?}", outcomes);
+    };
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, resolved).unwrap(),
+        vec![head]
+    );
+    assert_eq!(
+        message(&repo_root, resolved),
+        format!("shout three\n\n(cherry picked from commit {})\n", edit)
+    );
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap().as_ref(),
+        Some(added)
+    );
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+    assert!(!repo_root.join(".rustygit/sequencer").exists());
+    assert!(!repo_root.join(".rustygit/CHERRY_PICK_HEAD").exists());
+}
+
+#[test]
+fn abort_returns_to_the_original_head_and_keeps_unrelated_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, add) = setup_feature(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    fs::write(repo_root.join("notes.txt"), "notes\n").unwrap();
+    let head = commit_all(&repo_root, "exclaim three");
+    fs::write(repo_root.join("notes.txt"), "local notes\n").unwrap();
+
+    let outcomes = commands::cherry_pick(&repo_root, &[add, edit]).unwrap();
+    assert!(matches!(
+        outcomes.last(),
+        Some(commands::PickOutcome::Conflicted { .. })
+    ));
+    assert!(repo_root.join("b.txt").exists());
+
+    commands::cherry_pick_abort(&repo_root).unwrap();
+
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(head)
+    );
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nthree!\n");
+    assert!(!repo_root.join("b.txt").exists());
+    assert_eq!(read(&repo_root, "notes.txt"), "local notes\n");
+    assert!(utils::read_index_conflicts(&repo_root).unwrap().is_empty());
+    assert!(!repo_root.join(".rustygit/sequencer").exists());
+    assert!(commands::cherry_pick_abort(&repo_root).is_err());
+}
+
+#[test]
+fn refuses_to_pick_with_staged_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, _) = setup_feature(&repo_root);
+
+    fs::write(repo_root.join("c.txt"), "c\n").unwrap();
+    commands::add(&repo_root, Path::new("c.txt")).unwrap();
+
+    let err = commands::cherry_pick(&repo_root, &[edit]).unwrap_err();
+
+    assert!(err.to_string().contains("staged changes"));
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nthree\n");
+}
+
+#[test]
+fn cli_reports_conflicts_and_continues() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (edit, _) = setup_feature(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    commit_all(&repo_root, "exclaim three");
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["cherry-pick", "feature~1"])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert_eq!(stdout, "CONFLICT: Merge conflict in a.txt\n");
+    assert!(stderr.contains(&format!("Could not apply {}", &edit[..7])));
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE!\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["cherry-pick", "--continue"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let head = utils::get_current_commit_hash(&repo_root).unwrap().unwrap();
+    assert_eq!(stdout, format!("[{}] shout three\n", &head[..7]));
+}
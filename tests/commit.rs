@@ -49,11 +49,7 @@ fn second_commit_has_parent() {
     commands::add(&repo_root, &repo_root.join("file.txt")).unwrap();
     let second_commit = commands::commit(&repo_root, "second".to_string(), &ignore_rules).unwrap();
 
-    let (d, f) = second_commit.split_at(2);
-    let commit_path = repo_root.join(".rustygit").join("objects").join(d).join(f);
-
-    let contents = fs::read(commit_path).unwrap();
-    let contents_str = String::from_utf8_lossy(&contents);
+    let contents_str = utils::parse_commit(&repo_root, &second_commit).unwrap();
 
     assert!(contents_str.contains(&first_commit));
 }
@@ -0,0 +1,270 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+/// Runs rustygit in `repo_root` with `home` as the home directory and no identity overrides.
+fn rustygit(repo_root: &Path, home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("rustygit").unwrap();
+    cmd.current_dir(repo_root)
+        .env("HOME", home)
+        .env_remove("RUSTYGIT_AUTHOR_NAME")
+        .env_remove("RUSTYGIT_AUTHOR_EMAIL")
+        .env_remove("RUSTYGIT_AUTHOR_DATE")
+        .env_remove("RUSTYGIT_COMMITTER_NAME")
+        .env_remove("RUSTYGIT_COMMITTER_EMAIL")
+        .env_remove("RUSTYGIT_COMMITTER_DATE");
+    cmd
+}
+
+fn stdout(cmd: &mut Command) -> String {
+    let assert = cmd.assert().success();
+    String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+}
+
+fn head_commit_text(repo_root: &Path) -> String {
+    let hash = utils::get_current_commit_hash(repo_root).unwrap().unwrap();
+    let object = utils::read_object(repo_root, &hash).unwrap();
+    let (_, data) = utils::split_object(&object).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+#[test]
+fn set_get_and_unset_repository_values() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    commands::config_set(
+        &repo_root,
+        utils::ConfigScope::Local,
+        "user.name",
+        "Ada Lovelace",
+    )
+    .unwrap();
+    commands::config_set(
+        &repo_root,
+        utils::ConfigScope::Local,
+        "User.Email",
+        "ada@example.com",
+    )
+    .unwrap();
+
+    assert_eq!(
+        commands::config_get(&repo_root, "user.name").unwrap(),
+        "Ada Lovelace"
+    );
+    assert_eq!(
+        commands::config_get(&repo_root, "USER.email").unwrap(),
+        "ada@example.com"
+    );
+
+    let content = fs::read_to_string(repo_root.join(".rustygit/config")).unwrap();
+    assert_eq!(
+        content,
+        "[user]\n\tname = Ada Lovelace\n\temail = ada@example.com\n"
+    );
+
+    commands::config_unset(&repo_root, utils::ConfigScope::Local, "user.name").unwrap();
+    assert!(commands::config_get(&repo_root, "user.name").is_err());
+    assert!(commands::config_unset(&repo_root, utils::ConfigScope::Local, "user.name").is_err());
+}
+
+#[test]
+fn reads_subsections_and_hand_written_files() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(
+        repo_root.join(".rustygit/config"),
+        "# comment\n[core]\n\tbare\n[branch \"main\"]\n\tremote = origin\n[user]\nname = \"First\"\n[user]\nname = Second\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        commands::config_get(&repo_root, "core.bare").unwrap(),
+        "true"
+    );
+    assert_eq!(
+        commands::config_get(&repo_root, "branch.main.remote").unwrap(),
+        "origin"
+    );
+    assert_eq!(
+        commands::config_get(&repo_root, "user.name").unwrap(),
+        "Second"
+    );
+    assert!(commands::config_get(&repo_root, "nodot").is_err());
+}
+
+#[test]
+fn edits_keep_comments_and_values_round_trip() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    let config = repo_root.join(".rustygit/config");
+
+    fs::write(
+        &config,
+        "# my settings\n[user] ; who I am\n\tname = Ada # not Grace\n\temail = \"ada@example.com\" ; work\n\n[core]\n\tbare = false\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        commands::config_get(&repo_root, "user.name").unwrap(),
+        "Ada"
+    );
+    assert_eq!(
+        commands::config_get(&repo_root, "user.email").unwrap(),
+        "ada@example.com"
+    );
+
+    let set = |key: &str, value: &str| {
+        commands::config_set(&repo_root, utils::ConfigScope::Local, key, value).unwrap()
+    };
+    set("user.name", "Ada Lovelace");
+    set("user.signingkey", " #key; ");
+    commands::config_unset(&repo_root, utils::ConfigScope::Local, "core.bare").unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&config).unwrap(),
+        "# my settings\n[user] ; who I am\n\tname = Ada Lovelace\n\temail = \"ada@example.com\" ; work\n\tsigningkey = \" #key; \"\n\n[core]\n"
+    );
+    assert_eq!(
+        commands::config_get(&repo_root, "user.signingkey").unwrap(),
+        " #key; "
+    );
+    assert!(!repo_root.join(".rustygit/config.lock").exists());
+}
+
+#[test]
+fn repository_config_overrides_global_config() {
+    let dir = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    rustygit(&repo_root, home.path())
+        .args(["config", "set", "--global", "user.name", "Global Name"])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .args([
+            "config",
+            "set",
+            "--global",
+            "user.email",
+            "global@example.com",
+        ])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .args(["config", "set", "user.name", "Local Name"])
+        .assert()
+        .success();
+
+    assert!(home.path().join(".rustygitconfig").exists());
+    assert_eq!(
+        stdout(rustygit(&repo_root, home.path()).args(["config", "get", "user.name"])),
+        "Local Name\n"
+    );
+    assert_eq!(
+        stdout(rustygit(&repo_root, home.path()).args(["config", "--list"])),
+        "user.name=Global Name\nuser.email=global@example.com\nuser.name=Local Name\n"
+    );
+
+    rustygit(&repo_root, home.path())
+        .args(["config", "get", "user.missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn commit_uses_configured_identity() {
+    let dir = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one").unwrap();
+
+    rustygit(&repo_root, home.path())
+        .args(["config", "set", "user.name", "Ada Lovelace"])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .args(["config", "set", "user.email", "ada@example.com"])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .args(["add", "a.txt"])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .args(["commit", "-m", "first"])
+        .assert()
+        .success();
+
+    let text = head_commit_text(&repo_root);
+    assert!(text.contains("\nauthor Ada Lovelace <ada@example.com> "));
+    assert!(text.contains("\ncommitter Ada Lovelace <ada@example.com> "));
+}
+
+#[test]
+fn environment_overrides_make_commits_reproducible() {
+    let mut hashes = Vec::new();
+
+    for _ in 0..2 {
+        let dir = tempdir().unwrap();
+        let home = tempdir().unwrap();
+        let repo_root = dir.path().canonicalize().unwrap();
+        commands::init(&repo_root).unwrap();
+        commands::config_set(
+            &repo_root,
+            utils::ConfigScope::Local,
+            "user.name",
+            "Config Name",
+        )
+        .unwrap();
+        fs::write(repo_root.join("a.txt"), "one").unwrap();
+
+        rustygit(&repo_root, home.path())
+            .args(["add", "a.txt"])
+            .assert()
+            .success();
+        rustygit(&repo_root, home.path())
+            .env("RUSTYGIT_AUTHOR_NAME", "Script")
+            .env("RUSTYGIT_AUTHOR_EMAIL", "script@example.com")
+            .env("RUSTYGIT_AUTHOR_DATE", "1700000000 +0530")
+            .env("RUSTYGIT_COMMITTER_DATE", "2023-11-14T22:13:20Z")
+            .args(["commit", "-m", "scripted"])
+            .assert()
+            .success();
+
+        let text = head_commit_text(&repo_root);
+        assert!(text.contains("\nauthor Script <script@example.com> 1700000000 +0530\n"));
+        assert!(text.contains("\ncommitter Config Name <"));
+        assert!(text.contains("> 1700000000 +0000\n"));
+        hashes.push(utils::get_current_commit_hash(&repo_root).unwrap().unwrap());
+    }
+
+    assert_eq!(hashes[0], hashes[1]);
+}
+
+#[test]
+fn invalid_date_override_is_rejected() {
+    let dir = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one").unwrap();
+
+    rustygit(&repo_root, home.path())
+        .args(["add", "a.txt"])
+        .assert()
+        .success();
+    rustygit(&repo_root, home.path())
+        .env("RUSTYGIT_AUTHOR_DATE", "yesterday-ish")
+        .args(["commit", "-m", "bad date"])
+        .assert()
+        .failure();
+}
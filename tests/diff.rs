@@ -1,5 +1,6 @@
 use assert_cmd::Command;
-use rustygit::commands;
+use rustygit::commands::{self, DiffMode};
+use rustygit::utils::{self, ChangeKind};
 use std::{fs, path::Path};
 use tempfile::tempdir;
 
@@ -26,7 +27,7 @@ fn diff_no_changes_after_commit() {
 
     let output = run_diff(repo_root);
 
-    assert!(output.trim().is_empty() || output.to_lowercase().contains("no changes"));
+    assert!(output.is_empty());
 }
 
 #[test]
@@ -43,10 +44,10 @@ fn diff_single_line_change_shows_one_deletion_and_insertion() {
 
     let output = run_diff(repo_root);
 
-    assert!(output.contains("Modified files:"));
-    assert!(output.contains("a.txt"));
-    assert_eq!(output.matches("\t\t-").count(), 1);
-    assert_eq!(output.matches("\t\t+").count(), 1);
+    assert!(output.starts_with("diff --git a/a.txt b/a.txt\n"));
+    assert!(output.contains("--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n line one\n"));
+    assert_eq!(output.matches("\n-line").count(), 1);
+    assert_eq!(output.matches("\n+line").count(), 1);
 }
 
 #[test]
@@ -66,9 +67,8 @@ fn diff_multiple_files_lists_both_files() {
 
     let output = run_diff(repo_root);
 
-    assert!(output.contains("Modified files:"));
-    assert!(output.contains("a.txt"));
-    assert!(output.contains("b.txt"));
+    assert!(output.contains("diff --git a/a.txt b/a.txt\n"));
+    assert!(output.contains("diff --git a/b.txt b/b.txt\n"));
 }
 
 #[test]
@@ -85,6 +85,244 @@ fn delete_one_file_shows_deleted_file() {
 
     let output = run_diff(repo_root);
 
-    assert!(output.contains("Deleted files:"));
-    assert!(output.contains("a.txt"));
+    assert!(output.contains("deleted file mode 100644\n"));
+    assert!(output.contains("--- a/a.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-alpha\n"));
+}
+
+fn run_diff_args(repo_root: &Path, args: &[&str]) -> String {
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(repo_root)
+        .arg("diff")
+        .args(args)
+        .assert()
+        .success();
+
+    String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+}
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn changed_paths(
+    repo_root: &Path,
+    mode: &DiffMode,
+    pathspecs: &[&str],
+) -> Vec<(String, ChangeKind)> {
+    let pathspecs: Vec<String> = pathspecs.iter().map(|spec| spec.to_string()).collect();
+    commands::diff_changes(repo_root, mode, &pathspecs)
+        .unwrap()
+        .into_iter()
+        .map(|change| (change.path.display().to_string(), change.kind()))
+        .collect()
+}
+
+#[test]
+fn worktree_diff_excludes_staged_and_untracked_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo_root.join("b.txt"), "b\n").unwrap();
+    commit_all(repo_root, "initial");
+
+    fs::write(repo_root.join("a.txt"), "one\nstaged\n").unwrap();
+    commands::add(repo_root, Path::new("a.txt")).unwrap();
+    fs::write(repo_root.join("a.txt"), "unstaged\nstaged\n").unwrap();
+    fs::write(repo_root.join("new.txt"), "untracked\n").unwrap();
+    fs::remove_file(repo_root.join("b.txt")).unwrap();
+
+    assert_eq!(
+        changed_paths(repo_root, &DiffMode::WorkTree, &[]),
+        vec![
+            ("a.txt".to_string(), ChangeKind::Modified),
+            ("b.txt".to_string(), ChangeKind::Deleted),
+        ]
+    );
+
+    let output = run_diff(repo_root);
+    assert!(output.contains("@@ -1,2 +1,2 @@\n-one\n+unstaged\n staged\n"));
+    assert!(!output.contains("new.txt"));
+}
+
+#[test]
+fn cached_diff_compares_index_with_head_or_a_revision() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\n").unwrap();
+    commit_all(repo_root, "initial");
+    fs::write(repo_root.join("c.txt"), "c\n").unwrap();
+    commit_all(repo_root, "add c");
+
+    fs::write(repo_root.join("a.txt"), "one\nstaged\n").unwrap();
+    fs::write(repo_root.join("new.txt"), "new\nfile\n").unwrap();
+    commands::add(repo_root, Path::new(".")).unwrap();
+    fs::write(repo_root.join("a.txt"), "unstaged\nstaged\n").unwrap();
+
+    assert_eq!(
+        changed_paths(repo_root, &DiffMode::Cached(None), &[]),
+        vec![
+            ("a.txt".to_string(), ChangeKind::Modified),
+            ("new.txt".to_string(), ChangeKind::Added),
+        ]
+    );
+    assert_eq!(
+        changed_paths(
+            repo_root,
+            &DiffMode::Cached(Some("HEAD~1".to_string())),
+            &[]
+        ),
+        vec![
+            ("a.txt".to_string(), ChangeKind::Modified),
+            ("c.txt".to_string(), ChangeKind::Added),
+            ("new.txt".to_string(), ChangeKind::Added),
+        ]
+    );
+
+    let output = run_diff_args(repo_root, &["--cached"]);
+    assert!(output.contains("@@ -1,2 +1,2 @@\n one\n-two\n+staged\n"));
+    assert!(!output.contains("unstaged"));
+    assert!(output.contains("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+new\n+file\n"));
+    assert_eq!(run_diff_args(repo_root, &["--staged"]), output);
+}
+
+#[test]
+fn revision_diffs_compare_commits_and_the_worktree() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "a1\n").unwrap();
+    fs::create_dir(repo_root.join("src")).unwrap();
+    fs::write(repo_root.join("src/lib.rs"), "lib1\n").unwrap();
+    let first = commit_all(repo_root, "first");
+    fs::write(repo_root.join("a.txt"), "a2\n").unwrap();
+    fs::write(repo_root.join("src/lib.rs"), "lib2\n").unwrap();
+    fs::write(repo_root.join("src/main.rs"), "main\n").unwrap();
+    let second = commit_all(repo_root, "second");
+    fs::write(repo_root.join("a.txt"), "a3\n").unwrap();
+
+    let both = vec![
+        ("a.txt".to_string(), ChangeKind::Modified),
+        ("src/lib.rs".to_string(), ChangeKind::Modified),
+        ("src/main.rs".to_string(), ChangeKind::Added),
+    ];
+    assert_eq!(
+        changed_paths(
+            repo_root,
+            &DiffMode::Commits(first.clone(), second.clone()),
+            &[]
+        ),
+        both
+    );
+    assert_eq!(
+        changed_paths(repo_root, &DiffMode::Commit("HEAD".to_string()), &[]),
+        vec![("a.txt".to_string(), ChangeKind::Modified)]
+    );
+    assert_eq!(
+        changed_paths(
+            repo_root,
+            &DiffMode::from_args(false, &[format!("{}..", first)]).unwrap(),
+            &["src"]
+        ),
+        both[1..].to_vec()
+    );
+
+    let output = run_diff_args(repo_root, &["HEAD~1..HEAD", "--", "src/*.rs"]);
+    assert!(output.contains("+++ b/src/lib.rs\n@@ -1 +1 @@\n-lib1\n+lib2\n"));
+    assert!(output.contains("+++ b/src/main.rs\n@@ -0,0 +1 @@\n+main\n"));
+    assert!(!output.contains("a.txt"));
+
+    let output = run_diff_args(repo_root, &[&first, "--", "a.txt"]);
+    assert!(output.contains("-a1\n+a3\n"));
+
+    assert_eq!(
+        run_diff_args(repo_root, &["HEAD", "HEAD", "--", "a.txt"]),
+        ""
+    );
+}
+
+#[test]
+fn diff_mode_arguments() {
+    let args = |values: &[&str]| -> Vec<String> { values.iter().map(|v| v.to_string()).collect() };
+
+    assert_eq!(DiffMode::from_args(false, &[]).unwrap(), DiffMode::WorkTree);
+    assert_eq!(
+        DiffMode::from_args(true, &[]).unwrap(),
+        DiffMode::Cached(None)
+    );
+    assert_eq!(
+        DiffMode::from_args(true, &args(&["main"])).unwrap(),
+        DiffMode::Cached(Some("main".to_string()))
+    );
+    assert_eq!(
+        DiffMode::from_args(false, &args(&["main"])).unwrap(),
+        DiffMode::Commit("main".to_string())
+    );
+    assert_eq!(
+        DiffMode::from_args(false, &args(&["..topic"])).unwrap(),
+        DiffMode::Commits("HEAD".to_string(), "topic".to_string())
+    );
+    assert_eq!(
+        DiffMode::from_args(false, &args(&["a", "b"])).unwrap(),
+        DiffMode::Commits("a".to_string(), "b".to_string())
+    );
+    assert!(DiffMode::from_args(true, &args(&["a", "b"])).is_err());
+    assert!(DiffMode::from_args(false, &args(&["a", "b", "c"])).is_err());
+}
+
+#[test]
+fn unified_diff_uses_git_headers_and_context() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    commands::init(repo_root).unwrap();
+    let lines: String = (1..=12).map(|n| format!("line {}\n", n)).collect();
+    fs::write(repo_root.join("a.txt"), &lines).unwrap();
+    fs::write(repo_root.join("run.sh"), "echo hi\n").unwrap();
+    commit_all(repo_root, "initial");
+
+    let old_hash = utils::read_index_map(repo_root).unwrap()[Path::new("a.txt")].clone();
+    let changed = lines
+        .replace("line 2\n", "line two\n")
+        .replace("line 12\n", "line twelve");
+    fs::write(repo_root.join("a.txt"), &changed).unwrap();
+    let new_hash = utils::hash_bytes(&commands::format_object(changed.as_bytes()));
+    fs::set_permissions(
+        repo_root.join("run.sh"),
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+
+    let output = run_diff(repo_root);
+    assert_eq!(
+        output,
+        format!(
+            "diff --git a/a.txt b/a.txt\n\
+             index {}..{} 100644\n\
+             --- a/a.txt\n\
+             +++ b/a.txt\n\
+             @@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n line 4\n line 5\n\
+             @@ -9,4 +9,4 @@\n line 9\n line 10\n line 11\n-line 12\n+line twelve\n\
+             \\ No newline at end of file\n\
+             diff --git a/run.sh b/run.sh\n\
+             old mode 100644\n\
+             new mode 100755\n",
+            &old_hash[..7],
+            &new_hash[..7]
+        )
+    );
+
+    let output = run_diff_args(repo_root, &["-U1", "--", "a.txt"]);
+    assert!(
+        output
+            .contains("@@ -1,3 +1,3 @@\n line 1\n-line 2\n+line two\n line 3\n@@ -11,2 +11,2 @@\n")
+    );
+    let output = run_diff_args(repo_root, &["--unified", "0", "--", "a.txt"]);
+    assert!(output.contains("@@ -2 +2 @@\n-line 2\n+line two\n@@ -12 +12 @@\n"));
+    assert_eq!(
+        commands::diff(repo_root, &DiffMode::WorkTree, &["a.txt".to_string()], 0).unwrap(),
+        output
+    );
 }
@@ -0,0 +1,167 @@
+#![cfg(unix)]
+
+use rustygit::{commands, utils};
+use std::{
+    collections::HashMap,
+    fs,
+    os::unix::fs::{PermissionsExt, symlink},
+    path::Path,
+};
+use tempfile::tempdir;
+
+fn set_mode(path: &Path, mode: u32) {
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+fn head_entries(repo_root: &Path) -> HashMap<std::path::PathBuf, utils::IndexEntry> {
+    let tree_hash = utils::get_current_tree_hash(repo_root).unwrap().unwrap();
+    let mut entries = HashMap::new();
+    utils::get_tree_entries_map(repo_root, Path::new(""), &tree_hash, &mut entries).unwrap();
+    entries
+}
+
+#[test]
+fn executable_bit_is_committed_and_restored() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("run.sh"), b"#!/bin/sh\necho hi\n").unwrap();
+    set_mode(&repo_root.join("run.sh"), 0o755);
+    fs::write(repo_root.join("notes.txt"), b"notes").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let first = commands::commit(&repo_root, "first".to_string(), &vec![]).unwrap();
+
+    let entries = head_entries(&repo_root);
+    assert_eq!(entries[Path::new("run.sh")].mode, "100755");
+    assert_eq!(entries[Path::new("notes.txt")].mode, "100644");
+
+    fs::write(repo_root.join("run.sh"), b"#!/bin/sh\necho bye\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    commands::commit(&repo_root, "second".to_string(), &vec![]).unwrap();
+
+    commands::checkout(&repo_root, &first).unwrap();
+
+    let mode = fs::metadata(repo_root.join("run.sh")).unwrap().permissions().mode();
+    assert_ne!(mode & 0o100, 0);
+    let mode = fs::metadata(repo_root.join("notes.txt")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o111, 0);
+}
+
+#[test]
+fn symlink_is_stored_as_link_and_recreated() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("target.txt"), b"target content").unwrap();
+    symlink("target.txt", repo_root.join("link")).unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let first = commands::commit(&repo_root, "first".to_string(), &vec![]).unwrap();
+
+    let entries = head_entries(&repo_root);
+    let link_entry = &entries[Path::new("link")];
+    assert_eq!(link_entry.mode, "120000");
+    assert_eq!(
+        utils::read_blob(&repo_root, &link_entry.hash).unwrap(),
+        b"target.txt"
+    );
+
+    fs::remove_file(repo_root.join("link")).unwrap();
+    fs::write(repo_root.join("link"), b"now a file").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    commands::commit(&repo_root, "second".to_string(), &vec![]).unwrap();
+
+    commands::checkout(&repo_root, &first).unwrap();
+
+    let metadata = fs::symlink_metadata(repo_root.join("link")).unwrap();
+    assert!(metadata.file_type().is_symlink());
+    assert_eq!(
+        fs::read_link(repo_root.join("link")).unwrap(),
+        Path::new("target.txt")
+    );
+}
+
+#[test]
+fn dangling_symlink_can_be_added() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    symlink("missing.txt", repo_root.join("dangling")).unwrap();
+    commands::add(&repo_root, &repo_root.join("dangling")).unwrap();
+
+    let entries = utils::read_index_entries(&repo_root).unwrap();
+    assert_eq!(entries[Path::new("dangling")].mode, "120000");
+}
+
+#[test]
+fn mode_change_is_reported_by_status() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("run.sh"), b"echo hi\n").unwrap();
+    set_mode(&repo_root.join("run.sh"), 0o644);
+    commands::add(&repo_root, &repo_root.join("run.sh")).unwrap();
+    commands::commit(&repo_root, "first".to_string(), &vec![]).unwrap();
+
+    set_mode(&repo_root.join("run.sh"), 0o755);
+
+    let status = commands::status(&repo_root, &vec![]).unwrap();
+    assert!(status.contains("Modified files:\n\t\t\trun.sh"));
+
+    commands::commit_with_all(&repo_root, "chmod".to_string(), &vec![], true).unwrap();
+    assert_eq!(head_entries(&repo_root)[Path::new("run.sh")].mode, "100755");
+}
+
+#[test]
+fn legacy_index_lines_without_mode_are_read() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    let hash = commands::write_blob(&repo_root, &repo_root.join("a.txt")).unwrap();
+    fs::write(
+        repo_root.join(".rustygit/index"),
+        format!("{} a.txt\n", hash),
+    )
+    .unwrap();
+
+    let entries = utils::read_index_entries(&repo_root).unwrap();
+    assert_eq!(
+        entries[Path::new("a.txt")],
+        utils::IndexEntry {
+            mode: "100644",
+            hash
+        }
+    );
+}
+
+#[test]
+fn local_mode_change_blocks_checkout() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("run.sh"), b"v1\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let first = commands::commit(&repo_root, "first".to_string(), &vec![]).unwrap();
+    fs::write(repo_root.join("run.sh"), b"v2\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    commands::commit(&repo_root, "second".to_string(), &vec![]).unwrap();
+
+    // Same content as HEAD, but executable: checkout must not drop the bit.
+    set_mode(&repo_root.join("run.sh"), 0o755);
+    let err = commands::checkout(&repo_root, &first).unwrap_err();
+    assert!(
+        err.to_string().contains("Local changes to 'run.sh'"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        utils::work_file_mode(&repo_root.join("run.sh")).unwrap(),
+        "100755"
+    );
+}
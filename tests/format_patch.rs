@@ -0,0 +1,154 @@
+use assert_cmd::Command;
+use rustygit::commands;
+use rustygit::utils::{self, ConfigScope};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn setup_repo(repo_root: &Path) -> Vec<String> {
+    commands::init(repo_root).unwrap();
+    utils::set_config_value(repo_root, ConfigScope::Local, "user.name", "Alice Example").unwrap();
+    utils::set_config_value(
+        repo_root,
+        ConfigScope::Local,
+        "user.email",
+        "alice@example.com",
+    )
+    .unwrap();
+
+    fs::write(repo_root.join("greeting.txt"), "hello\n").unwrap();
+    let base = commit_all(repo_root, "Initial commit");
+    fs::write(repo_root.join("greeting.txt"), "hello\nworld\n").unwrap();
+    let second = commit_all(repo_root, "Add world to the greeting");
+    fs::write(repo_root.join("notes.txt"), "remember\n").unwrap();
+    let third = commit_all(
+        repo_root,
+        "Add notes: a file\nfor reminders\n\nNotes are kept apart from the greeting.\n\nSecond paragraph.",
+    );
+
+    vec![base, second, third]
+}
+
+#[test]
+fn writes_one_mbox_file_per_commit() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    let commits = setup_repo(&repo_root);
+    let out_dir = dir.path().join("outgoing");
+
+    let files = commands::format_patch(&repo_root, &commits[0], &out_dir).unwrap();
+
+    assert_eq!(
+        files,
+        vec![
+            out_dir.join("0001-Add-world-to-the-greeting.patch"),
+            out_dir.join("0002-Add-notes-a-file-for-reminders.patch"),
+        ]
+    );
+
+    let author = utils::get_commit_author(&repo_root, &commits[1]).unwrap();
+    let date = utils::format_rfc2822_date(author.timestamp, &author.timezone).unwrap();
+    let first = fs::read_to_string(&files[0]).unwrap();
+    assert!(
+        first.starts_with(&format!(
+            "From {} Mon Sep 17 00:00:00 2001\n\
+             From: Alice Example <alice@example.com>\n\
+             Date: {}\n\
+             Subject: [PATCH 1/2] Add world to the greeting\n\
+             \n\
+             ---\n\
+             diff --git a/greeting.txt b/greeting.txt\n",
+            commits[1], date
+        )),
+        "{}",
+        first
+    );
+    assert!(first.contains("@@ -1 +1,2 @@\n hello\n+world\n-- \nrustygit "));
+
+    // A multi-line first paragraph is joined into the subject; the rest of
+    // the message is the body.
+    let second = fs::read_to_string(&files[1]).unwrap();
+    assert!(
+        second.contains(
+            "Subject: [PATCH 2/2] Add notes: a file for reminders\n\n\
+             Notes are kept apart from the greeting.\n\n\
+             Second paragraph.\n\
+             ---\n\
+             diff --git a/notes.txt b/notes.txt\n\
+             new file mode 100644\n"
+        ),
+        "{}",
+        second
+    );
+}
+
+#[test]
+fn ranges_select_commits_and_skip_merges() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().join("repo");
+    fs::create_dir_all(&repo_root).unwrap();
+    let commits = setup_repo(&repo_root);
+    let out_dir = dir.path().join("outgoing");
+
+    let files =
+        commands::format_patch(&repo_root, &format!("{}..HEAD~1", commits[0]), &out_dir).unwrap();
+    assert_eq!(files.len(), 1);
+    let patch = fs::read_to_string(&files[0]).unwrap();
+    assert!(patch.contains("Subject: [PATCH] Add world to the greeting\n"));
+
+    assert!(
+        commands::format_patch(&repo_root, "HEAD", &out_dir)
+            .unwrap()
+            .is_empty()
+    );
+
+    // Merge commits are left out; the merged branch's commits are not.
+    commands::create_branch_at(&repo_root, "topic", Some(&commits[1]), false).unwrap();
+    commands::checkout(&repo_root, "topic").unwrap();
+    fs::write(repo_root.join("topic.txt"), "topic\n").unwrap();
+    commit_all(&repo_root, "Topic work");
+    commands::checkout(&repo_root, "main").unwrap();
+    commands::merge(&repo_root, "topic", None).unwrap();
+
+    let out_dir = dir.path().join("merged");
+    let files = commands::format_patch(&repo_root, &commits[1], &out_dir).unwrap();
+    let names: Vec<String> = files
+        .iter()
+        .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.iter().any(|name| name.ends_with("-Topic-work.patch")));
+    assert!(
+        names
+            .iter()
+            .any(|name| name.ends_with("-Add-notes-a-file-for-reminders.patch"))
+    );
+}
+
+#[test]
+fn cli_format_patch_prints_file_names() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(repo_root)
+        .args(["format-patch", "-o", "patches", "HEAD~2"])
+        .assert()
+        .success()
+        .stdout(
+            "patches/0001-Add-world-to-the-greeting.patch\n\
+             patches/0002-Add-notes-a-file-for-reminders.patch\n",
+        );
+    assert!(
+        repo_root
+            .join("patches/0002-Add-notes-a-file-for-reminders.patch")
+            .exists()
+    );
+}
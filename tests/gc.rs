@@ -0,0 +1,130 @@
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_versions(repo_root: &Path, versions: std::ops::Range<usize>) -> Vec<String> {
+    let mut commits = Vec::new();
+    for i in versions {
+        let content: String = (0..200 + i).map(|n| format!("line {}\n", n)).collect();
+        fs::write(repo_root.join("a.txt"), content).unwrap();
+        commands::add(repo_root, &repo_root.join("a.txt")).unwrap();
+        commits.push(commands::commit(repo_root, format!("c{}", i), &vec![]).unwrap());
+    }
+    commits
+}
+
+fn loose_object_count(repo_root: &Path) -> usize {
+    fs::read_dir(repo_root.join(".rustygit/objects"))
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.file_name().len() == 2)
+        .map(|entry| fs::read_dir(entry.path()).unwrap().count())
+        .sum()
+}
+
+#[test]
+fn gc_packs_reachable_objects_and_removes_loose_copies() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let commits = commit_versions(&repo_root, 0..5);
+    assert_eq!(loose_object_count(&repo_root), 15);
+
+    let summary = commands::gc(&repo_root, 14).unwrap();
+
+    assert_eq!(summary.packed, 15);
+    assert_eq!(summary.removed_loose, 15);
+    assert!(summary.deltas > 0);
+    assert_eq!(loose_object_count(&repo_root), 0);
+    assert_eq!(utils::read_pack_indexes(&repo_root).unwrap().len(), 1);
+
+    for commit in &commits {
+        let object = utils::read_object(&repo_root, commit).unwrap();
+        assert_eq!(&utils::hash_bytes(&object), commit);
+    }
+
+    commands::checkout(&repo_root, &commits[0]).unwrap();
+    let content = fs::read_to_string(repo_root.join("a.txt")).unwrap();
+    assert_eq!(content.lines().count(), 200);
+}
+
+#[test]
+fn gc_twice_replaces_the_old_pack() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    commit_versions(&repo_root, 0..2);
+    commands::gc(&repo_root, 14).unwrap();
+    commit_versions(&repo_root, 2..4);
+    let summary = commands::gc(&repo_root, 14).unwrap();
+
+    assert_eq!(summary.packed, 12);
+    assert_eq!(utils::read_pack_indexes(&repo_root).unwrap().len(), 1);
+    assert_eq!(loose_object_count(&repo_root), 0);
+
+    // A half-written index left behind by an interrupted gc is not a pack.
+    let pack_dir = repo_root.join(".rustygit/objects/pack");
+    fs::write(pack_dir.join("pack-interrupted.idx.tmp"), b"partial").unwrap();
+    assert_eq!(utils::read_pack_indexes(&repo_root).unwrap().len(), 1);
+}
+
+#[test]
+fn gc_prune_now_removes_unreachable_but_keeps_staged() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    commit_versions(&repo_root, 0..1);
+
+    fs::write(repo_root.join("orphan.txt"), b"orphan").unwrap();
+    let orphan = commands::write_blob(&repo_root, &repo_root.join("orphan.txt")).unwrap();
+    fs::remove_file(repo_root.join("orphan.txt")).unwrap();
+
+    fs::write(repo_root.join("staged.txt"), b"staged").unwrap();
+    commands::add(&repo_root, &repo_root.join("staged.txt")).unwrap();
+    let staged = utils::read_index_map(&repo_root).unwrap()[Path::new("staged.txt")].clone();
+
+    let summary = commands::gc(&repo_root, 0).unwrap();
+
+    assert_eq!(summary.pruned, 1);
+    assert!(!utils::object_exists(&repo_root, &orphan));
+    assert!(utils::object_exists(&repo_root, &staged));
+}
+
+#[test]
+fn gc_keeps_recent_unreachable_objects_within_grace_period() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    commit_versions(&repo_root, 0..1);
+
+    fs::write(repo_root.join("orphan.txt"), b"orphan").unwrap();
+    let orphan = commands::write_blob(&repo_root, &repo_root.join("orphan.txt")).unwrap();
+
+    let summary = commands::gc(&repo_root, 14).unwrap();
+
+    assert_eq!(summary.pruned, 0);
+    assert!(utils::object_exists(&repo_root, &orphan));
+}
+
+#[test]
+fn repack_keeps_unreachable_packed_objects_loose() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    let commits = commit_versions(&repo_root, 0..2);
+
+    commands::repack(&repo_root).unwrap();
+
+    // Drop the second commit from every ref, reflog and the index; its objects now only live in the pack.
+    commands::reset(&repo_root, &commits[0], commands::ResetMode::Mixed).unwrap();
+    fs::remove_dir_all(repo_root.join(".rustygit/logs")).unwrap();
+
+    let summary = commands::repack(&repo_root).unwrap();
+
+    assert_eq!(summary.packed, 3);
+    assert_eq!(summary.pruned, 0);
+    assert!(utils::object_exists(&repo_root, &commits[1]));
+    assert!(utils::loose_object_path(&repo_root, &commits[1]).exists());
+}
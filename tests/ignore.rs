@@ -92,5 +92,5 @@ fn gitignore_file_is_not_tracked() {
 
     let rules = parse_ignore_file(&root).unwrap();
 
-    assert!(is_ignored(&root.join(".gitignore"), &root, &rules) == false);
+    assert!(!is_ignored(&root.join(".gitignore"), &root, &rules));
 }
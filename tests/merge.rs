@@ -0,0 +1,347 @@
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+/// Creates `main` with one commit and a `feature` branch at the same commit.
+fn setup_branches(repo_root: &Path) -> String {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    let base = commit_all(repo_root, "base");
+    commands::create_branch(repo_root, "feature").unwrap();
+    base
+}
+
+#[test]
+fn fast_forwards_when_head_is_behind() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_branches(&repo_root);
+
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::write(repo_root.join("b.txt"), "feature\n").unwrap();
+    let feature_commit = commit_all(&repo_root, "feature work");
+    commands::checkout(&repo_root, "main").unwrap();
+    assert!(!repo_root.join("b.txt").exists());
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert_eq!(
+        outcome,
+        commands::MergeOutcome::FastForward(feature_commit.clone())
+    );
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(feature_commit)
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("b.txt")).unwrap(),
+        "feature\n"
+    );
+}
+
+#[test]
+fn reports_up_to_date_when_target_is_an_ancestor() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_branches(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "main\n").unwrap();
+    let head = commit_all(&repo_root, "main work");
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert_eq!(outcome, commands::MergeOutcome::UpToDate);
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(head)
+    );
+}
+
+#[test]
+fn three_way_merge_creates_commit_with_two_parents() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let base = setup_branches(&repo_root);
+
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::create_dir_all(repo_root.join("src")).unwrap();
+    fs::write(repo_root.join("src/lib.rs"), "feature\n").unwrap();
+    let feature_commit = commit_all(&repo_root, "feature work");
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), "main\n").unwrap();
+    let main_commit = commit_all(&repo_root, "main work");
+
+    assert_eq!(
+        utils::merge_base(&repo_root, &main_commit, &feature_commit).unwrap(),
+        Some(base)
+    );
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+    let commands::MergeOutcome::Merged(merge_commit) = outcome else {
+        panic!("expected a merge commit, got {:?}", outcome);
+    };
+
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, &merge_commit).unwrap(),
+        vec![main_commit, feature_commit]
+    );
+    assert!(
+        utils::parse_commit(&repo_root, &merge_commit)
+            .unwrap()
+            .ends_with("\n\nMerge branch 'feature'")
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "main\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("src/lib.rs")).unwrap(),
+        "feature\n"
+    );
+
+    let mut tree_map = std::collections::HashMap::new();
+    let tree_hash = utils::get_current_tree_hash(&repo_root).unwrap().unwrap();
+    utils::get_tree_files_map(&repo_root, Path::new(""), &tree_hash, &mut tree_map).unwrap();
+    assert_eq!(tree_map.len(), 2);
+    assert!(commands::log(&repo_root).is_ok());
+}
+
+#[test]
+fn merge_applies_deletions_from_the_other_side() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "a\n").unwrap();
+    fs::create_dir_all(repo_root.join("old")).unwrap();
+    fs::write(repo_root.join("old/gone.txt"), "gone\n").unwrap();
+    commit_all(&repo_root, "base");
+    commands::create_branch(&repo_root, "feature").unwrap();
+
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::remove_dir_all(repo_root.join("old")).unwrap();
+    commands::commit_with_all(&repo_root, "delete".to_string(), &vec![], true).unwrap();
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), "changed\n").unwrap();
+    commit_all(&repo_root, "main work");
+
+    commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert!(!repo_root.join("old").exists());
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "changed\n"
+    );
+}
+
+/// Diverges `a.txt` on `main` and `feature` from a shared five-line base.
+fn diverge_lines(repo_root: &Path, main_lines: &str, feature_lines: &str) -> (String, String) {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\nfour\nfive\n").unwrap();
+    commit_all(repo_root, "base");
+    commands::create_branch(repo_root, "feature").unwrap();
+
+    commands::checkout(repo_root, "feature").unwrap();
+    fs::write(repo_root.join("a.txt"), feature_lines).unwrap();
+    let feature_commit = commit_all(repo_root, "feature work");
+
+    commands::checkout(repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), main_lines).unwrap();
+    let main_commit = commit_all(repo_root, "main work");
+
+    (main_commit, feature_commit)
+}
+
+#[test]
+fn edits_to_different_lines_merge_cleanly() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    diverge_lines(
+        &repo_root,
+        "ONE\ntwo\nthree\nfour\nfive\n",
+        "one\ntwo\nthree\nfour\nFIVE\n",
+    );
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert!(matches!(outcome, commands::MergeOutcome::Merged(_)));
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "ONE\ntwo\nthree\nfour\nFIVE\n"
+    );
+}
+
+#[test]
+fn overlapping_edits_write_conflict_markers_and_index_stages() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (main_commit, feature_commit) = diverge_lines(
+        &repo_root,
+        "one\ntwo\nmain\nfour\nfive\n",
+        "one\ntwo\nfeature\nfour\nfive\n",
+    );
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert_eq!(
+        outcome,
+        commands::MergeOutcome::Conflicted(vec![PathBuf::from("a.txt")])
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "one\ntwo\n<<<<<<< HEAD\nmain\n=======\nfeature\n>>>>>>> feature\nfour\nfive\n"
+    );
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(main_commit.clone())
+    );
+
+    let conflicts = utils::read_index_conflicts(&repo_root).unwrap();
+    let conflict = &conflicts[Path::new("a.txt")];
+    assert!(conflict.base.is_some() && conflict.ours.is_some() && conflict.theirs.is_some());
+    assert!(
+        !utils::read_index_entries(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("a.txt"))
+    );
+    let index = fs::read_to_string(repo_root.join(".rustygit/index")).unwrap();
+    assert!(index.contains("100644:1 "));
+    assert!(index.contains("100644:2 "));
+    assert!(index.contains("100644:3 "));
+
+    let status = commands::status(&repo_root, &vec![]).unwrap();
+    assert!(status.contains("Unmerged paths:"));
+    assert!(status.contains("both modified:\ta.txt"));
+
+    assert!(commands::commit(&repo_root, String::new(), &vec![]).is_err());
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nresolved\nfour\nfive\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+    assert!(utils::read_index_conflicts(&repo_root).unwrap().is_empty());
+
+    let merge_commit = commands::commit(&repo_root, String::new(), &vec![]).unwrap();
+
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, &merge_commit).unwrap(),
+        vec![main_commit, feature_commit]
+    );
+    assert!(
+        utils::parse_commit(&repo_root, &merge_commit)
+            .unwrap()
+            .ends_with("\n\nMerge branch 'feature'")
+    );
+    assert!(!repo_root.join(".rustygit/MERGE_HEAD").exists());
+}
+
+#[test]
+fn merge_abort_restores_head() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (main_commit, _) = diverge_lines(
+        &repo_root,
+        "main\ntwo\nthree\nfour\nfive\n",
+        "feature\ntwo\nthree\nfour\nfive\n",
+    );
+
+    let outcome = commands::merge(&repo_root, "feature", None).unwrap();
+    assert!(matches!(outcome, commands::MergeOutcome::Conflicted(_)));
+    assert!(commands::merge(&repo_root, "feature", None).is_err());
+
+    commands::merge_abort(&repo_root).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "main\ntwo\nthree\nfour\nfive\n"
+    );
+    assert!(utils::read_index_conflicts(&repo_root).unwrap().is_empty());
+    assert!(!repo_root.join(".rustygit/MERGE_HEAD").exists());
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(main_commit)
+    );
+    assert!(commands::merge_abort(&repo_root).is_err());
+}
+
+#[test]
+fn modify_delete_conflict_keeps_modified_file() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_branches(&repo_root);
+    fs::write(repo_root.join("keep.txt"), "keep\n").unwrap();
+    commit_all(&repo_root, "add keep");
+    commands::create_branch(&repo_root, "other").unwrap();
+
+    commands::checkout(&repo_root, "other").unwrap();
+    fs::write(repo_root.join("a.txt"), "changed\n").unwrap();
+    commit_all(&repo_root, "modify");
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::remove_file(repo_root.join("a.txt")).unwrap();
+    commands::commit_with_all(&repo_root, "delete".to_string(), &vec![], true).unwrap();
+
+    let outcome = commands::merge(&repo_root, "other", None).unwrap();
+
+    assert!(matches!(outcome, commands::MergeOutcome::Conflicted(_)));
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "changed\n"
+    );
+    let status = commands::status(&repo_root, &vec![]).unwrap();
+    assert!(status.contains("deleted by us:\ta.txt"));
+}
+
+#[test]
+fn merge_text_handles_identical_and_adjacent_changes() {
+    let base = b"a\nb\nc\n";
+
+    let same = utils::merge_text(base, b"a\nB\nc\n", b"a\nB\nc\n", "ours", "theirs");
+    assert!(!same.conflicted);
+    assert_eq!(same.content, b"a\nB\nc\n");
+
+    let one_sided = utils::merge_text(base, base, b"a\nb\nc\nd\n", "ours", "theirs");
+    assert!(!one_sided.conflicted);
+    assert_eq!(one_sided.content, b"a\nb\nc\nd\n");
+
+    let no_newline = utils::merge_text(b"x", b"y", b"z", "ours", "theirs");
+    assert!(no_newline.conflicted);
+    assert_eq!(
+        no_newline.content,
+        b"<<<<<<< ours\ny\n=======\nz\n>>>>>>> theirs\n"
+    );
+}
+
+#[test]
+fn refuses_to_merge_with_staged_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_branches(&repo_root);
+
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::write(repo_root.join("b.txt"), "feature\n").unwrap();
+    commit_all(&repo_root, "feature work");
+    commands::checkout(&repo_root, "main").unwrap();
+
+    fs::write(repo_root.join("a.txt"), "staged\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+
+    assert!(commands::merge(&repo_root, "feature", None).is_err());
+    assert!(!repo_root.join("b.txt").exists());
+}
+
+#[test]
+fn unknown_target_is_an_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_branches(&repo_root);
+
+    assert!(commands::merge(&repo_root, "missing", None).is_err());
+}
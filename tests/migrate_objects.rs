@@ -0,0 +1,61 @@
+use rustygit::{commands, utils};
+use std::fs;
+use tempfile::tempdir;
+
+fn write_legacy_object(repo_root: &std::path::Path, data: &[u8]) -> String {
+    let hash = utils::hash_bytes(data);
+    let object_path = utils::loose_object_path(repo_root, &hash);
+    fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+    fs::write(object_path, data).unwrap();
+    hash
+}
+
+#[test]
+fn legacy_uncompressed_object_is_still_readable() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    let hash = write_legacy_object(&repo_root, b"blob 5\0hello");
+
+    let content = utils::parse_blob(&repo_root, &hash).unwrap();
+    assert_eq!(content, "hello");
+}
+
+#[test]
+fn migrate_compresses_legacy_objects() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    let legacy_hash = write_legacy_object(&repo_root, b"blob 5\0hello");
+
+    fs::write(repo_root.join("a.txt"), b"already compressed").unwrap();
+    let compressed_hash = commands::write_blob(&repo_root, &repo_root.join("a.txt")).unwrap();
+
+    let migrated = commands::migrate_objects(&repo_root).unwrap();
+    assert_eq!(migrated, 1);
+
+    let raw = fs::read(utils::loose_object_path(&repo_root, &legacy_hash)).unwrap();
+    assert!(utils::is_compressed(&raw));
+    assert_eq!(utils::parse_blob(&repo_root, &legacy_hash).unwrap(), "hello");
+    assert_eq!(
+        utils::parse_blob(&repo_root, &compressed_hash).unwrap(),
+        "already compressed"
+    );
+
+    let migrated_again = commands::migrate_objects(&repo_root).unwrap();
+    assert_eq!(migrated_again, 0);
+}
+
+#[test]
+fn migrate_rejects_corrupt_objects() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    let hash = write_legacy_object(&repo_root, b"blob 5\0hello");
+    fs::write(utils::loose_object_path(&repo_root, &hash), b"blob 5\0jello").unwrap();
+
+    assert!(commands::migrate_objects(&repo_root).is_err());
+}
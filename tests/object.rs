@@ -48,3 +48,29 @@ fn object_is_written_to_disk() {
 
     assert!(object_path.exists());
 }
+
+#[test]
+fn object_is_zlib_compressed_on_disk() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    rustygit::commands::init(&repo_root).unwrap();
+
+    let file = repo_root.join("data.txt");
+    fs::write(&file, b"content").unwrap();
+
+    let hash = rustygit::commands::write_blob(&repo_root, &file).unwrap();
+
+    let object_path = rustygit::utils::loose_object_path(&repo_root, &hash);
+    let raw = fs::read(object_path).unwrap();
+
+    assert_eq!(raw[0], 0x78);
+    assert_eq!(
+        rustygit::utils::decompress(&raw).unwrap(),
+        b"blob 7\0content".to_vec()
+    );
+    assert_eq!(
+        rustygit::utils::parse_blob(&repo_root, &hash).unwrap(),
+        "content"
+    );
+}
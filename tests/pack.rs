@@ -0,0 +1,304 @@
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+enum Entry<'a> {
+    Full(u8, &'a [u8]),
+    OfsDelta(usize, Vec<u8>),
+    RefDelta(&'a str, Vec<u8>),
+    /// Entry bytes written as given, for corrupt entries.
+    Raw(Vec<u8>),
+}
+
+fn entry_header(type_code: u8, size: usize) -> Vec<u8> {
+    let mut size = size;
+    let mut byte = (type_code << 4) | (size & 0x0f) as u8;
+    size >>= 4;
+    let mut out = Vec::new();
+    while size > 0 {
+        out.push(byte | 0x80);
+        byte = (size & 0x7f) as u8;
+        size >>= 7;
+    }
+    out.push(byte);
+    out
+}
+
+fn ofs_distance(distance: usize) -> Vec<u8> {
+    let mut distance = distance;
+    let mut out = vec![(distance & 0x7f) as u8];
+    distance >>= 7;
+    while distance > 0 {
+        distance -= 1;
+        out.push(0x80 | (distance & 0x7f) as u8);
+        distance >>= 7;
+    }
+    out.reverse();
+    out
+}
+
+/// Writes `pack-test.pack` and a matching v2 `pack-test.idx` containing `entries`,
+/// where `hashes[i]` is the object id of `entries[i]`.
+fn write_pack(repo_root: &Path, entries: &[Entry], hashes: &[String]) {
+    let mut pack = b"PACK".to_vec();
+    pack.extend_from_slice(&2u32.to_be_bytes());
+    pack.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+
+    let mut offsets = Vec::new();
+    for entry in entries {
+        let offset = pack.len();
+        match entry {
+            Entry::Full(type_code, data) => {
+                pack.extend(entry_header(*type_code, data.len()));
+                pack.extend(utils::compress(data).unwrap());
+            }
+            Entry::OfsDelta(base_index, delta) => {
+                pack.extend(entry_header(6, delta.len()));
+                pack.extend(ofs_distance(offset - offsets[*base_index]));
+                pack.extend(utils::compress(delta).unwrap());
+            }
+            Entry::RefDelta(base_hash, delta) => {
+                pack.extend(entry_header(7, delta.len()));
+                pack.extend(utils::hex_to_bytes(base_hash));
+                pack.extend(utils::compress(delta).unwrap());
+            }
+            Entry::Raw(bytes) => pack.extend_from_slice(bytes),
+        }
+        offsets.push(offset);
+    }
+    let pack_checksum = utils::hex_to_bytes(&utils::hash_bytes(&pack));
+    pack.extend_from_slice(&pack_checksum);
+
+    let mut sorted: Vec<(String, usize)> = hashes.iter().cloned().zip(offsets).collect();
+    sorted.sort();
+
+    let mut idx = vec![0xff, b't', b'O', b'c'];
+    idx.extend_from_slice(&2u32.to_be_bytes());
+    for byte in 0..256usize {
+        let count = sorted
+            .iter()
+            .filter(|(hash, _)| (utils::hex_to_bytes(hash)[0] as usize) <= byte)
+            .count();
+        idx.extend_from_slice(&(count as u32).to_be_bytes());
+    }
+    for (hash, _) in &sorted {
+        idx.extend_from_slice(&utils::hex_to_bytes(hash));
+    }
+    for _ in &sorted {
+        idx.extend_from_slice(&0u32.to_be_bytes());
+    }
+    for (_, offset) in &sorted {
+        idx.extend_from_slice(&(*offset as u32).to_be_bytes());
+    }
+    idx.extend_from_slice(&pack_checksum);
+    let idx_checksum = utils::hex_to_bytes(&utils::hash_bytes(&idx));
+    idx.extend_from_slice(&idx_checksum);
+
+    let pack_dir = repo_root.join(".rustygit/objects/pack");
+    fs::create_dir_all(&pack_dir).unwrap();
+    fs::write(pack_dir.join("pack-test.pack"), pack).unwrap();
+    fs::write(pack_dir.join("pack-test.idx"), idx).unwrap();
+}
+
+fn blob_hash(content: &[u8]) -> String {
+    utils::hash_bytes(&commands::format_object(content))
+}
+
+/// Delta turning "hello world\n" into "hello rusty world\n".
+fn hello_delta() -> Vec<u8> {
+    let mut delta = vec![12, 18];
+    delta.extend_from_slice(&[0x90, 6]);
+    delta.push(6);
+    delta.extend_from_slice(b"rusty ");
+    delta.extend_from_slice(&[0x91, 6, 6]);
+    delta
+}
+
+#[test]
+fn reads_full_and_ofs_delta_objects_from_pack() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let base = b"hello world\n";
+    let target = b"hello rusty world\n";
+    let hashes = vec![blob_hash(base), blob_hash(target)];
+
+    write_pack(
+        &repo_root,
+        &[Entry::Full(3, base), Entry::OfsDelta(0, hello_delta())],
+        &hashes,
+    );
+
+    assert!(utils::object_exists(&repo_root, &hashes[0]));
+    assert!(utils::object_exists(&repo_root, &hashes[1]));
+    assert_eq!(
+        utils::parse_blob(&repo_root, &hashes[0]).unwrap(),
+        "hello world\n"
+    );
+    assert_eq!(
+        utils::parse_blob(&repo_root, &hashes[1]).unwrap(),
+        "hello rusty world\n"
+    );
+}
+
+#[test]
+fn resolves_ref_delta_against_loose_base() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("base.txt"), b"hello world\n").unwrap();
+    let base_hash = commands::write_blob(&repo_root, &repo_root.join("base.txt")).unwrap();
+
+    let target_hash = blob_hash(b"hello rusty world\n");
+    write_pack(
+        &repo_root,
+        &[Entry::RefDelta(&base_hash, hello_delta())],
+        std::slice::from_ref(&target_hash),
+    );
+
+    let object = utils::read_object(&repo_root, &target_hash).unwrap();
+    assert_eq!(utils::hash_bytes(&object), target_hash);
+}
+
+#[test]
+fn packed_commit_history_is_usable() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    let commit_hash = commands::commit(&repo_root, "first".to_string(), &vec![]).unwrap();
+
+    // Move every loose object into a pack and delete the loose copies.
+    let tree_hash = utils::get_current_tree_hash(&repo_root).unwrap().unwrap();
+    let blob = blob_hash(b"one");
+    let hashes = vec![commit_hash.clone(), tree_hash.clone(), blob.clone()];
+    let objects: Vec<(String, Vec<u8>)> = hashes
+        .iter()
+        .map(|hash| utils::split_object(&utils::read_object(&repo_root, hash).unwrap()).unwrap())
+        .collect();
+    let entries = vec![
+        Entry::Full(1, &objects[0].1),
+        Entry::Full(2, &objects[1].1),
+        Entry::Full(3, &objects[2].1),
+    ];
+    write_pack(&repo_root, &entries, &hashes);
+    for hash in &hashes {
+        fs::remove_file(utils::loose_object_path(&repo_root, hash)).unwrap();
+    }
+
+    assert!(commands::log(&repo_root).is_ok());
+
+    let mut tree_map = std::collections::HashMap::new();
+    utils::get_tree_files_map(&repo_root, Path::new(""), &tree_hash, &mut tree_map).unwrap();
+    assert_eq!(tree_map.get(Path::new("a.txt")), Some(&blob));
+}
+
+#[test]
+fn missing_object_is_an_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let missing = blob_hash(b"missing");
+    assert!(!utils::object_exists(&repo_root, &missing));
+    assert!(utils::read_object(&repo_root, &missing).is_err());
+}
+
+#[test]
+fn create_delta_round_trips_through_apply_delta() {
+    let base: Vec<u8> = (0..5000).map(|n| (n % 251) as u8).collect();
+    let mut target = base.clone();
+    target.splice(1000..1010, b"inserted bytes".iter().copied());
+    target.extend_from_slice(b"trailer");
+
+    let delta = utils::create_delta(&base, &target);
+
+    assert!(delta.len() < target.len() / 10);
+    assert_eq!(utils::apply_delta(&base, &delta).unwrap(), target);
+}
+
+#[test]
+fn overlong_size_header_is_a_corrupt_pack_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let content = b"hello world\n";
+    let hash = blob_hash(content);
+    write_pack(
+        &repo_root,
+        &[Entry::Full(3, content)],
+        std::slice::from_ref(&hash),
+    );
+
+    // Make the entry header continue for longer than a u64 can hold.
+    let pack_path = repo_root.join(".rustygit/objects/pack/pack-test.pack");
+    let mut pack = fs::read(&pack_path).unwrap();
+    pack[12..24].fill(0xff);
+    fs::write(&pack_path, pack).unwrap();
+
+    let err = utils::read_object(&repo_root, &hash).unwrap_err();
+    assert!(err.to_string().contains("Corrupt pack"), "{}", err);
+}
+
+#[test]
+fn cyclic_delta_bases_are_a_corrupt_pack_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    // An OFS_DELTA at distance 0, a REF_DELTA naming itself, and two
+    // REF_DELTAs naming each other.
+    let own_offset = blob_hash(b"own offset");
+    let own_hash = blob_hash(b"own hash");
+    let first = blob_hash(b"first");
+    let second = blob_hash(b"second");
+    let mut zero_distance = entry_header(6, hello_delta().len());
+    zero_distance.push(0);
+    zero_distance.extend(utils::compress(&hello_delta()).unwrap());
+    write_pack(
+        &repo_root,
+        &[
+            Entry::Raw(zero_distance),
+            Entry::RefDelta(&own_hash, hello_delta()),
+            Entry::RefDelta(&second, hello_delta()),
+            Entry::RefDelta(&first, hello_delta()),
+        ],
+        &[
+            own_offset.clone(),
+            own_hash.clone(),
+            first.clone(),
+            second.clone(),
+        ],
+    );
+
+    let err = utils::read_object(&repo_root, &own_offset).unwrap_err();
+    assert!(err.to_string().contains("its own base"), "{}", err);
+    for hash in [&own_hash, &first, &second] {
+        let err = utils::read_object(&repo_root, hash).unwrap_err();
+        assert!(err.to_string().contains("delta chain"), "{}", err);
+    }
+}
+
+#[test]
+fn huge_declared_size_is_an_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let hash = blob_hash(b"hi");
+    let mut entry = entry_header(3, 1 << 60);
+    entry.extend(utils::compress(b"hi").unwrap());
+    write_pack(
+        &repo_root,
+        &[Entry::Raw(entry)],
+        std::slice::from_ref(&hash),
+    );
+
+    let err = utils::read_object(&repo_root, &hash).unwrap_err();
+    assert!(err.to_string().contains("size mismatch"), "{}", err);
+}
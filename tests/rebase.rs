@@ -0,0 +1,410 @@
+use assert_cmd::Command;
+use rustygit::utils::ConfigScope;
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn read(repo_root: &Path, file: &str) -> String {
+    fs::read_to_string(repo_root.join(file)).unwrap()
+}
+
+fn head(repo_root: &Path) -> String {
+    utils::get_current_commit_hash(repo_root).unwrap().unwrap()
+}
+
+fn parent(repo_root: &Path, commit_hash: &str) -> String {
+    utils::get_commit_parents(repo_root, commit_hash)
+        .unwrap()
+        .remove(0)
+}
+
+fn subject(repo_root: &Path, commit_hash: &str) -> String {
+    let message = utils::get_commit_message(repo_root, commit_hash).unwrap();
+    message.lines().next().unwrap_or_default().to_string()
+}
+
+/// Creates `main` with a base commit and a `topic` branch with two commits
+/// (editing `a.txt`, then adding `b.txt`), then adds a commit to `main`
+/// that edits `c.txt`. Leaves `topic` checked out; returns main's tip.
+fn setup_diverged(repo_root: &Path) -> String {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    fs::write(repo_root.join("c.txt"), "c\n").unwrap();
+    commit_all(repo_root, "base");
+    commands::create_branch(repo_root, "topic").unwrap();
+
+    fs::write(repo_root.join("c.txt"), "c on main\n").unwrap();
+    let main_tip = commit_all(repo_root, "main work");
+
+    commands::checkout(repo_root, "topic").unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE\n").unwrap();
+    commit_all(repo_root, "shout three");
+    fs::write(repo_root.join("b.txt"), "b\n").unwrap();
+    commit_all(repo_root, "add b");
+
+    main_tip
+}
+
+/// Configures `editor` (a shell command given the file to edit) as the
+/// sequence or message editor of the repository.
+fn set_editor(repo_root: &Path, key: &str, editor: &str) {
+    utils::set_config_value(repo_root, ConfigScope::Local, key, editor).unwrap();
+}
+
+/// Returns an editor command that replaces the edited file with `content`.
+fn replacing_editor(repo_root: &Path, name: &str, content: &str) -> String {
+    let file = repo_root.join(".rustygit").join(name);
+    fs::write(&file, content).unwrap();
+    format!("cp {}", file.display())
+}
+
+#[test]
+fn replays_branch_commits_onto_upstream() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let main_tip = setup_diverged(&repo_root);
+
+    let outcome = commands::rebase(&repo_root, "main", false).unwrap();
+
+    let tip = head(&repo_root);
+    assert_eq!(outcome, commands::RebaseOutcome::Finished(tip.clone()));
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/topic").unwrap(),
+        Some(tip.clone())
+    );
+    assert_eq!(subject(&repo_root, &tip), "add b");
+    let first = parent(&repo_root, &tip);
+    assert_eq!(subject(&repo_root, &first), "shout three");
+    assert_eq!(parent(&repo_root, &first), main_tip);
+
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nTHREE\n");
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+    assert_eq!(read(&repo_root, "c.txt"), "c on main\n");
+    assert!(!repo_root.join(".rustygit/rebase-merge").exists());
+
+    let reflog = commands::reflog(&repo_root, None).unwrap();
+    assert!(reflog[0].ends_with("rebase (pick): add b"));
+    assert!(reflog[2].ends_with("rebase (start): checkout main"));
+}
+
+#[test]
+fn replays_commits_merged_in_from_a_second_parent() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let main_tip = setup_diverged(&repo_root);
+
+    commands::create_branch(&repo_root, "side").unwrap();
+    commands::checkout(&repo_root, "side").unwrap();
+    fs::write(repo_root.join("t.txt"), "t\n").unwrap();
+    commit_all(&repo_root, "add t");
+    commands::checkout(&repo_root, "topic").unwrap();
+    fs::write(repo_root.join("d.txt"), "d\n").unwrap();
+    commit_all(&repo_root, "add d");
+    commands::merge(&repo_root, "side", None).unwrap();
+
+    commands::rebase(&repo_root, "main", false).unwrap();
+
+    // First-parent history first, then the merged-in commit; no merge.
+    let mut subjects = Vec::new();
+    let mut commit_hash = head(&repo_root);
+    while commit_hash != main_tip {
+        assert_eq!(
+            utils::get_commit_parents(&repo_root, &commit_hash)
+                .unwrap()
+                .len(),
+            1
+        );
+        subjects.push(subject(&repo_root, &commit_hash));
+        commit_hash = parent(&repo_root, &commit_hash);
+    }
+    assert_eq!(subjects, ["add t", "add d", "add b", "shout three"]);
+    assert_eq!(read(&repo_root, "t.txt"), "t\n");
+    assert_eq!(read(&repo_root, "d.txt"), "d\n");
+}
+
+#[test]
+fn reports_up_to_date_and_fast_forwards() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let main_tip = setup_diverged(&repo_root);
+    let topic_tip = head(&repo_root);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    commands::create_branch(&repo_root, "behind").unwrap();
+    commands::checkout(&repo_root, "behind").unwrap();
+    commands::reset(&repo_root, "main~1", commands::ResetMode::Hard).unwrap();
+
+    assert_eq!(
+        commands::rebase(&repo_root, "main~1", false).unwrap(),
+        commands::RebaseOutcome::UpToDate
+    );
+    assert_eq!(
+        commands::rebase(&repo_root, "main", false).unwrap(),
+        commands::RebaseOutcome::Finished(main_tip)
+    );
+    assert_eq!(read(&repo_root, "c.txt"), "c on main\n");
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/topic").unwrap(),
+        Some(topic_tip)
+    );
+}
+
+#[test]
+fn stops_on_conflict_and_continues_after_resolution() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+    let orig_head = head(&repo_root);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    let main_tip = commit_all(&repo_root, "exclaim three");
+    commands::checkout(&repo_root, "topic").unwrap();
+
+    let outcome = commands::rebase(&repo_root, "main", false).unwrap();
+
+    let commands::RebaseOutcome::Conflicted { commit, paths } = outcome else {
+        panic!("unexpected outcome: {:?}", outcome);
+    };
+    assert_eq!(subject(&repo_root, &commit), "shout three");
+    assert_eq!(paths, vec![PathBuf::from("a.txt")]);
+    assert_eq!(head(&repo_root), main_tip);
+    assert_eq!(
+        utils::read_symbolic_head(&repo_root).unwrap().as_deref(),
+        Some("refs/heads/topic")
+    );
+    assert!(read(&repo_root, "a.txt").contains("<<<<<<< HEAD\n"));
+    assert!(commands::rebase_continue(&repo_root).is_err());
+    assert!(commands::rebase(&repo_root, "main", false).is_err());
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE!\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+    let outcome = commands::rebase_continue(&repo_root).unwrap();
+
+    let tip = head(&repo_root);
+    assert_eq!(outcome, commands::RebaseOutcome::Finished(tip.clone()));
+    assert_eq!(subject(&repo_root, &tip), "add b");
+    let resolved = parent(&repo_root, &tip);
+    assert_eq!(subject(&repo_root, &resolved), "shout three");
+    assert_eq!(parent(&repo_root, &resolved), main_tip);
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nTHREE!\n");
+    assert!(!repo_root.join(".rustygit/rebase-merge").exists());
+    assert_ne!(tip, orig_head);
+}
+
+#[test]
+fn skip_drops_the_conflicting_commit() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    let main_tip = commit_all(&repo_root, "exclaim three");
+    commands::checkout(&repo_root, "topic").unwrap();
+
+    commands::rebase(&repo_root, "main", false).unwrap();
+    let outcome = commands::rebase_skip(&repo_root).unwrap();
+
+    let tip = head(&repo_root);
+    assert_eq!(outcome, commands::RebaseOutcome::Finished(tip.clone()));
+    assert_eq!(subject(&repo_root, &tip), "add b");
+    assert_eq!(parent(&repo_root, &tip), main_tip);
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nthree!\n");
+    assert!(utils::read_index_conflicts(&repo_root).unwrap().is_empty());
+}
+
+#[test]
+fn abort_restores_the_original_branch() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+    let orig_head = head(&repo_root);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("b.txt"), "b on main\n").unwrap();
+    commit_all(&repo_root, "add b on main");
+    commands::checkout(&repo_root, "topic").unwrap();
+
+    let outcome = commands::rebase(&repo_root, "main", false).unwrap();
+    assert!(matches!(
+        outcome,
+        commands::RebaseOutcome::Conflicted { .. }
+    ));
+
+    commands::rebase_abort(&repo_root).unwrap();
+
+    assert_eq!(head(&repo_root), orig_head);
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nTHREE\n");
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+    assert_eq!(read(&repo_root, "c.txt"), "c\n");
+    assert!(!repo_root.join(".rustygit/rebase-merge").exists());
+    assert!(commands::rebase_abort(&repo_root).is_err());
+}
+
+#[test]
+fn refuses_to_start_with_local_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+    let orig_head = head(&repo_root);
+
+    fs::write(repo_root.join("b.txt"), "edited\n").unwrap();
+
+    let err = commands::rebase(&repo_root, "main", false).unwrap_err();
+
+    assert!(err.to_string().contains("unstaged changes"));
+    assert_eq!(head(&repo_root), orig_head);
+    assert!(!repo_root.join(".rustygit/rebase-merge").exists());
+}
+
+#[test]
+fn interactive_rebase_follows_the_edited_todo_list() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "a\n").unwrap();
+    let base = commit_all(&repo_root, "base");
+    let mut commits = Vec::new();
+    for name in ["one", "two", "three", "four", "five"] {
+        fs::write(repo_root.join(format!("{}.txt", name)), name).unwrap();
+        commits.push(commit_all(&repo_root, name));
+    }
+
+    let todo = format!(
+        "# reordered\nreword {}\npick {} two\nf {}\nd {}\nsquash {}\n",
+        &commits[2][..7],
+        &commits[0][..7],
+        &commits[1][..7],
+        &commits[3][..7],
+        &commits[4][..7]
+    );
+    let editor = replacing_editor(&repo_root, "todo", &todo);
+    set_editor(&repo_root, "sequence.editor", &editor);
+    let editor = replacing_editor(&repo_root, "message", "edited message\n\n# comment\n");
+    set_editor(&repo_root, "core.editor", &editor);
+
+    let outcome = commands::rebase(&repo_root, "HEAD~5", true).unwrap();
+
+    let tip = head(&repo_root);
+    assert_eq!(outcome, commands::RebaseOutcome::Finished(tip.clone()));
+    // squash five into (one + fixup two), with an edited message
+    assert_eq!(
+        utils::get_commit_message(&repo_root, &tip).unwrap(),
+        "edited message\n"
+    );
+    let reworded = parent(&repo_root, &tip);
+    assert_eq!(
+        utils::get_commit_message(&repo_root, &reworded).unwrap(),
+        "edited message\n"
+    );
+    assert_eq!(parent(&repo_root, &reworded), base);
+
+    for (file, exists) in [
+        ("one.txt", true),
+        ("two.txt", true),
+        ("three.txt", true),
+        ("four.txt", false),
+        ("five.txt", true),
+    ] {
+        assert_eq!(repo_root.join(file).exists(), exists, "{}", file);
+    }
+    let tree = utils::get_commit_entries_map(&repo_root, Some(&reworded)).unwrap();
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn edit_step_stops_and_amends_staged_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+    let orig_first = parent(&repo_root, &head(&repo_root));
+
+    let todo = format!("edit {}\npick HEAD\n", &orig_first[..7]);
+    let editor = replacing_editor(&repo_root, "todo", &todo);
+    set_editor(&repo_root, "sequence.editor", &editor);
+
+    let outcome = commands::rebase(&repo_root, "topic~2", true).unwrap();
+
+    assert_eq!(outcome, commands::RebaseOutcome::Edit(orig_first.clone()));
+    // The unchanged commit is reused rather than recreated.
+    assert_eq!(head(&repo_root), orig_first);
+
+    fs::write(repo_root.join("a.txt"), "one\nTWO\nTHREE\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+    let outcome = commands::rebase_continue(&repo_root).unwrap();
+
+    let tip = head(&repo_root);
+    assert_eq!(outcome, commands::RebaseOutcome::Finished(tip.clone()));
+    assert_eq!(subject(&repo_root, &tip), "add b");
+    let amended = parent(&repo_root, &tip);
+    assert_ne!(amended, orig_first);
+    assert_eq!(subject(&repo_root, &amended), "shout three");
+    assert_eq!(read(&repo_root, "a.txt"), "one\nTWO\nTHREE\n");
+}
+
+#[test]
+fn emptied_todo_list_aborts_without_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+    let orig_head = head(&repo_root);
+    let editor = replacing_editor(&repo_root, "todo", "# nothing\n");
+    set_editor(&repo_root, "sequence.editor", &editor);
+
+    let err = commands::rebase(&repo_root, "main", true).unwrap_err();
+
+    assert!(err.to_string().contains("Nothing to do"));
+    assert_eq!(head(&repo_root), orig_head);
+    assert!(!repo_root.join(".rustygit/rebase-merge").exists());
+}
+
+#[test]
+fn cli_resumes_a_stopped_rebase_in_a_new_process() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    setup_diverged(&repo_root);
+
+    commands::checkout(&repo_root, "main").unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree!\n").unwrap();
+    commit_all(&repo_root, "exclaim three");
+    commands::checkout(&repo_root, "topic").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::cargo_bin("rustygit")
+            .unwrap()
+            .current_dir(&repo_root)
+            .args(args)
+            .assert()
+    };
+
+    let assert = run(&["rebase", "main"]).failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert_eq!(stdout, "CONFLICT: Merge conflict in a.txt\n");
+    assert!(stderr.contains("shout three"));
+    assert!(
+        repo_root
+            .join(".rustygit/rebase-merge/git-rebase-todo")
+            .exists()
+    );
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nTHREE!\n").unwrap();
+    run(&["add", "a.txt"]).success();
+
+    let assert = run(&["rebase", "--continue"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(
+        stdout,
+        "Successfully rebased and updated refs/heads/topic.\n"
+    );
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+}
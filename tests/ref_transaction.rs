@@ -0,0 +1,282 @@
+use rustygit::{commands, utils};
+use std::{fs, path::Path, thread};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn init_with_commit(repo_root: &Path) -> String {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    commit_all(repo_root, "first")
+}
+
+fn lock_files(dir: &Path) -> Vec<String> {
+    let mut locks = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            locks.extend(lock_files(&path));
+        } else if path.to_string_lossy().ends_with(".lock") {
+            locks.push(path.display().to_string());
+        }
+    }
+    locks
+}
+
+#[test]
+fn held_ref_lock_blocks_updates() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let first = init_with_commit(&repo_root);
+
+    let lock_path = repo_root.join(".rustygit/refs/heads/main.lock");
+    fs::write(&lock_path, "").unwrap();
+
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let err = commands::commit(&repo_root, "second".to_string(), &vec![]).unwrap_err();
+    assert!(err.to_string().contains("main.lock': File exists"));
+
+    // The other writer's lock is left alone and the branch is untouched.
+    assert!(lock_path.exists());
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/main").unwrap(),
+        Some(first.clone())
+    );
+
+    fs::remove_file(&lock_path).unwrap();
+    let second = commit_all(&repo_root, "second");
+    assert_ne!(second, first);
+}
+
+#[test]
+fn stale_expected_value_is_rejected() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let first = init_with_commit(&repo_root);
+    let stale = "0123456789abcdef0123456789abcdef01234567";
+
+    let err = utils::update_ref(
+        &repo_root,
+        "refs/heads/main",
+        stale,
+        Some(stale),
+        "update: stale",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Cannot update ref 'refs/heads/main': expected {}, but it is {}.",
+            stale, first
+        )
+    );
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/main").unwrap(),
+        Some(first.clone())
+    );
+    assert_eq!(
+        utils::read_reflog(&repo_root, "refs/heads/main")
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(lock_files(&repo_root.join(".rustygit")).is_empty());
+
+    // Creating a ref that already exists fails the same way.
+    assert!(commands::create_tag(&repo_root, "v1", None, None).is_ok());
+    assert!(
+        utils::update_ref(
+            &repo_root,
+            "refs/tags/v1",
+            &first,
+            Some(utils::NULL_HASH),
+            "tag: created",
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn transaction_applies_all_updates_or_none() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let first = init_with_commit(&repo_root);
+    commands::create_branch(&repo_root, "topic").unwrap();
+
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(&repo_root, "second");
+
+    let err = utils::RefTransaction::new()
+        .update("refs/heads/topic", &second, Some(&first), Some("sync"))
+        .update("refs/tags/release", &second, Some(&first), None)
+        .commit(&repo_root)
+        .unwrap_err();
+    assert!(err.to_string().contains("refs/tags/release"));
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/topic").unwrap(),
+        Some(first.clone())
+    );
+    assert!(!utils::ref_exists(&repo_root, "refs/tags/release"));
+    assert!(lock_files(&repo_root.join(".rustygit")).is_empty());
+
+    utils::RefTransaction::new()
+        .update("refs/heads/topic", &second, Some(&first), Some("sync"))
+        .update("refs/tags/release", &second, Some(utils::NULL_HASH), None)
+        .commit(&repo_root)
+        .unwrap();
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/topic").unwrap(),
+        Some(second.clone())
+    );
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/tags/release").unwrap(),
+        Some(second)
+    );
+}
+
+#[test]
+fn concurrent_updates_from_the_same_value_have_one_winner() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let first = init_with_commit(&repo_root);
+
+    let results: Vec<bool> = (0..8)
+        .map(|n| {
+            let repo_root = repo_root.clone();
+            let first = first.clone();
+            thread::spawn(move || {
+                let new_hash = format!("{:040x}", n + 1);
+                utils::update_ref(
+                    &repo_root,
+                    "refs/heads/main",
+                    &new_hash,
+                    Some(&first),
+                    "update: race",
+                )
+                .is_ok()
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    assert_eq!(results.iter().filter(|won| **won).count(), 1);
+    assert_eq!(
+        utils::read_reflog(&repo_root, "refs/heads/main")
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(lock_files(&repo_root.join(".rustygit")).is_empty());
+}
+
+#[test]
+fn index_writes_respect_the_index_lock() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_with_commit(&repo_root);
+
+    let lock_path = repo_root.join(".rustygit/index.lock");
+    fs::write(&lock_path, "").unwrap();
+
+    fs::write(repo_root.join("b.txt"), "new\n").unwrap();
+    let err = commands::add(&repo_root, Path::new("b.txt")).unwrap_err();
+    assert!(err.to_string().contains("index.lock': File exists"));
+    assert!(
+        !utils::read_index_map(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("b.txt"))
+    );
+
+    fs::remove_file(&lock_path).unwrap();
+    commands::add(&repo_root, Path::new("b.txt")).unwrap();
+    assert!(
+        utils::read_index_map(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("b.txt"))
+    );
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn concurrent_adds_never_drop_each_others_entries() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_with_commit(&repo_root);
+
+    let added: Vec<String> = (0..8)
+        .map(|n| {
+            let repo_root = repo_root.clone();
+            thread::spawn(move || {
+                let name = format!("file{}.txt", n);
+                fs::write(repo_root.join(&name), format!("{}\n", n)).unwrap();
+                commands::add(&repo_root, Path::new(&name))
+                    .is_ok()
+                    .then_some(name)
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter_map(|handle| handle.join().unwrap())
+        .collect();
+
+    // An add may lose the race for index.lock, but one that succeeded
+    // must not be overwritten by another.
+    assert!(!added.is_empty());
+    let index = utils::read_index_map(&repo_root).unwrap();
+    for name in &added {
+        assert!(index.contains_key(Path::new(name)), "{} was dropped", name);
+    }
+    assert!(lock_files(&repo_root.join(".rustygit")).is_empty());
+}
+
+#[test]
+fn head_updates_hold_the_head_lock() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let first = init_with_commit(&repo_root);
+
+    let lock_path = repo_root.join(".rustygit/HEAD.lock");
+    fs::write(&lock_path, "").unwrap();
+
+    // Moving the checked-out branch resolves HEAD, so it needs HEAD's lock too.
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let err = commands::commit(&repo_root, "second".to_string(), &vec![]).unwrap_err();
+    assert!(
+        err.to_string().contains("HEAD.lock': File exists"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/heads/main").unwrap(),
+        Some(first.clone())
+    );
+    fs::remove_file(&lock_path).unwrap();
+
+    // Attaching HEAD checks the commit it is expected to move from.
+    commands::create_branch(&repo_root, "topic").unwrap();
+    let err =
+        utils::set_head_to_branch(&repo_root, "topic", Some(utils::NULL_HASH), None).unwrap_err();
+    assert!(
+        err.to_string().contains("Cannot update ref 'HEAD'"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        utils::current_branch(&repo_root).unwrap().as_deref(),
+        Some("main")
+    );
+
+    utils::set_head_to_branch(&repo_root, "topic", Some(&first), None).unwrap();
+    assert_eq!(
+        utils::current_branch(&repo_root).unwrap().as_deref(),
+        Some("topic")
+    );
+    assert!(lock_files(&repo_root.join(".rustygit")).is_empty());
+}
@@ -0,0 +1,188 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn messages(repo_root: &Path, ref_name: &str) -> Vec<String> {
+    utils::read_reflog(repo_root, ref_name)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.message)
+        .collect()
+}
+
+#[test]
+fn commits_are_logged_for_branch_and_head() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    let first = commit_all(&repo_root, "first\n\nbody");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(&repo_root, "second");
+
+    let entries = utils::read_reflog(&repo_root, "refs/heads/main").unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].old_hash, utils::NULL_HASH);
+    assert_eq!(entries[0].new_hash, first);
+    assert_eq!(entries[0].message, "commit (initial): first");
+    assert_eq!(entries[1].old_hash, first);
+    assert_eq!(entries[1].new_hash, second);
+    assert_eq!(entries[1].message, "commit: second");
+    assert!(entries[1].identity.contains('<'));
+
+    assert_eq!(utils::read_reflog(&repo_root, "HEAD").unwrap(), entries);
+
+    let raw = fs::read_to_string(repo_root.join(".rustygit/logs/refs/heads/main")).unwrap();
+    let line = raw.lines().nth(1).unwrap();
+    assert!(line.starts_with(&format!("{} {} ", first, second)));
+    assert!(line.ends_with("\tcommit: second"));
+}
+
+#[test]
+fn bad_reset_can_be_recovered_through_the_reflog() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let commits: Vec<String> = (1..=3)
+        .map(|n| {
+            fs::write(repo_root.join("a.txt"), format!("v{}\n", n)).unwrap();
+            commit_all(&repo_root, &format!("c{}", n))
+        })
+        .collect();
+
+    commands::reset(&repo_root, "HEAD~2", commands::ResetMode::Mixed).unwrap();
+    assert_eq!(
+        messages(&repo_root, "HEAD").last().unwrap(),
+        "reset: moving to HEAD~2"
+    );
+
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "main@{0}").unwrap(),
+        commits[0]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "main@{1}").unwrap(),
+        commits[2]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD@{2}").unwrap(),
+        commits[1]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "@{1}~1").unwrap(),
+        commits[1]
+    );
+    assert!(utils::resolve_revision(&repo_root, "main@{9}").is_err());
+
+    // The lost commit survives gc because the reflog still names it.
+    commands::gc(&repo_root, 0).unwrap();
+    assert!(utils::object_exists(&repo_root, &commits[2]));
+
+    commands::reset(&repo_root, "main@{1}", commands::ResetMode::Mixed).unwrap();
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(commits[2].clone())
+    );
+}
+
+#[test]
+fn checkout_branch_and_merge_updates_are_logged() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    let base = commit_all(&repo_root, "base");
+    commands::create_branch(&repo_root, "feature").unwrap();
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::write(repo_root.join("b.txt"), "feature\n").unwrap();
+    let feature = commit_all(&repo_root, "feature work");
+    commands::checkout(&repo_root, "main").unwrap();
+    commands::merge(&repo_root, "feature", None).unwrap();
+    commands::create_branch_at(&repo_root, "feature", Some(&base), true).unwrap();
+
+    assert_eq!(
+        messages(&repo_root, "HEAD"),
+        vec![
+            "commit (initial): base",
+            "checkout: moving from main to feature",
+            "commit: feature work",
+            "checkout: moving from feature to main",
+            "merge feature: Fast-forward",
+        ]
+    );
+    assert_eq!(
+        messages(&repo_root, "refs/heads/feature"),
+        vec![
+            "branch: Created from HEAD",
+            "commit: feature work",
+            format!("branch: Reset to {}", base).as_str(),
+        ]
+    );
+
+    let head_log = utils::read_reflog(&repo_root, "HEAD").unwrap();
+    assert_eq!(head_log[3].old_hash, feature);
+    assert_eq!(head_log[3].new_hash, base);
+}
+
+#[test]
+fn rename_moves_and_delete_removes_the_log() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    commit_all(&repo_root, "base");
+    commands::create_branch(&repo_root, "topic").unwrap();
+
+    commands::rename_branch(&repo_root, "topic", "feature/topic").unwrap();
+    assert!(!repo_root.join(".rustygit/logs/refs/heads/topic").exists());
+    assert_eq!(
+        messages(&repo_root, "refs/heads/feature/topic"),
+        vec![
+            "branch: Created from HEAD",
+            "Branch: renamed refs/heads/topic to refs/heads/feature/topic",
+        ]
+    );
+
+    commands::delete_branch(&repo_root, "feature/topic", false).unwrap();
+    assert!(!repo_root.join(".rustygit/logs/refs/heads/feature").exists());
+}
+
+#[test]
+fn reflog_cli_lists_newest_first() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    let first = commit_all(&repo_root, "first");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(&repo_root, "second");
+
+    assert_eq!(
+        commands::reflog(&repo_root, Some("main")).unwrap(),
+        vec![
+            format!("{} main@{{0}}: commit: second", &second[..7]),
+            format!("{} main@{{1}}: commit (initial): first", &first[..7]),
+        ]
+    );
+    assert!(commands::reflog(&repo_root, Some("missing")).is_err());
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .arg("reflog")
+        .assert()
+        .success();
+    let output = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(output.starts_with(&format!("{} HEAD@{{0}}: commit: second\n", &second[..7])));
+}
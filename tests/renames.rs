@@ -0,0 +1,276 @@
+use assert_cmd::Command;
+use rustygit::commands::{self, ApplyOptions, DiffMode};
+use rustygit::utils::{self, RenameOptions};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn numbered_lines(count: usize) -> String {
+    (1..=count).map(|n| format!("line {}\n", n)).collect()
+}
+
+/// Commits `a.txt` (twenty lines) and `keep.txt`.
+fn setup_repo(repo_root: &Path) {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), numbered_lines(20)).unwrap();
+    fs::write(repo_root.join("keep.txt"), "keep\n").unwrap();
+    commit_all(repo_root, "base");
+}
+
+/// Stages the removal of `a.txt` and the addition of `b.txt` with one
+/// more line: 20 of its 21 lines are unchanged.
+fn stage_edited_move(repo_root: &Path) {
+    commands::rm(repo_root, Path::new("a.txt")).unwrap();
+    fs::write(repo_root.join("b.txt"), numbered_lines(21)).unwrap();
+    commands::add(repo_root, Path::new("b.txt")).unwrap();
+}
+
+fn cached_diff(repo_root: &Path, renames: Option<RenameOptions>) -> String {
+    commands::diff_with_renames(
+        repo_root,
+        &DiffMode::Cached(None),
+        &[],
+        commands::DEFAULT_CONTEXT,
+        renames,
+    )
+    .unwrap()
+}
+
+#[test]
+fn diff_shows_a_staged_move_as_a_rename() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+    stage_edited_move(repo_root);
+
+    let diff = commands::diff(
+        repo_root,
+        &DiffMode::Cached(None),
+        &[],
+        commands::DEFAULT_CONTEXT,
+    )
+    .unwrap();
+
+    assert!(
+        diff.starts_with(
+            "diff --git a/a.txt b/b.txt\n\
+             similarity index 94%\n\
+             rename from a.txt\n\
+             rename to b.txt\n\
+             index "
+        ),
+        "{}",
+        diff
+    );
+    assert!(
+        diff.contains(
+            "--- a/a.txt\n+++ b/b.txt\n@@ -18,3 +18,4 @@\n line 18\n line 19\n line 20\n+line 21\n"
+        ),
+        "{}",
+        diff
+    );
+    assert!(!diff.contains("deleted file"), "{}", diff);
+}
+
+#[test]
+fn threshold_and_no_renames_fall_back_to_delete_and_add() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+    stage_edited_move(repo_root);
+
+    let strict = RenameOptions {
+        threshold: 95,
+        copies: false,
+    };
+    for renames in [Some(strict), None] {
+        let diff = cached_diff(repo_root, renames);
+        assert!(!diff.contains("rename from"), "{}", diff);
+        assert!(diff.contains("diff --git a/a.txt b/a.txt\ndeleted file mode 100644\n"));
+        assert!(diff.contains("diff --git a/b.txt b/b.txt\nnew file mode 100644\n"));
+    }
+
+    let loose = RenameOptions {
+        threshold: 90,
+        copies: false,
+    };
+    assert!(cached_diff(repo_root, Some(loose)).contains("rename from a.txt\n"));
+}
+
+#[test]
+fn exact_rename_has_no_hunks() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+
+    fs::create_dir_all(repo_root.join("docs")).unwrap();
+    fs::rename(repo_root.join("a.txt"), repo_root.join("docs/a.txt")).unwrap();
+    commands::rm(repo_root, Path::new("a.txt")).unwrap();
+    commands::add(repo_root, Path::new("docs/a.txt")).unwrap();
+
+    assert_eq!(
+        cached_diff(repo_root, Some(RenameOptions::default())),
+        "diff --git a/a.txt b/docs/a.txt\n\
+         similarity index 100%\n\
+         rename from a.txt\n\
+         rename to docs/a.txt\n"
+    );
+}
+
+#[test]
+fn status_reports_renames() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+
+    // Moved in the working tree only: a deleted and an untracked path.
+    fs::rename(repo_root.join("a.txt"), repo_root.join("moved.txt")).unwrap();
+    let status = commands::status(repo_root, &vec![]).unwrap();
+    assert!(
+        status.contains(
+            "\tChanges not staged for commit:\n\
+             \t\tRenamed files:\n\
+             \t\t\trenamed: a.txt -> moved.txt\n"
+        ),
+        "{}",
+        status
+    );
+    assert!(!status.contains("Deleted files"), "{}", status);
+    assert!(!status.contains("Untracked files"), "{}", status);
+
+    // Staged, with an edit.
+    fs::remove_file(repo_root.join("moved.txt")).unwrap();
+    stage_edited_move(repo_root);
+    let status = commands::status(repo_root, &vec![]).unwrap();
+    assert!(
+        status.contains("\tStaged files:\n\t\trenamed: a.txt -> b.txt\n"),
+        "{}",
+        status
+    );
+    assert!(!status.contains("not staged"), "{}", status);
+}
+
+#[test]
+fn log_name_status_lists_renames_and_copies() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+    stage_edited_move(repo_root);
+    let renamed = commands::commit(repo_root, "move".to_string(), &vec![]).unwrap();
+
+    assert_eq!(
+        commands::commit_name_status(repo_root, &renamed, Some(RenameOptions::default())).unwrap(),
+        "R094\ta.txt\tb.txt\n"
+    );
+    assert_eq!(
+        commands::commit_name_status(repo_root, &renamed, None).unwrap(),
+        "D\ta.txt\nA\tb.txt\n"
+    );
+
+    fs::write(repo_root.join("keep.txt"), "keep\nmore\n").unwrap();
+    fs::write(repo_root.join("copy.txt"), "keep\n").unwrap();
+    let copied = commit_all(repo_root, "copy");
+    let copies = RenameOptions {
+        copies: true,
+        ..RenameOptions::default()
+    };
+
+    assert_eq!(
+        commands::commit_name_status(repo_root, &copied, Some(copies)).unwrap(),
+        "C100\tkeep.txt\tcopy.txt\nM\tkeep.txt\n"
+    );
+    assert_eq!(
+        commands::commit_name_status(repo_root, &copied, Some(RenameOptions::default())).unwrap(),
+        "A\tcopy.txt\nM\tkeep.txt\n"
+    );
+}
+
+#[test]
+fn copy_and_rename_patches_apply_in_order() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("source");
+    let target = dir.path().join("target");
+    for repo_root in [&source, &target] {
+        fs::create_dir_all(repo_root).unwrap();
+        setup_repo(repo_root);
+    }
+
+    // a.txt goes away and shows up twice. b.txt is the closer match, but
+    // the rename goes last so that the copy still finds a.txt.
+    commands::rm(&source, Path::new("a.txt")).unwrap();
+    fs::write(source.join("b.txt"), numbered_lines(21)).unwrap();
+    fs::write(source.join("c.txt"), numbered_lines(20) + "extra\nmore\n").unwrap();
+    commit_all(&source, "split");
+
+    let mode = DiffMode::from_args(false, &[String::from("HEAD~1..HEAD")]).unwrap();
+    let copies = RenameOptions {
+        copies: true,
+        ..RenameOptions::default()
+    };
+    let patch =
+        commands::diff_with_renames(&source, &mode, &[], commands::DEFAULT_CONTEXT, Some(copies))
+            .unwrap();
+    assert!(
+        patch.contains("copy from a.txt\ncopy to b.txt\n"),
+        "{}",
+        patch
+    );
+    assert!(
+        patch.contains("rename from a.txt\nrename to c.txt\n"),
+        "{}",
+        patch
+    );
+
+    let patch_file = dir.path().join("change.patch");
+    fs::write(&patch_file, &patch).unwrap();
+    commands::apply(&target, &patch_file, ApplyOptions::default()).unwrap();
+
+    assert!(!target.join("a.txt").exists());
+    for path in ["b.txt", "c.txt"] {
+        assert_eq!(
+            fs::read(target.join(path)).unwrap(),
+            fs::read(source.join(path)).unwrap()
+        );
+    }
+
+    let reverse = ApplyOptions {
+        reverse: true,
+        ..ApplyOptions::default()
+    };
+    let err = commands::apply(&target, &patch_file, reverse).unwrap_err();
+    assert!(err.to_string().contains("cannot reverse a copy"), "{}", err);
+}
+
+#[test]
+fn cli_diff_takes_attached_thresholds() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path();
+    setup_repo(repo_root);
+    stage_edited_move(repo_root);
+
+    let run = |args: &[&str]| {
+        let assert = Command::cargo_bin("rustygit")
+            .unwrap()
+            .current_dir(repo_root)
+            .args(args)
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    assert!(run(&["diff", "--cached", "-M90%"]).contains("rename from a.txt\n"));
+    assert!(!run(&["diff", "--cached", "-M95"]).contains("rename from"));
+    assert!(!run(&["diff", "--cached", "--no-renames"]).contains("rename from"));
+    // A bare -M does not take the revision after it as its value.
+    assert!(run(&["diff", "--cached", "-M", "HEAD"]).contains("rename from a.txt\n"));
+
+    let base = utils::get_current_commit_hash(repo_root).unwrap().unwrap();
+    commands::commit(repo_root, "move".to_string(), &vec![]).unwrap();
+    let log = run(&["log", "--name-status"]);
+    assert!(log.contains("    move\n\nR094\ta.txt\tb.txt\n"), "{}", log);
+    assert!(log.contains(&format!("commit {}\n", base)), "{}", log);
+}
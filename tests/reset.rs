@@ -27,7 +27,7 @@ fn soft_reset_moves_head_and_keeps_index() {
     commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
     let commit_b = commands::commit(&repo_root, "B".to_string(), &vec![]).unwrap();
 
-    commands::reset(&repo_root, &commit_a, true).unwrap();
+    commands::reset(&repo_root, &commit_a, commands::ResetMode::Soft).unwrap();
 
     let head_after = utils::get_current_commit_hash(&repo_root).unwrap().unwrap();
     assert_eq!(head_after, commit_a);
@@ -55,7 +55,7 @@ fn mixed_reset_moves_head_and_resets_index_only() {
     commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
     let _commit_b = commands::commit(&repo_root, "B".to_string(), &vec![]).unwrap();
 
-    commands::reset(&repo_root, &commit_a, false).unwrap();
+    commands::reset(&repo_root, &commit_a, commands::ResetMode::Mixed).unwrap();
 
     let head_after = utils::get_current_commit_hash(&repo_root).unwrap().unwrap();
     assert_eq!(head_after, commit_a);
@@ -67,3 +67,130 @@ fn mixed_reset_moves_head_and_resets_index_only() {
     let work_content = fs::read_to_string(repo_root.join("a.txt")).unwrap();
     assert_eq!(work_content, "two");
 }
+
+#[test]
+fn hard_reset_rewrites_tracked_files_and_keeps_untracked() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let commit_a = commands::commit(&repo_root, "A".to_string(), &vec![]).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    fs::create_dir(repo_root.join("dir")).unwrap();
+    fs::write(repo_root.join("dir/b.txt"), b"b").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    commands::commit(&repo_root, "B".to_string(), &vec![]).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"dirty").unwrap();
+    fs::write(repo_root.join("staged.txt"), b"staged").unwrap();
+    commands::add(&repo_root, &repo_root.join("staged.txt")).unwrap();
+    fs::write(repo_root.join("untracked.txt"), b"keep me").unwrap();
+
+    commands::reset(&repo_root, "HEAD~1", commands::ResetMode::Hard).unwrap();
+
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap().unwrap(),
+        commit_a
+    );
+    assert_eq!(
+        utils::read_index_map(&repo_root).unwrap(),
+        tree_map_for_commit(&repo_root, &commit_a)
+    );
+    assert_eq!(fs::read_to_string(repo_root.join("a.txt")).unwrap(), "one");
+    assert!(!repo_root.join("dir").exists());
+    assert!(!repo_root.join("staged.txt").exists());
+    assert_eq!(
+        fs::read_to_string(repo_root.join("untracked.txt")).unwrap(),
+        "keep me"
+    );
+}
+
+#[test]
+fn keep_reset_preserves_unrelated_changes_and_refuses_to_lose_work() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"one").unwrap();
+    fs::write(repo_root.join("b.txt"), b"b").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let commit_a = commands::commit(&repo_root, "A".to_string(), &vec![]).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    commands::add(&repo_root, Path::new(".")).unwrap();
+    let commit_b = commands::commit(&repo_root, "B".to_string(), &vec![]).unwrap();
+
+    // a.txt differs between HEAD and the target, so its local edit blocks the reset.
+    fs::write(repo_root.join("a.txt"), b"local").unwrap();
+    let err = commands::reset(&repo_root, &commit_a, commands::ResetMode::Keep).unwrap_err();
+    assert!(err.to_string().contains("a.txt"));
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap().unwrap(),
+        commit_b
+    );
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "local"
+    );
+
+    // b.txt is the same in both commits, so its local edit survives.
+    fs::write(repo_root.join("a.txt"), b"two").unwrap();
+    fs::write(repo_root.join("b.txt"), b"local b").unwrap();
+    commands::reset(&repo_root, "HEAD^", commands::ResetMode::Keep).unwrap();
+
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap().unwrap(),
+        commit_a
+    );
+    assert_eq!(fs::read_to_string(repo_root.join("a.txt")).unwrap(), "one");
+    assert_eq!(
+        fs::read_to_string(repo_root.join("b.txt")).unwrap(),
+        "local b"
+    );
+}
+
+#[test]
+fn mixed_reset_abandons_a_conflicted_merge() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"base\n").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    commands::commit(&repo_root, "base".to_string(), &vec![]).unwrap();
+    commands::create_branch(&repo_root, "topic").unwrap();
+
+    fs::write(repo_root.join("a.txt"), b"main\n").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    let main_tip = commands::commit(&repo_root, "main".to_string(), &vec![]).unwrap();
+
+    commands::checkout(&repo_root, "topic").unwrap();
+    fs::write(repo_root.join("a.txt"), b"topic\n").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    commands::commit(&repo_root, "topic".to_string(), &vec![]).unwrap();
+    commands::checkout(&repo_root, "main").unwrap();
+
+    let outcome = commands::merge(&repo_root, "topic", None).unwrap();
+    assert!(matches!(outcome, commands::MergeOutcome::Conflicted(_)));
+    assert!(repo_root.join(".rustygit/MERGE_HEAD").exists());
+
+    commands::reset(&repo_root, "HEAD", commands::ResetMode::Mixed).unwrap();
+
+    assert!(!repo_root.join(".rustygit/MERGE_HEAD").exists());
+    assert!(utils::read_index_conflicts(&repo_root).unwrap().is_empty());
+
+    // The next commit is an ordinary one on top of main.
+    fs::write(repo_root.join("a.txt"), b"resolved by hand\n").unwrap();
+    commands::add(&repo_root, &repo_root.join("a.txt")).unwrap();
+    let next = commands::commit(&repo_root, "after reset".to_string(), &vec![]).unwrap();
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, &next).unwrap(),
+        vec![main_tip]
+    );
+}
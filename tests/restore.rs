@@ -1,7 +1,38 @@
-use rustygit::commands;
-use std::fs;
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use tempfile::tempdir;
 
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+/// Commits `src/{a,b}.rs` and `docs/guide.md` as v1, then v2, and returns both commits.
+fn two_versions(repo_root: &Path) -> (String, String) {
+    commands::init(repo_root).unwrap();
+    fs::create_dir_all(repo_root.join("src")).unwrap();
+    fs::create_dir_all(repo_root.join("docs")).unwrap();
+
+    let commits: Vec<String> = ["v1", "v2"]
+        .iter()
+        .map(|version| {
+            for file in ["src/a.rs", "src/b.rs", "docs/guide.md"] {
+                fs::write(repo_root.join(file), format!("{} {}\n", file, version)).unwrap();
+            }
+            commit_all(repo_root, version)
+        })
+        .collect();
+    (commits[0].clone(), commits[1].clone())
+}
+
+fn read(repo_root: &Path, file: &str) -> String {
+    fs::read_to_string(repo_root.join(file)).unwrap()
+}
+
 #[test]
 fn restore_modified_file_resets_content() {
     let dir = tempdir().unwrap();
@@ -46,3 +77,170 @@ fn restore_untracked_file_deletes_it() {
 
     assert!(!repo_root.join("untracked.txt").exists());
 }
+
+#[test]
+fn untracked_file_missing_from_source_is_an_error_and_kept() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, _) = two_versions(&repo_root);
+    fs::write(repo_root.join("untracked.txt"), b"temp").unwrap();
+
+    for (source, staged) in [(Some(first.as_str()), false), (None, true)] {
+        let err = commands::restore_paths(
+            &repo_root,
+            &["untracked.txt".to_string()],
+            source,
+            staged,
+            true,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("did not match"), "{}", err);
+    }
+
+    assert_eq!(read(&repo_root, "untracked.txt"), "temp");
+}
+
+#[test]
+fn restore_directory_from_source_into_worktree_only() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (_, second) = two_versions(&repo_root);
+
+    let restored = commands::restore_paths(
+        &repo_root,
+        &["src".to_string()],
+        Some("HEAD~1"),
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        restored,
+        vec![PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")]
+    );
+
+    assert_eq!(read(&repo_root, "src/a.rs"), "src/a.rs v1\n");
+    assert_eq!(read(&repo_root, "docs/guide.md"), "docs/guide.md v2\n");
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(second)
+    );
+}
+
+#[test]
+fn restore_glob_into_index_only() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, _) = two_versions(&repo_root);
+
+    commands::restore_paths(&repo_root, &["*.md".to_string()], Some(&first), true, false).unwrap();
+
+    let first_entries = utils::get_commit_entries_map(&repo_root, Some(&first)).unwrap();
+    let index = utils::read_index_entries(&repo_root).unwrap();
+    assert_eq!(
+        index[Path::new("docs/guide.md")],
+        first_entries[Path::new("docs/guide.md")]
+    );
+    assert_ne!(
+        index[Path::new("src/a.rs")],
+        first_entries[Path::new("src/a.rs")]
+    );
+    assert_eq!(read(&repo_root, "docs/guide.md"), "docs/guide.md v2\n");
+
+    // `--staged` without a source unstages back to HEAD.
+    commands::restore_paths(&repo_root, &["docs".to_string()], None, true, false).unwrap();
+    let head_commit = utils::get_current_commit_hash(&repo_root).unwrap();
+    assert_eq!(
+        utils::read_index_entries(&repo_root).unwrap(),
+        utils::get_commit_entries_map(&repo_root, head_commit.as_deref()).unwrap()
+    );
+}
+
+#[test]
+fn restore_from_source_removes_paths_missing_there() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (_, second) = two_versions(&repo_root);
+
+    fs::write(repo_root.join("src/new.rs"), "new\n").unwrap();
+    commit_all(&repo_root, "add new.rs");
+
+    commands::restore_paths(&repo_root, &["src".to_string()], Some(&second), true, true).unwrap();
+
+    assert!(!repo_root.join("src/new.rs").exists());
+    assert!(
+        !utils::read_index_map(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("src/new.rs"))
+    );
+}
+
+#[test]
+fn checkout_paths_restores_index_and_worktree_without_moving_head() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, second) = two_versions(&repo_root);
+
+    commands::checkout_paths(&repo_root, Some(&first), &["src/a.rs".to_string()]).unwrap();
+
+    assert_eq!(read(&repo_root, "src/a.rs"), "src/a.rs v1\n");
+    assert_eq!(read(&repo_root, "src/b.rs"), "src/b.rs v2\n");
+    assert_eq!(
+        utils::read_index_entries(&repo_root).unwrap()[Path::new("src/a.rs")],
+        utils::get_commit_entries_map(&repo_root, Some(&first)).unwrap()[Path::new("src/a.rs")]
+    );
+    assert_eq!(
+        utils::current_branch(&repo_root).unwrap().as_deref(),
+        Some("main")
+    );
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(second)
+    );
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["checkout", "--", "src/a.rs"])
+        .assert()
+        .success();
+    assert_eq!(read(&repo_root, "src/a.rs"), "src/a.rs v1\n");
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["checkout", "HEAD", "--", "src/*"])
+        .assert()
+        .success();
+    assert_eq!(read(&repo_root, "src/a.rs"), "src/a.rs v2\n");
+}
+
+#[test]
+fn unmatched_pathspec_is_an_error() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    two_versions(&repo_root);
+
+    let err = commands::restore_paths(&repo_root, &["lib/*.rs".to_string()], None, false, true)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "pathspec 'lib/*.rs' did not match any file(s) known to rustygit"
+    );
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args([
+            "restore",
+            "--source",
+            "HEAD~1",
+            "--staged",
+            "--worktree",
+            "docs",
+        ])
+        .assert()
+        .success()
+        .stdout("Restored docs/guide.md\n");
+    assert_eq!(read(&repo_root, "docs/guide.md"), "docs/guide.md v1\n");
+}
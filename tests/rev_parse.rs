@@ -0,0 +1,246 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+/// Builds `c1 <- c2 <- c3` on `main` and returns the three hashes.
+fn linear_history(repo_root: &Path) -> Vec<String> {
+    commands::init(repo_root).unwrap();
+
+    (1..=3)
+        .map(|n| {
+            fs::write(repo_root.join("a.txt"), format!("version {}\n", n)).unwrap();
+            commit_all(repo_root, &format!("c{}", n))
+        })
+        .collect()
+}
+
+#[test]
+fn resolves_head_and_ancestry_suffixes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD").unwrap(),
+        commits[2]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "@").unwrap(),
+        commits[2]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD~").unwrap(),
+        commits[1]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD~2").unwrap(),
+        commits[0]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "main^").unwrap(),
+        commits[1]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "main^^").unwrap(),
+        commits[0]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD~1^0").unwrap(),
+        commits[1]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "refs/heads/main~1").unwrap(),
+        commits[1]
+    );
+
+    let err = utils::resolve_revision(&repo_root, "HEAD~3").unwrap_err();
+    assert!(err.to_string().contains("goes past the root commit"));
+}
+
+#[test]
+fn caret_n_selects_merge_parents() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "base\n").unwrap();
+    commit_all(&repo_root, "base");
+    commands::create_branch(&repo_root, "feature").unwrap();
+
+    fs::write(repo_root.join("a.txt"), "main\n").unwrap();
+    let ours = commit_all(&repo_root, "main work");
+
+    commands::checkout(&repo_root, "feature").unwrap();
+    fs::write(repo_root.join("b.txt"), "feature\n").unwrap();
+    let theirs = commit_all(&repo_root, "feature work");
+
+    commands::checkout(&repo_root, "main").unwrap();
+    commands::merge(&repo_root, "feature", None).unwrap();
+
+    assert_eq!(utils::resolve_revision(&repo_root, "HEAD^1").unwrap(), ours);
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "HEAD^2").unwrap(),
+        theirs
+    );
+
+    let err = utils::resolve_revision(&repo_root, "HEAD^3").unwrap_err();
+    assert!(err.to_string().contains("has no parent 3"));
+}
+
+#[test]
+fn resolves_abbreviated_hashes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    assert_eq!(
+        utils::resolve_revision(&repo_root, &commits[0][..7]).unwrap(),
+        commits[0]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, &commits[1][..4].to_ascii_uppercase()).unwrap(),
+        commits[1]
+    );
+
+    // Three hex characters are never treated as a hash.
+    assert!(utils::resolve_revision(&repo_root, &commits[0][..3]).is_err());
+
+    // Packed objects are found as well.
+    commands::gc(&repo_root, 0).unwrap();
+    assert_eq!(
+        utils::resolve_revision(&repo_root, &format!("{}~1", &commits[2][..8])).unwrap(),
+        commits[1]
+    );
+}
+
+#[test]
+fn ambiguous_prefix_lists_candidates() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+
+    let fanout = repo_root.join(".rustygit/objects/ab");
+    fs::create_dir_all(&fanout).unwrap();
+    for suffix in [
+        "cd".to_string() + &"0".repeat(36),
+        "cd".to_string() + &"1".repeat(36),
+    ] {
+        fs::write(fanout.join(suffix), b"").unwrap();
+    }
+
+    let err = utils::resolve_revision(&repo_root, "abcd")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("ambiguous"));
+    assert!(err.contains(&format!("abcd{}", "0".repeat(36))));
+    assert!(err.contains(&format!("abcd{}", "1".repeat(36))));
+
+    assert_eq!(
+        utils::find_objects_by_prefix(&repo_root, "abcd1").unwrap(),
+        vec![format!("abcd{}", "1".repeat(36))]
+    );
+}
+
+#[test]
+fn resolves_tags_and_reports_ambiguous_ref_names() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    let tags_dir = repo_root.join(".rustygit/refs/tags");
+    fs::create_dir_all(&tags_dir).unwrap();
+    fs::write(tags_dir.join("v1"), format!("{}\n", commits[0])).unwrap();
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "v1").unwrap(),
+        commits[0]
+    );
+
+    fs::write(tags_dir.join("main"), format!("{}\n", commits[0])).unwrap();
+    let err = utils::resolve_revision(&repo_root, "main").unwrap_err();
+    assert!(err.to_string().contains("ambiguous"));
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "refs/heads/main").unwrap(),
+        commits[2]
+    );
+}
+
+#[test]
+fn previous_checkout_is_tracked() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    commands::create_branch(&repo_root, "other").unwrap();
+    commands::checkout(&repo_root, "other").unwrap();
+    assert_eq!(
+        utils::resolve_previous_checkout(&repo_root, "@{-1}").unwrap(),
+        Some("main".to_string())
+    );
+
+    commands::checkout(&repo_root, &commits[0][..6]).unwrap();
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(commits[0].clone())
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "@{-1}").unwrap(),
+        commits[2]
+    );
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "@{-2}~1").unwrap(),
+        commits[1]
+    );
+
+    commands::checkout(&repo_root, "-").unwrap();
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head.trim(), "ref: refs/heads/other");
+
+    assert!(utils::resolve_revision(&repo_root, "@{-9}").is_err());
+}
+
+#[test]
+fn reset_and_merge_accept_revision_expressions() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    commands::reset(&repo_root, "HEAD~2", commands::ResetMode::Mixed).unwrap();
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(commits[0].clone())
+    );
+
+    // A mixed reset leaves the newer content in the working tree.
+    fs::write(repo_root.join("a.txt"), "version 1\n").unwrap();
+    let outcome = commands::merge(&repo_root, &commits[2][..10], None).unwrap();
+    assert!(matches!(outcome, commands::MergeOutcome::FastForward(hash) if hash == commits[2]));
+}
+
+#[test]
+fn rev_parse_cli_prints_full_hashes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let commits = linear_history(&repo_root);
+
+    let assert = Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["rev-parse", "HEAD", "HEAD~2"])
+        .assert()
+        .success();
+    let output = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(output, format!("{}\n{}\n", commits[2], commits[0]));
+
+    Command::cargo_bin("rustygit")
+        .unwrap()
+        .current_dir(&repo_root)
+        .args(["rev-parse", "no-such-rev"])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,91 @@
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn read(repo_root: &Path, file: &str) -> String {
+    fs::read_to_string(repo_root.join(file)).unwrap()
+}
+
+#[test]
+fn reverts_an_older_commit_and_keeps_later_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    commit_all(&repo_root, "base");
+    fs::write(repo_root.join("a.txt"), "ONE\ntwo\nthree\n").unwrap();
+    fs::write(repo_root.join("b.txt"), "b\n").unwrap();
+    let bad = commit_all(&repo_root, "shout one");
+    fs::write(repo_root.join("a.txt"), "ONE\ntwo\nTHREE\n").unwrap();
+    let head = commit_all(&repo_root, "shout three");
+
+    let outcomes = commands::revert(&repo_root, &["HEAD~1".to_string()]).unwrap();
+
+    let [commands::PickOutcome::Committed { original, commit }] = &outcomes[..] else {
+        panic!("unexpected outcomes: {:?}", outcomes);
+    };
+    assert_eq!(original, &bad);
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, commit).unwrap(),
+        vec![head]
+    );
+    assert_eq!(
+        utils::get_commit_message(&repo_root, commit).unwrap(),
+        format!("Revert \"shout one\"\n\nThis reverts commit {}.\n", bad)
+    );
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nTHREE\n");
+    assert!(!repo_root.join("b.txt").exists());
+
+    let reflog = commands::reflog(&repo_root, None).unwrap();
+    assert!(reflog[0].ends_with("revert: Revert \"shout one\""));
+}
+
+#[test]
+fn conflicted_revert_can_be_concluded_with_commit() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    commands::init(&repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    commit_all(&repo_root, "base");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let bad = commit_all(&repo_root, "two");
+    fs::write(repo_root.join("a.txt"), "three\n").unwrap();
+    commit_all(&repo_root, "three");
+
+    let outcomes = commands::revert(&repo_root, std::slice::from_ref(&bad)).unwrap();
+    assert_eq!(
+        outcomes,
+        vec![commands::PickOutcome::Conflicted {
+            original: bad.clone(),
+            paths: vec![PathBuf::from("a.txt")],
+        }]
+    );
+    assert!(read(&repo_root, "a.txt").contains(">>>>>>> parent of "));
+
+    fs::write(repo_root.join("a.txt"), "one\nthree\n").unwrap();
+    commands::add(&repo_root, Path::new("a.txt")).unwrap();
+    let commit = commands::commit(&repo_root, String::new(), &vec![]).unwrap();
+
+    assert_eq!(
+        utils::get_commit_message(&repo_root, &commit).unwrap(),
+        format!("Revert \"two\"\n\nThis reverts commit {}.\n", bad)
+    );
+    assert!(!repo_root.join(".rustygit/REVERT_HEAD").exists());
+
+    // The sequence is still recorded; continuing finishes it without a new commit.
+    assert!(commands::revert_continue(&repo_root).unwrap().is_empty());
+    assert!(!repo_root.join(".rustygit/sequencer").exists());
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(commit)
+    );
+    assert!(commands::cherry_pick_continue(&repo_root).is_err());
+}
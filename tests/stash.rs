@@ -0,0 +1,279 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+fn init_repo(repo_root: &Path) -> String {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    fs::write(repo_root.join("b.txt"), "b\n").unwrap();
+    commit_all(repo_root, "base")
+}
+
+fn read(repo_root: &Path, file: &str) -> String {
+    fs::read_to_string(repo_root.join(file)).unwrap()
+}
+
+#[test]
+fn push_saves_changes_and_resets_to_head() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let base = init_repo(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+    fs::write(repo_root.join("new.txt"), "new\n").unwrap();
+    commands::add(&repo_root, Path::new("new.txt")).unwrap();
+    fs::remove_file(repo_root.join("b.txt")).unwrap();
+    fs::write(repo_root.join("scratch.txt"), "untracked\n").unwrap();
+
+    let stash = commands::stash_push(&repo_root, None, false)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(read(&repo_root, "a.txt"), "one\ntwo\nthree\n");
+    assert_eq!(read(&repo_root, "b.txt"), "b\n");
+    assert!(!repo_root.join("new.txt").exists());
+    assert_eq!(read(&repo_root, "scratch.txt"), "untracked\n");
+    assert_eq!(
+        utils::get_current_commit_hash(&repo_root).unwrap(),
+        Some(base.clone())
+    );
+
+    let parents = utils::get_commit_parents(&repo_root, &stash).unwrap();
+    assert_eq!(parents.len(), 2);
+    assert_eq!(parents[0], base);
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "stash@{0}").unwrap(),
+        stash
+    );
+    assert_eq!(utils::resolve_revision(&repo_root, "stash").unwrap(), stash);
+
+    assert_eq!(
+        commands::stash_list(&repo_root).unwrap(),
+        vec![format!("stash@{{0}}: WIP on main: {} base", &base[..7])]
+    );
+    assert_eq!(
+        commands::stash_show(&repo_root, None).unwrap(),
+        vec!["M\ta.txt", "D\tb.txt", "A\tnew.txt"]
+    );
+
+    // Nothing left to save.
+    fs::remove_file(repo_root.join("scratch.txt")).unwrap();
+    assert_eq!(commands::stash_push(&repo_root, None, false).unwrap(), None);
+}
+
+#[test]
+fn pop_reapplies_changes_after_head_moved() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+    fs::write(repo_root.join("new.txt"), "new\n").unwrap();
+    commands::add(&repo_root, Path::new("new.txt")).unwrap();
+    commands::stash_push(&repo_root, Some("feature work"), false).unwrap();
+
+    // HEAD moves on an unrelated part of the same file.
+    fs::write(repo_root.join("a.txt"), "zero\none\ntwo\nthree\n").unwrap();
+    commit_all(&repo_root, "prepend");
+
+    assert_eq!(
+        commands::stash_pop(&repo_root, None).unwrap(),
+        commands::StashApplyOutcome::Applied
+    );
+    assert_eq!(read(&repo_root, "a.txt"), "zero\none\ntwo\nthree\nfour\n");
+    assert_eq!(read(&repo_root, "new.txt"), "new\n");
+    assert!(
+        utils::read_index_map(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("new.txt"))
+    );
+    assert!(commands::stash_list(&repo_root).unwrap().is_empty());
+    assert!(!utils::ref_exists(&repo_root, "refs/stash"));
+}
+
+#[test]
+fn conflicting_pop_keeps_the_stash() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "one\nstashed\nthree\n").unwrap();
+    commands::stash_push(&repo_root, None, false).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "one\ncommitted\nthree\n").unwrap();
+    commit_all(&repo_root, "conflicting");
+
+    assert_eq!(
+        commands::stash_pop(&repo_root, None).unwrap(),
+        commands::StashApplyOutcome::Conflicted(vec![PathBuf::from("a.txt")])
+    );
+    let content = read(&repo_root, "a.txt");
+    assert!(content.contains("<<<<<<< Updated upstream"));
+    assert!(content.contains(">>>>>>> Stashed changes"));
+    assert!(
+        utils::read_index_conflicts(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("a.txt"))
+    );
+    assert_eq!(commands::stash_list(&repo_root).unwrap().len(), 1);
+}
+
+#[test]
+fn apply_refuses_to_overwrite_local_changes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "stashed\n").unwrap();
+    commands::stash_push(&repo_root, None, false).unwrap();
+
+    fs::write(repo_root.join("a.txt"), "local\n").unwrap();
+    assert!(commands::stash_apply(&repo_root, None).is_err());
+    assert_eq!(read(&repo_root, "a.txt"), "local\n");
+
+    // Local changes elsewhere do not get in the way.
+    fs::write(repo_root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    fs::write(repo_root.join("b.txt"), "local b\n").unwrap();
+    commands::stash_apply(&repo_root, Some("stash@{0}")).unwrap();
+    assert_eq!(read(&repo_root, "a.txt"), "stashed\n");
+    assert_eq!(read(&repo_root, "b.txt"), "local b\n");
+    assert_eq!(commands::stash_list(&repo_root).unwrap().len(), 1);
+}
+
+#[test]
+fn include_untracked_and_drop_from_the_middle_of_the_stack() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+    fs::write(repo_root.join(".rustygitignore"), "*.log\n").unwrap();
+    commit_all(&repo_root, "ignore logs");
+
+    fs::write(repo_root.join("a.txt"), "first\n").unwrap();
+    commands::stash_push(&repo_root, Some("first"), false).unwrap();
+
+    fs::write(repo_root.join("scratch.txt"), "untracked\n").unwrap();
+    fs::write(repo_root.join("debug.log"), "ignored\n").unwrap();
+    let second = commands::stash_push(&repo_root, Some("second"), true)
+        .unwrap()
+        .unwrap();
+    assert!(!repo_root.join("scratch.txt").exists());
+    assert_eq!(read(&repo_root, "debug.log"), "ignored\n");
+    assert_eq!(
+        utils::get_commit_parents(&repo_root, &second)
+            .unwrap()
+            .len(),
+        3
+    );
+
+    fs::write(repo_root.join("b.txt"), "third\n").unwrap();
+    commands::stash_push(&repo_root, Some("third"), false).unwrap();
+
+    assert_eq!(
+        commands::stash_list(&repo_root).unwrap(),
+        vec![
+            "stash@{0}: On main: third",
+            "stash@{1}: On main: second",
+            "stash@{2}: On main: first",
+        ]
+    );
+
+    commands::stash_drop(&repo_root, Some("stash@{2}")).unwrap();
+    assert_eq!(
+        commands::stash_list(&repo_root).unwrap(),
+        vec!["stash@{0}: On main: third", "stash@{1}: On main: second"]
+    );
+
+    commands::stash_drop(&repo_root, None).unwrap();
+    assert_eq!(
+        utils::read_ref(&repo_root, "refs/stash").unwrap(),
+        Some(second)
+    );
+
+    commands::stash_pop(&repo_root, None).unwrap();
+    assert_eq!(read(&repo_root, "scratch.txt"), "untracked\n");
+    assert!(
+        !utils::read_index_map(&repo_root)
+            .unwrap()
+            .contains_key(Path::new("scratch.txt"))
+    );
+    assert!(commands::stash_drop(&repo_root, None).is_err());
+}
+
+#[test]
+fn stash_cli_round_trip() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+
+    fs::write(repo_root.join("a.txt"), "changed\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let assert = Command::cargo_bin("rustygit")
+            .unwrap()
+            .current_dir(&repo_root)
+            .args(args)
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    assert_eq!(
+        run(&["stash", "-m", "wip"]),
+        "Saved working directory and index state On main: wip\n"
+    );
+    assert_eq!(run(&["stash", "list"]), "stash@{0}: On main: wip\n");
+    assert_eq!(run(&["stash", "show"]), "M\ta.txt\n");
+    assert_eq!(run(&["stash", "pop"]), "Dropped stash@{0}\n");
+    assert_eq!(read(&repo_root, "a.txt"), "changed\n");
+    assert_eq!(
+        run(&["stash"]),
+        "Saved working directory and index state WIP on main: ".to_string()
+            + &run(&["rev-parse", "HEAD"])[..7]
+            + " base\n"
+    );
+}
+
+#[test]
+fn drop_keeps_the_stack_and_ref_in_step() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    init_repo(&repo_root);
+
+    for message in ["first", "second", "third", "fourth"] {
+        fs::write(repo_root.join("a.txt"), format!("{}\n", message)).unwrap();
+        commands::stash_push(&repo_root, Some(message), false).unwrap();
+    }
+
+    // A held ref lock blocks the drop before the stack is touched.
+    let lock_path = repo_root.join(".rustygit/refs/stash.lock");
+    fs::write(&lock_path, "").unwrap();
+    assert!(commands::stash_drop(&repo_root, Some("stash@{1}")).is_err());
+    assert_eq!(commands::stash_list(&repo_root).unwrap().len(), 4);
+    fs::remove_file(&lock_path).unwrap();
+
+    // Concurrent drops each remove exactly one entry or fail on the lock.
+    let dropped = (0..3)
+        .map(|_| {
+            let repo_root = repo_root.clone();
+            std::thread::spawn(move || commands::stash_drop(&repo_root, Some("stash@{1}")).is_ok())
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .filter(|dropped| *dropped)
+        .count();
+
+    let list = commands::stash_list(&repo_root).unwrap();
+    assert_eq!(list.len(), 4 - dropped);
+    assert_eq!(list[0], "stash@{0}: On main: fourth");
+    assert!(!lock_path.exists());
+}
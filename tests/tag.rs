@@ -0,0 +1,183 @@
+use assert_cmd::Command;
+use rustygit::{commands, utils};
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn commit_all(repo_root: &Path, message: &str) -> String {
+    commands::add(repo_root, Path::new(".")).unwrap();
+    commands::commit(repo_root, message.to_string(), &vec![]).unwrap()
+}
+
+/// Initializes a repository with two commits and returns their hashes.
+fn two_commits(repo_root: &Path) -> (String, String) {
+    commands::init(repo_root).unwrap();
+    fs::write(repo_root.join("a.txt"), "one\n").unwrap();
+    let first = commit_all(repo_root, "first");
+    fs::write(repo_root.join("a.txt"), "two\n").unwrap();
+    let second = commit_all(repo_root, "second");
+    (first, second)
+}
+
+#[test]
+fn lightweight_tag_points_at_commit() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, second) = two_commits(&repo_root);
+
+    assert_eq!(
+        commands::create_tag(&repo_root, "v2", None, None).unwrap(),
+        second
+    );
+    assert_eq!(
+        commands::create_tag(&repo_root, "v1", Some("HEAD~1"), None).unwrap(),
+        first
+    );
+
+    let ref_content = fs::read_to_string(repo_root.join(".rustygit/refs/tags/v1")).unwrap();
+    assert_eq!(ref_content.trim(), first);
+    assert_eq!(utils::resolve_revision(&repo_root, "v1").unwrap(), first);
+
+    let err = commands::create_tag(&repo_root, "v1", None, None).unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+}
+
+#[test]
+fn annotated_tag_writes_tag_object() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, _) = two_commits(&repo_root);
+
+    let tag_hash =
+        commands::create_tag(&repo_root, "v1.0", Some(&first), Some("Release 1.0")).unwrap();
+    assert_ne!(tag_hash, first);
+
+    let (object_type, payload) =
+        utils::split_object(&utils::read_object(&repo_root, &tag_hash).unwrap()).unwrap();
+    let payload = String::from_utf8(payload).unwrap();
+    assert_eq!(object_type, "tag");
+    assert!(payload.starts_with(&format!("object {}\ntype commit\ntag v1.0\ntagger ", first)));
+    assert!(payload.ends_with("\n\nRelease 1.0\n"));
+
+    // The ref names the tag object; commit lookups peel through it.
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "v1.0").unwrap(),
+        tag_hash
+    );
+    assert_eq!(utils::resolve_commit(&repo_root, "v1.0").unwrap(), first);
+    assert_eq!(
+        utils::resolve_revision(&repo_root, "v1.0^0").unwrap(),
+        first
+    );
+
+    // Tag objects survive gc through the tag ref.
+    commands::gc(&repo_root, 0).unwrap();
+    assert_eq!(utils::resolve_commit(&repo_root, "v1.0").unwrap(), first);
+}
+
+#[test]
+fn checkout_accepts_tag_names() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    let (first, _) = two_commits(&repo_root);
+
+    commands::create_tag(&repo_root, "pinned", Some(&first), Some("pin")).unwrap();
+    commands::checkout(&repo_root, "pinned").unwrap();
+
+    let head = fs::read_to_string(repo_root.join(".rustygit/HEAD")).unwrap();
+    assert_eq!(head.trim(), first);
+    assert_eq!(
+        fs::read_to_string(repo_root.join("a.txt")).unwrap(),
+        "one\n"
+    );
+}
+
+#[test]
+fn lists_tags_with_glob_filter() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    two_commits(&repo_root);
+
+    for name in ["v1.1", "v1.0", "v2.0", "release/final"] {
+        commands::create_tag(&repo_root, name, None, None).unwrap();
+    }
+
+    assert_eq!(
+        commands::list_tags(&repo_root, None).unwrap(),
+        vec!["release/final", "v1.0", "v1.1", "v2.0"]
+    );
+    assert_eq!(
+        commands::list_tags(&repo_root, Some("v1.*")).unwrap(),
+        vec!["v1.0", "v1.1"]
+    );
+}
+
+#[test]
+fn delete_removes_ref_only() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    two_commits(&repo_root);
+
+    let tag_hash = commands::create_tag(&repo_root, "release/v1", None, Some("msg")).unwrap();
+    assert_eq!(
+        commands::delete_tag(&repo_root, "release/v1").unwrap(),
+        tag_hash
+    );
+
+    assert!(!repo_root.join(".rustygit/refs/tags/release").exists());
+    assert!(repo_root.join(".rustygit/refs/tags").is_dir());
+    assert!(utils::object_exists(&repo_root, &tag_hash));
+    assert!(commands::delete_tag(&repo_root, "release/v1").is_err());
+}
+
+#[test]
+fn rejects_invalid_tag_names() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    two_commits(&repo_root);
+
+    for name in [
+        "",
+        "-v1",
+        "a..b",
+        "v1~",
+        "has space",
+        "x.lock",
+        "a//b",
+        ".hidden",
+    ] {
+        assert!(
+            commands::create_tag(&repo_root, name, None, None).is_err(),
+            "{:?} should be rejected",
+            name
+        );
+    }
+}
+
+#[test]
+fn tag_cli_creates_lists_and_deletes() {
+    let dir = tempdir().unwrap();
+    let repo_root = dir.path().canonicalize().unwrap();
+    two_commits(&repo_root);
+
+    let rustygit = || {
+        let mut cmd = Command::cargo_bin("rustygit").unwrap();
+        cmd.current_dir(&repo_root);
+        cmd
+    };
+
+    rustygit().args(["tag", "v1"]).assert().success();
+    rustygit()
+        .args(["tag", "-a", "v2", "-m", "second release"])
+        .assert()
+        .success();
+    rustygit().args(["tag", "-a", "v3"]).assert().failure();
+
+    let assert = rustygit().args(["tag", "-l", "v*"]).assert().success();
+    let output = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(output, "v1\nv2\n");
+
+    rustygit().args(["tag", "-d", "v1"]).assert().success();
+    let assert = rustygit().arg("tag").assert().success();
+    let output = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(output, "v2\n");
+}